@@ -200,12 +200,8 @@ pub fn check_scope(record_own_paths: &str, record_scope_level: Option<i16>, filt
     }
     if let Some(record_scope_level) = record_scope_level {
         if let Some(standard_sub_paths) = get_pre_paths(record_scope_level, standard_own_paths) {
-            let record_sub_paths = if record_own_paths.len() <= standard_sub_paths.len() {
-                record_own_paths
-            } else {
-                &record_own_paths[0..standard_sub_paths.len()]
-            };
-            return standard_sub_paths.starts_with(record_sub_paths);
+            // compared as whole strings instead of slicing by bytes, which may split a multi-byte character
+            return record_own_paths.starts_with(&standard_sub_paths) || standard_sub_paths.starts_with(record_own_paths);
         }
     }
     false
@@ -132,7 +132,8 @@ pub mod common {
 /// Some common postgresql initialization helper methods
 /// 一些公共的PostgreSQL初始化辅助方法
 pub mod common_pg {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{OnceLock, RwLock};
 
     use tardis::{
         basic::{dto::TardisContext, error::TardisError, result::TardisResult},
@@ -373,20 +374,7 @@ pub mod common_pg {
         )
         .await?;
         for (idx, (field_name_or_fun, index_type)) in indexes.into_iter().enumerate() {
-            // index name shouldn't be longer than 63 characters
-            // [4 ][     18    ][ 12 ][     26    ][ 3 ]
-            // idx_{schema_name}{tag}_{table_flag}_{idx}
-            #[inline]
-            fn truncate_str(s: &str, max_size: usize) -> &str {
-                &s[..max_size.min(s.len())]
-            }
-            let index_name = format!(
-                "idx_{schema_name}{tag}_{table_flag}_{idx}",
-                schema_name = truncate_str(schema_name, 18),
-                tag = truncate_str(tag, 11),
-                table_flag = truncate_str(table_flag, 25),
-                idx = truncate_str(idx.to_string().as_str(), 3),
-            );
+            let index_name = index_name(schema_name, tag, table_flag, idx);
             conn.execute_one(
                 &format!("CREATE INDEX {index_name} ON {schema_name}.{GLOBAL_STORAGE_FLAG}_{table_flag}{tag} USING {index_type}({field_name_or_fun})"),
                 vec![],
@@ -437,6 +425,63 @@ EXECUTE PROCEDURE TARDIS_AUTO_UPDATE_TIME_{}();"###,
         Ok(())
     }
 
+    /// Name of the index at the position of the index list of [`init_table_and_conn`], `tag` includes the leading `_`
+    /// [`init_table_and_conn`] 索引列表中对应位置的索引名称，`tag` 包含前导的 `_`
+    // index name shouldn't be longer than 63 characters
+    // [4 ][     18    ][ 12 ][     26    ][ 3 ]
+    // idx_{schema_name}{tag}_{table_flag}_{idx}
+    pub fn index_name(schema_name: &str, tag: &str, table_flag: &str, idx: usize) -> String {
+        #[inline]
+        fn truncate_str(s: &str, max_size: usize) -> &str {
+            &s[..max_size.min(s.len())]
+        }
+        format!(
+            "idx_{schema_name}{tag}_{table_flag}_{idx}",
+            schema_name = truncate_str(schema_name, 18),
+            tag = truncate_str(tag, 11),
+            table_flag = truncate_str(table_flag, 25),
+            idx = truncate_str(idx.to_string().as_str(), 3),
+        )
+    }
+
+    /// Upgrade the table created before the columns or indexes were introduced, only runs once for each table in the process
+    /// 升级在字段或索引引入之前创建的表，每个表在进程内只执行一次
+    ///
+    /// The indexes are named the same as the ones created by [`init_table_and_conn`], so that tables created afterwards are not indexed twice.
+    /// 索引名称与 [`init_table_and_conn`] 创建的索引一致，避免之后创建的表重复建索引。
+    pub async fn upgrade_table(
+        conn: &TardisRelDBlConnection,
+        // Full table name, as returned by init_table_and_conn
+        table_name: &str,
+        // Tag, as a table name suffix
+        tag: Option<&str>,
+        // Table flag, as part of the table name
+        table_flag: &str,
+        // Column definitions added if absent, e.g. `ext jsonb NOT NULL DEFAULT '{}'::jsonb`
+        columns: &[&str],
+        // Indexes created if absent
+        // Format: position in the index list of init_table_and_conn -> field name -> index type
+        indexes: &[(usize, &str, &str)],
+    ) -> TardisResult<()> {
+        static UPGRADED_TABLES: OnceLock<RwLock<HashSet<String>>> = OnceLock::new();
+        let upgraded_tables = UPGRADED_TABLES.get_or_init(Default::default);
+        if upgraded_tables.read().unwrap_or_else(|e| e.into_inner()).contains(table_name) {
+            return Ok(());
+        }
+        if !columns.is_empty() {
+            let add_columns = columns.iter().map(|column| format!("ADD COLUMN IF NOT EXISTS {column}")).collect::<Vec<_>>().join(", ");
+            conn.execute_one(&format!("ALTER TABLE {table_name} {add_columns}"), vec![]).await?;
+        }
+        let schema_name = table_name.split_once('.').map(|(schema_name, _)| schema_name).unwrap_or_default();
+        let tag = tag.map(|t| format!("_{t}")).unwrap_or_default();
+        for (idx, field_name_or_fun, index_type) in indexes {
+            let index_name = index_name(schema_name, &tag, table_flag, *idx);
+            conn.execute_one(&format!("CREATE INDEX IF NOT EXISTS {index_name} ON {table_name} USING {index_type}({field_name_or_fun})"), vec![]).await?;
+        }
+        upgraded_tables.write().unwrap_or_else(|e| e.into_inner()).insert(table_name.to_string());
+        Ok(())
+    }
+
     /// alter table column
     /// 更改表字段
     pub async fn alter_table_column(
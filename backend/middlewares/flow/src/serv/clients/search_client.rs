@@ -188,6 +188,7 @@ impl FlowSearchClient {
                     ext_override: Some(false),
                     visit_keys: None,
                     kv_disable: None,
+                    embedding: None,
                 },
                 funs,
                 ctx,
@@ -268,6 +269,7 @@ impl FlowSearchClient {
                     ext_override: Some(false),
                     visit_keys: None,
                     kv_disable: None,
+                    embedding: None,
                 },
                 funs,
                 ctx,
@@ -337,6 +339,7 @@ impl FlowSearchClient {
                         update_time: None,
                         ext: Some(ext),
                         visit_keys: None,
+                        embedding: None,
                     });
                 }
                 if !batch_req.is_empty() {
@@ -412,6 +415,8 @@ impl FlowSearchClient {
                         size: 1,
                         fetch_total: false,
                     },
+                    vector: None,
+                    highlight: None,
                 }, funs, ctx).await?;
                 if let Some(result) = result {
                     kind = Some(result.kind);
@@ -461,6 +466,7 @@ impl FlowSearchClient {
                     update_time,
                     ext: Some(ext),
                     visit_keys: None,
+                    embedding: None,
                 });
             }
         }
@@ -569,6 +575,7 @@ impl FlowSearchClient {
                     groups: None,
                 }),
                 kv_disable: None,
+                embedding: None,
             };
             SpiSearchClient::modify_item_and_name(SEARCH_MODEL_TAG, &key, &modify_req, funs, ctx).await?;
         } else {
@@ -600,6 +607,7 @@ impl FlowSearchClient {
                 }),
                 kv_disable: None,
                 data_source: None,
+                embedding: None,
             };
             SpiSearchClient::add_item_and_name(&add_req, Some(model_resp.name.clone()), funs, ctx).await?;
         }
@@ -711,6 +719,7 @@ impl FlowSearchClient {
                     groups: None,
                 }),
                 kv_disable: None,
+                embedding: None,
             };
             SpiSearchClient::modify_item_and_name(SEARCH_INSTANCE_TAG, key, &modify_req, funs, ctx).await?;
         } else {
@@ -749,6 +758,7 @@ impl FlowSearchClient {
                 }),
                 kv_disable: None,
                 data_source: None,
+                embedding: None,
             };
             SpiSearchClient::add_item_and_name(&add_req, inst_resp.title.clone(), funs, ctx).await?;
         }
@@ -819,6 +829,7 @@ impl FlowSearchClient {
                     roles: None,
                     groups: None,
                 }),
+                embedding: None,
             });
         }
         
@@ -904,6 +915,8 @@ impl FlowSearchClient {
                     size: 999,
                     fetch_total: false,
                 },
+                vector: None,
+                highlight: None,
             },
             funs,
             ctx,
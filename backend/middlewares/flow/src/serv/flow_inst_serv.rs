@@ -5013,6 +5013,8 @@ impl FlowInstServ {
                             size: page_size,
                             fetch_total: false,
                         },
+                        vector: None,
+                        highlight: None,
                     },
                     funs,
                     ctx,
@@ -5166,6 +5168,8 @@ impl FlowInstServ {
                                 size: 100,
                                 fetch_total: false,
                             },
+                            vector: None,
+                            highlight: None,
                         },
                         funs,
                         ctx,
@@ -5234,6 +5238,8 @@ impl FlowInstServ {
                                 size: 100,
                                 fetch_total: false,
                             },
+                            vector: None,
+                            highlight: None,
                         },
                         funs,
                         ctx,
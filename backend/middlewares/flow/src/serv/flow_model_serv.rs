@@ -2402,6 +2402,8 @@ impl FlowModelServ {
                     size: 999,
                     fetch_total: false,
                 },
+                vector: None,
+                highlight: None,
             },
             funs,
             ctx,
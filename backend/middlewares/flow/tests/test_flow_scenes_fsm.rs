@@ -348,6 +348,8 @@ pub async fn test(
                     size: 20,
                     fetch_total: true,
                 },
+                vector: None,
+                highlight: None,
             },
         )
         .await;
@@ -389,6 +391,8 @@ pub async fn test(
                     size: 20,
                     fetch_total: true,
                 },
+                vector: None,
+                highlight: None,
             },
         )
         .await;
@@ -414,6 +418,8 @@ pub async fn test(
                     size: 20,
                     fetch_total: true,
                 },
+                vector: None,
+                highlight: None,
             },
         )
         .await;
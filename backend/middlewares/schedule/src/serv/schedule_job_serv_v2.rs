@@ -9,7 +9,7 @@ use tardis::{
 
 use crate::{dto::schedule_job_dto::ScheduleJob, schedule_constants::DOMAIN_CODE};
 use event::{EventComponent, SpiLog};
-use repo::{Repository, SpiKv};
+use repo::{Repository, ScheduleJobChange, SpiKv};
 
 pub mod event;
 pub mod repo;
@@ -55,10 +55,16 @@ pub(crate) fn init() {
                 break;
             }
         }
+        let mut since_seq = None;
         // 五秒钟轮询一次
         loop {
+            // 同步前先获取监听游标，避免遗漏同步期间的变更，获取失败时与同步一并重试
+            if since_seq.is_none() {
+                since_seq = repo.watch(None).await.ok().map(|(seq, _)| seq);
+            }
             // 从仓库同步所有任务
-            if let Ok(jobs) = repo.get_all().await {
+            let jobs = if since_seq.is_some() { repo.get_all().await.ok() } else { None };
+            if let Some(jobs) = jobs {
                 for job in jobs {
                     if let Ok(task) = service.make_task(&job, spi_log.clone()) {
                         service.local_set_job(&job.code, task).await;
@@ -78,5 +84,31 @@ pub(crate) fn init() {
             }
             interval.tick().await;
         }
+        // 监听kv中任务的变更，及时更新本地任务
+        loop {
+            match repo.watch(since_seq).await {
+                Ok((seq, changes)) => {
+                    if since_seq.is_some() {
+                        for change in changes {
+                            match change {
+                                ScheduleJobChange::Set(job) => {
+                                    if let Ok(task) = service.make_task(&job, spi_log.clone()) {
+                                        service.local_set_job(&job.code, task).await;
+                                    } else {
+                                        error!("fail to create task for job {job:?}");
+                                    }
+                                }
+                                ScheduleJobChange::Delete(code) => service.local_delete_job(&code).await,
+                            }
+                        }
+                    }
+                    since_seq = Some(seq);
+                }
+                Err(e) => {
+                    warn!("fail to watch jobs from kv: {e:?}");
+                    interval.tick().await;
+                }
+            }
+        }
     });
 }
@@ -3,7 +3,7 @@ use std::{ops::Deref, sync::Arc};
 use bios_sdk_invoke::{
     clients::{
         base_spi_client::BaseSpiClient,
-        spi_kv_client::{KvItemDetailResp, SpiKvClient},
+        spi_kv_client::{KvItemChangeResp, KvItemDetailResp, SpiKvClient},
     },
     invoke_enumeration::InvokeModuleKind,
 };
@@ -14,6 +14,31 @@ use tardis::{
 };
 
 use crate::{dto::schedule_job_dto::ScheduleJob, schedule_constants::KV_KEY_CODE};
+/// Seconds to wait in a single watch request
+const WATCH_TIMEOUT_SEC: u64 = 25;
+
+/// Job change observed by watching spi-kv
+#[derive(Debug)]
+pub enum ScheduleJobChange {
+    Set(ScheduleJob),
+    Delete(String),
+}
+
+impl From<KvItemChangeResp> for ScheduleJobChange {
+    fn from(change: KvItemChangeResp) -> Self {
+        let code = change.key.replace(KV_KEY_CODE, "");
+        // deleted, expired and disabled jobs should not be scheduled any more
+        if change.disable || change.op == "delete" || change.op == "expire" {
+            ScheduleJobChange::Delete(code)
+        } else {
+            ScheduleJobChange::Set(ScheduleJob {
+                code: code.into(),
+                ..ScheduleJob::parse_from_json(&change.value)
+            })
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SpiKv {
     funs: Arc<TardisFunsInst>,
@@ -21,6 +46,18 @@ pub struct SpiKv {
     _client: SpiKvClient,
 }
 
+impl SpiKv {
+    /// Wait for the job changes after [since_seq], returns the cursor for the next call and the changes.
+    /// If [since_seq] is `None`, returns the latest cursor immediately.
+    pub async fn watch(&self, since_seq: Option<i64>) -> Result<(i64, Vec<ScheduleJobChange>), TardisError> {
+        let resp = SpiKvClient::watch_items(KV_KEY_CODE, since_seq, Some(WATCH_TIMEOUT_SEC), &self.funs, &self.ctx).await?;
+        let Some(resp) = resp else {
+            return Err(self.funs.err().conflict("watch_job", "watch", "watch Job Kv failed", ""));
+        };
+        Ok((resp.seq, resp.changes.into_iter().map(ScheduleJobChange::from).collect()))
+    }
+}
+
 impl super::Repository for SpiKv {
     fn from_context(funs: impl Into<Arc<TardisFunsInst>>, ctx: impl Into<Arc<TardisContext>>) -> Self {
         Self {
@@ -153,7 +153,7 @@ where
     }
 
     // 本地删除任务
-    async fn local_delete_job(&self, code: &str) {
+    pub(crate) async fn local_delete_job(&self, code: &str) {
         let task_uid = self.local_cache.write().await.remove(code);
         if let Some(task_uid) = task_uid {
             self.client.remove_task(task_uid);
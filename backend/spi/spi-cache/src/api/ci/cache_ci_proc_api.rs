@@ -1,12 +1,19 @@
 use std::collections::HashMap;
 
+use tardis::futures::{SinkExt, StreamExt};
+use tardis::serde_json::Value as JsonValue;
 use tardis::web::context_extractor::TardisContextExtractor;
 
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, Message, WebSocket};
 use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::Query;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use crate::dto::cache_proc_dto::{
+    CacheBatchReq, ChannelReq, ExpReq, KIncrReq, KRangeReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmReq, KmsReq, KmsResp, KsReq, KvReq, KvWithExReq, KvsReq,
+    PublishReq,
+};
 use crate::serv::cache_proc_serv;
 #[derive(Clone)]
 pub struct CacheCiProcApi;
@@ -209,4 +216,89 @@ impl CacheCiProcApi {
         let funs = crate::get_tardis_inst();
         TardisResp::ok(cache_proc_serv::bitcount_range_by_bit(&req.0, &funs, &ctx.0).await?)
     }
+
+    /// mget
+    #[oai(path = "/mget", method = "put")]
+    async fn mget(&self, req: Json<KsReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<Option<String>>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::mget(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// mset
+    #[oai(path = "/mset", method = "put")]
+    async fn mset(&self, req: Json<KvsReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        cache_proc_serv::mset(&req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// zadd
+    #[oai(path = "/zadd", method = "put")]
+    async fn zadd(&self, req: Json<KmsReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zadd(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrem
+    #[oai(path = "/zrem", method = "put")]
+    async fn zrem(&self, req: Json<KmReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrem(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// zrange (with scores)
+    #[oai(path = "/zrange", method = "put")]
+    async fn zrange(&self, req: Json<KRangeReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<KmsResp>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::zrange(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// Execute commands in batch
+    ///
+    /// The commands are executed atomically in order, the results are returned in the same order.
+    #[oai(path = "/batch", method = "post")]
+    async fn batch(&self, req: Json<CacheBatchReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<JsonValue>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::batch(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// publish
+    ///
+    /// Return the number of subscribers that received the message
+    #[oai(path = "/publish", method = "post")]
+    async fn publish(&self, req: Json<PublishReq>, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(cache_proc_serv::publish(&req.0, &funs, &ctx.0).await?)
+    }
+
+    /// subscribe
+    ///
+    /// Messages published to the channel are pushed as text frames through the websocket
+    #[oai(path = "/subscribe", method = "get")]
+    async fn subscribe(&self, channel: Query<String>, websocket: WebSocket, ctx: TardisContextExtractor) -> Result<BoxWebSocketUpgraded, tardis::web::poem::Error> {
+        let funs = crate::get_tardis_inst();
+        let mut messages = cache_proc_serv::subscribe(&ChannelReq { channel: channel.0.into() }, &funs, &ctx.0).await?;
+        let upgraded: BoxWebSocketUpgraded = websocket.on_upgrade(Box::new(|socket| {
+            Box::pin(async move {
+                let (mut sink, mut stream) = socket.split();
+                loop {
+                    tardis::tokio::select! {
+                        message = messages.next() => match message {
+                            Some(message) => {
+                                if sink.send(Message::Text(message)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            None => break,
+                        },
+                        received = stream.next() => match received {
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            _ => {}
+                        },
+                    }
+                }
+            })
+        }));
+        Ok(upgraded)
+    }
 }
@@ -84,3 +84,124 @@ pub struct KbRangeReq {
     pub start: u32,
     pub end: u32,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KsReq {
+    #[oai(validator(min_items = "1"))]
+    pub keys: Vec<TrimString>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvsReq {
+    #[oai(validator(min_items = "1"))]
+    pub items: Vec<KvReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KmsReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub member: String,
+    pub score: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KmReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub member: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KRangeReq {
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    /// Start index, negative numbers count from the end, e.g. -1 is the last element
+    pub start: i64,
+    /// Stop index (inclusive), negative numbers count from the end
+    pub stop: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KmsResp {
+    pub member: String,
+    pub score: f64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ChannelReq {
+    #[oai(validator(min_length = "1"))]
+    pub channel: TrimString,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct PublishReq {
+    #[oai(validator(min_length = "1"))]
+    pub channel: TrimString,
+    pub message: String,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CacheCmdKind {
+    Set,
+    SetEx,
+    SetNx,
+    Get,
+    Getset,
+    Incr,
+    Del,
+    Exists,
+    Expire,
+    Ttl,
+    Lpush,
+    Lrangeall,
+    Llen,
+    Hget,
+    Hset,
+    HsetNx,
+    Hdel,
+    Hincr,
+    Hexists,
+    Hkeys,
+    Hvals,
+    Hgetall,
+    Hlen,
+    Setbit,
+    Getbit,
+    Bitcount,
+    BitcountRangeByBit,
+    Zadd,
+    Zrem,
+    Zrange,
+}
+
+/// A cache command in a batch.
+///
+/// Only the arguments required by ``op`` need to be filled in, they have the same meaning as the fields of the single command request.
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct CacheCmdReq {
+    pub op: CacheCmdKind,
+    #[oai(validator(min_length = "1"))]
+    pub key: TrimString,
+    pub field: Option<TrimString>,
+    pub value: Option<String>,
+    pub delta: Option<i64>,
+    pub exp_sec: Option<u64>,
+    pub offset: Option<u32>,
+    /// Bit value of ``setbit``
+    pub bit_value: Option<bool>,
+    /// Start index of ``zrange`` or start bit of ``bitcount_range_by_bit``
+    pub start: Option<i64>,
+    /// Stop index (inclusive) of ``zrange`` or end bit (inclusive) of ``bitcount_range_by_bit``
+    pub end: Option<i64>,
+    pub member: Option<String>,
+    pub score: Option<f64>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct CacheBatchReq {
+    #[oai(validator(min_items = "1"))]
+    pub cmds: Vec<CacheCmdReq>,
+}
@@ -1,3 +1,5 @@
 pub mod cache_proc_serv;
+#[cfg(feature = "spi-pg")]
+pub mod pg;
 #[cfg(feature = "spi-redis")]
 pub mod redis;
@@ -1,20 +1,26 @@
 use std::collections::HashMap;
 
+use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 
+use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
+use tardis::futures::stream::BoxStream;
+use tardis::serde_json::Value as JsonValue;
 
 use crate::dto::cache_proc_dto::*;
 use crate::{cache_constants, cache_initializer};
 use bios_basic::spi_dispatch_service;
 
-use super::redis;
+use super::{pg, redis};
 spi_dispatch_service! {
     @mgr: true,
     @init: cache_initializer::init_fun,
     @dispatch: {
         #[cfg(feature = "spi-redis")]
         cache_constants::SPI_REDIS_KIND_CODE => redis::cache_redis_proc_serv,
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::cache_pg_proc_serv,
     },
     @method: {
         set(req: &KvReq) -> TardisResult<()>;
@@ -44,5 +50,18 @@ spi_dispatch_service! {
         getbit(req: &KbReq) -> TardisResult<bool>;
         bitcount(req: &KReq) -> TardisResult<u32>;
         bitcount_range_by_bit(req: &KbRangeReq) -> TardisResult<u32>;
+        mget(req: &KsReq) -> TardisResult<Vec<Option<String>>>;
+        mset(req: &KvsReq) -> TardisResult<()>;
+        zadd(req: &KmsReq) -> TardisResult<bool>;
+        zrem(req: &KmReq) -> TardisResult<bool>;
+        zrange(req: &KRangeReq) -> TardisResult<Vec<KmsResp>>;
+        batch(req: &CacheBatchReq) -> TardisResult<Vec<JsonValue>>;
+        publish(req: &PublishReq) -> TardisResult<u64>;
+        subscribe(req: &ChannelReq) -> TardisResult<BoxStream<'static, String>>;
     }
 }
+
+/// Get the argument required by the command in the batch
+pub(crate) fn required_arg<'a, T>(cmd: &CacheCmdReq, arg: &'a Option<T>, arg_name: &str) -> TardisResult<&'a T> {
+    arg.as_ref().ok_or_else(|| TardisError::bad_request(&format!("Argument [{arg_name}] is required by the command [{:?}]", cmd.op), "400-spi-cache-cmd-arg-missing"))
+}
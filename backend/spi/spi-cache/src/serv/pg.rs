@@ -0,0 +1,2 @@
+pub mod cache_pg_initializer;
+pub mod cache_pg_proc_serv;
@@ -0,0 +1,50 @@
+use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+};
+
+pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "cache",
+        r#"k character varying NOT NULL PRIMARY KEY,
+    kind character varying NOT NULL,
+    v bytea NULL,
+    l jsonb NULL,
+    h jsonb NULL,
+    z jsonb NULL,
+    exp_time timestamp with time zone NULL,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("k", "btree"), ("exp_time", "btree")],
+        None,
+        Some("update_time"),
+    )
+    .await?;
+    // Add the sorted set column to the tables created before it was introduced
+    spi_initializer::common_pg::upgrade_table(&conn, &table_name, None, "cache", &["z jsonb NULL"], &[]).await?;
+    Ok((conn, table_name))
+}
+
+/// Sessions listening to the channels, used to count the receivers of the published messages
+pub async fn init_subscriber_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        Some("subscriber"),
+        "cache",
+        r#"channel character varying NOT NULL,
+    pid integer NOT NULL,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
+        None,
+        vec![("channel", "btree"), ("pid", "btree")],
+        None,
+        None,
+    )
+    .await
+}
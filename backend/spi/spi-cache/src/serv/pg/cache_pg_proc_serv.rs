@@ -0,0 +1,764 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_pg};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Duration, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::{
+            sqlx::{self, postgres::PgListener, PgPool},
+            Value,
+        },
+    },
+    futures::{future, stream::BoxStream, StreamExt},
+    log::warn,
+    serde_json::{json, Value as JsonValue},
+    tokio, TardisFuns, TardisFunsInst,
+};
+
+use crate::dto::cache_proc_dto::{
+    CacheBatchReq, CacheCmdKind, CacheCmdReq, ChannelReq, ExpReq, KIncrReq, KRangeReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmReq, KmsReq, KmsResp, KsReq,
+    KvReq, KvWithExReq, KvsReq, PublishReq,
+};
+use crate::serv::cache_proc_serv::required_arg;
+
+use super::cache_pg_initializer;
+
+const KIND_STRING: &str = "string";
+const KIND_LIST: &str = "list";
+const KIND_HASH: &str = "hash";
+const KIND_ZSET: &str = "zset";
+// Maximum number of expired rows purged by each write operation
+const PURGE_EXPIRED_BATCH_SIZE: u32 = 100;
+
+/// Value stored in the cache table.
+/// Strings (and bitmaps, which are strings as in redis) use the ``v`` column, lists use ``l``, hashes use ``h`` and sorted sets use ``z``.
+#[derive(Clone, PartialEq)]
+enum CacheValue {
+    String(Vec<u8>),
+    List(Vec<String>),
+    Hash(BTreeMap<String, String>),
+    ZSet(BTreeMap<String, f64>),
+}
+
+#[derive(Clone, PartialEq)]
+struct CacheItem {
+    value: CacheValue,
+    exp_time: Option<DateTime<Utc>>,
+}
+
+impl CacheItem {
+    fn new(value: CacheValue) -> Self {
+        CacheItem { value, exp_time: None }
+    }
+}
+
+fn wrong_type_error() -> TardisError {
+    TardisError::bad_request("Operation against a key holding the wrong kind of value", "400-spi-cache-wrong-type")
+}
+
+fn overflow_error() -> TardisError {
+    TardisError::bad_request("Increment or decrement would overflow", "400-spi-cache-overflow")
+}
+
+fn parse_int(value: &[u8]) -> TardisResult<i64> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .ok_or_else(|| TardisError::bad_request("Value is not an integer or out of range", "400-spi-cache-not-integer"))
+}
+
+fn exp_time_after(exp_sec: u64) -> TardisResult<DateTime<Utc>> {
+    i64::try_from(exp_sec)
+        .ok()
+        .and_then(Duration::try_seconds)
+        .and_then(|exp| Utc::now().checked_add_signed(exp))
+        .ok_or_else(|| TardisError::bad_request("Invalid expire time", "400-spi-cache-exp-invalid"))
+}
+
+// Bit offsets are counted from the most significant bit of the first byte, the same as redis.
+fn get_bit(bytes: &[u8], offset: usize) -> bool {
+    bytes.get(offset / 8).map(|b| b & (0x80 >> (offset % 8)) != 0).unwrap_or(false)
+}
+
+fn string_ref(item: &Option<CacheItem>) -> TardisResult<Option<&Vec<u8>>> {
+    match item {
+        Some(CacheItem { value: CacheValue::String(v), .. }) => Ok(Some(v)),
+        Some(_) => Err(wrong_type_error()),
+        None => Ok(None),
+    }
+}
+
+fn list_ref(item: &Option<CacheItem>) -> TardisResult<Option<&Vec<String>>> {
+    match item {
+        Some(CacheItem { value: CacheValue::List(v), .. }) => Ok(Some(v)),
+        Some(_) => Err(wrong_type_error()),
+        None => Ok(None),
+    }
+}
+
+fn hash_ref(item: &Option<CacheItem>) -> TardisResult<Option<&BTreeMap<String, String>>> {
+    match item {
+        Some(CacheItem { value: CacheValue::Hash(v), .. }) => Ok(Some(v)),
+        Some(_) => Err(wrong_type_error()),
+        None => Ok(None),
+    }
+}
+
+fn zset_ref(item: &Option<CacheItem>) -> TardisResult<Option<&BTreeMap<String, f64>>> {
+    match item {
+        Some(CacheItem { value: CacheValue::ZSet(v), .. }) => Ok(Some(v)),
+        Some(_) => Err(wrong_type_error()),
+        None => Ok(None),
+    }
+}
+
+fn string_mut(item: &mut Option<CacheItem>) -> TardisResult<&mut Vec<u8>> {
+    match &mut item.get_or_insert_with(|| CacheItem::new(CacheValue::String(vec![]))).value {
+        CacheValue::String(v) => Ok(v),
+        _ => Err(wrong_type_error()),
+    }
+}
+
+fn list_mut(item: &mut Option<CacheItem>) -> TardisResult<&mut Vec<String>> {
+    match &mut item.get_or_insert_with(|| CacheItem::new(CacheValue::List(vec![]))).value {
+        CacheValue::List(v) => Ok(v),
+        _ => Err(wrong_type_error()),
+    }
+}
+
+fn hash_mut(item: &mut Option<CacheItem>) -> TardisResult<&mut BTreeMap<String, String>> {
+    match &mut item.get_or_insert_with(|| CacheItem::new(CacheValue::Hash(BTreeMap::new()))).value {
+        CacheValue::Hash(v) => Ok(v),
+        _ => Err(wrong_type_error()),
+    }
+}
+
+fn zset_mut(item: &mut Option<CacheItem>) -> TardisResult<&mut BTreeMap<String, f64>> {
+    match &mut item.get_or_insert_with(|| CacheItem::new(CacheValue::ZSet(BTreeMap::new()))).value {
+        CacheValue::ZSet(v) => Ok(v),
+        _ => Err(wrong_type_error()),
+    }
+}
+
+// Same as redis, the key is removed when the list, hash or sorted set becomes empty
+fn remove_if_empty(item: &mut Option<CacheItem>) {
+    let is_empty = match item {
+        Some(CacheItem { value: CacheValue::List(v), .. }) => v.is_empty(),
+        Some(CacheItem { value: CacheValue::Hash(v), .. }) => v.is_empty(),
+        Some(CacheItem { value: CacheValue::ZSet(v), .. }) => v.is_empty(),
+        _ => false,
+    };
+    if is_empty {
+        *item = None;
+    }
+}
+
+// Convert redis style indexes (negative numbers count from the end) to an inclusive range
+fn to_range(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+    if len == 0 || start > stop || start >= len {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
+
+fn do_set(item: &mut Option<CacheItem>, value: &str, exp_sec: Option<u64>) -> TardisResult<()> {
+    *item = Some(CacheItem {
+        value: CacheValue::String(value.as_bytes().to_vec()),
+        exp_time: exp_sec.map(exp_time_after).transpose()?,
+    });
+    Ok(())
+}
+
+fn do_set_nx(item: &mut Option<CacheItem>, value: &str) -> TardisResult<bool> {
+    if item.is_some() {
+        return Ok(false);
+    }
+    do_set(item, value, None)?;
+    Ok(true)
+}
+
+fn do_get(item: &Option<CacheItem>) -> TardisResult<Option<String>> {
+    Ok(string_ref(item)?.map(|v| String::from_utf8_lossy(v).into_owned()))
+}
+
+fn do_getset(item: &mut Option<CacheItem>, value: &str) -> TardisResult<Option<String>> {
+    let old_value = do_get(item)?;
+    do_set(item, value, None)?;
+    Ok(old_value)
+}
+
+fn do_incr(item: &mut Option<CacheItem>, delta: i64) -> TardisResult<i64> {
+    let value = string_mut(item)?;
+    let old_value = if value.is_empty() { 0 } else { parse_int(value)? };
+    let new_value = old_value.checked_add(delta).ok_or_else(overflow_error)?;
+    *value = new_value.to_string().into_bytes();
+    Ok(new_value)
+}
+
+fn do_expire(item: &mut Option<CacheItem>, exp_sec: u64) -> TardisResult<()> {
+    if let Some(item) = item {
+        item.exp_time = Some(exp_time_after(exp_sec)?);
+    }
+    Ok(())
+}
+
+fn do_ttl(item: &Option<CacheItem>) -> i64 {
+    // Same as redis: -2 if the key does not exist, -1 if the key exists but has no associated expire
+    match item {
+        Some(CacheItem { exp_time: Some(exp_time), .. }) => ((*exp_time - Utc::now()).num_milliseconds() + 500) / 1000,
+        Some(_) => -1,
+        None => -2,
+    }
+}
+
+fn do_lpush(item: &mut Option<CacheItem>, value: &str) -> TardisResult<()> {
+    list_mut(item)?.insert(0, value.to_string());
+    Ok(())
+}
+
+fn do_lrangeall(item: &Option<CacheItem>) -> TardisResult<Vec<String>> {
+    Ok(list_ref(item)?.cloned().unwrap_or_default())
+}
+
+fn do_llen(item: &Option<CacheItem>) -> TardisResult<u64> {
+    Ok(list_ref(item)?.map(|v| v.len() as u64).unwrap_or(0))
+}
+
+fn do_hget(item: &Option<CacheItem>, field: &str) -> TardisResult<Option<String>> {
+    Ok(hash_ref(item)?.and_then(|v| v.get(field).cloned()))
+}
+
+fn do_hset(item: &mut Option<CacheItem>, field: &str, value: &str) -> TardisResult<()> {
+    hash_mut(item)?.insert(field.to_string(), value.to_string());
+    Ok(())
+}
+
+fn do_hset_nx(item: &mut Option<CacheItem>, field: &str, value: &str) -> TardisResult<bool> {
+    if do_hexists(item, field)? {
+        return Ok(false);
+    }
+    do_hset(item, field, value)?;
+    Ok(true)
+}
+
+fn do_hdel(item: &mut Option<CacheItem>, field: &str) -> TardisResult<()> {
+    if hash_ref(item)?.is_some() {
+        hash_mut(item)?.remove(field);
+        remove_if_empty(item);
+    }
+    Ok(())
+}
+
+fn do_hincr(item: &mut Option<CacheItem>, field: &str, delta: i64) -> TardisResult<i64> {
+    let hash = hash_mut(item)?;
+    let old_value = hash.get(field).map(|v| parse_int(v.as_bytes())).transpose()?.unwrap_or(0);
+    let new_value = old_value.checked_add(delta).ok_or_else(overflow_error)?;
+    hash.insert(field.to_string(), new_value.to_string());
+    Ok(new_value)
+}
+
+fn do_hexists(item: &Option<CacheItem>, field: &str) -> TardisResult<bool> {
+    Ok(hash_ref(item)?.map(|v| v.contains_key(field)).unwrap_or(false))
+}
+
+fn do_hgetall(item: &Option<CacheItem>) -> TardisResult<BTreeMap<String, String>> {
+    Ok(hash_ref(item)?.cloned().unwrap_or_default())
+}
+
+fn do_setbit(item: &mut Option<CacheItem>, offset: u32, value: bool) -> TardisResult<bool> {
+    let bytes = string_mut(item)?;
+    let offset = offset as usize;
+    if bytes.len() <= offset / 8 {
+        bytes.resize(offset / 8 + 1, 0);
+    }
+    let old_value = get_bit(bytes, offset);
+    let mask = 0x80 >> (offset % 8);
+    if value {
+        bytes[offset / 8] |= mask;
+    } else {
+        bytes[offset / 8] &= !mask;
+    }
+    Ok(old_value)
+}
+
+fn do_getbit(item: &Option<CacheItem>, offset: u32) -> TardisResult<bool> {
+    Ok(string_ref(item)?.map(|v| get_bit(v, offset as usize)).unwrap_or(false))
+}
+
+fn do_bitcount(item: &Option<CacheItem>) -> TardisResult<u32> {
+    Ok(string_ref(item)?.map(|v| v.iter().map(|b| b.count_ones()).sum()).unwrap_or(0))
+}
+
+fn do_bitcount_range_by_bit(item: &Option<CacheItem>, start: i64, end: i64) -> TardisResult<u32> {
+    let bytes = match string_ref(item)? {
+        Some(bytes) => bytes,
+        None => return Ok(0),
+    };
+    Ok(to_range(bytes.len() * 8, start, end).map(|(start, end)| (start..=end).filter(|offset| get_bit(bytes, *offset)).count() as u32).unwrap_or(0))
+}
+
+fn do_zadd(item: &mut Option<CacheItem>, member: &str, score: f64) -> TardisResult<bool> {
+    Ok(zset_mut(item)?.insert(member.to_string(), score).is_none())
+}
+
+fn do_zrem(item: &mut Option<CacheItem>, member: &str) -> TardisResult<bool> {
+    if zset_ref(item)?.is_none() {
+        return Ok(false);
+    }
+    let removed = zset_mut(item)?.remove(member).is_some();
+    remove_if_empty(item);
+    Ok(removed)
+}
+
+fn do_zrange(item: &Option<CacheItem>, start: i64, stop: i64) -> TardisResult<Vec<KmsResp>> {
+    let zset = match zset_ref(item)? {
+        Some(zset) => zset,
+        None => return Ok(vec![]),
+    };
+    // Same as redis, members are ordered by score, members with the same score are ordered lexicographically
+    let mut members = zset
+        .iter()
+        .map(|(member, score)| KmsResp {
+            member: member.clone(),
+            score: *score,
+        })
+        .collect::<Vec<_>>();
+    members.sort_by(|a, b| a.score.total_cmp(&b.score).then_with(|| a.member.cmp(&b.member)));
+    Ok(match to_range(members.len(), start, stop) {
+        Some((start, stop)) => members.drain(start..=stop).collect(),
+        None => vec![],
+    })
+}
+
+fn do_cmd(item: &mut Option<CacheItem>, cmd: &CacheCmdReq) -> TardisResult<JsonValue> {
+    let result = match cmd.op {
+        CacheCmdKind::Set => {
+            do_set(item, required_arg(cmd, &cmd.value, "value")?, None)?;
+            JsonValue::Null
+        }
+        CacheCmdKind::SetEx => {
+            do_set(item, required_arg(cmd, &cmd.value, "value")?, Some(*required_arg(cmd, &cmd.exp_sec, "exp_sec")?))?;
+            JsonValue::Null
+        }
+        CacheCmdKind::SetNx => json!(do_set_nx(item, required_arg(cmd, &cmd.value, "value")?)?),
+        CacheCmdKind::Get => json!(do_get(item)?),
+        CacheCmdKind::Getset => json!(do_getset(item, required_arg(cmd, &cmd.value, "value")?)?),
+        CacheCmdKind::Incr => json!(do_incr(item, *required_arg(cmd, &cmd.delta, "delta")?)?),
+        CacheCmdKind::Del => {
+            *item = None;
+            JsonValue::Null
+        }
+        CacheCmdKind::Exists => json!(item.is_some()),
+        CacheCmdKind::Expire => {
+            do_expire(item, *required_arg(cmd, &cmd.exp_sec, "exp_sec")?)?;
+            JsonValue::Null
+        }
+        CacheCmdKind::Ttl => json!(do_ttl(item)),
+        CacheCmdKind::Lpush => {
+            do_lpush(item, required_arg(cmd, &cmd.value, "value")?)?;
+            JsonValue::Null
+        }
+        CacheCmdKind::Lrangeall => json!(do_lrangeall(item)?),
+        CacheCmdKind::Llen => json!(do_llen(item)?),
+        CacheCmdKind::Hget => json!(do_hget(item, required_arg(cmd, &cmd.field, "field")?)?),
+        CacheCmdKind::Hset => {
+            do_hset(item, required_arg(cmd, &cmd.field, "field")?, required_arg(cmd, &cmd.value, "value")?)?;
+            JsonValue::Null
+        }
+        CacheCmdKind::HsetNx => json!(do_hset_nx(item, required_arg(cmd, &cmd.field, "field")?, required_arg(cmd, &cmd.value, "value")?)?),
+        CacheCmdKind::Hdel => {
+            do_hdel(item, required_arg(cmd, &cmd.field, "field")?)?;
+            JsonValue::Null
+        }
+        CacheCmdKind::Hincr => json!(do_hincr(item, required_arg(cmd, &cmd.field, "field")?, *required_arg(cmd, &cmd.delta, "delta")?)?),
+        CacheCmdKind::Hexists => json!(do_hexists(item, required_arg(cmd, &cmd.field, "field")?)?),
+        CacheCmdKind::Hkeys => json!(do_hgetall(item)?.into_keys().collect::<Vec<_>>()),
+        CacheCmdKind::Hvals => json!(do_hgetall(item)?.into_values().collect::<Vec<_>>()),
+        CacheCmdKind::Hgetall => json!(do_hgetall(item)?),
+        CacheCmdKind::Hlen => json!(do_hgetall(item)?.len()),
+        CacheCmdKind::Setbit => json!(do_setbit(
+            item,
+            *required_arg(cmd, &cmd.offset, "offset")?,
+            *required_arg(cmd, &cmd.bit_value, "bit_value")?
+        )?),
+        CacheCmdKind::Getbit => json!(do_getbit(item, *required_arg(cmd, &cmd.offset, "offset")?)?),
+        CacheCmdKind::Bitcount => json!(do_bitcount(item)?),
+        CacheCmdKind::BitcountRangeByBit => json!(do_bitcount_range_by_bit(
+            item,
+            *required_arg(cmd, &cmd.start, "start")?,
+            *required_arg(cmd, &cmd.end, "end")?
+        )?),
+        CacheCmdKind::Zadd => json!(do_zadd(item, required_arg(cmd, &cmd.member, "member")?, *required_arg(cmd, &cmd.score, "score")?)?),
+        CacheCmdKind::Zrem => json!(do_zrem(item, required_arg(cmd, &cmd.member, "member")?)?),
+        CacheCmdKind::Zrange => json!(do_zrange(item, *required_arg(cmd, &cmd.start, "start")?, *required_arg(cmd, &cmd.end, "end")?)?),
+    };
+    Ok(result)
+}
+
+async fn fetch_item(key: &str, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<Option<CacheItem>> {
+    let result = conn.query_one(&format!("SELECT kind, v, l, h, z, exp_time FROM {table_name} WHERE k = $1"), vec![Value::from(key)]).await?;
+    let result = match result {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let exp_time: Option<DateTime<Utc>> = result.try_get("", "exp_time")?;
+    // Expired items are treated as non-existent, they will be overwritten or purged later
+    if exp_time.map(|exp_time| exp_time <= Utc::now()).unwrap_or(false) {
+        return Ok(None);
+    }
+    let kind: String = result.try_get("", "kind")?;
+    let value = match kind.as_str() {
+        KIND_STRING => CacheValue::String(result.try_get::<Option<Vec<u8>>>("", "v")?.unwrap_or_default()),
+        KIND_LIST => CacheValue::List(TardisFuns::json.json_to_obj(result.try_get::<Option<JsonValue>>("", "l")?.unwrap_or(json!([])))?),
+        KIND_HASH => CacheValue::Hash(TardisFuns::json.json_to_obj(result.try_get::<Option<JsonValue>>("", "h")?.unwrap_or(json!({})))?),
+        KIND_ZSET => CacheValue::ZSet(TardisFuns::json.json_to_obj(result.try_get::<Option<JsonValue>>("", "z")?.unwrap_or(json!({})))?),
+        _ => return Err(TardisError::internal_error(&format!("Unsupported cache kind {kind}"), "500-spi-cache-kind-invalid")),
+    };
+    Ok(Some(CacheItem { value, exp_time }))
+}
+
+async fn save_item(key: &str, item: &Option<CacheItem>, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
+    let item = match item {
+        Some(item) => item,
+        None => {
+            conn.execute_one(&format!("DELETE FROM {table_name} WHERE k = $1"), vec![Value::from(key)]).await?;
+            return Ok(());
+        }
+    };
+    let (kind, v, l, h, z) = match &item.value {
+        CacheValue::String(v) => (KIND_STRING, Some(v.clone()), None, None, None),
+        CacheValue::List(l) => (KIND_LIST, None, Some(TardisFuns::json.obj_to_json(l)?), None, None),
+        CacheValue::Hash(h) => (KIND_HASH, None, None, Some(TardisFuns::json.obj_to_json(h)?), None),
+        CacheValue::ZSet(z) => (KIND_ZSET, None, None, None, Some(TardisFuns::json.obj_to_json(z)?)),
+    };
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+    (k, kind, v, l, h, z, exp_time)
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (k)
+DO UPDATE SET
+    kind = EXCLUDED.kind, v = EXCLUDED.v, l = EXCLUDED.l, h = EXCLUDED.h, z = EXCLUDED.z, exp_time = EXCLUDED.exp_time
+"#
+        ),
+        vec![
+            Value::from(key),
+            Value::from(kind),
+            Value::from(v),
+            Value::from(l),
+            Value::from(h),
+            Value::from(z),
+            Value::from(item.exp_time),
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn read(key: &str, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<CacheItem>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = cache_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    fetch_item(key, &conn, &table_name).await
+}
+
+/// Read-modify-write the items of the keys in one transaction.
+///
+/// Writers of the same key are serialized by transaction level advisory locks,
+/// the items left in the map after ``modify_fun`` are saved back (``None`` means deleted).
+/// Each call also purges a batch of expired items.
+async fn modify_many<T, F>(keys: Vec<String>, ctx: &TardisContext, inst: &SpiBsInst, modify_fun: F) -> TardisResult<T>
+where
+    F: FnOnce(&mut HashMap<String, Option<CacheItem>>) -> TardisResult<T>,
+{
+    let mut keys = keys;
+    // Locks are acquired in key order to avoid deadlocks between concurrent writers
+    keys.sort();
+    keys.dedup();
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = cache_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    let mut items = HashMap::with_capacity(keys.len());
+    for key in keys {
+        conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(format!("{table_name}:{key}"))]).await?;
+        let item = fetch_item(&key, &conn, &table_name).await?;
+        items.insert(key, item);
+    }
+    let origin_items = items.clone();
+    let result = modify_fun(&mut items)?;
+    for (key, item) in &items {
+        if origin_items.get(key) != Some(item) {
+            save_item(key, item, &conn, &table_name).await?;
+        }
+    }
+    conn.execute_one(
+        &format!("DELETE FROM {table_name} WHERE k IN (SELECT k FROM {table_name} WHERE exp_time <= $1 LIMIT {PURGE_EXPIRED_BATCH_SIZE})"),
+        vec![Value::from(Utc::now())],
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(result)
+}
+
+async fn modify<T, F>(key: &str, ctx: &TardisContext, inst: &SpiBsInst, modify_fun: F) -> TardisResult<T>
+where
+    F: FnOnce(&mut Option<CacheItem>) -> TardisResult<T>,
+{
+    modify_many(vec![key.to_string()], ctx, inst, |items| modify_fun(items.get_mut(key).expect("ignore"))).await
+}
+
+pub async fn set(req: &KvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| do_set(item, &req.value, None)).await
+}
+
+pub async fn set_ex(req: &KvWithExReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| do_set(item, &req.value, Some(req.exp_sec))).await
+}
+
+pub async fn set_nx(req: &KvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    modify(&req.key, ctx, inst, |item| do_set_nx(item, &req.value)).await
+}
+
+pub async fn get(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    do_get(&read(&req.key, ctx, inst).await?)
+}
+
+pub async fn getset(req: &KvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    modify(&req.key, ctx, inst, |item| do_getset(item, &req.value)).await
+}
+
+pub async fn incr(req: &KIncrReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    modify(&req.key, ctx, inst, |item| do_incr(item, req.delta)).await
+}
+
+pub async fn del(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| {
+        *item = None;
+        Ok(())
+    })
+    .await
+}
+
+pub async fn exists(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    Ok(read(&req.key, ctx, inst).await?.is_some())
+}
+
+pub async fn expire(req: &ExpReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| do_expire(item, req.exp_sec)).await
+}
+
+pub async fn ttl(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    Ok(do_ttl(&read(&req.key, ctx, inst).await?) as u64)
+}
+
+// list operations
+
+pub async fn lpush(req: &KvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| do_lpush(item, &req.value)).await
+}
+
+pub async fn lrangeall(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    do_lrangeall(&read(&req.key, ctx, inst).await?)
+}
+
+pub async fn llen(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    do_llen(&read(&req.key, ctx, inst).await?)
+}
+
+// hash operations
+
+pub async fn hget(req: &KfReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<String>> {
+    do_hget(&read(&req.key, ctx, inst).await?, &req.field)
+}
+
+pub async fn hset(req: &KfvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| do_hset(item, &req.field, &req.value)).await
+}
+
+pub async fn hset_nx(req: &KfvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    modify(&req.key, ctx, inst, |item| do_hset_nx(item, &req.field, &req.value)).await
+}
+
+pub async fn hdel(req: &KfReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify(&req.key, ctx, inst, |item| do_hdel(item, &req.field)).await
+}
+
+pub async fn hincr(req: &KfIncrReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    modify(&req.key, ctx, inst, |item| do_hincr(item, &req.field, req.delta)).await
+}
+
+pub async fn hexists(req: &KfReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    do_hexists(&read(&req.key, ctx, inst).await?, &req.field)
+}
+
+pub async fn hkeys(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    Ok(do_hgetall(&read(&req.key, ctx, inst).await?)?.into_keys().collect())
+}
+
+pub async fn hvals(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    Ok(do_hgetall(&read(&req.key, ctx, inst).await?)?.into_values().collect())
+}
+
+pub async fn hgetall(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashMap<String, String>> {
+    Ok(do_hgetall(&read(&req.key, ctx, inst).await?)?.into_iter().collect())
+}
+
+pub async fn hlen(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    Ok(do_hgetall(&read(&req.key, ctx, inst).await?)?.len() as u64)
+}
+
+// bitmap operations
+
+pub async fn setbit(req: &KbvReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    modify(&req.key, ctx, inst, |item| do_setbit(item, req.offset, req.value)).await
+}
+
+pub async fn getbit(req: &KbReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    do_getbit(&read(&req.key, ctx, inst).await?, req.offset)
+}
+
+pub async fn bitcount(req: &KReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
+    do_bitcount(&read(&req.key, ctx, inst).await?)
+}
+
+pub async fn bitcount_range_by_bit(req: &KbRangeReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u32> {
+    do_bitcount_range_by_bit(&read(&req.key, ctx, inst).await?, req.start as i64, req.end as i64)
+}
+
+// multiple keys operations
+
+pub async fn mget(req: &KsReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<Option<String>>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = cache_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let mut values = Vec::with_capacity(req.keys.len());
+    for key in &req.keys {
+        // Same as redis, keys holding a non-string value are returned as nil
+        values.push(do_get(&fetch_item(key, &conn, &table_name).await?).unwrap_or(None));
+    }
+    Ok(values)
+}
+
+pub async fn mset(req: &KvsReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    modify_many(req.items.iter().map(|item| item.key.to_string()).collect(), ctx, inst, |items| {
+        for kv in &req.items {
+            do_set(items.get_mut(&*kv.key).expect("ignore"), &kv.value, None)?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+// sorted set operations
+
+pub async fn zadd(req: &KmsReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    modify(&req.key, ctx, inst, |item| do_zadd(item, &req.member, req.score)).await
+}
+
+pub async fn zrem(req: &KmReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    modify(&req.key, ctx, inst, |item| do_zrem(item, &req.member)).await
+}
+
+pub async fn zrange(req: &KRangeReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KmsResp>> {
+    do_zrange(&read(&req.key, ctx, inst).await?, req.start, req.stop)
+}
+
+// batch operations
+
+/// Execute the commands in order in one transaction, any failed command rolls back the whole batch.
+pub async fn batch(req: &CacheBatchReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<JsonValue>> {
+    modify_many(req.cmds.iter().map(|cmd| cmd.key.to_string()).collect(), ctx, inst, |items| {
+        req.cmds.iter().map(|cmd| do_cmd(items.get_mut(&*cmd.key).expect("ignore"), cmd)).collect()
+    })
+    .await
+}
+
+// publish/subscribe operations
+
+/// Name of the postgres notification channel, hashed since the channel names are limited to 63 bytes.
+fn format_channel(channel: &str, inst: &SpiBsInst) -> TardisResult<String> {
+    let schema_name = common_pg::get_schema_name_from_ext(&inst.ext).unwrap_or_default();
+    Ok(format!("spi_cache_{}", TardisFuns::crypto.digest.md5(&format!("{schema_name}:{channel}"))?))
+}
+
+fn listen_error(error: sqlx::Error) -> TardisError {
+    TardisError::internal_error(&format!("Fail to listen to the channel: {error}"), "500-spi-cache-subscribe-error")
+}
+
+/// Registration of a listening session, removed when the subscription stream is dropped.
+struct Subscriber {
+    pool: PgPool,
+    table_name: String,
+    channel: String,
+    pid: i32,
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        let pool = self.pool.clone();
+        let sql = format!("DELETE FROM {} WHERE channel = $1 AND pid = $2", self.table_name);
+        let channel = self.channel.clone();
+        let pid = self.pid;
+        // Without a runtime the process is shutting down, its closed sessions are not counted anyway
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        handle.spawn(async move {
+            if let Err(error) = sqlx::query(&sql).bind(channel).bind(pid).execute(&pool).await {
+                warn!("[SPI-Cache] Fail to unregister the subscriber {pid}: {error}");
+            }
+        });
+    }
+}
+
+/// Publish the message by postgres ``NOTIFY``, the subscribers of all nodes listening to the channel receive it.
+///
+/// Return the number of the listening sessions registered for the channel.
+pub async fn publish(req: &PublishReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let channel = format_channel(&req.channel, inst)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = cache_pg_initializer::init_subscriber_table_and_conn(bs_inst, ctx, true).await?;
+    conn.query_one("SELECT pg_notify($1, $2)", vec![Value::from(channel.as_str()), Value::from(req.message.as_str())]).await?;
+    // Sessions closed without unregistering (e.g. the node crashed) are not counted
+    let receivers = conn
+        .count_by_sql(
+            &format!("SELECT 1 FROM {table_name} s WHERE s.channel = $1 AND EXISTS (SELECT 1 FROM pg_stat_activity a WHERE a.pid = s.pid)"),
+            vec![Value::from(channel)],
+        )
+        .await?;
+    Ok(receivers)
+}
+
+pub async fn subscribe(req: &ChannelReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<BoxStream<'static, String>> {
+    let channel = format_channel(&req.channel, inst)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = cache_pg_initializer::init_subscriber_table_and_conn(bs_inst, ctx, true).await?;
+    let pool = conn.raw_conn().get_postgres_connection_pool().clone();
+    // Listening occupies the session, so a dedicated connection is used and released when the stream is dropped
+    let mut listener = PgListener::connect_with(&pool).await.map_err(listen_error)?;
+    listener.listen(&channel).await.map_err(listen_error)?;
+    let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(&mut listener).await.map_err(listen_error)?;
+    conn.execute_one(
+        &format!("DELETE FROM {table_name} s WHERE NOT EXISTS (SELECT 1 FROM pg_stat_activity a WHERE a.pid = s.pid)"),
+        vec![],
+    )
+    .await?;
+    conn.execute_one(
+        &format!("INSERT INTO {table_name} (channel, pid) VALUES ($1, $2)"),
+        vec![Value::from(channel.as_str()), Value::from(pid)],
+    )
+    .await?;
+    let subscriber = Subscriber { pool, table_name, channel, pid };
+    Ok(listener
+        .into_stream()
+        .filter_map(move |notification| {
+            let _subscriber = &subscriber;
+            future::ready(notification.ok().map(|notification| notification.payload().to_string()))
+        })
+        .boxed())
+}
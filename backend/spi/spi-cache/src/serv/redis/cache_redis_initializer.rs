@@ -7,12 +7,16 @@ use tardis::{
     config::config_dto::CacheModuleConfig,
 };
 
+/// The connection uri is kept in the extension, subscriptions need dedicated connections
+pub const CONN_URI_FLAG: &str = "__conn_uri__";
+
 pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _: bool) -> TardisResult<SpiBsInst> {
     let config = CacheModuleConfig {
         url: bs_cert.conn_uri.parse().expect("invalid url"),
     };
     let client = TardisCacheClient::init(&config).await?;
     let mut ext = HashMap::new();
+    ext.insert(CONN_URI_FLAG.to_string(), bs_cert.conn_uri.clone());
     if !bs_cert.private {
         let key_prefix = spi_initializer::common::get_isolation_flag_from_context(ctx);
         spi_initializer::common::set_isolation_flag_to_ext(&key_prefix, &mut ext);
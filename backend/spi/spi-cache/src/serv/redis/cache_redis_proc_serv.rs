@@ -3,11 +3,19 @@ use std::collections::HashMap;
 use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    cache::cache_client::TardisCacheClient,
+    cache::{cache_client::TardisCacheClient, cmd, from_redis_value, Client as RedisClient, Value as RedisValue},
+    futures::{stream::BoxStream, StreamExt},
+    serde_json::{json, Value as JsonValue},
     TardisFunsInst,
 };
 
-use crate::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use crate::dto::cache_proc_dto::{
+    CacheBatchReq, CacheCmdKind, CacheCmdReq, ChannelReq, ExpReq, KIncrReq, KRangeReq, KReq, KbRangeReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmReq, KmsReq, KmsResp, KsReq,
+    KvReq, KvWithExReq, KvsReq, PublishReq,
+};
+use crate::serv::cache_proc_serv::required_arg;
+
+use super::cache_redis_initializer;
 
 pub(crate) fn format_key(req_key: &str, ext: &HashMap<String, String>) -> String {
     if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
@@ -157,3 +165,246 @@ pub async fn bitcount_range_by_bit(req: &KbRangeReq, _funs: &TardisFunsInst, _ct
     let bs_inst = inst.inst::<TardisCacheClient>();
     Ok(bs_inst.0.bitcount_range_by_bit(&format_key(&req.key, bs_inst.1), req.start as usize, req.end as usize).await? as u32)
 }
+
+// multiple keys operations
+
+pub async fn mget(req: &KsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<Option<String>>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut mget_cmd = cmd("MGET");
+    for key in &req.keys {
+        mget_cmd.arg(format_key(key, bs_inst.1));
+    }
+    let mut conn = bs_inst.0.cmd().await?;
+    let values: Vec<Option<String>> = mget_cmd.query_async(&mut *conn).await?;
+    Ok(values)
+}
+
+pub async fn mset(req: &KvsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut mset_cmd = cmd("MSET");
+    for item in &req.items {
+        mset_cmd.arg(format_key(&item.key, bs_inst.1)).arg(&item.value);
+    }
+    let mut conn = bs_inst.0.cmd().await?;
+    let _: () = mset_cmd.query_async(&mut *conn).await?;
+    Ok(())
+}
+
+// sorted set operations
+
+pub async fn zadd(req: &KmsReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let added: u64 = cmd("ZADD").arg(format_key(&req.key, bs_inst.1)).arg(req.score).arg(&req.member).query_async(&mut *conn).await?;
+    Ok(added > 0)
+}
+
+pub async fn zrem(req: &KmReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let removed: u64 = cmd("ZREM").arg(format_key(&req.key, bs_inst.1)).arg(&req.member).query_async(&mut *conn).await?;
+    Ok(removed > 0)
+}
+
+pub async fn zrange(req: &KRangeReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<KmsResp>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let members: Vec<(String, f64)> = cmd("ZRANGE").arg(format_key(&req.key, bs_inst.1)).arg(req.start).arg(req.stop).arg("WITHSCORES").query_async(&mut *conn).await?;
+    Ok(members.into_iter().map(|(member, score)| KmsResp { member, score }).collect())
+}
+
+// batch operations
+
+fn to_redis_args(key: String, cmd_req: &CacheCmdReq) -> TardisResult<Vec<String>> {
+    let name = match cmd_req.op {
+        CacheCmdKind::Set => "SET",
+        CacheCmdKind::SetEx => "SET",
+        CacheCmdKind::SetNx => "SETNX",
+        CacheCmdKind::Get => "GET",
+        CacheCmdKind::Getset => "GETSET",
+        CacheCmdKind::Incr => "INCRBY",
+        CacheCmdKind::Del => "DEL",
+        CacheCmdKind::Exists => "EXISTS",
+        CacheCmdKind::Expire => "EXPIRE",
+        CacheCmdKind::Ttl => "TTL",
+        CacheCmdKind::Lpush => "LPUSH",
+        CacheCmdKind::Lrangeall => "LRANGE",
+        CacheCmdKind::Llen => "LLEN",
+        CacheCmdKind::Hget => "HGET",
+        CacheCmdKind::Hset => "HSET",
+        CacheCmdKind::HsetNx => "HSETNX",
+        CacheCmdKind::Hdel => "HDEL",
+        CacheCmdKind::Hincr => "HINCRBY",
+        CacheCmdKind::Hexists => "HEXISTS",
+        CacheCmdKind::Hkeys => "HKEYS",
+        CacheCmdKind::Hvals => "HVALS",
+        CacheCmdKind::Hgetall => "HGETALL",
+        CacheCmdKind::Hlen => "HLEN",
+        CacheCmdKind::Setbit => "SETBIT",
+        CacheCmdKind::Getbit => "GETBIT",
+        CacheCmdKind::Bitcount | CacheCmdKind::BitcountRangeByBit => "BITCOUNT",
+        CacheCmdKind::Zadd => "ZADD",
+        CacheCmdKind::Zrem => "ZREM",
+        CacheCmdKind::Zrange => "ZRANGE",
+    };
+    let mut args = vec![name.to_string(), key];
+    match cmd_req.op {
+        CacheCmdKind::Set | CacheCmdKind::SetNx | CacheCmdKind::Getset | CacheCmdKind::Lpush => {
+            args.push(required_arg(cmd_req, &cmd_req.value, "value")?.to_string());
+        }
+        CacheCmdKind::SetEx => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.value, "value")?.to_string(),
+                "EX".to_string(),
+                required_arg(cmd_req, &cmd_req.exp_sec, "exp_sec")?.to_string(),
+            ]);
+        }
+        CacheCmdKind::Incr => {
+            args.push(required_arg(cmd_req, &cmd_req.delta, "delta")?.to_string());
+        }
+        CacheCmdKind::Expire => {
+            args.push(required_arg(cmd_req, &cmd_req.exp_sec, "exp_sec")?.to_string());
+        }
+        CacheCmdKind::Lrangeall => {
+            args.extend(["0".to_string(), "-1".to_string()]);
+        }
+        CacheCmdKind::Hget | CacheCmdKind::Hdel | CacheCmdKind::Hexists => {
+            args.push(required_arg(cmd_req, &cmd_req.field, "field")?.to_string());
+        }
+        CacheCmdKind::Hset | CacheCmdKind::HsetNx => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.field, "field")?.to_string(),
+                required_arg(cmd_req, &cmd_req.value, "value")?.to_string(),
+            ]);
+        }
+        CacheCmdKind::Hincr => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.field, "field")?.to_string(),
+                required_arg(cmd_req, &cmd_req.delta, "delta")?.to_string(),
+            ]);
+        }
+        CacheCmdKind::Setbit => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.offset, "offset")?.to_string(),
+                if *required_arg(cmd_req, &cmd_req.bit_value, "bit_value")? { "1" } else { "0" }.to_string(),
+            ]);
+        }
+        CacheCmdKind::Getbit => {
+            args.push(required_arg(cmd_req, &cmd_req.offset, "offset")?.to_string());
+        }
+        CacheCmdKind::BitcountRangeByBit => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.start, "start")?.to_string(),
+                required_arg(cmd_req, &cmd_req.end, "end")?.to_string(),
+                "BIT".to_string(),
+            ]);
+        }
+        CacheCmdKind::Zadd => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.score, "score")?.to_string(),
+                required_arg(cmd_req, &cmd_req.member, "member")?.to_string(),
+            ]);
+        }
+        CacheCmdKind::Zrem => {
+            args.push(required_arg(cmd_req, &cmd_req.member, "member")?.to_string());
+        }
+        CacheCmdKind::Zrange => {
+            args.extend([
+                required_arg(cmd_req, &cmd_req.start, "start")?.to_string(),
+                required_arg(cmd_req, &cmd_req.end, "end")?.to_string(),
+                "WITHSCORES".to_string(),
+            ]);
+        }
+        CacheCmdKind::Get
+        | CacheCmdKind::Del
+        | CacheCmdKind::Exists
+        | CacheCmdKind::Ttl
+        | CacheCmdKind::Llen
+        | CacheCmdKind::Hkeys
+        | CacheCmdKind::Hvals
+        | CacheCmdKind::Hgetall
+        | CacheCmdKind::Hlen
+        | CacheCmdKind::Bitcount => {}
+    }
+    Ok(args)
+}
+
+fn to_json_value(op: CacheCmdKind, value: &RedisValue) -> TardisResult<JsonValue> {
+    let result = match op {
+        CacheCmdKind::Set | CacheCmdKind::SetEx | CacheCmdKind::Del | CacheCmdKind::Expire | CacheCmdKind::Lpush | CacheCmdKind::Hset | CacheCmdKind::Hdel => JsonValue::Null,
+        CacheCmdKind::SetNx
+        | CacheCmdKind::Exists
+        | CacheCmdKind::HsetNx
+        | CacheCmdKind::Hexists
+        | CacheCmdKind::Setbit
+        | CacheCmdKind::Getbit
+        | CacheCmdKind::Zadd
+        | CacheCmdKind::Zrem => json!(from_redis_value::<bool>(value)?),
+        CacheCmdKind::Get | CacheCmdKind::Getset | CacheCmdKind::Hget => json!(from_redis_value::<Option<String>>(value)?),
+        CacheCmdKind::Incr | CacheCmdKind::Ttl | CacheCmdKind::Hincr => json!(from_redis_value::<i64>(value)?),
+        CacheCmdKind::Llen | CacheCmdKind::Hlen | CacheCmdKind::Bitcount | CacheCmdKind::BitcountRangeByBit => json!(from_redis_value::<u64>(value)?),
+        CacheCmdKind::Lrangeall | CacheCmdKind::Hkeys | CacheCmdKind::Hvals => json!(from_redis_value::<Vec<String>>(value)?),
+        CacheCmdKind::Hgetall => json!(from_redis_value::<HashMap<String, String>>(value)?),
+        CacheCmdKind::Zrange => json!(from_redis_value::<Vec<(String, f64)>>(value)?.into_iter().map(|(member, score)| KmsResp { member, score }).collect::<Vec<_>>()),
+    };
+    Ok(result)
+}
+
+// Snapshot the keys, run the commands and restore the snapshots if any command fails,
+// scripts are executed atomically, so no other client commands are interleaved.
+const BATCH_SCRIPT: &str = r#"
+local snapshots = {}
+for i, key in ipairs(KEYS) do
+    snapshots[i] = { redis.call('DUMP', key), redis.call('PTTL', key) }
+end
+local results = {}
+for i, args in ipairs(cjson.decode(ARGV[1])) do
+    local result = redis.pcall(unpack(args))
+    if type(result) == 'table' and result.err then
+        for j, key in ipairs(KEYS) do
+            redis.call('DEL', key)
+            if snapshots[j][1] then
+                redis.call('RESTORE', key, math.max(snapshots[j][2], 0), snapshots[j][1])
+            end
+        end
+        return redis.error_reply('Command ' .. i .. ' failed: ' .. result.err)
+    end
+    results[i] = result
+end
+return results
+"#;
+
+/// Execute the commands in order in a lua script, any failed command rolls back the whole batch.
+pub async fn batch(req: &CacheBatchReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<JsonValue>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut keys = vec![];
+    let mut cmds = vec![];
+    for cmd_req in &req.cmds {
+        let key = format_key(&cmd_req.key, bs_inst.1);
+        if !keys.contains(&key) {
+            keys.push(key.clone());
+        }
+        cmds.push(to_redis_args(key, cmd_req)?);
+    }
+    let mut conn = bs_inst.0.cmd().await?;
+    let values: Vec<RedisValue> = cmd("EVAL").arg(BATCH_SCRIPT).arg(keys.len()).arg(&keys).arg(json!(cmds).to_string()).query_async(&mut *conn).await?;
+    req.cmds.iter().zip(values.iter()).map(|(cmd_req, value)| to_json_value(cmd_req.op, value)).collect()
+}
+
+// publish/subscribe operations
+
+pub async fn publish(req: &PublishReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let mut conn = bs_inst.0.cmd().await?;
+    let receivers: u64 = cmd("PUBLISH").arg(format_key(&req.channel, bs_inst.1)).arg(&req.message).query_async(&mut *conn).await?;
+    Ok(receivers)
+}
+
+pub async fn subscribe(req: &ChannelReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<BoxStream<'static, String>> {
+    let bs_inst = inst.inst::<TardisCacheClient>();
+    let conn_uri = bs_inst.1.get(cache_redis_initializer::CONN_URI_FLAG).expect("ignore");
+    // Subscriptions occupy the connection, so a dedicated connection is created and released when the stream is dropped
+    let mut pubsub = RedisClient::open(conn_uri.as_str())?.get_async_pubsub().await?;
+    pubsub.subscribe(format_key(&req.channel, bs_inst.1)).await?;
+    Ok(pubsub.into_on_message().filter_map(|msg| async move { msg.get_payload::<String>().ok() }).boxed())
+}
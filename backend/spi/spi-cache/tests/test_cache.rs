@@ -3,6 +3,7 @@ use std::time::Duration;
 
 use bios_basic::rbum::serv::rbum_kind_serv::RbumKindServ;
 use bios_basic::spi::dto::spi_bs_dto::SpiBsAddReq;
+use bios_basic::spi::spi_constants;
 use bios_basic::test::init_test_container;
 use bios_basic::test::test_http_client::TestHttpClient;
 use bios_spi_cache::cache_constants::{self, DOMAIN_CODE};
@@ -38,7 +39,8 @@ async fn init_data() -> TardisResult<()> {
     sleep(Duration::from_millis(500)).await;
 
     let funs = TardisFuns::inst_with_db_conn(DOMAIN_CODE.to_string(), None);
-    let kind_id = RbumKindServ::get_rbum_kind_id_by_code(cache_constants::SPI_REDIS_KIND_CODE, &funs).await?.unwrap();
+    let redis_kind_id = RbumKindServ::get_rbum_kind_id_by_code(cache_constants::SPI_REDIS_KIND_CODE, &funs).await?.unwrap();
+    let pg_kind_id = RbumKindServ::get_rbum_kind_id_by_code(spi_constants::SPI_PG_KIND_CODE, &funs).await?.unwrap();
     let ctx = TardisContext {
         own_paths: "".to_string(),
         ak: "".to_string(),
@@ -57,7 +59,7 @@ async fn init_data() -> TardisResult<()> {
             "/ci/manage/bs",
             &SpiBsAddReq {
                 name: TrimString("test-spi".to_string()),
-                kind_id: TrimString(kind_id),
+                kind_id: TrimString(redis_kind_id),
                 conn_uri: env::var("TARDIS_FW.CACHE.URL").unwrap(),
                 ak: TrimString("".to_string()),
                 sk: TrimString("".to_string()),
@@ -70,7 +72,29 @@ async fn init_data() -> TardisResult<()> {
 
     let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app001", bs_id), &Void {}).await;
 
-    test_cache_proc::test(&mut client).await?;
+    test_cache_proc::test("app001", &mut client).await?;
+
+    // Embedded backend, the cache data is persisted to the relational database
+    client.set_auth(&ctx)?;
+    let bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-pg".to_string()),
+                kind_id: TrimString(pg_kind_id),
+                conn_uri: env::var("TARDIS_FW.DB.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app002", bs_id), &Void {}).await;
+
+    test_cache_proc::test("app002", &mut client).await?;
 
     Ok(())
 }
@@ -1,21 +1,34 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_cache::dto::cache_proc_dto::{ExpReq, KIncrReq, KReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KvReq, KvWithExReq};
+use bios_spi_cache::cache_constants::DOMAIN_CODE;
+use bios_spi_cache::dto::cache_proc_dto::{
+    CacheBatchReq, CacheCmdKind, CacheCmdReq, ExpReq, KIncrReq, KRangeReq, KReq, KbReq, KbvReq, KfIncrReq, KfReq, KfvReq, KmReq, KmsReq, KmsResp, KsReq, KvReq, KvWithExReq,
+    KvsReq, PublishReq,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::futures::StreamExt;
 use tardis::log::info;
+use tardis::serde_json::{json, Value as JsonValue};
+use tardis::tokio::time::timeout;
 use tardis::web::web_resp::{TardisResp, Void};
+use tardis::TardisFuns;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::{connect_async_tls_with_config, Connector};
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
-    client.set_auth(&TardisContext {
-        own_paths: "t1/app001".to_string(),
-        ak: "app001".to_string(),
+pub async fn test(app_id: &str, client: &mut TestHttpClient) -> TardisResult<()> {
+    let ctx = TardisContext {
+        own_paths: format!("t1/{app_id}"),
+        ak: app_id.to_string(),
         roles: vec![],
         groups: vec![],
         owner: "".to_string(),
         ..Default::default()
-    })?;
+    };
+    client.set_auth(&ctx)?;
 
     info!("【test_cache_basic】");
     let _: Void = client
@@ -325,5 +338,275 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     let result: u32 = client.put("/ci/proc/bitcount", &KReq { key: "k_bitmap".into() }).await;
     assert_eq!(result, 2);
 
+    info!("【test_cache_multiple_keys】");
+
+    let _: Void = client
+        .put(
+            "/ci/proc/mset",
+            &KvsReq {
+                items: vec![
+                    KvReq {
+                        key: "k_m1".into(),
+                        value: "v_m1".to_string(),
+                    },
+                    KvReq {
+                        key: "k_m2".into(),
+                        value: "v_m2".to_string(),
+                    },
+                ],
+            },
+        )
+        .await;
+    let result: Vec<Option<String>> = client
+        .put(
+            "/ci/proc/mget",
+            &KsReq {
+                keys: vec!["k_m1".into(), "k_m_none".into(), "k_m2".into()],
+            },
+        )
+        .await;
+    assert_eq!(result, vec![Some("v_m1".to_string()), None, Some("v_m2".to_string())]);
+
+    info!("【test_cache_sorted_set】");
+
+    let result: bool = client
+        .put(
+            "/ci/proc/zadd",
+            &KmsReq {
+                key: "k_zset".into(),
+                member: "m1".to_string(),
+                score: 2.0,
+            },
+        )
+        .await;
+    assert!(result);
+    let result: bool = client
+        .put(
+            "/ci/proc/zadd",
+            &KmsReq {
+                key: "k_zset".into(),
+                member: "m2".to_string(),
+                score: 1.0,
+            },
+        )
+        .await;
+    assert!(result);
+    let result: bool = client
+        .put(
+            "/ci/proc/zadd",
+            &KmsReq {
+                key: "k_zset".into(),
+                member: "m1".to_string(),
+                score: 3.0,
+            },
+        )
+        .await;
+    assert!(!result);
+    let result: Vec<KmsResp> = client
+        .put(
+            "/ci/proc/zrange",
+            &KRangeReq {
+                key: "k_zset".into(),
+                start: 0,
+                stop: -1,
+            },
+        )
+        .await;
+    assert_eq!(
+        result,
+        vec![
+            KmsResp {
+                member: "m2".to_string(),
+                score: 1.0
+            },
+            KmsResp {
+                member: "m1".to_string(),
+                score: 3.0
+            }
+        ]
+    );
+    let result: bool = client
+        .put(
+            "/ci/proc/zrem",
+            &KmReq {
+                key: "k_zset".into(),
+                member: "m2".to_string(),
+            },
+        )
+        .await;
+    assert!(result);
+    let result: Vec<KmsResp> = client
+        .put(
+            "/ci/proc/zrange",
+            &KRangeReq {
+                key: "k_zset".into(),
+                start: 0,
+                stop: -1,
+            },
+        )
+        .await;
+    assert_eq!(result.len(), 1);
+
+    info!("【test_cache_batch】");
+
+    let result: Vec<JsonValue> = client
+        .post(
+            "/ci/proc/batch",
+            &CacheBatchReq {
+                cmds: vec![
+                    CacheCmdReq {
+                        op: CacheCmdKind::Set,
+                        key: "k_batch".into(),
+                        field: None,
+                        value: Some("1".to_string()),
+                        delta: None,
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                    CacheCmdReq {
+                        op: CacheCmdKind::Incr,
+                        key: "k_batch".into(),
+                        field: None,
+                        value: None,
+                        delta: Some(2),
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                    CacheCmdReq {
+                        op: CacheCmdKind::Hset,
+                        key: "k_batch_hash".into(),
+                        field: Some("f1".into()),
+                        value: Some("v1".to_string()),
+                        delta: None,
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                    CacheCmdReq {
+                        op: CacheCmdKind::Get,
+                        key: "k_batch".into(),
+                        field: None,
+                        value: None,
+                        delta: None,
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                    CacheCmdReq {
+                        op: CacheCmdKind::Hget,
+                        key: "k_batch_hash".into(),
+                        field: Some("f1".into()),
+                        value: None,
+                        delta: None,
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                ],
+            },
+        )
+        .await;
+    assert_eq!(result, vec![JsonValue::Null, json!(3), JsonValue::Null, json!("3"), json!("v1")]);
+
+    // the failed command rolls back the whole batch
+    let result: TardisResp<Vec<JsonValue>> = client
+        .post_resp(
+            "/ci/proc/batch",
+            &CacheBatchReq {
+                cmds: vec![
+                    CacheCmdReq {
+                        op: CacheCmdKind::Set,
+                        key: "k_batch".into(),
+                        field: None,
+                        value: Some("a".to_string()),
+                        delta: None,
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                    CacheCmdReq {
+                        op: CacheCmdKind::Incr,
+                        key: "k_batch".into(),
+                        field: None,
+                        value: None,
+                        delta: Some(1),
+                        exp_sec: None,
+                        offset: None,
+                        bit_value: None,
+                        start: None,
+                        end: None,
+                        member: None,
+                        score: None,
+                    },
+                ],
+            },
+        )
+        .await;
+    assert_ne!(result.code, "200");
+    let result: Option<String> = client.put("/ci/proc/get", &KReq { key: "k_batch".into() }).await;
+    assert_eq!(result, Some("3".to_string()));
+
+    info!("【test_cache_publish】");
+
+    let result: u64 = client
+        .post(
+            "/ci/proc/publish",
+            &PublishReq {
+                channel: "c1".into(),
+                message: "hello".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, 0);
+
+    info!("【test_cache_subscribe】");
+
+    let mut request = format!("wss://127.0.0.1:8080/{DOMAIN_CODE}/ci/proc/subscribe?channel=c1").into_client_request().unwrap();
+    request.headers_mut().insert(
+        HeaderName::from_bytes(TardisFuns::fw_config().web_server().context_conf.context_header_name.as_bytes()).unwrap(),
+        HeaderValue::from_str(&TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(&ctx)?)).unwrap(),
+    );
+    // The test server uses a self-signed certificate
+    let connector = Connector::NativeTls(native_tls::TlsConnector::builder().danger_accept_invalid_certs(true).build().unwrap());
+    let (mut messages, _) = connect_async_tls_with_config(request, None, false, Some(connector)).await.unwrap();
+    let result: u64 = client
+        .post(
+            "/ci/proc/publish",
+            &PublishReq {
+                channel: "c1".into(),
+                message: "hello".to_string(),
+            },
+        )
+        .await;
+    assert_eq!(result, 1);
+    let message = timeout(Duration::from_secs(5), messages.next()).await.unwrap().unwrap().unwrap();
+    assert_eq!(message.into_text().unwrap(), "hello");
+
     Ok(())
 }
@@ -5,7 +5,7 @@ use tardis::web::poem_openapi::param::Query;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelFindReq, GraphRelUpgradeVersionReq, GraphSubgraphResp, GraphTraversalPageResp};
 use crate::serv::graph_basic_serv;
 #[derive(Clone)]
 pub struct GraphCiRelApi;
@@ -60,4 +60,93 @@ impl GraphCiRelApi {
         let resp = graph_basic_serv::find_rels(from_key.0, from_version.0, depth.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+
+    /// Find Rels By Conditions
+    #[oai(path = "/rels/find", method = "put")]
+    async fn search_rels(&self, find_req: Json<GraphRelFindReq>, ctx: TardisContextExtractor) -> TardisApiResult<GraphRelDetailResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::search_rels(&find_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Ancestors
+    #[oai(path = "/ancestors", method = "get")]
+    async fn find_ancestors(
+        &self,
+        key: Query<String>,
+        version: Query<String>,
+        tag: Query<Option<String>>,
+        depth: Query<Option<u8>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<GraphTraversalPageResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_ancestors(key.0, version.0, tag.0, depth.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Descendants
+    #[oai(path = "/descendants", method = "get")]
+    async fn find_descendants(
+        &self,
+        key: Query<String>,
+        version: Query<String>,
+        tag: Query<Option<String>>,
+        depth: Query<Option<u8>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<GraphTraversalPageResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_descendants(key.0, version.0, tag.0, depth.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Impact
+    #[oai(path = "/impact", method = "get")]
+    async fn find_impact(
+        &self,
+        key: Query<String>,
+        version: Query<String>,
+        depth: Query<Option<u8>>,
+        page_number: Query<u32>,
+        page_size: Query<u32>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<GraphTraversalPageResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_impact(key.0, version.0, depth.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Shortest Path
+    #[oai(path = "/path/shortest", method = "get")]
+    async fn find_shortest_path(
+        &self,
+        from_key: Query<String>,
+        from_version: Query<String>,
+        to_key: Query<String>,
+        to_version: Query<String>,
+        tag: Query<Option<String>>,
+        depth: Query<Option<u8>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Option<GraphSubgraphResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_shortest_path(from_key.0, from_version.0, to_key.0, to_version.0, tag.0, depth.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Find Cycles
+    #[oai(path = "/cycles", method = "get")]
+    async fn find_cycles(
+        &self,
+        tag: Query<Option<String>>,
+        key: Query<Option<String>>,
+        version: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Vec<GraphSubgraphResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = graph_basic_serv::find_cycles(tag.0, key.0, version.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
 }
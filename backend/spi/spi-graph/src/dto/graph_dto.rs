@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
+use bios_basic::dto::BasicQueryCondInfo;
 use serde::{Deserialize, Serialize};
 use tardis::{
     basic::field::TrimString,
     chrono::{DateTime, Utc},
     db::sea_orm,
+    serde_json::Value,
     web::poem_openapi,
 };
 
@@ -18,6 +20,8 @@ pub struct GraphRelAddReq {
     pub to_key: TrimString,
     #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
     pub to_version: String,
+    /// Extended attributes of the relationship, e.g. weight, label
+    pub ext: Option<Value>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -45,6 +49,20 @@ pub struct GraphRelUpgradeDelRelReq {
     pub rel_version: Option<String>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphRelFindReq {
+    pub from_key: TrimString,
+    #[oai(validator(pattern = r"^[a-z0-9-_.]+$"))]
+    pub from_version: String,
+    pub depth: Option<u8>,
+    pub tags: Option<Vec<String>>,
+    /// Prefix match of the own_paths of the relationships
+    pub own_paths: Option<String>,
+    pub owners: Option<Vec<String>>,
+    // Extended filtering conditions
+    pub ext: Option<Vec<BasicQueryCondInfo>>,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct GraphRelDetailResp {
     pub key: String,
@@ -52,3 +70,46 @@ pub struct GraphRelDetailResp {
     pub form_rels: HashMap<String, Vec<GraphRelDetailResp>>,
     pub to_rels: HashMap<String, Vec<GraphRelDetailResp>>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphNodeResp {
+    pub key: String,
+    pub version: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphTraversalNodeResp {
+    pub key: String,
+    pub version: String,
+    /// Distance from the start node
+    pub depth: u8,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdgeResp {
+    pub tag: String,
+    pub from_key: String,
+    pub from_version: String,
+    pub to_key: String,
+    pub to_version: String,
+    pub ext: Value,
+    pub own_paths: String,
+    pub owner: String,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphSubgraphResp {
+    pub nodes: Vec<GraphNodeResp>,
+    pub edges: Vec<GraphEdgeResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct GraphTraversalPageResp {
+    pub page_number: u32,
+    pub page_size: u32,
+    pub total_size: u64,
+    /// Nodes reached from the start node, ordered by depth
+    pub nodes: Vec<GraphTraversalNodeResp>,
+    /// Edges leading to the nodes of the current page
+    pub edges: Vec<GraphEdgeResp>,
+}
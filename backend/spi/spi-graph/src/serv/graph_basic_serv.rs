@@ -5,7 +5,7 @@ use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::TardisFunsInst;
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelFindReq, GraphRelUpgradeVersionReq, GraphSubgraphResp, GraphTraversalPageResp};
 use crate::graph_initializer;
 
 use super::pg;
@@ -22,6 +22,11 @@ spi_dispatch_service! {
         upgrade_version(upgrade_version_req: &GraphRelUpgradeVersionReq) -> TardisResult<()>;
         find_versions(tag: String, key: String) -> TardisResult<Vec<GraphNodeVersionResp>>;
         find_rels(from_key: String, from_version: String, depth: Option<u8>) -> TardisResult<GraphRelDetailResp>;
+        search_rels(find_req: &GraphRelFindReq) -> TardisResult<GraphRelDetailResp>;
+        find_ancestors(key: String, version: String, tag: Option<String>, depth: Option<u8>, page_number: u32, page_size: u32) -> TardisResult<GraphTraversalPageResp>;
+        find_descendants(key: String, version: String, tag: Option<String>, depth: Option<u8>, page_number: u32, page_size: u32) -> TardisResult<GraphTraversalPageResp>;
+        find_shortest_path(from_key: String, from_version: String, to_key: String, to_version: String, tag: Option<String>, depth: Option<u8>) -> TardisResult<Option<GraphSubgraphResp>>;
+        find_cycles(tag: Option<String>, key: Option<String>, version: Option<String>) -> TardisResult<Vec<GraphSubgraphResp>>;
     }
 }
 
@@ -49,3 +54,16 @@ pub async fn delete_rels(
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
+
+/// Find the nodes that depend on the specified node (at the specified version) through relationships of any tag.
+pub async fn find_impact(
+    key: String,
+    version: String,
+    depth: Option<u8>,
+    page_number: u32,
+    page_size: u32,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<GraphTraversalPageResp> {
+    find_ancestors(key, version, None, depth, page_number, page_size, funs, ctx).await
+}
@@ -1,28 +1,43 @@
-use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer};
+use std::collections::{HashMap, HashSet};
+
+use bios_basic::{
+    dto::BasicQueryCondInfo,
+    enumeration::BasicQueryOpKind,
+    helper::db_helper,
+    spi::{spi_funs::SpiBsInst, spi_initializer},
+};
 use itertools::Itertools;
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
+    basic::{dto::TardisContext, field::TrimString, result::TardisResult},
     db::{
-        reldb_client::TardisRelDBClient,
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
         sea_orm::{self, Value},
     },
+    serde_json::{self, json},
     TardisFunsInst,
 };
 
-use crate::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeVersionReq};
+use crate::dto::graph_dto::{
+    GraphEdgeResp, GraphNodeResp, GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelFindReq, GraphRelUpgradeVersionReq, GraphSubgraphResp, GraphTraversalNodeResp,
+    GraphTraversalPageResp,
+};
 
 use super::graph_pg_initializer;
 
 pub async fn add_rel(add_req: &GraphRelAddReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let ext = add_req.ext.clone().unwrap_or_else(|| json!({}));
+    // the ownership always comes from the context, so that relations can not be written into the scope of others
+    let own_paths = &ctx.own_paths;
+    let owner = &ctx.owner;
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name}
-        (tag, from_key, from_version, to_key, to_version, reverse)
+        (tag, from_key, from_version, to_key, to_version, reverse, ext, own_paths, owner)
     VALUES
-        ($1, $2, $3, $4, $5, false)
+        ($1, $2, $3, $4, $5, false, $6, $7, $8)
         "#
         ),
         vec![
@@ -31,15 +46,18 @@ pub async fn add_rel(add_req: &GraphRelAddReq, _funs: &TardisFunsInst, ctx: &Tar
             Value::from(add_req.from_version.as_str()),
             Value::from(add_req.to_key.to_string()),
             Value::from(add_req.to_version.as_str()),
+            Value::from(ext.clone()),
+            Value::from(own_paths.as_str()),
+            Value::from(owner.as_str()),
         ],
     )
     .await?;
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name}
-        (tag, from_key, from_version, to_key, to_version, reverse)
+        (tag, from_key, from_version, to_key, to_version, reverse, ext, own_paths, owner)
     VALUES
-        ($1, $2, $3, $4, $5, true)
+        ($1, $2, $3, $4, $5, true, $6, $7, $8)
         "#
         ),
         vec![
@@ -48,6 +66,9 @@ pub async fn add_rel(add_req: &GraphRelAddReq, _funs: &TardisFunsInst, ctx: &Tar
             Value::from(add_req.to_version.as_str()),
             Value::from(add_req.from_key.to_string()),
             Value::from(add_req.from_version.as_str()),
+            Value::from(ext),
+            Value::from(own_paths.as_str()),
+            Value::from(owner.as_str()),
         ],
     )
     .await?;
@@ -88,8 +109,8 @@ pub async fn upgrade_version(upgrade_version_req: &GraphRelUpgradeVersionReq, _f
     conn.begin().await?;
     conn.execute_one(
         format!(
-            r#"INSERT INTO {table_name}
-SELECT tag, from_key, $1, to_key, to_version, reverse
+            r#"INSERT INTO {table_name} (tag, from_key, from_version, to_key, to_version, reverse, ext, own_paths, owner)
+SELECT tag, from_key, $1, to_key, to_version, reverse, ext, own_paths, owner
 FROM {table_name}
 WHERE from_key = $2 AND from_version = $3 {}"#,
             where_fragments.replace("rel_key", "to_key").replace("rel_version", "to_version"),
@@ -100,8 +121,8 @@ WHERE from_key = $2 AND from_version = $3 {}"#,
     .await?;
     conn.execute_one(
         format!(
-            r#"INSERT INTO {table_name}
-SELECT tag, from_key, from_version, to_key, $1, reverse
+            r#"INSERT INTO {table_name} (tag, from_key, from_version, to_key, to_version, reverse, ext, own_paths, owner)
+SELECT tag, from_key, from_version, to_key, $1, reverse, ext, own_paths, owner
 FROM {table_name}
 WHERE to_key = $2 AND to_version = $3 {}"#,
             where_fragments.replace("rel_key", "from_key").replace("rel_version", "from_version"),
@@ -135,10 +156,23 @@ pub async fn find_rels(
     from_key: String,
     from_version: String,
     depth: Option<u8>,
-    _funs: &TardisFunsInst,
+    funs: &TardisFunsInst,
     ctx: &TardisContext,
     inst: &SpiBsInst,
 ) -> TardisResult<GraphRelDetailResp> {
+    let find_req = GraphRelFindReq {
+        from_key: TrimString(from_key),
+        from_version,
+        depth,
+        tags: None,
+        own_paths: None,
+        owners: None,
+        ext: None,
+    };
+    search_rels(&find_req, funs, ctx, inst).await
+}
+
+pub async fn search_rels(find_req: &GraphRelFindReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<GraphRelDetailResp> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let mut sql_vals: Vec<Value> = vec![];
     if let Some(schema_name) = spi_initializer::common_pg::get_schema_name_from_ext(bs_inst.1) {
@@ -146,22 +180,110 @@ pub async fn find_rels(
     } else {
         sql_vals.push(Value::from("public".to_string()));
     }
-    sql_vals.push(Value::from(from_key.as_str()));
-    sql_vals.push(Value::from(from_version.as_str()));
-    if let Some(depth) = depth {
+    sql_vals.push(Value::from(find_req.from_key.to_string()));
+    sql_vals.push(Value::from(find_req.from_version.as_str()));
+    if let Some(depth) = find_req.depth {
         sql_vals.push(Value::from(depth));
     }
-    let (conn, _) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
-    let result = conn.find_dtos_by_sql(
-        &format!(r#"SELECT o_tag AS tag, o_from_key AS from_key, o_from_version AS from_version, o_to_key AS to_key, o_to_version AS to_version, o_reverse AS reverse FROM public.GRAPH_SEARCH($1, $2, $3{}) ORDER BY O_DEPTH, O_TAG"#, if depth.is_some() { ", $4" } else { "" }),
-        sql_vals.clone(),
-    )
-    .await?;
+    let search_args = if find_req.depth.is_some() { "$1, $2, $3, $4" } else { "$1, $2, $3" };
+    let mut where_fragments: Vec<String> = vec!["1 = 1".to_string()];
+    if let Some(tags) = &find_req.tags {
+        sql_vals.push(Value::from(tags.clone()));
+        where_fragments.push(format!("g.tag = ANY(${})", sql_vals.len()));
+    }
+    if let Some(own_paths) = &find_req.own_paths {
+        sql_vals.push(Value::from(format!("{own_paths}%")));
+        where_fragments.push(format!("g.own_paths LIKE ${}", sql_vals.len()));
+    }
+    if let Some(owners) = &find_req.owners {
+        sql_vals.push(Value::from(owners.clone()));
+        where_fragments.push(format!("g.owner = ANY(${})", sql_vals.len()));
+    }
+    if let Some(ext) = &find_req.ext {
+        where_fragments.extend(package_ext_where_fragments("g.ext", ext, &mut sql_vals, funs)?);
+    }
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    // The relationships that do not meet the conditions are removed, and so are the relationships that are only reachable through them
+    let result = conn
+        .find_dtos_by_sql(
+            &format!(
+                r#"SELECT s.o_tag AS tag, s.o_from_key AS from_key, s.o_from_version AS from_version, s.o_to_key AS to_key, s.o_to_version AS to_version, s.o_reverse AS reverse,
+    g.ext, g.own_paths, g.owner
+FROM public.GRAPH_SEARCH({search_args}) s
+    INNER JOIN {table_name} g ON g.tag = s.o_tag AND g.from_key = s.o_from_key AND g.from_version = s.o_from_version
+        AND g.to_key = s.o_to_key AND g.to_version = s.o_to_version AND g.reverse = s.o_reverse
+WHERE {}
+ORDER BY s.o_depth, s.o_tag"#,
+                where_fragments.join(" AND ")
+            ),
+            sql_vals,
+        )
+        .await?;
 
-    let result = package_rels(&from_key, &from_version, &result);
+    let result = package_rels(&find_req.from_key.to_string(), &find_req.from_version, &result);
     Ok(result)
 }
 
+fn package_ext_where_fragments(ext_column: &str, ext: &[BasicQueryCondInfo], sql_vals: &mut Vec<Value>, funs: &TardisFunsInst) -> TardisResult<Vec<String>> {
+    let err_not_legal = |ext_item: &BasicQueryCondInfo| {
+        Err(funs.err().bad_request(
+            "spi-graph-rel",
+            "find",
+            &format!("The ext field=[{}] value=[{}] operation=[{}] is not legal.", ext_item.field, ext_item.value, ext_item.op),
+            "400-spi-graph-op-not-legal",
+        ))
+    };
+    let mut where_fragments = vec![];
+    for ext_item in ext {
+        let field = &ext_item.field;
+        if let Some(fragment) = value_free_ext_fragment(ext_column, ext_item) {
+            where_fragments.push(fragment);
+            continue;
+        }
+        let Some(mut value) = db_helper::json_to_sea_orm_value(&ext_item.value, &ext_item.op) else {
+            return err_not_legal(ext_item);
+        };
+        if ext_item.op == BasicQueryOpKind::In || ext_item.op == BasicQueryOpKind::NotIn {
+            let place_holders = value
+                .into_iter()
+                .map(|val| {
+                    sql_vals.push(val);
+                    format!("${}", sql_vals.len())
+                })
+                .join(", ");
+            let not = if ext_item.op == BasicQueryOpKind::NotIn { "NOT " } else { "" };
+            where_fragments.push(format!("{not}({ext_column} -> '{field}' ?| array[{place_holders}])"));
+        } else {
+            if value.len() > 1 {
+                return err_not_legal(ext_item);
+            }
+            let Some(value) = value.pop() else {
+                return err_not_legal(ext_item);
+            };
+            let cast = match value {
+                Value::Bool(_) => "::boolean",
+                Value::BigInt(_) | Value::BigUnsigned(_) => "::bigint",
+                Value::Double(_) => "::double precision",
+                Value::ChronoDateTimeUtc(_) => "::timestamp with time zone",
+                _ => "",
+            };
+            sql_vals.push(value);
+            where_fragments.push(format!("({ext_column} ->> '{field}'){cast} {} ${}", ext_item.op.to_sql(), sql_vals.len()));
+        }
+    }
+    Ok(where_fragments)
+}
+
+fn value_free_ext_fragment(ext_column: &str, ext_item: &BasicQueryCondInfo) -> Option<String> {
+    let field = &ext_item.field;
+    match ext_item.op {
+        BasicQueryOpKind::IsNull => Some(format!("{ext_column} ->> '{field}' IS NULL")),
+        BasicQueryOpKind::IsNotNull => Some(format!("{ext_column} ->> '{field}' IS NOT NULL")),
+        BasicQueryOpKind::IsNullOrEmpty => Some(format!("({ext_column} ->> '{field}' IS NULL OR {ext_column} ->> '{field}' = '')")),
+        _ => None,
+    }
+}
+
 fn package_rels(from_key: &str, from_version: &str, records: &[GraphRelRecord]) -> GraphRelDetailResp {
     let form_rels = records
         .iter()
@@ -247,6 +369,320 @@ pub async fn delete_rels(
     Ok(())
 }
 
+pub async fn find_ancestors(
+    key: String,
+    version: String,
+    tag: Option<String>,
+    depth: Option<u8>,
+    page_number: u32,
+    page_size: u32,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<GraphTraversalPageResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let traversal = traverse(&conn, &table_name, (key, version), true, tag.as_deref(), depth, None, funs).await?;
+    Ok(package_traversal_page(traversal, page_number, page_size))
+}
+
+pub async fn find_descendants(
+    key: String,
+    version: String,
+    tag: Option<String>,
+    depth: Option<u8>,
+    page_number: u32,
+    page_size: u32,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<GraphTraversalPageResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let traversal = traverse(&conn, &table_name, (key, version), false, tag.as_deref(), depth, None, funs).await?;
+    Ok(package_traversal_page(traversal, page_number, page_size))
+}
+
+pub async fn find_shortest_path(
+    from_key: String,
+    from_version: String,
+    to_key: String,
+    to_version: String,
+    tag: Option<String>,
+    depth: Option<u8>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<GraphSubgraphResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let target = (to_key, to_version);
+    let traversal = traverse(&conn, &table_name, (from_key, from_version), false, tag.as_deref(), depth, Some(&target), funs).await?;
+    let Some(mut depth) = traversal.depths.get(&target).copied() else {
+        return Ok(None);
+    };
+    // Walk back from the target, each step choosing a rel that comes from the previous level
+    let mut nodes = vec![target.clone()];
+    let mut edges = vec![];
+    let mut current = target;
+    while depth > 0 {
+        let Some(rel) = traversal.rels.iter().find(|rel| rel.next == current && traversal.depths.get(&rel.near) == Some(&(depth - 1))) else {
+            return Ok(None);
+        };
+        edges.push(rel.edge.clone());
+        nodes.push(rel.near.clone());
+        current = rel.near.clone();
+        depth -= 1;
+    }
+    Ok(Some(GraphSubgraphResp {
+        nodes: nodes.into_iter().rev().map(|(key, version)| GraphNodeResp { key, version }).collect(),
+        edges: edges.into_iter().rev().collect(),
+    }))
+}
+
+/// Find the cycles, each cycle is returned as a strongly connected component.
+///
+/// When [key] and [version] are specified, only the cycles reachable from this node are detected,
+/// otherwise all relationships (of the [tag]) are checked.
+pub async fn find_cycles(
+    tag: Option<String>,
+    key: Option<String>,
+    version: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Vec<GraphSubgraphResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = graph_pg_initializer::init_table_and_conn(bs_inst, ctx, false).await?;
+    let edges = if let (Some(key), Some(version)) = (key, version) {
+        traverse(&conn, &table_name, (key, version), false, tag.as_deref(), None, None, funs).await?.rels.into_iter().map(|rel| rel.edge).collect_vec()
+    } else {
+        let mut sql_vals: Vec<Value> = vec![];
+        let tag_fragment = if let Some(tag) = &tag {
+            sql_vals.push(Value::from(tag.as_str()));
+            " AND tag = $1"
+        } else {
+            ""
+        };
+        conn.find_dtos_by_sql::<GraphRelRecord>(
+            &format!("SELECT tag, from_key, from_version, to_key, to_version, reverse, ext, own_paths, owner FROM {table_name} WHERE reverse = false{tag_fragment}"),
+            sql_vals,
+        )
+        .await?
+        .into_iter()
+        .map(GraphRelRecord::into_edge)
+        .collect_vec()
+    };
+
+    let mut node_idxes: HashMap<NodeId, usize> = HashMap::new();
+    let mut nodes: Vec<NodeId> = vec![];
+    let mut adjacency: Vec<Vec<usize>> = vec![];
+    for edge in &edges {
+        let mut node_idx = |node: NodeId| {
+            *node_idxes.entry(node).or_insert_with_key(|node| {
+                nodes.push(node.clone());
+                adjacency.push(vec![]);
+                nodes.len() - 1
+            })
+        };
+        let from_idx = node_idx((edge.from_key.clone(), edge.from_version.clone()));
+        let to_idx = node_idx((edge.to_key.clone(), edge.to_version.clone()));
+        adjacency[from_idx].push(to_idx);
+    }
+    let cycles = strongly_connected_components(&adjacency)
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|component| {
+            let component_nodes = component.iter().map(|idx| &nodes[*idx]).sorted().collect_vec();
+            let component_edges = edges
+                .iter()
+                .filter(|edge| {
+                    let from = (edge.from_key.clone(), edge.from_version.clone());
+                    let to = (edge.to_key.clone(), edge.to_version.clone());
+                    component_nodes.contains(&&from) && component_nodes.contains(&&to)
+                })
+                .cloned()
+                .collect();
+            GraphSubgraphResp {
+                nodes: component_nodes
+                    .into_iter()
+                    .map(|(key, version)| GraphNodeResp {
+                        key: key.clone(),
+                        version: version.clone(),
+                    })
+                    .collect(),
+                edges: component_edges,
+            }
+        })
+        .collect();
+    Ok(cycles)
+}
+
+/// Upper limit of the nodes visited in one traversal, to protect the database from wide graphs.
+const MAX_TRAVERSAL_NODES: usize = 10000;
+
+type NodeId = (String, String);
+
+struct TraversedRel {
+    near: NodeId,
+    next: NodeId,
+    edge: GraphEdgeResp,
+}
+
+struct Traversal {
+    /// Visited nodes in visiting order, the first one is the start node
+    nodes: Vec<NodeId>,
+    depths: HashMap<NodeId, u8>,
+    rels: Vec<TraversedRel>,
+}
+
+/// Breadth-first traversal, one query per level.
+///
+/// [reverse] = false follows the relationships from [from] to [to], [reverse] = true goes the opposite way.
+/// Stops after the level where [target] is reached.
+async fn traverse(
+    conn: &TardisRelDBlConnection,
+    table_name: &str,
+    start: NodeId,
+    reverse: bool,
+    tag: Option<&str>,
+    depth: Option<u8>,
+    target: Option<&NodeId>,
+    funs: &TardisFunsInst,
+) -> TardisResult<Traversal> {
+    let max_depth = depth.unwrap_or(u8::MAX);
+    let mut traversal = Traversal {
+        nodes: vec![start.clone()],
+        depths: HashMap::from([(start.clone(), 0)]),
+        rels: vec![],
+    };
+    let mut frontier = vec![start];
+    let mut current_depth = 0;
+    while !frontier.is_empty() && current_depth < max_depth && !target.is_some_and(|target| traversal.depths.contains_key(target)) {
+        current_depth += 1;
+        let mut next_frontier = vec![];
+        for record in find_next_rels(conn, table_name, &frontier, reverse, tag).await? {
+            let near = (record.from_key.clone(), record.from_version.clone());
+            let next = (record.to_key.clone(), record.to_version.clone());
+            if !traversal.depths.contains_key(&next) {
+                traversal.depths.insert(next.clone(), current_depth);
+                traversal.nodes.push(next.clone());
+                next_frontier.push(next.clone());
+            }
+            traversal.rels.push(TraversedRel {
+                near,
+                next,
+                edge: record.into_edge(),
+            });
+        }
+        if traversal.nodes.len() > MAX_TRAVERSAL_NODES {
+            return Err(funs.err().bad_request(
+                "spi-graph-rel",
+                "traverse",
+                &format!("the traversal exceeds {MAX_TRAVERSAL_NODES} nodes, please limit the [depth] or [tag]"),
+                "400-spi-graph-traversal-too-large",
+            ));
+        }
+        frontier = next_frontier;
+    }
+    Ok(traversal)
+}
+
+async fn find_next_rels(conn: &TardisRelDBlConnection, table_name: &str, frontier: &[NodeId], reverse: bool, tag: Option<&str>) -> TardisResult<Vec<GraphRelRecord>> {
+    let mut sql_vals: Vec<Value> = vec![
+        Value::from(reverse),
+        Value::from(frontier.iter().map(|(key, _)| key.clone()).collect_vec()),
+        Value::from(frontier.iter().map(|(_, version)| version.clone()).collect_vec()),
+    ];
+    let tag_fragment = if let Some(tag) = tag {
+        sql_vals.push(Value::from(tag));
+        " AND tag = $4"
+    } else {
+        ""
+    };
+    conn.find_dtos_by_sql(
+        &format!(
+            r#"SELECT tag, from_key, from_version, to_key, to_version, reverse, ext, own_paths, owner FROM {table_name}
+WHERE reverse = $1 AND (from_key, from_version) IN (SELECT * FROM UNNEST($2::varchar[], $3::varchar[])){tag_fragment}
+ORDER BY tag, to_key, to_version"#
+        ),
+        sql_vals,
+    )
+    .await
+}
+
+fn package_traversal_page(traversal: Traversal, page_number: u32, page_size: u32) -> GraphTraversalPageResp {
+    let nodes = traversal.nodes.into_iter().skip(1).map(|node| (traversal.depths[&node], node)).sorted().collect_vec();
+    let total_size = nodes.len() as u64;
+    let page_nodes = nodes.into_iter().skip((page_number.max(1) as usize - 1) * page_size as usize).take(page_size as usize).collect_vec();
+    let page_node_ids = page_nodes.iter().map(|(_, node)| node).collect::<HashSet<_>>();
+    let edges = traversal.rels.into_iter().filter(|rel| page_node_ids.contains(&rel.next)).map(|rel| rel.edge).collect();
+    GraphTraversalPageResp {
+        page_number,
+        page_size,
+        total_size,
+        nodes: page_nodes.into_iter().map(|(depth, (key, version))| GraphTraversalNodeResp { key, version, depth }).collect(),
+        edges,
+    }
+}
+
+/// Tarjan's algorithm, implemented iteratively to avoid stack overflow on deep graphs.
+fn strongly_connected_components(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut indexes: Vec<Option<usize>> = vec![None; adjacency.len()];
+    let mut low_links = vec![0; adjacency.len()];
+    let mut on_stack = vec![false; adjacency.len()];
+    let mut stack = vec![];
+    let mut components = vec![];
+    let mut next_index = 0;
+    for root in 0..adjacency.len() {
+        if indexes[root].is_some() {
+            continue;
+        }
+        indexes[root] = Some(next_index);
+        low_links[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        // (node, position of the next child to visit)
+        let mut call_stack = vec![(root, 0)];
+        while let Some((node, child_pos)) = call_stack.last_mut() {
+            let node = *node;
+            if let Some(&child) = adjacency[node].get(*child_pos) {
+                *child_pos += 1;
+                if let Some(child_index) = indexes[child] {
+                    if on_stack[child] {
+                        low_links[node] = low_links[node].min(child_index);
+                    }
+                } else {
+                    indexes[child] = Some(next_index);
+                    low_links[child] = next_index;
+                    next_index += 1;
+                    stack.push(child);
+                    on_stack[child] = true;
+                    call_stack.push((child, 0));
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some((parent, _)) = call_stack.last() {
+                low_links[*parent] = low_links[*parent].min(low_links[node]);
+            }
+            if Some(low_links[node]) == indexes[node] {
+                let mut component = vec![];
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
 #[derive(sea_orm::FromQueryResult)]
 struct GraphRelRecord {
     pub tag: String,
@@ -255,4 +691,36 @@ struct GraphRelRecord {
     pub to_key: String,
     pub to_version: String,
     pub reverse: bool,
+    pub ext: serde_json::Value,
+    pub own_paths: String,
+    pub owner: String,
+}
+
+impl GraphRelRecord {
+    /// Convert to the edge in the original direction
+    fn into_edge(self) -> GraphEdgeResp {
+        if self.reverse {
+            GraphEdgeResp {
+                tag: self.tag,
+                from_key: self.to_key,
+                from_version: self.to_version,
+                to_key: self.from_key,
+                to_version: self.from_version,
+                ext: self.ext,
+                own_paths: self.own_paths,
+                owner: self.owner,
+            }
+        } else {
+            GraphEdgeResp {
+                tag: self.tag,
+                from_key: self.from_key,
+                from_version: self.from_version,
+                to_key: self.to_key,
+                to_version: self.to_version,
+                ext: self.ext,
+                own_paths: self.own_paths,
+                owner: self.owner,
+            }
+        }
+    }
 }
@@ -5,7 +5,7 @@ use tardis::{
 };
 
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -17,6 +17,9 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     to_key character varying NOT NULL,
     to_version character varying NOT NULL,
     reverse bool DEFAULT false NOT NULL, 
+    ext jsonb NOT NULL DEFAULT '{}'::jsonb,
+    own_paths character varying NOT NULL DEFAULT '',
+    owner character varying NOT NULL DEFAULT '',
     ts timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     check (from_key <> to_key),  
     unique (from_key, from_version, to_key, to_version, tag)"#,
@@ -27,9 +30,27 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
             ("from_version", "btree"),
             ("to_key", "btree"),
             ("to_version", "btree"),
+            ("own_paths", "btree"),
+            ("owner", "btree"),
+            ("ext", "gin"),
         ],
         None,
         None,
     )
-    .await
+    .await?;
+    // Add the ext and ownership columns to the tables created before they were introduced
+    spi_initializer::common_pg::upgrade_table(
+        &conn,
+        &table_name,
+        None,
+        "graph",
+        &[
+            "ext jsonb NOT NULL DEFAULT '{}'::jsonb",
+            "own_paths character varying NOT NULL DEFAULT ''",
+            "owner character varying NOT NULL DEFAULT ''",
+        ],
+        &[(5, "own_paths", "btree"), (6, "owner", "btree"), (7, "ext", "gin")],
+    )
+    .await?;
+    Ok((conn, table_name))
 }
@@ -1,5 +1,10 @@
+use bios_basic::dto::BasicQueryCondInfo;
+use bios_basic::enumeration::BasicQueryOpKind;
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_graph::dto::graph_dto::{GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq};
+use bios_spi_graph::dto::graph_dto::{
+    GraphNodeResp, GraphNodeVersionResp, GraphRelAddReq, GraphRelDetailResp, GraphRelFindReq, GraphRelUpgradeDelRelReq, GraphRelUpgradeVersionReq, GraphSubgraphResp,
+    GraphTraversalPageResp,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
@@ -17,23 +22,23 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     })?;
 
     let test_case_sqls = vec![
-        ("iter-req", "iter1", "1", "req1", "1"),
-        ("iter-req", "iter1", "1", "req2", "1"),
-        ("req-task", "req1", "1", "task1", "1"),
-        ("req-task", "req1", "1", "task2", "1"),
-        ("req-task", "req2", "1", "task2", "1"),
-        ("req-task", "req1", "2", "task1", "1"),
-        ("req-task", "req1", "2", "task3", "1"),
-        ("req-bug", "req1", "1", "bug1", "1"),
-        ("req-bug-2", "req1", "1", "bug1", "1"),
-        ("task-bug", "task1", "1", "bug1", "1"),
-        ("task-bug", "task1", "1", "bug2", "1"),
-        ("task-bug", "task2", "1", "bug2", "1"),
-        ("task-bug", "task3", "1", "bug3", "1"),
+        ("iter-req", "iter1", "1", "req1", "1", 1),
+        ("iter-req", "iter1", "1", "req2", "1", 1),
+        ("req-task", "req1", "1", "task1", "1", 1),
+        ("req-task", "req1", "1", "task2", "1", 1),
+        ("req-task", "req2", "1", "task2", "1", 1),
+        ("req-task", "req1", "2", "task1", "1", 1),
+        ("req-task", "req1", "2", "task3", "1", 1),
+        ("req-bug", "req1", "1", "bug1", "1", 1),
+        ("req-bug-2", "req1", "1", "bug1", "1", 2),
+        ("task-bug", "task1", "1", "bug1", "1", 1),
+        ("task-bug", "task1", "1", "bug2", "1", 2),
+        ("task-bug", "task2", "1", "bug2", "1", 1),
+        ("task-bug", "task3", "1", "bug3", "1", 1),
     ];
 
     // Add Rel
-    for (tag, from_key, from_version, to_key, to_version) in test_case_sqls {
+    for (tag, from_key, from_version, to_key, to_version, weight) in test_case_sqls {
         let _: Void = client
             .put(
                 "/ci/rel",
@@ -43,6 +48,7 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
                     from_version: from_version.to_string(),
                     to_key: to_key.into(),
                     to_version: to_version.to_string(),
+                    ext: Some(json!({ "weight": weight })),
                 },
             )
             .await;
@@ -129,6 +135,79 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
             }
         })
     );
+    //  Find Rels By Conditions
+    let result: GraphRelDetailResp = client
+        .put(
+            "/ci/rels/find",
+            &GraphRelFindReq {
+                from_key: "req1".into(),
+                from_version: "1".to_string(),
+                depth: None,
+                tags: None,
+                own_paths: None,
+                owners: None,
+                ext: Some(vec![BasicQueryCondInfo {
+                    field: "weight".to_string(),
+                    op: BasicQueryOpKind::Ge,
+                    value: json!(2),
+                }]),
+            },
+        )
+        .await;
+    assert_eq!(
+        TardisFuns::json.obj_to_json(&result)?,
+        json!({
+            "key": "req1",
+            "version": "1",
+            "form_rels": {
+                "req-bug-2": [
+                    {
+                        "key": "bug1",
+                        "version": "1",
+                        "form_rels": {},
+                        "to_rels": {}
+                    }
+                ]
+            },
+            "to_rels": {}
+        })
+    );
+    let result: GraphRelDetailResp = client
+        .put(
+            "/ci/rels/find",
+            &GraphRelFindReq {
+                from_key: "req1".into(),
+                from_version: "1".to_string(),
+                depth: None,
+                tags: Some(vec!["req-task".to_string()]),
+                own_paths: Some("t1".to_string()),
+                owners: Some(vec!["app001".to_string()]),
+                ext: None,
+            },
+        )
+        .await;
+    assert!(result.to_rels.is_empty());
+    assert_eq!(result.form_rels.len(), 1);
+    let mut task_keys = result.form_rels["req-task"].iter().map(|rel| rel.key.as_str()).collect::<Vec<_>>();
+    task_keys.sort();
+    assert_eq!(task_keys, vec!["task1", "task2"]);
+    assert!(result.form_rels["req-task"].iter().all(|rel| rel.form_rels.is_empty()));
+    let result: GraphRelDetailResp = client
+        .put(
+            "/ci/rels/find",
+            &GraphRelFindReq {
+                from_key: "req1".into(),
+                from_version: "1".to_string(),
+                depth: None,
+                tags: None,
+                own_paths: Some("t1/app002".to_string()),
+                owners: None,
+                ext: None,
+            },
+        )
+        .await;
+    assert!(result.form_rels.is_empty() && result.to_rels.is_empty());
+
     let result: GraphRelDetailResp = client.get("/ci/rels?from_key=req1&from_version=2").await;
     assert_eq!(
         TardisFuns::json.obj_to_json(&result)?,
@@ -445,5 +524,98 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
             }
         })
     );
+
+    // Find Descendants
+    let result: GraphTraversalPageResp = client.get("/ci/descendants?key=iter1&version=1&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 8);
+    assert_eq!(
+        result.nodes.iter().map(|node| (node.key.as_str(), node.version.as_str(), node.depth)).collect::<Vec<_>>(),
+        vec![
+            ("req1", "1", 1),
+            ("req1", "3", 1),
+            ("req1", "4", 1),
+            ("req2", "1", 1),
+            ("bug1", "1", 2),
+            ("task1", "1", 2),
+            ("task2", "1", 2),
+            ("bug2", "1", 3)
+        ]
+    );
+    let result: GraphTraversalPageResp = client.get("/ci/descendants?key=iter1&version=1&tag=iter-req&page_number=2&page_size=2").await;
+    assert_eq!(result.total_size, 4);
+    assert_eq!(
+        result.nodes.iter().map(|node| (node.key.as_str(), node.version.as_str())).collect::<Vec<_>>(),
+        vec![("req1", "4"), ("req2", "1")]
+    );
+    assert_eq!(result.edges.len(), 2);
+    assert!(result.edges.iter().all(|edge| edge.tag == "iter-req" && edge.from_key == "iter1"));
+
+    // Find Ancestors
+    let result: GraphTraversalPageResp = client.get("/ci/ancestors?key=bug2&version=1&tag=task-bug&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 2);
+    assert_eq!(result.nodes.iter().map(|node| node.key.as_str()).collect::<Vec<_>>(), vec!["task1", "task2"]);
+    assert!(result.edges.iter().all(|edge| edge.to_key == "bug2" && edge.to_version == "1" && edge.own_paths == "t1/app001" && edge.owner == "app001"));
+    assert!(result.edges.iter().any(|edge| edge.from_key == "task1" && edge.ext == json!({ "weight": 2 })));
+
+    // Find Impact
+    let result: GraphTraversalPageResp = client.get("/ci/impact?key=task2&version=1&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 5);
+    assert_eq!(
+        result.nodes.iter().map(|node| (node.key.as_str(), node.version.as_str(), node.depth)).collect::<Vec<_>>(),
+        vec![("req1", "1", 1), ("req1", "3", 1), ("req1", "4", 1), ("req2", "1", 1), ("iter1", "1", 2)]
+    );
+
+    // Find Shortest Path
+    let result: GraphSubgraphResp = client.get("/ci/path/shortest?from_key=iter1&from_version=1&to_key=bug2&to_version=1").await;
+    assert_eq!(result.nodes.len(), 4);
+    assert_eq!(
+        result.nodes.first(),
+        Some(&GraphNodeResp {
+            key: "iter1".to_string(),
+            version: "1".to_string()
+        })
+    );
+    assert_eq!(
+        result.nodes.last(),
+        Some(&GraphNodeResp {
+            key: "bug2".to_string(),
+            version: "1".to_string()
+        })
+    );
+    assert_eq!(result.edges.len(), 3);
+    assert_eq!(result.edges[2].tag, "task-bug");
+    let result: GraphSubgraphResp = client.get("/ci/path/shortest?from_key=iter1&from_version=1&to_key=bug1&to_version=1").await;
+    assert_eq!(result.nodes.len(), 3);
+    let result = client.get_resp::<GraphSubgraphResp>("/ci/path/shortest?from_key=iter1&from_version=1&to_key=bug2&to_version=1&tag=iter-req").await;
+    assert!(result.data.is_none());
+    let result = client.get_resp::<GraphSubgraphResp>("/ci/path/shortest?from_key=bug2&from_version=1&to_key=iter1&to_version=1").await;
+    assert!(result.data.is_none());
+
+    // Find Cycles
+    let result: Vec<GraphSubgraphResp> = client.get("/ci/cycles").await;
+    assert!(result.is_empty());
+    let _: Void = client
+        .put(
+            "/ci/rel",
+            &GraphRelAddReq {
+                tag: "bug-req".to_string(),
+                from_key: "bug2".into(),
+                from_version: "1".to_string(),
+                to_key: "req2".into(),
+                to_version: "1".to_string(),
+                ext: None,
+            },
+        )
+        .await;
+    let result: Vec<GraphSubgraphResp> = client.get("/ci/cycles").await;
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].nodes.iter().map(|node| node.key.as_str()).collect::<Vec<_>>(), vec!["bug2", "req2", "task2"]);
+    assert_eq!(result[0].edges.len(), 3);
+    let result: Vec<GraphSubgraphResp> = client.get("/ci/cycles?key=iter1&version=1").await;
+    assert_eq!(result.len(), 1);
+    let result: Vec<GraphSubgraphResp> = client.get("/ci/cycles?key=bug1&version=1").await;
+    assert!(result.is_empty());
+    let result: Vec<GraphSubgraphResp> = client.get("/ci/cycles?tag=task-bug").await;
+    assert!(result.is_empty());
     Ok(())
 }
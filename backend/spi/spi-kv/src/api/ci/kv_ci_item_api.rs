@@ -1,3 +1,4 @@
+use tardis::chrono::{DateTime, Utc};
 use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem_openapi;
 use tardis::web::poem_openapi::param::Query;
@@ -5,7 +6,8 @@ use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
 use crate::dto::kv_item_dto::{
-    KvItemAddOrModifyReq, KvItemDetailResp, KvItemKeyReq, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    KvItemAddOrModifyReq, KvItemDetailResp, KvItemHistoryResp, KvItemKeyReq, KvItemMatchReq, KvItemRollbackReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq,
+    KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
 };
 use crate::serv::kv_item_serv;
 
@@ -156,6 +158,80 @@ impl KvCiItemApi {
         TardisResp::ok(Void {})
     }
 
+    /// Find Item History
+    ///
+    /// 查找Item的变更历史
+    #[oai(path = "/item/history", method = "get")]
+    async fn find_item_history(
+        &self,
+        key: Query<String>,
+        page_number: Query<u32>,
+        page_size: Query<u16>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<KvItemHistoryResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::find_item_history(key.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Get Item At The Specified Time
+    ///
+    /// 获取Item在指定时间的值
+    #[oai(path = "/item/at", method = "get")]
+    async fn get_item_at(
+        &self,
+        key: Query<String>,
+        ts: Query<DateTime<Utc>>,
+        extract: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Option<KvItemHistoryResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::get_item_at(key.0, ts.0, extract.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Rollback Item To The Specified Revision
+    ///
+    /// 回滚Item到指定版本
+    #[oai(path = "/item/rollback", method = "put")]
+    async fn rollback_item(&self, rollback_req: Json<KvItemRollbackReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        kv_item_serv::rollback_item(rollback_req.0.key.to_string(), rollback_req.0.revision, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Watch Item Changes By key prefix
+    ///
+    /// Long polling, pass the returned `seq` as `since_seq` in the next request.
+    /// If `since_seq` is empty, the latest `seq` is returned immediately.
+    ///
+    /// 通过key前缀监听Item的变更
+    ///
+    /// 长轮询，下次请求时将返回的`seq`作为`since_seq`传入。
+    /// 如果`since_seq`为空，立即返回最新的`seq`。
+    #[oai(path = "/item/watch", method = "get")]
+    async fn watch_items(
+        &self,
+        key_prefix: Query<String>,
+        since_seq: Query<Option<i64>>,
+        timeout_sec: Query<Option<u64>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<KvItemWatchResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::watch_items(key_prefix.0, since_seq.0, timeout_sec.0.unwrap_or(30), &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Purge Expired Items
+    ///
+    /// 清理已过期的Item
+    #[oai(path = "/item/expired", method = "delete")]
+    async fn purge_expired_items(&self, ctx: TardisContextExtractor) -> TardisApiResult<u64> {
+        let funs = crate::get_tardis_inst();
+        let resp = kv_item_serv::purge_expired_items(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Add Or Modify Key-Name
     ///
     /// 添加或修改Key-Name
@@ -15,6 +15,11 @@ pub struct KvItemAddOrModifyReq {
     pub disable: Option<bool>,
     pub info: Option<String>,
     pub scope_level: Option<i16>,
+    /// Expected current version of the item, the modification is rejected if it does not match.
+    /// `0` means the item must not exist.
+    pub version: Option<i64>,
+    /// The item is treated as not existing after this time
+    pub exp_time: Option<DateTime<Utc>>,
 }
 impl From<bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq> for KvItemAddOrModifyReq {
     fn from(req: bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq) -> Self {
@@ -24,6 +29,8 @@ impl From<bios_sdk_invoke::clients::spi_kv_client::KvItemAddOrModifyReq> for KvI
             info: req.info,
             scope_level: req.scope_level,
             disable: None,
+            version: None,
+            exp_time: None,
         }
     }
 }
@@ -37,6 +44,8 @@ pub struct KvItemDetailResp {
     pub own_paths: String,
     pub disable: bool,
     pub scope_level: i16,
+    pub version: i64,
+    pub exp_time: Option<DateTime<Utc>>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
@@ -51,10 +60,59 @@ pub struct KvItemSummaryResp {
     pub own_paths: String,
     pub disable: bool,
     pub scope_level: i16,
+    pub version: i64,
+    pub exp_time: Option<DateTime<Utc>>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
+pub struct KvItemHistoryResp {
+    pub key: String,
+    pub revision: i64,
+    pub value: Value,
+    pub info: String,
+    /// Who made the change
+    pub owner: String,
+    pub own_paths: String,
+    pub disable: bool,
+    pub scope_level: i16,
+    /// Operation of the change, see `kv_constants::HISTORY_OP_*`
+    pub op: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemChangeResp {
+    /// Sequence of the change, increases monotonically in the order of the changes
+    pub seq: i64,
+    pub key: String,
+    pub revision: i64,
+    /// Value after the change, or the last value if the item was deleted or expired
+    pub value: Value,
+    pub disable: bool,
+    pub owner: String,
+    pub own_paths: String,
+    pub scope_level: i16,
+    /// Operation of the change, see `kv_constants::HISTORY_OP_*`
+    pub op: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemWatchResp {
+    /// Cursor to be passed as `since_seq` in the next watch request
+    pub seq: i64,
+    pub changes: Vec<KvItemChangeResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct KvItemRollbackReq {
+    #[oai(validator(min_length = "2"))]
+    pub key: TrimString,
+    pub revision: i64,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
 pub struct KvItemMatchReq {
     pub key_prefix: String,
@@ -15,11 +15,13 @@ pub struct KvExportDataReq {
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct KvExportDataResp {
     pub kv_data: Vec<KvExportAggResp>,
+    pub kv_history_data: Vec<KvHistoryExportAggResp>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
 pub struct KvImportDataReq {
     pub kv_data: Vec<KvImportAggReq>,
+    pub kv_history_data: Option<Vec<KvHistoryImportAggReq>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
@@ -31,6 +33,8 @@ pub struct KvExportAggResp {
     pub own_paths: String,
     pub disable: bool,
     pub scope_level: i16,
+    pub version: i64,
+    pub exp_time: Option<DateTime<Utc>>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
@@ -44,6 +48,36 @@ pub struct KvImportAggReq {
     pub own_paths: String,
     pub disable: bool,
     pub scope_level: i16,
+    pub version: Option<i64>,
+    pub exp_time: Option<DateTime<Utc>>,
     pub create_time: DateTime<Utc>,
     pub update_time: DateTime<Utc>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, sea_orm::FromQueryResult)]
+pub struct KvHistoryExportAggResp {
+    pub key: String,
+    pub revision: i64,
+    pub value: Value,
+    pub info: String,
+    pub owner: String,
+    pub own_paths: String,
+    pub disable: bool,
+    pub scope_level: i16,
+    pub op: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct KvHistoryImportAggReq {
+    pub key: String,
+    pub revision: i64,
+    pub value: Value,
+    pub info: String,
+    pub owner: String,
+    pub own_paths: String,
+    pub disable: bool,
+    pub scope_level: i16,
+    pub op: String,
+    pub ts: DateTime<Utc>,
+}
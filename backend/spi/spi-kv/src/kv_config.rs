@@ -7,6 +7,8 @@ use std::fmt::Debug;
 pub struct KvConfig {
     pub rbum: RbumConfig,
     pub cache_key_async_task_status: String,
+    /// Interval for purging the expired items, 0 means disabled
+    pub expired_item_sweep_interval_sec: u64,
 }
 
 impl Default for KvConfig {
@@ -14,6 +16,7 @@ impl Default for KvConfig {
         KvConfig {
             rbum: Default::default(),
             cache_key_async_task_status: "iam:cache:task:status".to_string(),
+            expired_item_sweep_interval_sec: 60,
         }
     }
 }
@@ -1,3 +1,14 @@
 pub const DOMAIN_CODE: &str = "spi-kv";
 pub const KEY_PREFIX_BY_KEY_NAME: &str = "__k_n__:";
 pub const KEY_PREFIX_BY_TAG: &str = "__tag__:";
+// Operations recorded in the item history
+pub const HISTORY_OP_ADD_OR_MODIFY: &str = "add_or_modify";
+pub const HISTORY_OP_DELETE: &str = "delete";
+pub const HISTORY_OP_DISABLE: &str = "disable";
+pub const HISTORY_OP_ENABLE: &str = "enable";
+pub const HISTORY_OP_ROLLBACK: &str = "rollback";
+pub const HISTORY_OP_EXPIRE: &str = "expire";
+// Maximum number of changes returned by a single watch request
+pub const WATCH_MAX_CHANGES: u16 = 100;
+// Maximum waiting time of a watch request
+pub const WATCH_MAX_TIMEOUT_SEC: u64 = 60;
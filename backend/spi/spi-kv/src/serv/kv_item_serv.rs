@@ -1,14 +1,32 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::chrono::{DateTime, Utc};
+use tardis::log::{info, warn};
+use tardis::tokio::sync::Notify;
+use tardis::tokio::time;
 use tardis::web::web_resp::TardisPage;
+use tardis::TardisFunsInst;
 
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumRelFilterReq};
+use bios_basic::rbum::rbum_enumeration::RbumRelFromKind;
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::rbum::serv::rbum_rel_serv::RbumRelServ;
+use bios_basic::spi::dto::spi_bs_dto::SpiBsFilterReq;
+use bios_basic::spi::serv::spi_bs_serv::SpiBsServ;
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
 
 use crate::dto::kv_item_dto::{
-    KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp,
+    KvItemAddOrModifyReq, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq,
+    KvTagFindResp,
 };
-use crate::kv_initializer;
+use crate::{kv_constants, kv_initializer};
 
 use super::pg;
 
@@ -40,5 +58,125 @@ spi_dispatch_service! {
         delete_item(key: String) -> TardisResult<()>;
         disable_item(key: String) -> TardisResult<()>;
         enabled_item(key: String) -> TardisResult<()>;
+        find_item_history(key: String, page_number: u32, page_size: u16) -> TardisResult<TardisPage<KvItemHistoryResp>>;
+        get_item_at(key: String, ts: DateTime<Utc>, extract: Option<String>) -> TardisResult<Option<KvItemHistoryResp>>;
+        rollback_item(key: String, revision: i64) -> TardisResult<()>;
+        purge_expired_items() -> TardisResult<u64>;
+        find_item_changes(key_prefix: String, since_seq: i64) -> TardisResult<KvItemWatchResp>;
+        get_latest_item_change_seq() -> TardisResult<i64>;
+    }
+}
+
+fn get_item_changed_notify() -> &'static Notify {
+    static ITEM_CHANGED_NOTIFY: OnceLock<Notify> = OnceLock::new();
+    ITEM_CHANGED_NOTIFY.get_or_init(Notify::new)
+}
+
+/// Wake up the watchers of this node, changes made by other nodes are picked up by the periodic check in [watch_items]
+pub(crate) fn notify_item_changed() {
+    get_item_changed_notify().notify_waiters();
+}
+
+/// Watch the changes of the items whose key starts with [key_prefix] (long polling)
+///
+/// If [since_seq] is `None`, returns the latest cursor immediately without changes.
+/// Otherwise waits until there are changes after [since_seq] or [timeout_sec] elapses.
+pub async fn watch_items(key_prefix: String, since_seq: Option<i64>, timeout_sec: u64, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<KvItemWatchResp> {
+    let Some(mut since_seq) = since_seq else {
+        return Ok(KvItemWatchResp {
+            seq: get_latest_item_change_seq(funs, ctx).await?,
+            changes: vec![],
+        });
+    };
+    let deadline = time::Instant::now() + Duration::from_secs(timeout_sec.min(kv_constants::WATCH_MAX_TIMEOUT_SEC));
+    loop {
+        // Register interest before querying so that a change committed in between is not missed
+        let notified = get_item_changed_notify().notified();
+        let resp = find_item_changes(key_prefix.clone(), since_seq, funs, ctx).await?;
+        if !resp.changes.is_empty() || time::Instant::now() >= deadline {
+            return Ok(resp);
+        }
+        since_seq = resp.seq;
+        tardis::tokio::select! {
+            _ = notified => {}
+            _ = time::sleep_until(deadline.min(time::Instant::now() + Duration::from_secs(1))) => {}
+        }
+    }
+}
+
+/// Periodically purge the expired items of all the subjects (tenants or applications) bound to the kv backend services
+pub(crate) fn start_expired_item_sweeper(interval_sec: u64, ctx: TardisContext) {
+    info!("[BIOS.KV] Expired item sweeper started, interval: {}s", interval_sec);
+    tardis::tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(interval_sec));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_all_expired_items(&ctx).await {
+                warn!("[BIOS.KV] Failed to sweep expired items: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Purge the expired items in the tables of the subjects found by the persisted backend service bindings,
+/// so that every node sweeps all the items regardless of which node wrote them
+async fn purge_all_expired_items(ctx: &TardisContext) -> TardisResult<()> {
+    let funs = crate::get_tardis_inst();
+    let bs_ids = SpiBsServ::find_id_items(
+        &SpiBsFilterReq {
+            basic: RbumBasicFilterReq {
+                enabled: Some(true),
+                ..Default::default()
+            },
+            kind_code: Some(spi_constants::SPI_PG_KIND_CODE.to_string()),
+            domain_code: Some(funs.module_code().to_string()),
+            ..Default::default()
+        },
+        None,
+        None,
+        &funs,
+        ctx,
+    )
+    .await?;
+    if bs_ids.is_empty() {
+        return Ok(());
+    }
+    let app_tenant_ids = RbumRelServ::find_rbums(
+        &RbumRelFilterReq {
+            basic: RbumBasicFilterReq {
+                own_paths: Some("".to_string()),
+                with_sub_own_paths: true,
+                ..Default::default()
+            },
+            tag: Some(spi_constants::SPI_IDENT_REL_TAG.to_string()),
+            from_rbum_kind: Some(RbumRelFromKind::Item),
+            from_rbum_ids: Some(bs_ids),
+            ..Default::default()
+        },
+        None,
+        None,
+        &funs,
+        ctx,
+    )
+    .await?
+    .into_iter()
+    .map(|rel| rel.to_rbum_item_id)
+    .collect::<HashSet<_>>();
+    for app_tenant_id in app_tenant_ids {
+        let sweep_ctx = TardisContext {
+            ak: app_tenant_id,
+            owner: ctx.owner.clone(),
+            ..Default::default()
+        };
+        // Initialize in non-management mode so that the sweeper does not create schemas for the subjects that never used kv
+        if funs.init(None, &sweep_ctx, false, kv_initializer::init_fun).await.is_err() {
+            continue;
+        }
+        match purge_expired_items(&funs, &sweep_ctx).await {
+            Ok(purged) if purged > 0 => info!("[BIOS.KV] Purged {} expired items of [{}]", purged, sweep_ctx.ak),
+            Ok(_) => {}
+            Err(e) => warn!("[BIOS.KV] Failed to purge expired items of [{}]: {:?}", sweep_ctx.ak, e),
+        }
     }
+    Ok(())
 }
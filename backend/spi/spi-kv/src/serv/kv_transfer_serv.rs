@@ -6,20 +6,21 @@ use tardis::db::sea_orm::Value;
 use tardis::TardisFunsInst;
 use tardis::{basic::dto::TardisContext, db::reldb_client::TardisRelDBClient};
 
-use crate::dto::kv_transfer_dto::{KvExportAggResp, KvExportDataReq, KvExportDataResp, KvImportAggReq, KvImportDataReq};
+use crate::dto::kv_transfer_dto::{KvExportAggResp, KvExportDataReq, KvExportDataResp, KvHistoryExportAggResp, KvHistoryImportAggReq, KvImportAggReq, KvImportDataReq};
 use crate::kv_config::KvConfig;
 use crate::kv_initializer;
 
-use super::pg::kv_pg_initializer;
+use super::pg::{kv_pg_initializer, kv_pg_item_serv};
 
 pub async fn export_data(export_req: &KvExportDataReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<KvExportDataResp> {
     let inst_arc = funs.init(None, ctx, true, kv_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
     let (conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     let kv_data = conn
         .find_dtos_by_sql::<KvExportAggResp>(
             &format!(
-                r#"SELECT k AS key, v AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+                r#"SELECT k AS key, v AS value, info, owner, own_paths, disable, scope_level, version, exp_time, create_time, update_time
 FROM {}
 WHERE ((create_time > $1 and create_time < $2) or (update_time > $1 and update_time <= $2)) AND k NOT LIKE 'flow:config:%'
 ORDER BY create_time DESC
@@ -29,7 +30,20 @@ ORDER BY create_time DESC
             vec![Value::from(export_req.start_time.clone()), Value::from(export_req.end_time.clone())],
         )
         .await?;
-    Ok(KvExportDataResp { kv_data })
+    let kv_history_data = conn
+        .find_dtos_by_sql::<KvHistoryExportAggResp>(
+            &format!(
+                r#"SELECT k AS key, revision, v AS value, info, owner, own_paths, disable, scope_level, op, ts
+FROM {}
+WHERE ts > $1 AND ts <= $2 AND k NOT LIKE 'flow:config:%'
+ORDER BY k, revision
+"#,
+                history_table_name
+            ),
+            vec![Value::from(export_req.start_time), Value::from(export_req.end_time)],
+        )
+        .await?;
+    Ok(KvExportDataResp { kv_data, kv_history_data })
 }
 
 pub async fn import_data(receive_req: &KvImportDataReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<bool> {
@@ -37,12 +51,14 @@ pub async fn import_data(receive_req: &KvImportDataReq, funs: &TardisFunsInst, c
     let ctx_cloned = ctx.clone();
     let init_cloned = inst.clone();
     let kv_data = receive_req.kv_data.clone();
+    let kv_history_data = receive_req.kv_history_data.clone().unwrap_or_default();
     TaskProcessor::execute_task_with_ctx(
         &funs.conf::<KvConfig>().cache_key_async_task_status,
         {
             move |_task_id| async move {
                 let funs = crate::get_tardis_inst();
                 let _ = import_kv(kv_data.clone(), &funs, &ctx_cloned, &init_cloned).await?;
+                let _ = import_kv_history(kv_history_data.clone(), &funs, &ctx_cloned, &init_cloned).await?;
                 Ok(())
             }
         },
@@ -61,9 +77,9 @@ pub async fn import_kv(kv_data: Vec<KvImportAggReq>, _funs: &TardisFunsInst, ctx
     conn.begin().await?;
     for kv in &kv_data {
         let sql = format!(
-            r#"INSERT INTO {} (k, v, info, owner, own_paths, disable, scope_level, create_time, update_time)
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) 
-ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v, info = EXCLUDED.info, owner = EXCLUDED.owner, own_paths = EXCLUDED.own_paths, disable = EXCLUDED.disable, scope_level = EXCLUDED.scope_level, create_time = EXCLUDED.create_time, update_time = EXCLUDED.update_time"#,
+            r#"INSERT INTO {} (k, v, info, owner, own_paths, disable, scope_level, create_time, update_time, version, exp_time)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) 
+ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v, info = EXCLUDED.info, owner = EXCLUDED.owner, own_paths = EXCLUDED.own_paths, disable = EXCLUDED.disable, scope_level = EXCLUDED.scope_level, create_time = EXCLUDED.create_time, update_time = EXCLUDED.update_time, version = EXCLUDED.version, exp_time = EXCLUDED.exp_time"#,
             table_name
         );
         let params = vec![
@@ -76,6 +92,41 @@ ON CONFLICT (k) DO UPDATE SET v = EXCLUDED.v, info = EXCLUDED.info, owner = EXCL
             Value::from(kv.scope_level),
             Value::from(kv.create_time),
             Value::from(kv.update_time),
+            Value::from(kv.version.unwrap_or(1)),
+            Value::from(kv.exp_time),
+        ];
+        conn.execute_one(&sql, params).await?;
+    }
+    conn.commit().await?;
+    Ok(true)
+}
+
+/// Import the histories of the items
+///
+/// The imported histories are marked as imported and are not delivered to the watchers, as they are not changes of the items.
+pub async fn import_kv_history(kv_history_data: Vec<KvHistoryImportAggReq>, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<bool> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    kv_pg_item_serv::lock_history_seq(&conn, &history_table_name).await?;
+    for history in &kv_history_data {
+        let sql = format!(
+            r#"INSERT INTO {} (k, revision, v, info, owner, own_paths, disable, scope_level, op, ts, imported)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, true)
+ON CONFLICT (k, revision) DO NOTHING"#,
+            history_table_name
+        );
+        let params = vec![
+            Value::from(history.key.clone()),
+            Value::from(history.revision),
+            Value::from(history.value.clone()),
+            Value::from(history.info.clone()),
+            Value::from(history.owner.clone()),
+            Value::from(history.own_paths.clone()),
+            Value::from(history.disable),
+            Value::from(history.scope_level),
+            Value::from(history.op.clone()),
+            Value::from(history.ts),
         ];
         conn.execute_one(&sql, params).await?;
     }
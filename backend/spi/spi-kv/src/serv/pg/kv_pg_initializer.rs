@@ -5,7 +5,7 @@ use tardis::{
 };
 
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
-    spi_initializer::common_pg::init_table_and_conn(
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -18,12 +18,55 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     owner VARCHAR(255) NULL,
     scope_level SMALLINT NULL,
     disable BOOLEAN NOT NULL,
+    version bigint NOT NULL DEFAULT 1,
+    exp_time timestamp with time zone NULL,
     create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
     update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP"#,
         None,
-        vec![("k", "btree"), ("v", "gin")],
+        vec![("k", "btree"), ("v", "gin"), ("exp_time", "btree")],
         None,
         Some("update_time"),
     )
-    .await
+    .await?;
+    // Add the version and expiration columns to the tables created before they were introduced
+    spi_initializer::common_pg::upgrade_table(
+        &conn,
+        &table_name,
+        None,
+        "kv",
+        &["version bigint NOT NULL DEFAULT 1", "exp_time timestamp with time zone NULL"],
+        &[(2, "exp_time", "btree")],
+    )
+    .await?;
+    Ok((conn, table_name))
+}
+
+pub async fn init_history_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "kv_history",
+        r#"k character varying NOT NULL,
+    revision bigint NOT NULL,
+    v jsonb NOT NULL,
+    info character varying NOT NULL,
+    own_paths VARCHAR(255) NULL,
+    owner VARCHAR(255) NULL,
+    scope_level SMALLINT NULL,
+    disable BOOLEAN NOT NULL,
+    op character varying NOT NULL,
+    ts timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    seq bigserial NOT NULL,
+    imported BOOLEAN NOT NULL DEFAULT false"#,
+        None,
+        vec![("k", "btree"), ("ts", "btree"), ("seq", "btree")],
+        Some(vec!["k", "revision"]),
+        None,
+    )
+    .await?;
+    // Add the import marker column to the tables created before it was introduced
+    spi_initializer::common_pg::upgrade_table(&conn, &table_name, None, "kv_history", &["imported BOOLEAN NOT NULL DEFAULT false"], &[]).await?;
+    Ok((conn, table_name))
 }
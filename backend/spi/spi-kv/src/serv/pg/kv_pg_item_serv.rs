@@ -1,31 +1,44 @@
 use bios_basic::{
     rbum::{dto::rbum_filer_dto::RbumBasicFilterReq, helper::rbum_scope_helper},
-    spi::spi_funs::{SpiBsInst, SpiBsInstExtractor},
+    spi::{
+        spi_funs::{SpiBsInst, SpiBsInstExtractor},
+        spi_initializer,
+    },
 };
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    db::{reldb_client::TardisRelDBClient, sea_orm::Value},
+    chrono::{DateTime, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
     serde_json::json,
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
 
 use crate::{
-    dto::kv_item_dto::{KvItemAddOrModifyReq, KvItemDetailResp, KvItemMatchReq, KvItemSummaryResp, KvNameAddOrModifyReq, KvNameFindResp, KvTagAddOrModifyReq, KvTagFindResp},
+    dto::kv_item_dto::{
+        KvItemAddOrModifyReq, KvItemChangeResp, KvItemDetailResp, KvItemHistoryResp, KvItemMatchReq, KvItemSummaryResp, KvItemWatchResp, KvNameAddOrModifyReq, KvNameFindResp,
+        KvTagAddOrModifyReq, KvTagFindResp,
+    },
     kv_constants,
+    serv::kv_item_serv,
 };
 
 use super::kv_pg_initializer;
 
-pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
-    let params = vec![
-        Value::from(add_or_modify_req.key.to_string()),
+pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let key = add_or_modify_req.key.to_string();
+    let mut params = vec![
+        Value::from(key.as_str()),
         Value::from(add_or_modify_req.value.clone()),
         Value::from(add_or_modify_req.info.as_ref().unwrap_or(&"".to_string()).as_str()),
         Value::from(ctx.owner.clone()),
         Value::from(ctx.own_paths.clone()),
         Value::from(add_or_modify_req.disable.unwrap_or(false)),
         Value::from(add_or_modify_req.scope_level.unwrap_or(0)),
+        Value::from(add_or_modify_req.exp_time),
     ];
     let mut update_opt_fragments: Vec<&str> = Vec::new();
     update_opt_fragments.push("v = $2");
@@ -40,15 +53,23 @@ pub async fn add_or_modify_item(add_or_modify_req: &KvItemAddOrModifyReq, _funs:
     if add_or_modify_req.scope_level.is_some() {
         update_opt_fragments.push("scope_level = $7");
     }
+    if add_or_modify_req.exp_time.is_some() {
+        update_opt_fragments.push("exp_time = $8");
+    }
+    update_opt_fragments.push("version = kv.version + 1");
     let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
-    conn.execute_one(
-        &format!(
-            r#"INSERT INTO {} 
-    (k, v, info, owner, own_paths, disable, scope_level)
+    lock_key(&conn, &table_name, &key).await?;
+    // An expired item is treated as not existing, so it is purged before the version check
+    expire_items(&conn, &table_name, &history_table_name, Some(&key), ctx).await?;
+    let sql = match add_or_modify_req.version {
+        None => format!(
+            r#"INSERT INTO {} AS kv
+    (k, v, info, owner, own_paths, disable, scope_level, exp_time)
 VALUES
-    ($1, $2, $3, $4, $5, $6, $7)
+    ($1, $2, $3, $4, $5, $6, $7, $8)
 ON CONFLICT (k)
 DO UPDATE SET
     {}
@@ -56,10 +77,46 @@ DO UPDATE SET
             table_name,
             update_opt_fragments.join(", ")
         ),
-        params,
-    )
-    .await?;
+        Some(0) => format!(
+            r#"INSERT INTO {} AS kv
+    (k, v, info, owner, own_paths, disable, scope_level, exp_time)
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (k)
+DO NOTHING
+"#,
+            table_name
+        ),
+        Some(version) => {
+            params.push(Value::from(version));
+            format!(
+                r#"UPDATE {} AS kv
+SET
+    {}
+WHERE
+    k = $1 AND version = $9
+"#,
+                table_name,
+                update_opt_fragments.join(", ")
+            )
+        }
+    };
+    let result = conn.execute_one(&sql, params).await?;
+    if result.rows_affected() == 0 {
+        conn.rollback().await?;
+        return Err(funs.err().conflict(
+            "item",
+            "add_or_modify",
+            &format!(
+                "the version of item {key} does not match the expected version {}",
+                add_or_modify_req.version.unwrap_or_default()
+            ),
+            "409-spi-kv-version-conflict",
+        ));
+    }
+    add_history(&conn, &table_name, &history_table_name, &key, kv_constants::HISTORY_OP_ADD_OR_MODIFY, ctx).await?;
     conn.commit().await?;
+    kv_item_serv::notify_item_changed();
     Ok(())
 }
 
@@ -70,6 +127,8 @@ pub async fn add_or_modify_key_name(add_or_modify_req: &mut KvNameAddOrModifyReq
         scope_level: add_or_modify_req.scope_level,
         disable: add_or_modify_req.disable,
         info: None,
+        version: None,
+        exp_time: None,
     };
     self::add_or_modify_item(&req, funs, ctx, inst).await
 }
@@ -81,6 +140,8 @@ pub async fn add_or_modify_tag(add_or_modify_req: &mut KvTagAddOrModifyReq, funs
         scope_level: add_or_modify_req.scope_level,
         info: None,
         disable: add_or_modify_req.disable,
+        version: None,
+        exp_time: None,
     };
     self::add_or_modify_item(&req, funs, ctx, inst).await
 }
@@ -91,10 +152,10 @@ pub async fn get_item(key: String, extract: Option<String>, _funs: &TardisFunsIn
     let result = conn
         .get_dto_by_sql::<KvItemDetailResp>(
             &format!(
-                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, version, exp_time, create_time, update_time
 FROM {}
 WHERE 
-    k = $1"#,
+    k = $1 AND (exp_time IS NULL OR exp_time > CURRENT_TIMESTAMP)"#,
                 if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
                 table_name,
             ),
@@ -133,10 +194,10 @@ pub async fn find_items(keys: Vec<String>, extract: Option<String>, funs: &Tardi
     let result = conn
         .find_dtos_by_sql::<KvItemSummaryResp>(
             &format!(
-                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, create_time, update_time
+                r#"SELECT k AS key, v{} AS value, info, owner, own_paths, disable, scope_level, version, exp_time, create_time, update_time
 FROM {}
 WHERE 
-    k IN ({})"#,
+    k IN ({}) AND (exp_time IS NULL OR exp_time > CURRENT_TIMESTAMP)"#,
                 if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
                 table_name,
                 place_holder
@@ -199,6 +260,7 @@ pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx:
     let mut where_fragments: Vec<String> = Vec::new();
     let mut sql_vals: Vec<Value> = vec![];
     let mut order_fragments: Vec<String> = Vec::new();
+    where_fragments.push("(exp_time IS NULL OR exp_time > CURRENT_TIMESTAMP)".to_string());
     if match_req.key_like.unwrap_or(true) {
         sql_vals.push(Value::from(format!("{}%", match_req.key_prefix)));
         where_fragments.push(format!("k LIKE ${}", sql_vals.len()));
@@ -270,7 +332,7 @@ pub async fn match_items(match_req: KvItemMatchReq, _funs: &TardisFunsInst, ctx:
     let result = conn
         .query_all(
             &format!(
-                r#"SELECT k, v{} AS v, info, owner, own_paths, disable, scope_level, create_time, update_time, count(*) OVER() AS total
+                r#"SELECT k, v{} AS v, info, owner, own_paths, disable, scope_level, version, exp_time, create_time, update_time, count(*) OVER() AS total
 FROM {}
 WHERE 
     {}
@@ -310,6 +372,8 @@ WHERE
                 own_paths: item.try_get("", "own_paths")?,
                 disable: item.try_get("", "disable")?,
                 scope_level: item.try_get("", "scope_level")?,
+                version: item.try_get("", "version")?,
+                exp_time: item.try_get("", "exp_time")?,
                 create_time: item.try_get("", "create_time")?,
                 update_time: item.try_get("", "update_time")?,
             })
@@ -337,28 +401,48 @@ WHERE
 
 pub async fn delete_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
+    lock_key(&conn, &table_name, &key).await?;
+    add_history(&conn, &table_name, &history_table_name, &key, kv_constants::HISTORY_OP_DELETE, ctx).await?;
     conn.execute_one(&format!("DELETE FROM {table_name} WHERE k = $1"), vec![Value::from(key)]).await?;
     conn.commit().await?;
+    kv_item_serv::notify_item_changed();
     Ok(())
 }
 
 pub async fn disable_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("UPDATE {table_name} SET disable = true WHERE k = $1"), vec![Value::from(key)]).await?;
+    lock_key(&conn, &table_name, &key).await?;
+    conn.execute_one(
+        &format!("UPDATE {table_name} SET disable = true, version = version + 1 WHERE k = $1"),
+        vec![Value::from(key.as_str())],
+    )
+    .await?;
+    add_history(&conn, &table_name, &history_table_name, &key, kv_constants::HISTORY_OP_DISABLE, ctx).await?;
     conn.commit().await?;
+    kv_item_serv::notify_item_changed();
     Ok(())
 }
 
 pub async fn enabled_item(key: String, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
     let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
     conn.begin().await?;
-    conn.execute_one(&format!("UPDATE {table_name} SET disable = false WHERE k = $1"), vec![Value::from(key)]).await?;
+    lock_key(&conn, &table_name, &key).await?;
+    conn.execute_one(
+        &format!("UPDATE {table_name} SET disable = false, version = version + 1 WHERE k = $1"),
+        vec![Value::from(key.as_str())],
+    )
+    .await?;
+    add_history(&conn, &table_name, &history_table_name, &key, kv_constants::HISTORY_OP_ENABLE, ctx).await?;
     conn.commit().await?;
+    kv_item_serv::notify_item_changed();
     Ok(())
 }
 
@@ -412,3 +496,344 @@ pub async fn page_tags(
         })
     })
 }
+
+pub async fn find_item_history(
+    key: String,
+    page_number: u32,
+    page_size: u16,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<TardisPage<KvItemHistoryResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let mut sql_vals = vec![Value::from(key)];
+    let scope_fragment = package_scope_sql(&mut sql_vals, ctx);
+    sql_vals.push(Value::from(page_size));
+    sql_vals.push(Value::from((page_number - 1) * page_size as u32));
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT k, revision, v, info, owner, own_paths, disable, scope_level, op, ts, count(*) OVER() AS total
+FROM {history_table_name}
+WHERE 
+    k = $1 AND {scope_fragment}
+ORDER BY revision DESC
+LIMIT ${} OFFSET ${}"#,
+                sql_vals.len() - 1,
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?;
+    let mut total_size: i64 = 0;
+    let result = result
+        .into_iter()
+        .map(|item| {
+            if total_size == 0 {
+                total_size = item.try_get("", "total")?;
+            }
+            Ok(KvItemHistoryResp {
+                key: item.try_get("", "k")?,
+                revision: item.try_get("", "revision")?,
+                value: item.try_get("", "v")?,
+                info: item.try_get("", "info")?,
+                owner: item.try_get("", "owner")?,
+                own_paths: item.try_get("", "own_paths")?,
+                disable: item.try_get("", "disable")?,
+                scope_level: item.try_get("", "scope_level")?,
+                op: item.try_get("", "op")?,
+                ts: item.try_get("", "ts")?,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+    Ok(TardisPage {
+        page_size: page_size as u64,
+        page_number: page_number as u64,
+        total_size: total_size as u64,
+        records: result,
+    })
+}
+
+/// Get the item as it was at the specified time, `None` if it did not exist or had been deleted at that time
+pub async fn get_item_at(
+    key: String,
+    ts: DateTime<Utc>,
+    extract: Option<String>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Option<KvItemHistoryResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let mut sql_vals = vec![Value::from(key), Value::from(ts)];
+    let scope_fragment = package_scope_sql(&mut sql_vals, ctx);
+    let result = conn
+        .get_dto_by_sql::<KvItemHistoryResp>(
+            &format!(
+                r#"SELECT k AS key, revision, v{} AS value, info, owner, own_paths, disable, scope_level, op, ts
+FROM {history_table_name}
+WHERE 
+    k = $1 AND ts <= $2 AND {scope_fragment}
+ORDER BY revision DESC
+LIMIT 1"#,
+                if let Some(extract) = extract { format!("->'{extract}'") } else { "".to_string() },
+            ),
+            sql_vals,
+        )
+        .await?;
+    Ok(result.filter(|item| !is_removal_op(&item.op)))
+}
+
+/// Whether the history operation removed the item, the value recorded by it is not a live value of the item
+fn is_removal_op(op: &str) -> bool {
+    op == kv_constants::HISTORY_OP_DELETE || op == kv_constants::HISTORY_OP_EXPIRE
+}
+
+/// Restore the value of the item to the specified revision, the rollback itself is recorded as a new revision
+pub async fn rollback_item(key: String, revision: i64, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    let history = conn
+        .get_dto_by_sql::<KvItemHistoryResp>(
+            &format!(
+                r#"SELECT k AS key, revision, v AS value, info, owner, own_paths, disable, scope_level, op, ts
+FROM {history_table_name}
+WHERE 
+    k = $1 AND revision = $2"#
+            ),
+            vec![Value::from(key.as_str()), Value::from(revision)],
+        )
+        .await?
+        .filter(|item| check_history_scope(item, ctx))
+        .ok_or_else(|| funs.err().not_found("item", "rollback", &format!("revision {revision} of item {key} not found"), "404-spi-kv-revision-not-exist"))?;
+    if is_removal_op(&history.op) {
+        return Err(funs.err().bad_request("item", "rollback", "cannot roll back to a deleted or expired revision", "400-spi-kv-revision-deleted"));
+    }
+    conn.begin().await?;
+    lock_key(&conn, &table_name, &key).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name} AS kv
+    (k, v, info, owner, own_paths, disable, scope_level)
+VALUES
+    ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (k)
+DO UPDATE SET
+    v = $2, info = $3, owner = $4, own_paths = $5, disable = $6, scope_level = $7, exp_time = NULL, version = kv.version + 1
+"#
+        ),
+        vec![
+            Value::from(key.as_str()),
+            Value::from(history.value),
+            Value::from(history.info),
+            Value::from(ctx.owner.clone()),
+            Value::from(ctx.own_paths.clone()),
+            Value::from(history.disable),
+            Value::from(history.scope_level),
+        ],
+    )
+    .await?;
+    add_history(&conn, &table_name, &history_table_name, &key, kv_constants::HISTORY_OP_ROLLBACK, ctx).await?;
+    conn.commit().await?;
+    kv_item_serv::notify_item_changed();
+    Ok(())
+}
+
+/// Find the changes of the items whose key starts with [key_prefix] after [since_seq]
+///
+/// The imported histories are not changes of the items and are excluded.
+pub async fn find_item_changes(key_prefix: String, since_seq: i64, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<KvItemWatchResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT seq, k, revision, v, disable, owner, own_paths, scope_level, op, ts
+FROM {history_table_name}
+WHERE 
+    k LIKE $1 AND seq > $2 AND NOT imported
+ORDER BY seq
+LIMIT $3"#
+            ),
+            vec![
+                Value::from(format!("{}%", key_prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))),
+                Value::from(since_seq),
+                Value::from(kv_constants::WATCH_MAX_CHANGES),
+            ],
+        )
+        .await?;
+    let mut seq = since_seq;
+    let changes = result
+        .into_iter()
+        .map(|item| {
+            seq = item.try_get("", "seq")?;
+            Ok(KvItemChangeResp {
+                seq,
+                key: item.try_get("", "k")?,
+                revision: item.try_get("", "revision")?,
+                value: item.try_get("", "v")?,
+                disable: item.try_get("", "disable")?,
+                owner: item.try_get("", "owner")?,
+                own_paths: item.try_get("", "own_paths")?,
+                scope_level: item.try_get("", "scope_level")?,
+                op: item.try_get("", "op")?,
+                ts: item.try_get("", "ts")?,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|item| check_own_paths_scope(&item.own_paths, item.scope_level, ctx))
+        .collect();
+    Ok(KvItemWatchResp { seq, changes })
+}
+
+/// Get the sequence of the latest change, used as the initial cursor of watching
+pub async fn get_latest_item_change_seq(_funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<i64> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let result = conn.query_one(&format!("SELECT COALESCE(MAX(seq), 0) AS seq FROM {history_table_name}"), vec![]).await?;
+    Ok(match result {
+        Some(result) => result.try_get("", "seq")?,
+        None => 0,
+    })
+}
+
+/// Purge the expired items, returns the number of purged items
+pub async fn purge_expired_items(_funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    // Nothing to purge if the subject has never written any item
+    if !spi_initializer::common_pg::check_table_exit("kv", &conn, ctx).await? {
+        return Ok(0);
+    }
+    let (_, history_table_name) = kv_pg_initializer::init_history_table_and_conn(bs_inst, ctx, true).await?;
+    let (mut conn, table_name) = kv_pg_initializer::init_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    let purged = expire_items(&conn, &table_name, &history_table_name, None, ctx).await?;
+    conn.commit().await?;
+    kv_item_serv::notify_item_changed();
+    Ok(purged)
+}
+
+/// Record and delete the expired items (of the [key]), must be called in a transaction
+///
+/// When the [key] is specified, its lock must already be held by the transaction.
+async fn expire_items(conn: &TardisRelDBlConnection, table_name: &str, history_table_name: &str, key: Option<&str>, ctx: &TardisContext) -> TardisResult<u64> {
+    let keys = match key {
+        Some(key) => {
+            if conn
+                .query_one(
+                    &format!("SELECT k FROM {table_name} WHERE k = $1 AND exp_time <= CURRENT_TIMESTAMP"),
+                    vec![Value::from(key)],
+                )
+                .await?
+                .is_none()
+            {
+                return Ok(0);
+            }
+            vec![key.to_string()]
+        }
+        None => {
+            let keys = conn
+                .query_all(&format!("SELECT k FROM {table_name} WHERE exp_time <= CURRENT_TIMESTAMP ORDER BY k"), vec![])
+                .await?
+                .into_iter()
+                .map(|item| item.try_get::<String>("", "k"))
+                .collect::<TardisResult<Vec<_>>>()?;
+            // Locked in the order of the keys to avoid deadlocks between concurrent purges
+            for key in &keys {
+                lock_key(conn, table_name, key).await?;
+            }
+            keys
+        }
+    };
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let keys = Value::from(keys);
+    lock_history_seq(conn, history_table_name).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {history_table_name}
+    (k, revision, v, info, owner, own_paths, disable, scope_level, op)
+SELECT k, COALESCE((SELECT MAX(h.revision) FROM {history_table_name} AS h WHERE h.k = kv.k), 0) + 1, v, info, $1, own_paths, disable, scope_level, $2
+FROM {table_name} AS kv
+WHERE 
+    k = ANY($3) AND exp_time <= CURRENT_TIMESTAMP"#
+        ),
+        vec![Value::from(ctx.owner.clone()), Value::from(kv_constants::HISTORY_OP_EXPIRE), keys.clone()],
+    )
+    .await?;
+    let result = conn.execute_one(&format!("DELETE FROM {table_name} WHERE k = ANY($1) AND exp_time <= CURRENT_TIMESTAMP"), vec![keys]).await?;
+    Ok(result.rows_affected())
+}
+
+/// Record the current state of the item as a new revision, must be called in the transaction that changes the item
+async fn add_history(conn: &TardisRelDBlConnection, table_name: &str, history_table_name: &str, key: &str, op: &str, ctx: &TardisContext) -> TardisResult<()> {
+    lock_history_seq(conn, history_table_name).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {history_table_name}
+    (k, revision, v, info, owner, own_paths, disable, scope_level, op)
+SELECT k, COALESCE((SELECT MAX(revision) FROM {history_table_name} WHERE k = $1), 0) + 1, v, info, $2, own_paths, disable, scope_level, $3
+FROM {table_name}
+WHERE 
+    k = $1"#
+        ),
+        vec![Value::from(key), Value::from(ctx.owner.clone()), Value::from(op)],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Serialize the writes of the key, so that the revisions of the key are allocated without conflicts,
+/// must be called at the beginning of the transaction that changes the item
+async fn lock_key(conn: &TardisRelDBlConnection, table_name: &str, key: &str) -> TardisResult<()> {
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1 || $2))", vec![Value::from(table_name), Value::from(key)]).await?;
+    Ok(())
+}
+
+/// Serialize the history inserts of the tenant so that the `seq` of the history is allocated in commit order,
+/// which lets watchers use the `seq` as a cursor without missing changes.
+/// Must be called right before the last statements of the transaction, so that the lock is only held until the commit.
+pub(crate) async fn lock_history_seq(conn: &TardisRelDBlConnection, history_table_name: &str) -> TardisResult<()> {
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(history_table_name)]).await?;
+    Ok(())
+}
+
+/// Build the SQL condition equivalent to [`check_own_paths_scope`], so that the scope is filtered before pagination
+fn package_scope_sql(sql_vals: &mut Vec<Value>, ctx: &TardisContext) -> String {
+    sql_vals.push(Value::from(ctx.own_paths.as_str()));
+    let mut conditions = vec![format!("own_paths = ${}", sql_vals.len())];
+    let own_paths = ctx.own_paths.trim();
+    let own_paths = own_paths.strip_suffix('/').unwrap_or(own_paths);
+    let max_level = if own_paths.is_empty() { 0 } else { own_paths.split('/').count() as i16 };
+    for scope_level in 0..=max_level {
+        if let Some(pre_paths) = rbum_scope_helper::get_pre_paths(scope_level, &ctx.own_paths) {
+            sql_vals.push(Value::from(pre_paths.as_str()));
+            conditions.push(format!(
+                "(scope_level = {scope_level} AND (starts_with(own_paths, ${0}) OR starts_with(${0}, own_paths)))",
+                sql_vals.len()
+            ));
+        }
+    }
+    format!("({})", conditions.join(" OR "))
+}
+
+fn check_history_scope(item: &KvItemHistoryResp, ctx: &TardisContext) -> bool {
+    check_own_paths_scope(&item.own_paths, item.scope_level, ctx)
+}
+
+fn check_own_paths_scope(own_paths: &str, scope_level: i16, ctx: &TardisContext) -> bool {
+    rbum_scope_helper::check_scope(
+        own_paths,
+        Some(scope_level),
+        &RbumBasicFilterReq {
+            ignore_scope: false,
+            ..Default::default()
+        },
+        &ctx.own_paths,
+    )
+}
@@ -1,9 +1,10 @@
 use std::time::Duration;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_kv::dto::kv_item_dto::{KvItemDetailResp, KvItemSummaryResp, KvNameFindResp, KvTagFindResp};
+use bios_spi_kv::dto::kv_item_dto::{KvItemDetailResp, KvItemHistoryResp, KvItemSummaryResp, KvItemWatchResp, KvNameFindResp, KvTagFindResp};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
+use tardis::chrono::{SecondsFormat, Utc};
 use tardis::serde_json::json;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::{TardisPage, TardisResp, Void};
@@ -279,5 +280,203 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(result.value, "postgres://xxxx");
     assert_eq!(result.info, "xx系统的数据库地址");
 
+    // history
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"history:001",
+                "value": "v1",
+            }),
+        )
+        .await;
+    sleep(Duration::from_millis(100)).await;
+    let ts_v1 = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    sleep(Duration::from_millis(100)).await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"history:001",
+                "value": "v2",
+            }),
+        )
+        .await;
+
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=history:001&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 2);
+    assert_eq!(result.records[0].revision, 2);
+    assert_eq!(result.records[0].value, "v2");
+    assert_eq!(result.records[0].owner, "app001");
+    assert_eq!(result.records[1].revision, 1);
+    assert_eq!(result.records[1].value, "v1");
+
+    let result: KvItemHistoryResp = client.get(&format!("/ci/item/at?key=history:001&ts={ts_v1}")).await;
+    assert_eq!(result.revision, 1);
+    assert_eq!(result.value, "v1");
+
+    let _: Void = client
+        .put(
+            "/ci/item/rollback",
+            &json!({
+                "key":"history:001",
+                "revision": 1,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=history:001").await;
+    assert_eq!(result.value, "v1");
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=history:001&page_number=1&page_size=10").await;
+    assert_eq!(result.total_size, 3);
+    assert_eq!(result.records[0].op, "rollback");
+
+    client.delete("/ci/item?key=history:001").await;
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=history:001&page_number=1&page_size=1").await;
+    assert_eq!(result.total_size, 4);
+    assert_eq!(result.records[0].op, "delete");
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    let result: TardisResp<KvItemHistoryResp> = client.get_resp(&format!("/ci/item/at?key=history:001&ts={now}")).await;
+    assert!(result.data.is_none());
+    let result: KvItemHistoryResp = client.get(&format!("/ci/item/at?key=history:001&ts={ts_v1}")).await;
+    assert_eq!(result.value, "v1");
+
+    // version & expiration
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cas:001",
+                "value": "v1",
+                "version": 0,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=cas:001").await;
+    assert_eq!(result.version, 1);
+    assert!(result.exp_time.is_none());
+    let result: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "key":"cas:001",
+                "value": "v1",
+                "version": 0,
+            }),
+        )
+        .await;
+    assert_eq!(result.code, "409-spi-kv-version-conflict");
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"cas:001",
+                "value": "v2",
+                "version": 1,
+            }),
+        )
+        .await;
+    let result: TardisResp<Void> = client
+        .put_resp(
+            "/ci/item",
+            &json!({
+                "key":"cas:001",
+                "value": "v3",
+                "version": 1,
+            }),
+        )
+        .await;
+    assert_eq!(result.code, "409-spi-kv-version-conflict");
+    let result: KvItemDetailResp = client.get("/ci/item?key=cas:001").await;
+    assert_eq!(result.value, "v2");
+    assert_eq!(result.version, 2);
+
+    let exp_time = (Utc::now() + tardis::chrono::Duration::milliseconds(500)).to_rfc3339_opts(SecondsFormat::Millis, true);
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"exp:001",
+                "value": "v1",
+                "exp_time": exp_time,
+            }),
+        )
+        .await;
+    let result: KvItemDetailResp = client.get("/ci/item?key=exp:001").await;
+    assert!(result.exp_time.is_some());
+    sleep(Duration::from_millis(1000)).await;
+    let result: TardisResp<KvItemDetailResp> = client.get_resp("/ci/item?key=exp:001").await;
+    assert!(result.data.is_none());
+    client.delete("/ci/item/expired").await;
+    let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=exp:001&page_number=1&page_size=1").await;
+    assert_eq!(result.records[0].op, "expire");
+
+    // watch
+    let result: KvItemWatchResp = client.get("/ci/item/watch?key_prefix=watch:").await;
+    assert!(result.changes.is_empty());
+    let since_seq = result.seq;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"watch:001",
+                "value": "v1",
+            }),
+        )
+        .await;
+    let _: Void = client
+        .put(
+            "/ci/item",
+            &json!({
+                "key":"other:001",
+                "value": "v1",
+            }),
+        )
+        .await;
+    client.delete("/ci/item?key=watch:001").await;
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&since_seq={since_seq}&timeout_sec=1")).await;
+    assert_eq!(result.changes.len(), 2);
+    assert_eq!(result.changes[0].key, "watch:001");
+    assert_eq!(result.changes[0].op, "add_or_modify");
+    assert_eq!(result.changes[0].value, "v1");
+    assert_eq!(result.changes[1].op, "delete");
+    let since_seq = result.seq;
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&since_seq={since_seq}&timeout_sec=1")).await;
+    assert!(result.changes.is_empty());
+    assert_eq!(result.seq, since_seq);
+
+    // the imported histories are not delivered to the watchers
+    let _: TardisResp<Option<String>> = client
+        .put_resp(
+            "/ci/import",
+            &json!({
+                "kv_data": [],
+                "kv_history_data": [{
+                    "key": "watch:002",
+                    "revision": 1,
+                    "value": "v1",
+                    "info": "",
+                    "owner": ctx.owner,
+                    "own_paths": ctx.own_paths,
+                    "disable": false,
+                    "scope_level": 0,
+                    "op": "add_or_modify",
+                    "ts": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                }],
+            }),
+        )
+        .await;
+    let mut imported = false;
+    for _ in 0..50 {
+        let result: TardisPage<KvItemHistoryResp> = client.get("/ci/item/history?key=watch:002&page_number=1&page_size=1").await;
+        if result.total_size > 0 {
+            imported = true;
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(imported);
+    let result: KvItemWatchResp = client.get(&format!("/ci/item/watch?key_prefix=watch:&since_seq={since_seq}&timeout_sec=1")).await;
+    assert!(result.changes.is_empty());
+
     Ok(())
 }
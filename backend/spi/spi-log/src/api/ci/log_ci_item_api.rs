@@ -1,13 +1,20 @@
 use bios_basic::process::task_processor::TaskProcessor;
+use tardis::chrono::{DateTime, Utc};
+use tardis::futures::{SinkExt, StreamExt};
 use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem::web::websocket::{BoxWebSocketUpgraded, Message, WebSocket};
 use tardis::web::poem_openapi;
-use tardis::web::poem_openapi::param::Path;
+use tardis::web::poem_openapi::param::{Path, Query};
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp, Void};
 
-use crate::dto::log_item_dto::{LogConfigReq, LogExportDataReq, LogExportDataResp, LogImportDataReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp};
-use crate::serv::{log_item_serv, log_transfer_serv};
+use crate::dto::log_item_dto::{
+    LogChainVerifyResp, LogConfigReq, LogExportDataReq, LogExportDataResp, LogImportDataReq, LogItemAddReq, LogItemAddV2Req, LogItemAggReq, LogItemAggResp, LogItemFindReq,
+    LogItemFindResp, LogItemTailReq, LogRetentionResp,
+};
+use crate::serv::{log_item_serv, log_retention_serv, log_transfer_serv};
 use tardis::serde_json::Value;
+use tardis::TardisFuns;
 
 #[derive(Clone)]
 pub struct LogCiItemApi;
@@ -82,6 +89,14 @@ impl LogCiItemApiV2 {
         TardisResp::ok(resp)
     }
 
+    /// Aggregate Items
+    #[oai(path = "/agg", method = "put")]
+    async fn query_agg(&self, mut agg_req: Json<LogItemAggReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<LogItemAggResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::query_agg(&mut agg_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     /// Modify Item ext by key
     #[oai(path = "/modify/:tag/:key/ext", method = "post")]
     async fn modify_ext(&self, tag: Path<String>, key: Path<String>, mut ext: Json<Value>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
@@ -123,4 +138,74 @@ impl LogCiItemApiV2 {
             TardisResp::ok(None)
         }
     }
+
+    /// Verify the hash chain of the tag
+    #[oai(path = "/chain/verify/:tag", method = "get")]
+    async fn verify_chain(
+        &self,
+        tag: Path<String>,
+        ts_start: Query<Option<DateTime<Utc>>>,
+        ts_end: Query<Option<DateTime<Utc>>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<LogChainVerifyResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = log_item_serv::verify_chain(&tag.0, ts_start.0, ts_end.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Apply retention policies, the schedule service can call it periodically
+    ///
+    /// 执行保留策略，可由调度服务定期调用
+    #[oai(path = "/retention/apply", method = "put")]
+    async fn apply_retention(&self, tag: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<LogRetentionResp>> {
+        let funs = crate::get_tardis_inst();
+        let result = log_retention_serv::apply(tag.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Tail the newly added items of the tag
+    ///
+    /// The filter is a json of `LogItemTailReq`, only the items added with `push` are delivered as text frames through the websocket
+    ///
+    /// 实时订阅 tag 新增的日志，过滤条件为 `LogItemTailReq` 的 json，仅推送 `push` 为 true 的日志
+    #[oai(path = "/tail/:tag", method = "get")]
+    async fn tail(
+        &self,
+        tag: Path<String>,
+        filter: Query<Option<String>>,
+        websocket: WebSocket,
+        ctx: TardisContextExtractor,
+    ) -> Result<BoxWebSocketUpgraded, tardis::web::poem::Error> {
+        let funs = crate::get_tardis_inst();
+        let tail_req = match filter.0 {
+            Some(filter) if !filter.is_empty() => TardisFuns::json.str_to_obj::<LogItemTailReq>(&filter)?,
+            _ => LogItemTailReq::default(),
+        };
+        let mut items = log_item_serv::subscribe(&tag.0, &tail_req, &funs, &ctx.0).await?;
+        let upgraded: BoxWebSocketUpgraded = websocket.on_upgrade(Box::new(|socket| {
+            Box::pin(async move {
+                let (mut sink, mut stream) = socket.split();
+                loop {
+                    tardis::tokio::select! {
+                        item = items.next() => match item {
+                            Some(item) => {
+                                let Ok(item) = TardisFuns::json.obj_to_string(&item) else {
+                                    continue;
+                                };
+                                if sink.send(Message::Text(item)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            None => break,
+                        },
+                        received = stream.next() => match received {
+                            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                            _ => {}
+                        },
+                    }
+                }
+            })
+        }));
+        Ok(upgraded)
+    }
 }
@@ -138,7 +138,129 @@ pub struct LogItemFindReq {
     pub page_size: u16,
 }
 
-#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+/// Live tail request, the filters are the same as [`LogItemFindReq`]
+///
+/// 实时订阅请求，过滤条件与 [`LogItemFindReq`] 相同
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogItemTailReq {
+    pub kinds: Option<Vec<TrimString>>,
+    pub keys: Option<Vec<TrimString>>,
+    pub ops: Option<Vec<String>>,
+    pub owners: Option<Vec<String>>,
+    pub own_paths: Option<String>,
+    pub ext_or: Option<Vec<BasicQueryCondInfo>>,
+    // Extended filtering conditions
+    pub ext: Option<Vec<BasicQueryCondInfo>>,
+    // Advanced search
+    pub adv_query: Option<Vec<AdvLogItemQueryReq>>,
+    pub rel_keys: Option<Vec<TrimString>>,
+    pub ts_start: Option<DateTime<Utc>>,
+    pub ts_end: Option<DateTime<Utc>>,
+}
+
+/// Aggregation request, the filters are the same as [`LogItemFindReq`]
+///
+/// 聚合查询请求，过滤条件与 [`LogItemFindReq`] 相同
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemAggReq {
+    #[oai(validator(pattern = r"^[a-z0-9_]+$"))]
+    pub tag: String,
+    pub kinds: Option<Vec<TrimString>>,
+    pub keys: Option<Vec<TrimString>>,
+    pub ops: Option<Vec<String>>,
+    pub owners: Option<Vec<String>>,
+    pub own_paths: Option<String>,
+    pub ext_or: Option<Vec<BasicQueryCondInfo>>,
+    // Extended filtering conditions
+    pub ext: Option<Vec<BasicQueryCondInfo>>,
+    // Advanced search
+    pub adv_query: Option<Vec<AdvLogItemQueryReq>>,
+    pub rel_keys: Option<Vec<TrimString>>,
+    pub ts_start: Option<DateTime<Utc>>,
+    pub ts_end: Option<DateTime<Utc>>,
+    // 分组字段
+    pub group_by: Option<Vec<LogItemAggFieldReq>>,
+    // 时间分桶
+    pub time_bucket: Option<LogItemAggTimeBucketKind>,
+    // 需要去重计数的字段
+    pub distinct_count: Option<Vec<LogItemAggFieldReq>>,
+    // 返回的最大分组数
+    pub limit: Option<u32>,
+}
+
+impl From<&LogItemAggReq> for LogItemFindReq {
+    fn from(value: &LogItemAggReq) -> Self {
+        Self {
+            tag: value.tag.clone(),
+            kinds: value.kinds.clone(),
+            keys: value.keys.clone(),
+            ops: value.ops.clone(),
+            owners: value.owners.clone(),
+            own_paths: value.own_paths.clone(),
+            ext_or: value.ext_or.clone(),
+            ext: value.ext.clone(),
+            adv_query: value.adv_query.clone(),
+            rel_keys: value.rel_keys.clone(),
+            ts_start: value.ts_start,
+            ts_end: value.ts_end,
+            page_number: 1,
+            page_size: 0,
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct LogItemAggFieldReq {
+    // 是否为 ext 中的字段，默认为 false，此时仅支持 kind/key/op/owner/own_paths/rel_key/data_source
+    pub in_ext: Option<bool>,
+    #[oai(validator(pattern = r"^[a-zA-Z0-9_]+$"))]
+    pub field: String,
+}
+
+impl LogItemAggFieldReq {
+    /// Name of the field in the aggregation result, fields in ext are prefixed with `ext.`
+    ///
+    /// 字段在聚合结果中的名称，ext 中的字段以 `ext.` 为前缀
+    pub fn name(&self) -> String {
+        if self.in_ext.unwrap_or(false) {
+            format!("ext.{}", self.field)
+        } else {
+            self.field.clone()
+        }
+    }
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LogItemAggTimeBucketKind {
+    Hour,
+    Day,
+    Week,
+}
+
+impl LogItemAggTimeBucketKind {
+    pub fn to_sql(&self) -> &'static str {
+        match self {
+            LogItemAggTimeBucketKind::Hour => "hour",
+            LogItemAggTimeBucketKind::Day => "day",
+            LogItemAggTimeBucketKind::Week => "week",
+        }
+    }
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct LogItemAggResp {
+    // 时间分桶的起始时间
+    pub bucket: Option<DateTime<Utc>>,
+    // 分组字段值，key 为字段名称
+    pub group: HashMap<String, Option<String>>,
+    pub count: i64,
+    // 去重计数，key 为字段名称
+    pub distinct_count: HashMap<String, i64>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
 pub struct AdvLogItemQueryReq {
     pub group_by_or: Option<bool>,
     // Extended filtering conditions
@@ -155,7 +277,7 @@ pub struct AdvBasicQueryCondInfo {
     pub value: Value,
 }
 
-#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
 pub struct LogItemFindResp {
     pub content: Value,
     pub kind: Vec<String>,
@@ -234,6 +356,11 @@ pub struct LogExportV2AggResp {
     pub push: bool,
     pub disable: bool,
     pub msg: String,
+    // 哈希链证明，未启用哈希链的日志 chain_seq 为 0
+    pub chain_tenant: String,
+    pub chain_seq: i64,
+    pub prev_hash: String,
+    pub hash: String,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -271,3 +398,45 @@ pub struct LogImportV2AggReq {
     pub disable: bool,
     pub msg: String,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogRetentionResp {
+    pub tag: String,
+    pub deleted_rows: u64,
+    pub dropped_partitions: Vec<String>,
+    pub archived_objects: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LogChainVerifyResp {
+    pub tag: String,
+    pub chain_tenant: String,
+    // 已校验通过的记录数
+    pub verified: u64,
+    pub first_seq: Option<i64>,
+    pub last_seq: Option<i64>,
+    pub last_hash: Option<String>,
+    // 第一个断裂的链接，为空表示链完整
+    pub broken: Option<LogChainBrokenLinkResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct LogChainBrokenLinkResp {
+    pub seq: i64,
+    pub kind: LogChainBrokenKind,
+    // 记录缺失时为空
+    pub idempotent_id: Option<String>,
+    pub ts: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LogChainBrokenKind {
+    // 记录被删除
+    Missing,
+    // 与上一条记录的哈希不一致
+    PrevHashMismatch,
+    // 记录内容被篡改
+    HashMismatch,
+}
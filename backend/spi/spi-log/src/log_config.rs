@@ -1,6 +1,7 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
 use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -9,6 +10,15 @@ pub struct LogConfig {
     pub rbum: RbumConfig,
     pub invoke: InvokeConfig,
     pub cache_key_async_task_status: String,
+    pub retention: LogRetentionConfig,
+    /// Tags whose logs are hash chained (per tag and tenant), only supported by pg v2
+    ///
+    /// 启用哈希链的 tag（按 tag 及租户成链），仅 pg v2 支持
+    pub hash_chain_tags: Vec<String>,
+    /// Mq topic of the live tail, the added logs are published to it and delivered to the subscribers on all the nodes
+    ///
+    /// 实时订阅的 mq 主题，新增的日志发布到该主题并投递给所有节点上的订阅者
+    pub mq_topic_tail: String,
 }
 
 impl Default for LogConfig {
@@ -17,6 +27,9 @@ impl Default for LogConfig {
             rbum: RbumConfig::default(),
             invoke: InvokeConfig::default(),
             cache_key_async_task_status: "iam:cache:task:status".to_string(),
+            retention: LogRetentionConfig::default(),
+            hash_chain_tags: vec![],
+            mq_topic_tail: "spi-log:tail".to_string(),
         }
     }
 }
@@ -35,3 +48,83 @@ impl Default for EventClientConfig {
         }
     }
 }
+
+/// Log retention configuration
+///
+/// 日志保留配置，按 `interval_sec` 定时对所有租户执行，也可通过 `/ci/v2/item/retention/apply` 按租户执行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LogRetentionConfig {
+    /// Retention policies by tag
+    ///
+    /// 按 tag 配置的保留策略
+    pub tag_policies: HashMap<String, LogRetentionPolicy>,
+    /// Object path prefix of archived logs
+    ///
+    /// 归档日志的对象路径前缀
+    pub archive_path_prefix: String,
+    /// Rows per archived object
+    ///
+    /// 每个归档对象包含的最大行数
+    pub archive_batch_size: u32,
+    /// Custom spi-object backend service id used for archiving
+    ///
+    /// 归档使用的自定义 spi-object 后端服务id
+    pub archive_bs_id: Option<String>,
+    /// Custom bucket used for archiving, only valid with `archive_bs_id`
+    ///
+    /// 归档使用的自定义桶，仅在设置 `archive_bs_id` 时有效
+    pub archive_bucket: Option<String>,
+    /// Interval of applying the retention policies to all the tenants, 0 means only applied by the api
+    ///
+    /// 对所有租户执行保留策略的间隔（秒），为 0 时仅通过接口执行
+    pub interval_sec: u64,
+    /// Cache key of the cluster lock, only one node applies the retention policies in each interval
+    ///
+    /// 集群锁的缓存键，每个间隔内只有一个节点执行保留策略
+    pub cache_key_lock: String,
+}
+
+impl Default for LogRetentionConfig {
+    fn default() -> Self {
+        Self {
+            tag_policies: HashMap::new(),
+            archive_path_prefix: "log_archive".to_string(),
+            archive_batch_size: 10000,
+            archive_bs_id: None,
+            archive_bucket: None,
+            interval_sec: 3600,
+            cache_key_lock: "spi-log:retention:lock".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LogRetentionPolicy {
+    /// Logs older than the days are removed
+    ///
+    /// 超过该天数的日志将被删除
+    pub max_age_days: Option<u32>,
+    /// Only the latest rows are kept
+    ///
+    /// 仅保留最新的行数
+    pub max_rows: Option<u64>,
+    /// Time partition of the tag table, only supported by pg v2
+    ///
+    /// tag 表的时间分区方式，仅 pg v2 支持
+    pub partition: LogPartitionKind,
+    /// Export the removed logs to spi-object before deleting
+    ///
+    /// 删除前将日志导出至 spi-object
+    pub archive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogPartitionKind {
+    #[default]
+    None,
+    Day,
+    Month,
+}
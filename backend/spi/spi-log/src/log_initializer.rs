@@ -11,6 +11,7 @@ use crate::{
     api::ci::log_ci_item_api,
     log_config::LogConfig,
     log_constants::{self, DOMAIN_CODE},
+    serv::{log_retention_serv, log_tail_serv},
 };
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
@@ -23,6 +24,11 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     init_db(&funs, &ctx).await?;
     funs.commit().await?;
     init_api(web_server).await?;
+    log_tail_serv::init(&funs).await?;
+    let retention = &funs.conf::<LogConfig>().retention;
+    if retention.interval_sec > 0 && !retention.tag_policies.is_empty() {
+        log_retention_serv::start_retention_scheduler(retention.interval_sec, ctx);
+    }
     info!("[BIOS.Log] Module initialized");
     Ok(())
 }
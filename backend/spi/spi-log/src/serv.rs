@@ -1,4 +1,6 @@
 pub mod log_item_serv;
+pub mod log_retention_serv;
+pub mod log_tail_serv;
 pub mod log_transfer_serv;
 pub mod pg;
 pub mod pgv2;
@@ -1,10 +1,15 @@
 use tardis::basic::result::TardisResult;
+use tardis::chrono::{DateTime, Utc};
+use tardis::futures::stream::BoxStream;
 
 use bios_basic::spi::spi_constants;
 use bios_basic::spi::spi_funs::SpiBsInstExtractor;
 use bios_basic::spi_dispatch_service;
 
-use crate::dto::log_item_dto::{LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp};
+use crate::dto::log_item_dto::{
+    LogChainVerifyResp, LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemAggReq, LogItemAggResp, LogItemFindReq, LogItemFindResp, LogItemTailReq, LogRetentionResp,
+};
+use crate::log_config::LogRetentionPolicy;
 use crate::log_initializer;
 use tardis::web::web_resp::TardisPage;
 
@@ -26,9 +31,13 @@ spi_dispatch_service! {
         find(find_req: &mut LogItemFindReq) -> TardisResult<TardisPage<LogItemFindResp>>;
         addv2(add_req: &mut LogItemAddV2Req) -> TardisResult<String>;
         findv2(find_req: &mut LogItemFindReq) -> TardisResult<TardisPage<LogItemFindResp>>;
+        query_agg(agg_req: &mut LogItemAggReq) -> TardisResult<Vec<LogItemAggResp>>;
         modify_ext(tag: &str, key: &str, ext: &mut Value) -> TardisResult<()>;
         modify_ext_v2(tag: &str, key: &str, ext: &mut Value) -> TardisResult<()>;
         add_config(config: &mut LogConfigReq) -> TardisResult<()>;
         delete_config(config: &mut LogConfigReq) -> TardisResult<()>;
+        apply_retention(tag: &str, policy: &LogRetentionPolicy) -> TardisResult<LogRetentionResp>;
+        verify_chain(tag: &str, ts_start: Option<DateTime<Utc>>, ts_end: Option<DateTime<Utc>>) -> TardisResult<LogChainVerifyResp>;
+        subscribe(tag: &str, tail_req: &LogItemTailReq) -> TardisResult<BoxStream<'static, LogItemFindResp>>;
     }
 }
@@ -0,0 +1,507 @@
+use std::collections::HashSet;
+
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumRelFilterReq};
+use bios_basic::rbum::rbum_enumeration::RbumRelFromKind;
+use bios_basic::rbum::serv::rbum_crud_serv::RbumCrudOperation;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::rbum::serv::rbum_rel_serv::RbumRelServ;
+use bios_basic::spi::dto::spi_bs_dto::SpiBsFilterReq;
+use bios_basic::spi::serv::spi_bs_serv::SpiBsServ;
+use bios_basic::spi::spi_constants;
+use bios_sdk_invoke::clients::spi_object_client::SpiObjectClient;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::cache::cmd;
+use tardis::chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use tardis::db::reldb_client::TardisRelDBlConnection;
+use tardis::db::sea_orm::Value;
+use tardis::log::{info, warn};
+use tardis::tokio::time;
+use tardis::{TardisFuns, TardisFunsInst};
+
+use crate::dto::log_item_dto::LogRetentionResp;
+use crate::log_config::{LogConfig, LogPartitionKind, LogRetentionPolicy};
+use crate::log_initializer;
+
+use super::log_item_serv;
+
+// postgres truncates identifiers longer than 63 bytes
+const MAX_IDENTIFIER_LEN: usize = 63;
+const ARCHIVE_PRESIGN_EXP_SEC: u32 = 600;
+
+/// Apply the retention policies of the configured tags (or the specified tag) for the current tenant
+///
+/// 对当前租户执行已配置 tag（或指定 tag）的保留策略
+pub async fn apply(tag: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<LogRetentionResp>> {
+    let conf = funs.conf::<LogConfig>();
+    let mut tags = conf.retention.tag_policies.iter().filter(|(policy_tag, _)| tag.as_ref().map_or(true, |tag| tag == *policy_tag)).collect::<Vec<_>>();
+    if tags.is_empty() {
+        if let Some(tag) = tag {
+            return Err(funs.err().not_found(
+                "item",
+                "retention",
+                &format!("retention policy of tag {tag} is not found"),
+                "404-spi-log-retention-not-exist",
+            ));
+        }
+    }
+    tags.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut result = Vec::with_capacity(tags.len());
+    for (tag, policy) in tags {
+        result.push(log_item_serv::apply_retention(tag, policy, funs, ctx).await?);
+    }
+    Ok(result)
+}
+
+/// Periodically apply the retention policies to all the tenants bound to the log backend services
+///
+/// 定时对绑定日志后端服务的所有租户执行保留策略
+pub(crate) fn start_retention_scheduler(interval_sec: u64, ctx: TardisContext) {
+    info!("[SPI-Log] retention scheduler started, interval: {}s", interval_sec);
+    tardis::tokio::spawn(async move {
+        let mut interval = time::interval(time::Duration::from_secs(interval_sec));
+        loop {
+            interval.tick().await;
+            if let Err(e) = apply_all(interval_sec, &ctx).await {
+                warn!("[SPI-Log] failed to apply retention policies: {:?}", e);
+            }
+        }
+    });
+}
+
+/// Apply the retention policies to the tenants found by the persisted backend service bindings,
+/// only the node holding the cluster lock applies them in each interval
+///
+/// 对持久化的后端服务绑定关系中的租户执行保留策略，每个间隔内只有持有集群锁的节点执行
+async fn apply_all(interval_sec: u64, ctx: &TardisContext) -> TardisResult<()> {
+    let funs = crate::get_tardis_inst();
+    let lock_key = funs.conf::<LogConfig>().retention.cache_key_lock.clone();
+    // the value and the ttl of the lock are set atomically, so that the lock can not outlive a crashed node
+    let mut cache_conn = funs.cache().cmd().await?;
+    let locked: Option<String> = cmd("SET").arg(&lock_key).arg(TardisFuns::field.nanoid()).arg("NX").arg("EX").arg(interval_sec).query_async(&mut *cache_conn).await?;
+    drop(cache_conn);
+    if locked.is_none() {
+        return Ok(());
+    }
+    let bs_ids = SpiBsServ::find_id_items(
+        &SpiBsFilterReq {
+            basic: RbumBasicFilterReq {
+                enabled: Some(true),
+                ..Default::default()
+            },
+            domain_code: Some(funs.module_code().to_string()),
+            ..Default::default()
+        },
+        None,
+        None,
+        &funs,
+        ctx,
+    )
+    .await?;
+    if bs_ids.is_empty() {
+        return Ok(());
+    }
+    let app_tenant_ids = RbumRelServ::find_rbums(
+        &RbumRelFilterReq {
+            basic: RbumBasicFilterReq {
+                own_paths: Some("".to_string()),
+                with_sub_own_paths: true,
+                ..Default::default()
+            },
+            tag: Some(spi_constants::SPI_IDENT_REL_TAG.to_string()),
+            from_rbum_kind: Some(RbumRelFromKind::Item),
+            from_rbum_ids: Some(bs_ids),
+            ..Default::default()
+        },
+        None,
+        None,
+        &funs,
+        ctx,
+    )
+    .await?
+    .into_iter()
+    .map(|rel| rel.to_rbum_item_id)
+    .collect::<HashSet<_>>();
+    for app_tenant_id in app_tenant_ids {
+        let retention_ctx = TardisContext {
+            ak: app_tenant_id,
+            owner: ctx.owner.clone(),
+            ..Default::default()
+        };
+        // 以非管理模式初始化，避免为未使用日志的租户创建schema
+        if funs.init(None, &retention_ctx, false, log_initializer::init_fun).await.is_err() {
+            continue;
+        }
+        if let Err(e) = apply(None, &funs, &retention_ctx).await {
+            warn!("[SPI-Log] failed to apply retention policies of [{}]: {:?}", retention_ctx.ak, e);
+        }
+    }
+    Ok(())
+}
+
+/// Remove the expired logs of the tag table, including its time partitions
+///
+/// 删除 tag 表（含时间分区）中的过期日志
+///
+/// The logs are archived without a transaction, the transactions afterwards only remove the archived logs,
+/// so that the locks of the tag table are not held during the upload.
+/// If logs are written into the archived range in the meantime, the removal is skipped and retried in the next round.
+/// 归档时不开启事务，之后的短事务只删除已归档的日志，避免上传期间持有 tag 表的锁。
+/// 若归档期间有日志写入归档范围，则跳过删除，下一轮再处理。
+pub(crate) async fn apply_to_table(
+    conn: &mut TardisRelDBlConnection,
+    table_name: &str,
+    tag: &str,
+    policy: &LogRetentionPolicy,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<LogRetentionResp> {
+    let mut resp = LogRetentionResp {
+        tag: tag.to_string(),
+        ..Default::default()
+    };
+    let now = Utc::now();
+    if let Some(max_age_days) = policy.max_age_days {
+        let cutoff = now - Duration::days(max_age_days as i64);
+        let (schema_name, rel_name) = table_name.split_once('.').unwrap_or(("public", table_name));
+        // 分区范围互不重叠，未过期分区中的日志均不早于已过期分区的结束时间
+        let mut expired_end: Option<DateTime<Utc>> = None;
+        for partition in find_partitions(conn, schema_name, rel_name).await? {
+            let Some((_, end)) = partition_range(&partition) else {
+                continue;
+            };
+            if end > cutoff {
+                continue;
+            }
+            expired_end = Some(expired_end.map_or(end, |expired_end| expired_end.max(end)));
+            let partition_full_name = format!("{schema_name}.{partition}");
+            let archived = if policy.archive {
+                let (objects, rows) = archive(conn, &format!("ONLY {partition_full_name}"), "1 = 1", vec![], &partition, tag, funs, ctx).await?;
+                resp.archived_objects.extend(objects);
+                Some(rows)
+            } else {
+                None
+            };
+            conn.begin().await?;
+            conn.execute_one(&format!("LOCK TABLE ONLY {partition_full_name} IN ACCESS EXCLUSIVE MODE"), vec![]).await?;
+            let rows = conn.count_by_sql(&format!("SELECT 1 FROM ONLY {partition_full_name}"), vec![]).await?;
+            if archived.map_or(false, |archived| archived != rows) {
+                conn.rollback().await?;
+                warn!("[SPI-Log] partition {} of tag {} changed during archiving, skipped", partition, tag);
+                continue;
+            }
+            conn.execute_one(&format!("DROP TABLE {partition_full_name}"), vec![]).await?;
+            conn.commit().await?;
+            resp.deleted_rows += rows;
+            resp.dropped_partitions.push(partition);
+        }
+        let (where_fragment, params) = match expired_end {
+            Some(expired_end) => ("ts >= $1 AND ts < $2", vec![Value::from(expired_end), Value::from(cutoff)]),
+            None => ("ts < $1", vec![Value::from(cutoff)]),
+        };
+        let archived = if policy.archive {
+            let name = format!("{rel_name}-expired-{}", cutoff.format("%Y%m%d%H%M%S"));
+            let (objects, rows) = archive(conn, table_name, where_fragment, params.clone(), &name, tag, funs, ctx).await?;
+            resp.archived_objects.extend(objects);
+            Some(rows)
+        } else {
+            None
+        };
+        resp.deleted_rows += delete_archived(conn, table_name, where_fragment, params, archived, tag).await?;
+    }
+    if let Some(max_rows) = policy.max_rows {
+        // rows sharing the timestamp of the boundary row are removed together
+        let boundary = conn
+            .query_one(
+                &format!("SELECT ts FROM {table_name} ORDER BY ts DESC OFFSET $1 LIMIT 1"),
+                vec![Value::from(max_rows as i64)],
+            )
+            .await?;
+        if let Some(boundary) = boundary {
+            let boundary: DateTime<Utc> = boundary.try_get("", "ts")?;
+            let archived = if policy.archive {
+                let name = format!("{}-overflow-{}", table_name.rsplit('.').next().unwrap_or(table_name), boundary.format("%Y%m%d%H%M%S"));
+                let (objects, rows) = archive(conn, table_name, "ts <= $1", vec![Value::from(boundary)], &name, tag, funs, ctx).await?;
+                resp.archived_objects.extend(objects);
+                Some(rows)
+            } else {
+                None
+            };
+            resp.deleted_rows += delete_archived(conn, table_name, "ts <= $1", vec![Value::from(boundary)], archived, tag).await?;
+        }
+    }
+    info!(
+        "[SPI-Log] retention of tag {} applied, deleted rows: {}, dropped partitions: {:?}",
+        tag, resp.deleted_rows, resp.dropped_partitions
+    );
+    Ok(resp)
+}
+
+/// Delete the matched rows in a short transaction, rolled back if the rows differ from the archived ones
+///
+/// 在短事务中删除匹配的行，若与已归档的行数不一致则回滚
+async fn delete_archived(
+    conn: &mut TardisRelDBlConnection,
+    table_name: &str,
+    where_fragment: &str,
+    params: Vec<Value>,
+    archived: Option<u64>,
+    tag: &str,
+) -> TardisResult<u64> {
+    conn.begin().await?;
+    let deleted = conn.execute_one(&format!("DELETE FROM {table_name} WHERE {where_fragment}"), params).await?.rows_affected();
+    if archived.map_or(false, |archived| archived != deleted) {
+        conn.rollback().await?;
+        warn!("[SPI-Log] logs of tag {} changed during archiving, skipped", tag);
+        return Ok(0);
+    }
+    conn.commit().await?;
+    Ok(deleted)
+}
+
+/// Make sure the time partition of the log exists, returns the partition table name
+///
+/// 确保日志所属的时间分区存在，返回分区表名
+///
+/// Only tag tables created as partitioned tables (see `log_pg_initializer::init_table_and_conn`) have partitions,
+/// the partitions are checked in the catalog so that all the nodes share the same view.
+/// 只有以分区表方式创建的 tag 表（见 `log_pg_initializer::init_table_and_conn`）才有分区，分区是否存在以数据库目录为准，各节点保持一致。
+pub(crate) async fn ensure_partition(conn: &TardisRelDBlConnection, table_name: &str, kind: LogPartitionKind, ts: &DateTime<Utc>) -> TardisResult<Option<String>> {
+    let Some(partition_full_name) = partition_name(table_name, kind, ts) else {
+        return Ok(None);
+    };
+    let (schema_name, rel_name) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let partition = partition_full_name.rsplit('.').next().unwrap_or(&partition_full_name);
+    if relation_kind(conn, schema_name, partition).await?.is_some() {
+        return Ok(Some(partition_full_name));
+    }
+    // tag 表在配置分区策略之前已创建，或分区名超长时，日志写入 tag 表
+    if relation_kind(conn, schema_name, rel_name).await?.as_deref() != Some("p") {
+        return Ok(None);
+    }
+    let Some((start, end)) = partition_range(&partition_full_name) else {
+        return Ok(None);
+    };
+    let result = conn
+        .execute_one(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {partition_full_name} PARTITION OF {table_name} FOR VALUES FROM ('{}') TO ('{}')",
+                start.to_rfc3339(),
+                end.to_rfc3339()
+            ),
+            vec![],
+        )
+        .await;
+    // 其他节点可能同时创建了该分区
+    if let Err(e) = result {
+        if relation_kind(conn, schema_name, partition).await?.is_none() {
+            return Err(e);
+        }
+    }
+    Ok(Some(partition_full_name))
+}
+
+/// Whether the partition names of the tag table fit in the postgres identifier length
+///
+/// 判断 tag 表的分区名是否在 postgres 标识符长度限制内
+pub(crate) fn partition_name_fits(rel_name: &str, kind: LogPartitionKind) -> bool {
+    partition_name(rel_name, kind, &Utc::now()).map_or(false, |partition| partition.len() <= MAX_IDENTIFIER_LEN)
+}
+
+/// Returns the kind of the relation (`r` for ordinary tables, `p` for partitioned tables), or `None` if it does not exist
+///
+/// 返回关系的类型（`r` 为普通表，`p` 为分区表），不存在时返回 `None`
+pub(crate) async fn relation_kind(conn: &TardisRelDBlConnection, schema_name: &str, rel_name: &str) -> TardisResult<Option<String>> {
+    let row = conn
+        .query_one(
+            r#"SELECT c.relkind::text AS kind FROM pg_class c
+  JOIN pg_namespace n ON n.oid = c.relnamespace
+WHERE n.nspname = $1 AND c.relname = $2"#,
+            vec![Value::from(schema_name), Value::from(rel_name)],
+        )
+        .await?;
+    match row {
+        Some(row) => Ok(Some(row.try_get("", "kind")?)),
+        None => Ok(None),
+    }
+}
+
+async fn find_partitions(conn: &TardisRelDBlConnection, schema_name: &str, rel_name: &str) -> TardisResult<Vec<String>> {
+    let rows = conn
+        .query_all(
+            r#"SELECT c.relname AS partition FROM pg_inherits i
+  JOIN pg_class c ON c.oid = i.inhrelid
+  JOIN pg_class p ON p.oid = i.inhparent
+  JOIN pg_namespace n ON n.oid = p.relnamespace
+WHERE n.nspname = $1 AND p.relname = $2
+ORDER BY c.relname"#,
+            vec![Value::from(schema_name), Value::from(rel_name)],
+        )
+        .await?;
+    let prefix = format!("{rel_name}_p");
+    let mut partitions = Vec::with_capacity(rows.len());
+    for row in rows {
+        let partition: String = row.try_get("", "partition")?;
+        if partition.starts_with(&prefix) {
+            partitions.push(partition);
+        }
+    }
+    Ok(partitions)
+}
+
+/// Export the matched rows to spi-object as json lines, returns the object paths and the number of exported rows
+///
+/// 将匹配的行以 json lines 格式导出至 spi-object，返回对象路径及导出的行数
+#[allow(clippy::too_many_arguments)]
+async fn archive(
+    conn: &TardisRelDBlConnection,
+    from: &str,
+    where_fragment: &str,
+    params: Vec<Value>,
+    name: &str,
+    tag: &str,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<(Vec<String>, u64)> {
+    let conf = funs.conf::<LogConfig>();
+    let batch_size = conf.retention.archive_batch_size.max(1) as u64;
+    let mut objects = vec![];
+    let mut exported_rows = 0;
+    // 按 ts 游标分页，每批结束于边界时间戳，相同时间戳的行归入同一批
+    let mut last_ts: Option<DateTime<Utc>> = None;
+    loop {
+        let mut batch_where = where_fragment.to_string();
+        let mut batch_params = params.clone();
+        if let Some(last_ts) = last_ts {
+            batch_params.push(Value::from(last_ts));
+            batch_where = format!("{batch_where} AND ts > ${}", batch_params.len());
+        }
+        let boundary = conn
+            .query_one(
+                &format!("SELECT ts FROM {from} t WHERE {batch_where} ORDER BY ts OFFSET {} LIMIT 1", batch_size - 1),
+                batch_params.clone(),
+            )
+            .await?;
+        let boundary = match boundary {
+            Some(boundary) => Some(boundary.try_get::<DateTime<Utc>>("", "ts")?),
+            None => None,
+        };
+        if let Some(boundary) = boundary {
+            batch_params.push(Value::from(boundary));
+            batch_where = format!("{batch_where} AND ts <= ${}", batch_params.len());
+        }
+        let rows = conn.query_all(&format!("SELECT row_to_json(t)::text AS row FROM {from} t WHERE {batch_where} ORDER BY ts"), batch_params).await?;
+        if rows.is_empty() {
+            break;
+        }
+        let mut content = String::new();
+        for row in &rows {
+            let row: String = row.try_get("", "row")?;
+            content.push_str(&row);
+            content.push('\n');
+        }
+        let object_path = format!("{}/{tag}/{name}-{}.jsonl", conf.retention.archive_path_prefix, objects.len());
+        upload(&object_path, content, funs, ctx).await?;
+        objects.push(object_path);
+        exported_rows += rows.len() as u64;
+        if boundary.is_none() {
+            break;
+        }
+        last_ts = boundary;
+    }
+    Ok((objects, exported_rows))
+}
+
+async fn upload(object_path: &str, content: String, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let conf = funs.conf::<LogConfig>();
+    let url = SpiObjectClient::presign_put_obj_url(
+        object_path,
+        ARCHIVE_PRESIGN_EXP_SEC,
+        Some(true),
+        None,
+        None,
+        conf.retention.archive_bucket.clone(),
+        conf.retention.archive_bs_id.clone(),
+        funs,
+        ctx,
+    )
+    .await?;
+    let Some(url) = url else {
+        return Err(funs.err().internal_error(
+            "item",
+            "archive",
+            &format!("failed to presign archive object {object_path}"),
+            "500-spi-log-archive-presign-failed",
+        ));
+    };
+    let resp = funs.web_client().put_str_to_str(&url, &content, vec![]).await?;
+    if !(200..300).contains(&resp.code) {
+        return Err(funs.err().internal_error(
+            "item",
+            "archive",
+            &format!("failed to upload archive object {object_path}, status: {}", resp.code),
+            "500-spi-log-archive-upload-failed",
+        ));
+    }
+    Ok(())
+}
+
+fn partition_name(table_name: &str, kind: LogPartitionKind, ts: &DateTime<Utc>) -> Option<String> {
+    match kind {
+        LogPartitionKind::None => None,
+        LogPartitionKind::Day => Some(format!("{table_name}_p{}", ts.format("%Y%m%d"))),
+        LogPartitionKind::Month => Some(format!("{table_name}_p{}", ts.format("%Y%m"))),
+    }
+}
+
+fn partition_range(partition: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (_, suffix) = partition.rsplit_once("_p")?;
+    if !suffix.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (start, end) = match suffix.len() {
+        8 => {
+            let start = NaiveDate::parse_from_str(suffix, "%Y%m%d").ok()?;
+            (start, start.succ_opt()?)
+        }
+        6 => {
+            let start = NaiveDate::parse_from_str(&format!("{suffix}01"), "%Y%m%d").ok()?;
+            let end = if start.month() == 12 {
+                NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)?
+            } else {
+                NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)?
+            };
+            (start, end)
+        }
+        _ => return None,
+    };
+    Some((start.and_hms_opt(0, 0, 0)?.and_utc(), end.and_hms_opt(0, 0, 0)?.and_utc()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tardis::chrono::{TimeZone, Utc};
+
+    use super::{partition_name, partition_range};
+    use crate::log_config::LogPartitionKind;
+
+    #[test]
+    fn test_partition() {
+        let ts = Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).unwrap();
+        assert_eq!(partition_name("s.starsys_logv2_audit", LogPartitionKind::None, &ts), None);
+        let day = partition_name("s.starsys_logv2_audit", LogPartitionKind::Day, &ts).unwrap();
+        assert_eq!(day, "s.starsys_logv2_audit_p20241231");
+        assert_eq!(
+            partition_range(&day).unwrap(),
+            (Utc.with_ymd_and_hms(2024, 12, 31, 0, 0, 0).unwrap(), Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap())
+        );
+        let month = partition_name("s.starsys_logv2_audit", LogPartitionKind::Month, &ts).unwrap();
+        assert_eq!(month, "s.starsys_logv2_audit_p202412");
+        assert_eq!(
+            partition_range(&month).unwrap(),
+            (Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap(), Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(partition_range("s.starsys_logv2_audit_parent"), None);
+        assert_eq!(partition_range("s.starsys_logv2_audit"), None);
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use bios_basic::dto::BasicQueryCondInfo;
+use bios_basic::enumeration::BasicQueryOpKind;
+use tardis::basic::result::TardisResult;
+use tardis::futures::stream::BoxStream;
+use tardis::futures::StreamExt;
+use tardis::serde_json::{json, Value};
+use tardis::tokio::sync::{broadcast, RwLock};
+use tardis::TardisFunsInst;
+
+use crate::dto::log_item_dto::{AdvBasicQueryCondInfo, LogItemFindResp, LogItemTailReq};
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Added log published to the mq topic of the live tail
+///
+/// 发布到实时订阅 mq 主题的新增日志
+#[cfg(feature = "with-mq")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct LogTailMessage {
+    schema_name: String,
+    tag: String,
+    item: LogItemFindResp,
+}
+
+/// Live tail channels of the subscribers on the current node by `{schema}:{tag}`
+///
+/// 当前节点上订阅者的实时订阅通道，key 为 `{schema}:{tag}`
+fn get_channels() -> &'static RwLock<HashMap<String, broadcast::Sender<LogItemFindResp>>> {
+    static CHANNELS: OnceLock<RwLock<HashMap<String, broadcast::Sender<LogItemFindResp>>>> = OnceLock::new();
+    CHANNELS.get_or_init(Default::default)
+}
+
+fn format_channel(schema_name: &str, tag: &str) -> String {
+    format!("{schema_name}:{tag}")
+}
+
+/// Receive the added logs published by all the nodes and deliver them to the subscribers on the current node
+///
+/// 接收所有节点发布的新增日志，并投递给当前节点上的订阅者
+#[allow(unused_variables)]
+pub(crate) async fn init(funs: &TardisFunsInst) -> TardisResult<()> {
+    #[cfg(feature = "with-mq")]
+    {
+        use crate::log_config::LogConfig;
+        funs.mq()
+            .subscribe(&funs.conf::<LogConfig>().mq_topic_tail, |(_, message)| async move {
+                let message = tardis::TardisFuns::json.str_to_obj::<LogTailMessage>(&message)?;
+                deliver(&message.schema_name, &message.tag, message.item).await;
+                Ok(())
+            })
+            .await?;
+    }
+    Ok(())
+}
+
+/// Publish the added log to the subscribers of the tag on all the nodes,
+/// without mq the log is only delivered to the subscribers on the current node
+///
+/// 将新增的日志发布给所有节点上 tag 的订阅者，未启用 mq 时仅投递给当前节点上的订阅者
+#[allow(unused_variables)]
+pub(crate) async fn publish(schema_name: &str, tag: &str, item: LogItemFindResp, funs: &TardisFunsInst) -> TardisResult<()> {
+    #[cfg(feature = "with-mq")]
+    {
+        use crate::log_config::LogConfig;
+        funs.mq()
+            .publish(
+                &funs.conf::<LogConfig>().mq_topic_tail,
+                tardis::TardisFuns::json.obj_to_string(&LogTailMessage {
+                    schema_name: schema_name.to_string(),
+                    tag: tag.to_string(),
+                    item,
+                })?,
+                &HashMap::new(),
+            )
+            .await?;
+    }
+    #[cfg(not(feature = "with-mq"))]
+    deliver(schema_name, tag, item).await;
+    Ok(())
+}
+
+async fn deliver(schema_name: &str, tag: &str, item: LogItemFindResp) {
+    let channel = format_channel(schema_name, tag);
+    if !get_channels().read().await.contains_key(&channel) {
+        return;
+    }
+    let mut channels = get_channels().write().await;
+    if channels.get(&channel).map_or(true, |sender| sender.send(item).is_err()) {
+        // no subscribers left
+        channels.remove(&channel);
+    }
+}
+
+/// Subscribe the logs added to the tag that match the filters
+///
+/// 订阅 tag 中新增且满足过滤条件的日志
+pub(crate) async fn subscribe(schema_name: &str, tag: &str, tail_req: LogItemTailReq) -> BoxStream<'static, LogItemFindResp> {
+    let receiver = get_channels().write().await.entry(format_channel(schema_name, tag)).or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0).subscribe();
+    tardis::futures::stream::unfold((receiver, tail_req), |(mut receiver, tail_req)| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(item) if is_match(&tail_req, &item) => return Some((item, (receiver, tail_req))),
+                Ok(_) => continue,
+                // Slow subscribers skip the overwritten logs
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+    .boxed()
+}
+
+/// Check the log against the filters, with the same semantics as `findv2`
+///
+/// 按与 `findv2` 相同的语义检查日志是否满足过滤条件
+fn is_match(tail_req: &LogItemTailReq, item: &LogItemFindResp) -> bool {
+    if let Some(kinds) = &tail_req.kinds {
+        if !kinds.iter().any(|kind| item.kind.contains(&kind.to_string())) {
+            return false;
+        }
+    }
+    if let Some(keys) = &tail_req.keys {
+        if !keys.iter().any(|key| key.to_string() == item.key) {
+            return false;
+        }
+    }
+    if let Some(ops) = &tail_req.ops {
+        if !ops.contains(&item.op) {
+            return false;
+        }
+    }
+    if let Some(owners) = &tail_req.owners {
+        if !owners.contains(&item.owner) {
+            return false;
+        }
+    }
+    if let Some(rel_keys) = &tail_req.rel_keys {
+        if !rel_keys.iter().any(|rel_key| rel_key.to_string() == item.rel_key) {
+            return false;
+        }
+    }
+    if let Some(own_paths) = &tail_req.own_paths {
+        if !item.own_paths.starts_with(own_paths) {
+            return false;
+        }
+    }
+    if tail_req.ts_start.map_or(false, |ts_start| item.ts < ts_start) || tail_req.ts_end.map_or(false, |ts_end| item.ts > ts_end) {
+        return false;
+    }
+    let ext_vars = item.ext.as_object().map(|ext| ext.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<HashMap<_, _>>()).unwrap_or_default();
+    if let Some(ext) = &tail_req.ext {
+        if !ext.iter().all(|cond| check_cond(cond, &ext_vars)) {
+            return false;
+        }
+    }
+    if let Some(ext_or) = &tail_req.ext_or {
+        if !ext_or.iter().any(|cond| check_cond(cond, &ext_vars)) {
+            return false;
+        }
+    }
+    if let Some(adv_query) = &tail_req.adv_query {
+        let column_vars = HashMap::from([
+            ("kind".to_string(), json!(item.kind)),
+            ("key".to_string(), json!(item.key)),
+            ("op".to_string(), json!(item.op)),
+            ("owner".to_string(), json!(item.owner)),
+            ("owner_name".to_string(), json!(item.owner_name)),
+            ("own_paths".to_string(), json!(item.own_paths)),
+            ("rel_key".to_string(), json!(item.rel_key)),
+            ("data_source".to_string(), json!(item.data_source)),
+            ("msg".to_string(), json!(item.msg)),
+            ("idempotent_id".to_string(), json!(item.id)),
+        ]);
+        // the groups are joined as `1=1 AND g1 OR g2 ...`, AND takes precedence over OR
+        let mut or_terms = vec![true];
+        for group_query in adv_query {
+            let Some(ext) = group_query.ext.as_ref().filter(|ext| !ext.is_empty()) else {
+                continue;
+            };
+            let group_match = ext.iter().all(|cond| check_adv_cond(cond, &ext_vars, &column_vars));
+            if group_query.group_by_or.unwrap_or(false) {
+                or_terms.push(group_match);
+            } else if let Some(last) = or_terms.last_mut() {
+                *last = *last && group_match;
+            }
+        }
+        if !or_terms.into_iter().any(|term| term) {
+            return false;
+        }
+    }
+    true
+}
+
+fn check_adv_cond(cond: &AdvBasicQueryCondInfo, ext_vars: &HashMap<String, Value>, column_vars: &HashMap<String, Value>) -> bool {
+    let basic_cond = BasicQueryCondInfo {
+        field: cond.field.clone(),
+        op: cond.op.clone(),
+        value: cond.value.clone(),
+    };
+    if cond.in_ext.unwrap_or(true) {
+        check_cond(&basic_cond, ext_vars)
+    } else {
+        check_cond(&basic_cond, column_vars)
+    }
+}
+
+fn check_cond(cond: &BasicQueryCondInfo, vars: &HashMap<String, Value>) -> bool {
+    let value = vars.get(&cond.field).filter(|value| !value.is_null());
+    match cond.op {
+        BasicQueryOpKind::IsNull => value.is_none(),
+        BasicQueryOpKind::IsNotNull => value.is_some(),
+        BasicQueryOpKind::IsNullOrEmpty => value.map_or(true, |value| value.as_str() == Some("") || value.as_array().map_or(false, |array| array.is_empty())),
+        // `ext -> field ? value` matches both strings and arrays
+        BasicQueryOpKind::In | BasicQueryOpKind::NotIn => {
+            let values = cond.value.as_array().cloned().unwrap_or_else(|| vec![cond.value.clone()]);
+            let found = value.map_or(false, |value| match value {
+                Value::Array(array) => values.iter().any(|value| array.contains(value)),
+                _ => values.contains(value),
+            });
+            (cond.op == BasicQueryOpKind::In) == found
+        }
+        _ => BasicQueryCondInfo::check_or_and_conds(&[vec![cond.clone()]], vars).unwrap_or(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bios_basic::{dto::BasicQueryCondInfo, enumeration::BasicQueryOpKind};
+    use tardis::chrono::Utc;
+    use tardis::serde_json::json;
+
+    use super::is_match;
+    use crate::dto::log_item_dto::{AdvBasicQueryCondInfo, AdvLogItemQueryReq, LogItemFindResp, LogItemTailReq};
+
+    fn item() -> LogItemFindResp {
+        LogItemFindResp {
+            content: json!({"title": "login"}),
+            kind: vec!["audit".to_string(), "security".to_string()],
+            ext: json!({"status": 1, "apps": ["app01", "app02"], "name": "测试"}),
+            data_source: "".to_string(),
+            owner: "account001".to_string(),
+            owner_name: "".to_string(),
+            own_paths: "t1/app001".to_string(),
+            id: "id1".to_string(),
+            key: "001".to_string(),
+            op: "login".to_string(),
+            rel_key: "".to_string(),
+            ts: Utc::now(),
+            msg: "".to_string(),
+        }
+    }
+
+    fn cond(field: &str, op: BasicQueryOpKind, value: tardis::serde_json::Value) -> BasicQueryCondInfo {
+        BasicQueryCondInfo {
+            field: field.to_string(),
+            op,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_is_match() {
+        assert!(is_match(&LogItemTailReq::default(), &item()));
+        assert!(is_match(
+            &LogItemTailReq {
+                kinds: Some(vec!["security".into()]),
+                ops: Some(vec!["login".to_string()]),
+                own_paths: Some("t1".to_string()),
+                ..Default::default()
+            },
+            &item()
+        ));
+        assert!(!is_match(
+            &LogItemTailReq {
+                owners: Some(vec!["account002".to_string()]),
+                ..Default::default()
+            },
+            &item()
+        ));
+
+        // ext
+        assert!(is_match(
+            &LogItemTailReq {
+                ext: Some(vec![
+                    cond("status", BasicQueryOpKind::Eq, json!(1)),
+                    cond("apps", BasicQueryOpKind::In, json!(["app02"])),
+                    cond("missing", BasicQueryOpKind::IsNull, json!(null)),
+                ]),
+                ..Default::default()
+            },
+            &item()
+        ));
+        assert!(!is_match(
+            &LogItemTailReq {
+                ext: Some(vec![
+                    cond("status", BasicQueryOpKind::Eq, json!(1)),
+                    cond("apps", BasicQueryOpKind::NotIn, json!(["app01"]))
+                ]),
+                ..Default::default()
+            },
+            &item()
+        ));
+        assert!(is_match(
+            &LogItemTailReq {
+                ext_or: Some(vec![cond("status", BasicQueryOpKind::Eq, json!(2)), cond("name", BasicQueryOpKind::Like, json!("测"))]),
+                ..Default::default()
+            },
+            &item()
+        ));
+
+        // adv_query: 1=1 AND (key = 002) OR (op = login)
+        let adv_cond = |field: &str, value: &str| AdvBasicQueryCondInfo {
+            in_ext: Some(false),
+            field: field.to_string(),
+            op: BasicQueryOpKind::Eq,
+            value: json!(value),
+        };
+        let adv_query = vec![
+            AdvLogItemQueryReq {
+                group_by_or: Some(false),
+                ext: Some(vec![adv_cond("key", "002")]),
+            },
+            AdvLogItemQueryReq {
+                group_by_or: Some(true),
+                ext: Some(vec![adv_cond("op", "login")]),
+            },
+        ];
+        assert!(is_match(
+            &LogItemTailReq {
+                adv_query: Some(adv_query.clone()),
+                ..Default::default()
+            },
+            &item()
+        ));
+        assert!(!is_match(
+            &LogItemTailReq {
+                adv_query: Some(adv_query[..1].to_vec()),
+                ..Default::default()
+            },
+            &item()
+        ));
+    }
+}
@@ -83,7 +83,7 @@ async fn export_log_v2(
         let (conn, table_name) = pgv2::log_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
         let result = conn
             .query_all(
-                &format!("SELECT key, kind, content, data_source, owner, owner_name, own_paths, ext, tag, op, rel_key, ts, idempotent_id, disable, msg, push FROM {table_name} WHERE (ts > $1 or ts <= $2)  and own_paths like $3 order by ts desc"),
+                &format!("SELECT key, kind, content, data_source, owner, owner_name, own_paths, ext, tag, op, rel_key, ts, idempotent_id, disable, msg, push, chain_tenant, chain_seq, prev_hash, hash FROM {table_name} WHERE (ts > $1 or ts <= $2)  and own_paths like $3 order by ts desc"),
                 vec![Value::from(start_time), Value::from(end_time), Value::from(format!("{}%", ctx.own_paths.clone()))],
             )
             .await?;
@@ -107,6 +107,10 @@ async fn export_log_v2(
                     disable: item.try_get("", "disable")?,
                     msg: item.try_get("", "msg")?,
                     push: item.try_get("", "push")?,
+                    chain_tenant: item.try_get("", "chain_tenant")?,
+                    chain_seq: item.try_get("", "chain_seq")?,
+                    prev_hash: item.try_get("", "prev_hash")?,
+                    hash: item.try_get("", "hash")?,
                 })
             })
             .collect::<TardisResult<Vec<LogExportV2AggResp>>>()?;
@@ -1,14 +1,29 @@
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
     db::{reldb_client::TardisRelDBClient, sea_orm::Value},
+    futures::stream::BoxStream,
     serde_json::Value as JsonValue,
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
 };
 
-use bios_basic::{dto::BasicQueryCondInfo, enumeration::BasicQueryOpKind, helper::db_helper, spi::spi_funs::SpiBsInst};
+use bios_basic::{
+    dto::BasicQueryCondInfo,
+    enumeration::BasicQueryOpKind,
+    helper::db_helper,
+    spi::{spi_funs::SpiBsInst, spi_initializer::common_pg},
+};
 
-use crate::dto::log_item_dto::{AdvBasicQueryCondInfo, LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp};
+use crate::{
+    dto::log_item_dto::{
+        AdvBasicQueryCondInfo, LogChainVerifyResp, LogConfigReq, LogItemAddReq, LogItemAddV2Req, LogItemAggReq, LogItemAggResp, LogItemFindReq, LogItemFindResp, LogItemTailReq,
+        LogRetentionResp,
+    },
+    log_config::LogRetentionPolicy,
+    log_constants::TABLE_LOG_FLAG,
+    serv::log_retention_serv,
+};
 
 use super::log_pg_initializer;
 
@@ -557,6 +572,25 @@ pub async fn findv2(_: &mut LogItemFindReq, funs: &TardisFunsInst, _: &TardisCon
     Err(funs.err().bad_request("item", "find", "Find v2 is not supported", "400-spi-log-find-v2-not-supported"))
 }
 
+pub async fn query_agg(_: &mut LogItemAggReq, funs: &TardisFunsInst, _: &TardisContext, _: &SpiBsInst) -> TardisResult<Vec<LogItemAggResp>> {
+    Err(funs.err().bad_request("item", "agg", "Aggregation query is not supported", "400-spi-log-agg-not-supported"))
+}
+
+pub async fn verify_chain(
+    _tag: &str,
+    _ts_start: Option<DateTime<Utc>>,
+    _ts_end: Option<DateTime<Utc>>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    _inst: &SpiBsInst,
+) -> TardisResult<LogChainVerifyResp> {
+    Err(funs.err().bad_request("item", "verify_chain", "Hash chain is not supported", "400-spi-log-hash-chain-not-supported"))
+}
+
+pub async fn subscribe(_: &str, _: &LogItemTailReq, funs: &TardisFunsInst, _: &TardisContext, _: &SpiBsInst) -> TardisResult<BoxStream<'static, LogItemFindResp>> {
+    Err(funs.err().bad_request("item", "subscribe", "Live tail is not supported", "400-spi-log-tail-not-supported"))
+}
+
 pub async fn modify_ext(tag: &str, key: &str, ext: &mut JsonValue, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
@@ -580,3 +614,15 @@ pub async fn add_config(_req: &LogConfigReq, funs: &TardisFunsInst, _ctx: &Tardi
 pub async fn delete_config(_config: &mut LogConfigReq, funs: &TardisFunsInst, _ctx: &TardisContext, _inst: &SpiBsInst) -> TardisResult<()> {
     Err(funs.err().bad_request("item", "delete_config", "Delete config is not supported", "400-spi-log-delete-config-not-supported"))
 }
+
+pub async fn apply_retention(tag: &str, policy: &LogRetentionPolicy, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LogRetentionResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    if !common_pg::check_table_exit(&format!("{TABLE_LOG_FLAG}_{tag}"), &bs_inst.0.conn(), ctx).await? {
+        return Ok(LogRetentionResp {
+            tag: tag.to_string(),
+            ..Default::default()
+        });
+    }
+    let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    log_retention_serv::apply_to_table(&mut conn, &table_name, tag, policy, funs, ctx).await
+}
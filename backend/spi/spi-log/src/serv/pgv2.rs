@@ -1,2 +1,3 @@
+pub mod log_pg_chain_serv;
 pub mod log_pg_initializer;
 pub mod log_pg_item_serv;
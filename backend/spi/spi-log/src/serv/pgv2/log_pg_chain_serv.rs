@@ -0,0 +1,344 @@
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::{QueryResult, Value},
+    },
+    serde_json::{json, Map, Value as JsonValue},
+    TardisFuns, TardisFunsInst,
+};
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common_pg};
+
+use crate::{
+    dto::log_item_dto::{LogChainBrokenKind, LogChainBrokenLinkResp, LogChainVerifyResp, LogItemAddV2Req},
+    log_constants::TABLE_LOG_FLAG_V2,
+};
+
+use super::log_pg_initializer;
+
+const VERIFY_BATCH_SIZE: u64 = 1000;
+
+/// Hash chain position of a log
+///
+/// 日志在哈希链中的位置
+pub(crate) struct ChainLink {
+    pub chain_tenant: String,
+    pub chain_seq: i64,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// Fields of the log covered by the hash, `ext` is excluded as it can be modified by `modify_ext`
+///
+/// 哈希覆盖的日志字段，`ext` 可通过 `modify_ext` 修改，因此不参与计算
+struct ChainRecord {
+    idempotent_id: String,
+    ts: DateTime<Utc>,
+    key: String,
+    kind: Vec<String>,
+    tag: String,
+    op: String,
+    content: JsonValue,
+    data_source: String,
+    owner: String,
+    owner_name: String,
+    own_paths: String,
+    rel_key: String,
+    msg: String,
+}
+
+impl ChainRecord {
+    fn from_add_req(add_req: &LogItemAddV2Req) -> Self {
+        Self {
+            idempotent_id: add_req.idempotent_id.clone().unwrap_or_default(),
+            ts: add_req.ts.unwrap_or_default(),
+            key: add_req.key.as_ref().map(|key| key.to_string()).unwrap_or_default(),
+            kind: add_req.kind.as_ref().map(|kind| kind.to_string()).unwrap_or_default().split(',').map(|s| s.to_string()).collect(),
+            tag: add_req.tag.clone(),
+            op: add_req.op.clone().unwrap_or_default(),
+            content: add_req.content.clone(),
+            data_source: add_req.data_source.clone().unwrap_or_default(),
+            owner: add_req.owner.clone().unwrap_or_default(),
+            owner_name: add_req.owner_name.clone().unwrap_or_default(),
+            own_paths: add_req.own_paths.clone().unwrap_or_default(),
+            rel_key: add_req.rel_key.as_ref().map(|rel_key| rel_key.to_string()).unwrap_or_default(),
+            msg: add_req.msg.clone().unwrap_or_default(),
+        }
+    }
+
+    fn from_row(row: &QueryResult) -> TardisResult<Self> {
+        Ok(Self {
+            idempotent_id: row.try_get("", "idempotent_id")?,
+            ts: row.try_get("", "ts")?,
+            key: row.try_get("", "key")?,
+            kind: row.try_get("", "kind")?,
+            tag: row.try_get("", "tag")?,
+            op: row.try_get("", "op")?,
+            content: row.try_get("", "content")?,
+            data_source: row.try_get("", "data_source")?,
+            owner: row.try_get("", "owner")?,
+            owner_name: row.try_get("", "owner_name")?,
+            own_paths: row.try_get("", "own_paths")?,
+            rel_key: row.try_get("", "rel_key")?,
+            msg: row.try_get("", "msg")?,
+        })
+    }
+
+    /// sha256 of the canonical json array `[chain_tenant, chain_seq, prev_hash, idempotent_id, ts(μs), key, kind, tag, op, content, data_source, owner, owner_name, own_paths, rel_key, msg]`,
+    /// object keys of the content are sorted
+    ///
+    /// 对规范化的 json 数组计算 sha256，content 中对象的 key 按字典序排列
+    fn hash(&self, chain_tenant: &str, chain_seq: i64, prev_hash: &str) -> TardisResult<String> {
+        let payload = json!([
+            chain_tenant,
+            chain_seq,
+            prev_hash,
+            self.idempotent_id,
+            self.ts.timestamp_micros(),
+            self.key,
+            self.kind,
+            self.tag,
+            self.op,
+            canonical_json(&self.content),
+            self.data_source,
+            self.owner,
+            self.owner_name,
+            self.own_paths,
+            self.rel_key,
+            self.msg,
+        ]);
+        TardisFuns::crypto.digest.sha256(&payload.to_string())
+    }
+}
+
+/// Chains are isolated by tenant, i.e. the first level of own_paths
+///
+/// 哈希链按租户（即 own_paths 的第一级）隔离
+pub(crate) fn chain_tenant(own_paths: &str) -> String {
+    own_paths.split('/').next().unwrap_or_default().to_string()
+}
+
+/// Append the log to the end of the chain of its tag and tenant,
+/// must be called in the transaction that inserts the log and `add_req.ts` must be set with microsecond precision
+///
+/// 将日志追加至其 tag 及租户的哈希链末尾，须在写入日志的事务中调用，且 `add_req.ts` 须已设置为微秒精度
+pub(crate) async fn link(conn: &TardisRelDBlConnection, table_name: &str, add_req: &LogItemAddV2Req, ctx: &TardisContext) -> TardisResult<ChainLink> {
+    let chain_tenant = chain_tenant(add_req.own_paths.as_deref().unwrap_or(&ctx.own_paths));
+    // 串行化同一条链的写入，锁在事务结束时释放
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(format!("{table_name}:{chain_tenant}"))]).await?;
+    let last = conn
+        .query_one(
+            &format!("SELECT chain_seq, hash FROM {table_name} WHERE chain_tenant = $1 AND chain_seq > 0 ORDER BY chain_seq DESC LIMIT 1"),
+            vec![Value::from(chain_tenant.as_str())],
+        )
+        .await?;
+    let (chain_seq, prev_hash) = if let Some(last) = last {
+        (last.try_get::<i64>("", "chain_seq")? + 1, last.try_get::<String>("", "hash")?)
+    } else {
+        (1, "".to_string())
+    };
+    let hash = ChainRecord::from_add_req(add_req).hash(&chain_tenant, chain_seq, &prev_hash)?;
+    Ok(ChainLink {
+        chain_tenant,
+        chain_seq,
+        prev_hash,
+        hash,
+    })
+}
+
+/// Verify the chain of the tag for the current tenant, stops at the first broken link.
+/// When a time range is specified, all links between the first and the last matched logs are verified.
+///
+/// 校验当前租户下 tag 的哈希链，遇到第一个断裂的链接即停止。
+/// 指定时间范围时，校验范围内首尾日志之间的所有链接。
+///
+/// Removing the latest logs can only be detected by comparing `last_seq`/`last_hash` with a previously saved result.
+/// 删除最新的日志只能通过与之前保存的 `last_seq`/`last_hash` 对比发现。
+pub async fn verify_chain(
+    tag: &str,
+    ts_start: Option<DateTime<Utc>>,
+    ts_end: Option<DateTime<Utc>>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<LogChainVerifyResp> {
+    let chain_tenant = chain_tenant(&ctx.own_paths);
+    let mut resp = LogChainVerifyResp {
+        tag: tag.to_string(),
+        chain_tenant: chain_tenant.clone(),
+        ..Default::default()
+    };
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    if !common_pg::check_table_exit(&format!("{TABLE_LOG_FLAG_V2}_{tag}"), &bs_inst.0.conn(), ctx).await? {
+        return Ok(resp);
+    }
+    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+
+    let mut range_fragments = vec!["chain_tenant = $1".to_string(), "chain_seq > 0".to_string()];
+    let mut range_vals = vec![Value::from(chain_tenant.as_str())];
+    if let Some(ts_start) = ts_start {
+        range_vals.push(Value::from(ts_start));
+        range_fragments.push(format!("ts >= ${}", range_vals.len()));
+    }
+    if let Some(ts_end) = ts_end {
+        range_vals.push(Value::from(ts_end));
+        range_fragments.push(format!("ts <= ${}", range_vals.len()));
+    }
+    let Some(range) = conn
+        .query_one(
+            &format!(
+                "SELECT min(chain_seq) AS first_seq, max(chain_seq) AS last_seq FROM {table_name} WHERE {}",
+                range_fragments.join(" AND ")
+            ),
+            range_vals,
+        )
+        .await?
+    else {
+        return Ok(resp);
+    };
+    let (Some(first_seq), Some(last_seq)) = (range.try_get::<Option<i64>>("", "first_seq")?, range.try_get::<Option<i64>>("", "last_seq")?) else {
+        return Ok(resp);
+    };
+    resp.first_seq = Some(first_seq);
+
+    // 锚定到范围之前的一条记录（可能已被保留策略删除）
+    let mut prev: Option<(i64, String)> = if first_seq > 1 {
+        conn.query_one(
+            &format!("SELECT hash FROM {table_name} WHERE chain_tenant = $1 AND chain_seq = $2"),
+            vec![Value::from(chain_tenant.as_str()), Value::from(first_seq - 1)],
+        )
+        .await?
+        .map(|row| row.try_get::<String>("", "hash").map(|hash| (first_seq - 1, hash)))
+        .transpose()?
+    } else {
+        Some((0, "".to_string()))
+    };
+    let mut cursor = first_seq - 1;
+    loop {
+        let rows = conn
+            .query_all(
+                &format!(
+                    r#"SELECT idempotent_id, ts, key, kind, tag, op, content, data_source, owner, owner_name, own_paths, rel_key, msg, chain_seq, prev_hash, hash
+FROM {table_name}
+WHERE chain_tenant = $1 AND chain_seq > $2 AND chain_seq <= $3
+ORDER BY chain_seq ASC
+LIMIT {VERIFY_BATCH_SIZE}"#
+                ),
+                vec![Value::from(chain_tenant.as_str()), Value::from(cursor), Value::from(last_seq)],
+            )
+            .await?;
+        if rows.is_empty() {
+            break;
+        }
+        for row in &rows {
+            let chain_seq: i64 = row.try_get("", "chain_seq")?;
+            let prev_hash: String = row.try_get("", "prev_hash")?;
+            let hash: String = row.try_get("", "hash")?;
+            let record = ChainRecord::from_row(row)?;
+            let broken_kind = match &prev {
+                Some((prev_seq, _)) if chain_seq != prev_seq + 1 => {
+                    resp.broken = Some(LogChainBrokenLinkResp {
+                        seq: prev_seq + 1,
+                        kind: LogChainBrokenKind::Missing,
+                        idempotent_id: None,
+                        ts: None,
+                    });
+                    return Ok(resp);
+                }
+                Some((_, prev_chain_hash)) if *prev_chain_hash != prev_hash => Some(LogChainBrokenKind::PrevHashMismatch),
+                _ if record.hash(&chain_tenant, chain_seq, &prev_hash)? != hash => Some(LogChainBrokenKind::HashMismatch),
+                _ => None,
+            };
+            if let Some(kind) = broken_kind {
+                resp.broken = Some(LogChainBrokenLinkResp {
+                    seq: chain_seq,
+                    kind,
+                    idempotent_id: Some(record.idempotent_id),
+                    ts: Some(record.ts),
+                });
+                return Ok(resp);
+            }
+            resp.verified += 1;
+            resp.last_seq = Some(chain_seq);
+            resp.last_hash = Some(hash.clone());
+            prev = Some((chain_seq, hash));
+            cursor = chain_seq;
+        }
+        if (rows.len() as u64) < VERIFY_BATCH_SIZE {
+            break;
+        }
+    }
+    if resp.last_seq != Some(last_seq) {
+        resp.broken = Some(LogChainBrokenLinkResp {
+            seq: resp.last_seq.unwrap_or(first_seq - 1) + 1,
+            kind: LogChainBrokenKind::Missing,
+            idempotent_id: None,
+            ts: None,
+        });
+    }
+    Ok(resp)
+}
+
+fn canonical_json(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(object) => {
+            let mut keys = object.keys().collect::<Vec<_>>();
+            keys.sort();
+            let mut sorted = Map::new();
+            for key in keys {
+                sorted.insert(key.clone(), canonical_json(&object[key]));
+            }
+            JsonValue::Object(sorted)
+        }
+        JsonValue::Array(array) => JsonValue::Array(array.iter().map(canonical_json).collect()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tardis::chrono::{TimeZone, Utc};
+    use tardis::serde_json::json;
+
+    use super::{chain_tenant, ChainRecord};
+
+    fn record() -> ChainRecord {
+        ChainRecord {
+            idempotent_id: "id1".to_string(),
+            ts: Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap(),
+            key: "001".to_string(),
+            kind: vec!["req".to_string()],
+            tag: "audit".to_string(),
+            op: "login".to_string(),
+            content: json!({"b": 1, "a": {"d": [1, 2], "c": "x"}}),
+            data_source: "".to_string(),
+            owner: "account001".to_string(),
+            owner_name: "".to_string(),
+            own_paths: "t1/app001".to_string(),
+            rel_key: "".to_string(),
+            msg: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_chain_hash() {
+        assert_eq!(chain_tenant("t1/app001"), "t1");
+        assert_eq!(chain_tenant(""), "");
+
+        let hash = record().hash("t1", 1, "").unwrap();
+        assert_eq!(hash.len(), 64);
+        // key order of the content does not matter
+        let mut reordered = record();
+        reordered.content = json!({"a": {"c": "x", "d": [1, 2]}, "b": 1});
+        assert_eq!(reordered.hash("t1", 1, "").unwrap(), hash);
+        // any change of the content, position or previous hash changes the hash
+        let mut modified = record();
+        modified.content = json!({"b": 2, "a": {"d": [1, 2], "c": "x"}});
+        assert_ne!(modified.hash("t1", 1, "").unwrap(), hash);
+        assert_ne!(record().hash("t1", 2, "").unwrap(), hash);
+        assert_ne!(record().hash("t1", 1, &hash).unwrap(), hash);
+        assert_ne!(record().hash("t2", 1, "").unwrap(), hash);
+    }
+}
@@ -1,11 +1,36 @@
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
     db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+    TardisFuns,
 };
 
 use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
 
-use crate::log_constants::{self, CONFIG_TABLE_NAME};
+use crate::{
+    log_config::LogConfig,
+    log_constants::{self, CONFIG_TABLE_NAME, DOMAIN_CODE},
+    serv::log_retention_serv,
+};
+
+// 日志表索引，按时间分区的 tag 表在分区表上创建相同的索引
+pub const INDEXES: &[(&str, &str)] = &[
+    ("kind", "gin"),
+    ("ts", "btree"),
+    ("key", "btree"),
+    ("content", "gin"),
+    ("ext", "gin"),
+    ("data_source", "btree"),
+    ("owner", "btree"),
+    ("own_paths", "btree"),
+    ("rel_key", "btree"),
+    ("idempotent_id", "btree"),
+    ("disable", "btree"),
+    ("tag", "btree"),
+    ("push", "btree"),
+    ("chain_tenant, chain_seq", "btree"),
+];
+// 哈希链索引在 INDEXES 中的位置
+const CHAIN_INDEX_IDX: usize = 13;
 
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
     //添加父表
@@ -31,7 +56,11 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
                   rel_key       varchar NOT NULL,
                   ext           jsonb NOT NULL,
                   disable       boolean NOT NULL DEFAULT false,
-                  msg           varchar NOT NULL
+                  msg           varchar NOT NULL,
+                  chain_tenant  varchar NOT NULL DEFAULT '',
+                  chain_seq     bigint NOT NULL DEFAULT 0,
+                  prev_hash     varchar NOT NULL DEFAULT '',
+                  hash          varchar NOT NULL DEFAULT ''
                 );"#,
                 log_constants::PARENT_TABLE_NAME
             ),
@@ -39,6 +68,22 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
         )
         .await?;
 
+    //为已存在的父表添加哈希链字段
+    spi_initializer::common_pg::upgrade_table(
+        &bs_inst.0.conn(),
+        &format!("{schema_name}.{}", log_constants::PARENT_TABLE_NAME),
+        None,
+        log_constants::TABLE_LOG_FLAG_V2,
+        &[
+            "chain_tenant varchar NOT NULL DEFAULT ''",
+            "chain_seq bigint NOT NULL DEFAULT 0",
+            "prev_hash varchar NOT NULL DEFAULT ''",
+            "hash varchar NOT NULL DEFAULT ''",
+        ],
+        &[],
+    )
+    .await?;
+
     //添加配置表
     bs_inst
         .0
@@ -68,7 +113,30 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
         )
         .await?;
 
-    spi_initializer::common_pg::init_table_and_conn(
+    //按时间分区的 tag 表以分区表方式创建，分区表不能继承父表，因此复制父表的字段
+    let partition = TardisFuns::cs_config::<LogConfig>(DOMAIN_CODE).retention.tag_policies.get(tag).map(|policy| policy.partition).unwrap_or_default();
+    let rel_name = format!("starsys_{}_{tag}", log_constants::TABLE_LOG_FLAG_V2);
+    if mgr && log_retention_serv::partition_name_fits(&rel_name, partition) {
+        let conn = bs_inst.0.conn();
+        if !spi_initializer::common_pg::check_table_exit(&format!("{}_{tag}", log_constants::TABLE_LOG_FLAG_V2), &conn, ctx).await? {
+            let table_name = format!("{schema_name}.{rel_name}");
+            conn.execute_one(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {table_name} (LIKE {schema_name}.{} INCLUDING DEFAULTS) PARTITION BY RANGE (ts)",
+                    log_constants::PARENT_TABLE_NAME
+                ),
+                vec![],
+            )
+            .await?;
+            //分区表上的索引会自动创建到各分区，索引名称与普通 tag 表一致
+            for (idx, (field_name, index_type)) in INDEXES.iter().enumerate() {
+                let index_name = spi_initializer::common_pg::index_name(&schema_name, &format!("_{tag}"), log_constants::TABLE_LOG_FLAG_V2, idx);
+                conn.execute_one(&format!("CREATE INDEX IF NOT EXISTS {index_name} ON {table_name} USING {index_type}({field_name})"), vec![]).await?;
+            }
+        }
+    }
+
+    let (conn, table_name) = spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
         ctx,
         mgr,
@@ -76,23 +144,12 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
         log_constants::TABLE_LOG_FLAG_V2,
         "",
         Some(format!("{schema_name}.{}", crate::log_constants::PARENT_TABLE_NAME)),
-        vec![
-            ("kind", "gin"),
-            ("ts", "btree"),
-            ("key", "btree"),
-            ("content", "gin"),
-            ("ext", "gin"),
-            ("data_source", "btree"),
-            ("owner", "btree"),
-            ("own_paths", "btree"),
-            ("rel_key", "btree"),
-            ("idempotent_id", "btree"),
-            ("disable", "btree"),
-            ("tag", "btree"),
-            ("push", "btree"),
-        ],
+        INDEXES.to_vec(),
         None,
         None,
     )
-    .await
+    .await?;
+    //为已存在的 tag 表添加哈希链索引，避免读取链头时全表扫描
+    spi_initializer::common_pg::upgrade_table(&conn, &table_name, Some(tag), log_constants::TABLE_LOG_FLAG_V2, &[], &[(CHAIN_INDEX_IDX, "chain_tenant, chain_seq", "btree")]).await?;
+    Ok((conn, table_name))
 }
@@ -3,12 +3,12 @@ use std::{collections::HashMap, str::FromStr, vec};
 use bios_sdk_invoke::clients::spi_stats_client::SpiStatsClient;
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
-    chrono::{DateTime, Utc},
+    chrono::{DateTime, SubsecRound, Utc},
     db::{
         reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
         sea_orm::Value,
     },
-    futures::TryFutureExt as _,
+    futures::{stream::BoxStream, TryFutureExt as _},
     serde_json::{self, Value as JsonValue},
     web::web_resp::TardisPage,
     TardisFuns, TardisFunsInst,
@@ -18,17 +18,23 @@ use bios_basic::{
     dto::BasicQueryCondInfo,
     enumeration::BasicQueryOpKind,
     helper::db_helper,
-    spi::{spi_funs::SpiBsInst, spi_initializer::common_pg::get_schema_name_from_ext},
+    spi::{
+        spi_funs::SpiBsInst,
+        spi_initializer::common_pg::{self, get_schema_name_from_ext},
+    },
 };
 
 use crate::{
     dto::log_item_dto::{
-        AdvBasicQueryCondInfo, LogConfigReq, LogExportDataReq, LogExportDataResp, LogImportDataReq, LogItemAddReq, LogItemAddV2Req, LogItemFindReq, LogItemFindResp,
+        AdvBasicQueryCondInfo, LogChainVerifyResp, LogConfigReq, LogExportDataReq, LogExportDataResp, LogImportDataReq, LogItemAddReq, LogItemAddV2Req, LogItemAggFieldReq,
+        LogItemAggReq, LogItemAggResp, LogItemFindReq, LogItemFindResp, LogItemTailReq, LogRetentionResp,
     },
+    log_config::{LogConfig, LogPartitionKind, LogRetentionPolicy},
     log_constants::{CONFIG_TABLE_NAME, LOG_REF_FLAG, TABLE_LOG_FLAG_V2},
+    serv::{log_retention_serv, log_tail_serv},
 };
 
-use super::log_pg_initializer;
+use super::{log_pg_chain_serv, log_pg_initializer};
 
 pub async fn add(add_req: &mut LogItemAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<String> {
     crate::serv::pg::log_pg_item_serv::add(add_req, funs, ctx, inst).await
@@ -44,7 +50,23 @@ pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &T
     let bs_inst = inst.inst::<TardisRelDBClient>();
     // 初始化要保存的内容
     let mut insert_content = add_req.content.clone();
+    // 实时订阅推送的内容，ref字段保留原始值
+    let mut tail_content = add_req.content.clone();
     let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &add_req.tag, ctx, true).await?;
+    let hash_chained = funs.conf::<LogConfig>().hash_chain_tags.contains(&add_req.tag);
+    if hash_chained {
+        // 哈希按微秒计算时间，与数据库的存储精度一致
+        add_req.ts = Some(add_req.ts.unwrap_or_else(Utc::now).trunc_subsecs(6));
+    }
+    // 按时间分区的 tag 写入对应的分区表，分区在事务外创建
+    let partition = funs.conf::<LogConfig>().retention.tag_policies.get(&add_req.tag).map(|policy| policy.partition).unwrap_or_default();
+    if partition != LogPartitionKind::None && add_req.ts.is_none() {
+        add_req.ts = Some(Utc::now());
+    }
+    let insert_table_name = match &add_req.ts {
+        Some(ts) => log_retention_serv::ensure_partition(&conn, &table_name, partition, ts).await?.unwrap_or(table_name.clone()),
+        None => table_name.clone(),
+    };
     conn.begin().await?;
     let ref_fields = get_ref_fields_by_table_name(&conn, &get_schema_name_from_ext(&inst.ext).expect("ignore"), &table_name).await?;
     if let Some(key) = add_req.key.as_ref() {
@@ -69,6 +91,7 @@ pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &T
 
             insert_content = last_content.clone();
             merge(&mut insert_content, add_req.content.clone());
+            tail_content = insert_content.clone();
 
             //把上次的内容有ref字段的改为ref_key
             for ref_field in &ref_fields {
@@ -114,6 +137,11 @@ pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &T
     }
 
     add_req.content = insert_content;
+    let chain = if hash_chained {
+        Some(log_pg_chain_serv::link(&conn, &table_name, add_req, ctx).await?)
+    } else {
+        None
+    };
     let mut params = vec![
         Value::from(id.clone()),
         Value::from(add_req.kind.as_ref().unwrap_or(&"".into()).split(',').map(|s| s.to_string()).collect::<Vec<String>>()),
@@ -134,18 +162,26 @@ pub async fn addv2(add_req: &mut LogItemAddV2Req, funs: &TardisFunsInst, ctx: &T
         Value::from(add_req.rel_key.as_ref().unwrap_or(&"".into()).to_string()),
         Value::from(add_req.msg.as_ref().unwrap_or(&"".into()).as_str()),
     ];
+    let mut columns = "idempotent_id, kind, key, tag, op, content, data_source, owner, owner_name, own_paths, push, ext, rel_key, msg".to_string();
     if let Some(ts) = add_req.ts {
         params.push(Value::from(ts));
+        columns.push_str(", ts");
+    }
+    if let Some(chain) = chain {
+        params.push(Value::from(chain.chain_tenant));
+        params.push(Value::from(chain.chain_seq));
+        params.push(Value::from(chain.prev_hash));
+        params.push(Value::from(chain.hash));
+        columns.push_str(", chain_tenant, chain_seq, prev_hash, hash");
     }
     conn.execute_one(
         &format!(
-            r#"INSERT INTO {table_name}
-  (idempotent_id, kind, key, tag, op, content, data_source, owner, owner_name, own_paths, push, ext, rel_key, msg{})
+            r#"INSERT INTO {insert_table_name}
+  ({columns})
 VALUES
-  ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14{})
+  ({})
 "#,
-            if add_req.ts.is_some() { ", ts" } else { "" },
-            if add_req.ts.is_some() { ", $15" } else { "" },
+            (1..=params.len()).map(|idx| format!("${idx}")).collect::<Vec<String>>().join(", ")
         ),
         params,
     )
@@ -153,6 +189,28 @@ VALUES
     conn.commit().await?;
     //if push is true, then push to EDA
     if add_req.push.unwrap_or(false) && !add_req.ignore_push.unwrap_or(false) {
+        // 推送给所有节点的实时订阅者
+        log_tail_serv::publish(
+            &get_schema_name_from_ext(&inst.ext).unwrap_or_default(),
+            &add_req.tag,
+            LogItemFindResp {
+                content: tail_content,
+                kind: add_req.kind.as_ref().unwrap_or(&"".into()).split(',').map(|s| s.to_string()).collect(),
+                ext: add_req.ext.clone().unwrap_or_else(|| serde_json::json!({})),
+                data_source: add_req.data_source.clone().unwrap_or_default(),
+                owner: add_req.owner.clone().unwrap_or_default(),
+                owner_name: add_req.owner_name.clone().unwrap_or_default(),
+                own_paths: add_req.own_paths.clone().unwrap_or_default(),
+                id: id.clone(),
+                key: add_req.key.as_ref().map(|key| key.to_string()).unwrap_or_default(),
+                op: add_req.op.clone().unwrap_or_default(),
+                rel_key: add_req.rel_key.as_ref().map(|rel_key| rel_key.to_string()).unwrap_or_default(),
+                ts: add_req.ts.unwrap_or_else(Utc::now),
+                msg: add_req.msg.clone().unwrap_or_default(),
+            },
+            funs,
+        )
+        .await?;
         push_to_eda(add_req, &ref_fields, funs, ctx).await?;
     }
     Ok(id)
@@ -208,7 +266,10 @@ fn parse_ref_ts_key(ref_key: &str) -> TardisResult<(DateTime<Utc>, String)> {
     ))
 }
 
-pub async fn findv2(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+/// Build the where fragment (without the `WHERE` keyword) and the parameters of the filters
+///
+/// 根据过滤条件构建 where 片段（不含 `WHERE` 关键字）及其参数
+fn build_find_where(find_req: &LogItemFindReq, funs: &TardisFunsInst) -> TardisResult<(String, Vec<Value>)> {
     let mut where_fragments: Vec<String> = Vec::new();
     let mut sql_vals: Vec<Value> = vec![];
 
@@ -610,7 +671,22 @@ pub async fn findv2(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &
     if where_fragments.is_empty() {
         where_fragments.push("1 = 1".to_string());
     }
+    Ok((
+        format!(
+            "{}{}",
+            where_fragments.join(" AND "),
+            if sql_adv_query.is_empty() {
+                "".to_string()
+            } else {
+                format!(" AND ( 1=1 {})", sql_adv_query.join(" "))
+            }
+        ),
+        sql_vals,
+    ))
+}
 
+pub async fn findv2(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<LogItemFindResp>> {
+    let (where_fragment, mut sql_vals) = build_find_where(find_req, funs)?;
     sql_vals.push(Value::from(find_req.page_size));
     sql_vals.push(Value::from((find_req.page_number - 1) * find_req.page_size as u32));
     let page_fragments = format!("LIMIT ${} OFFSET ${}", sql_vals.len() - 1, sql_vals.len());
@@ -623,17 +699,9 @@ pub async fn findv2(find_req: &mut LogItemFindReq, funs: &TardisFunsInst, ctx: &
                 r#"SELECT ts, idempotent_id, key, op, content, kind, ext, data_source, owner, owner_name, own_paths, rel_key, msg, count(*) OVER() AS total
 FROM {table_name}
 WHERE
-  {}
-  {}
+  {where_fragment}
 ORDER BY ts DESC
-{}"#,
-                where_fragments.join(" AND "),
-                if sql_adv_query.is_empty() {
-                    "".to_string()
-                } else {
-                    format!(" AND ( 1=1 {})", sql_adv_query.join(" "))
-                },
-                page_fragments
+{page_fragments}"#
             )
             .as_str(),
             sql_vals,
@@ -705,6 +773,96 @@ ORDER BY ts DESC
     })
 }
 
+pub async fn query_agg(agg_req: &mut LogItemAggReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<LogItemAggResp>> {
+    let group_by = agg_req.group_by.clone().unwrap_or_default();
+    let distinct_count = agg_req.distinct_count.clone().unwrap_or_default();
+    // kind 为数组，按 kind 分组时需展开
+    let unnest_kind = group_by.iter().any(|field| !field.in_ext.unwrap_or(false) && field.field == "kind");
+
+    let mut select_fragments = vec![];
+    let mut group_fragments = vec![];
+    if let Some(time_bucket) = &agg_req.time_bucket {
+        select_fragments.push(format!("date_trunc('{}', ts) AS bucket", time_bucket.to_sql()));
+        group_fragments.push("bucket".to_string());
+    }
+    for (idx, field) in group_by.iter().enumerate() {
+        select_fragments.push(format!("{} AS dim_{idx}", agg_field_to_sql(field, unnest_kind, funs)?));
+        group_fragments.push(format!("dim_{idx}"));
+    }
+    select_fragments.push("count(*) AS agg_count".to_string());
+    for (idx, field) in distinct_count.iter().enumerate() {
+        select_fragments.push(format!("count(DISTINCT {}) AS distinct_{idx}", agg_field_to_sql(field, unnest_kind, funs)?));
+    }
+
+    let (where_fragment, mut sql_vals) = build_find_where(&LogItemFindReq::from(&*agg_req), funs)?;
+    let limit_fragment = if let Some(limit) = agg_req.limit {
+        sql_vals.push(Value::from(limit));
+        format!("LIMIT ${}", sql_vals.len())
+    } else {
+        "".to_string()
+    };
+
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, &agg_req.tag, ctx, false).await?;
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT {}
+FROM {table_name}{}
+WHERE
+  {where_fragment}
+{}
+ORDER BY {}agg_count DESC
+{limit_fragment}"#,
+                select_fragments.join(", "),
+                if unnest_kind { " CROSS JOIN LATERAL unnest(kind) AS agg_kind(kind_item)" } else { "" },
+                if group_fragments.is_empty() {
+                    "".to_string()
+                } else {
+                    format!("GROUP BY {}", group_fragments.join(", "))
+                },
+                if agg_req.time_bucket.is_some() { "bucket ASC, " } else { "" },
+            ),
+            sql_vals,
+        )
+        .await?;
+
+    result
+        .into_iter()
+        .map(|item| {
+            let mut group = HashMap::new();
+            for (idx, field) in group_by.iter().enumerate() {
+                group.insert(field.name(), item.try_get::<Option<String>>("", &format!("dim_{idx}"))?);
+            }
+            let mut distinct = HashMap::new();
+            for (idx, field) in distinct_count.iter().enumerate() {
+                distinct.insert(field.name(), item.try_get::<i64>("", &format!("distinct_{idx}"))?);
+            }
+            Ok(LogItemAggResp {
+                bucket: if agg_req.time_bucket.is_some() { item.try_get("", "bucket")? } else { None },
+                group,
+                count: item.try_get("", "agg_count")?,
+                distinct_count: distinct,
+            })
+        })
+        .collect()
+}
+
+fn agg_field_to_sql(field: &LogItemAggFieldReq, unnest_kind: bool, funs: &TardisFunsInst) -> TardisResult<String> {
+    if field.field.is_empty() || !field.field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(funs.err().bad_request("item", "agg", &format!("The field [{}] is not legal.", field.field), "400-spi-log-agg-field-not-legal"));
+    }
+    if field.in_ext.unwrap_or(false) {
+        return Ok(format!("ext ->> '{}'", field.field));
+    }
+    match field.field.as_str() {
+        "kind" if unnest_kind => Ok("kind_item".to_string()),
+        "kind" => Ok("array_to_string(kind, ',')".to_string()),
+        "key" | "op" | "owner" | "owner_name" | "own_paths" | "rel_key" | "data_source" => Ok(field.field.clone()),
+        _ => Err(funs.err().bad_request("item", "agg", &format!("The field [{}] is not legal.", field.field), "400-spi-log-agg-field-not-legal")),
+    }
+}
+
 pub async fn modify_ext(tag: &str, key: &str, ext: &mut JsonValue, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     crate::serv::pg::log_pg_item_serv::modify_ext(tag, key, ext, _funs, ctx, inst).await
 }
@@ -818,6 +976,34 @@ fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
     }
 }
 
+pub async fn apply_retention(tag: &str, policy: &LogRetentionPolicy, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<LogRetentionResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    if !common_pg::check_table_exit(&format!("{TABLE_LOG_FLAG_V2}_{tag}"), &bs_inst.0.conn(), ctx).await? {
+        return Ok(LogRetentionResp {
+            tag: tag.to_string(),
+            ..Default::default()
+        });
+    }
+    let (mut conn, table_name) = log_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    log_retention_serv::apply_to_table(&mut conn, &table_name, tag, policy, funs, ctx).await
+}
+
+pub async fn verify_chain(
+    tag: &str,
+    ts_start: Option<DateTime<Utc>>,
+    ts_end: Option<DateTime<Utc>>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<LogChainVerifyResp> {
+    log_pg_chain_serv::verify_chain(tag, ts_start, ts_end, funs, ctx, inst).await
+}
+
+pub async fn subscribe(tag: &str, tail_req: &LogItemTailReq, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<BoxStream<'static, LogItemFindResp>> {
+    let schema_name = get_schema_name_from_ext(&inst.ext).unwrap_or_default();
+    Ok(log_tail_serv::subscribe(&schema_name, tag, tail_req.clone()).await)
+}
+
 #[cfg(test)]
 mod test {
     use tardis::{chrono::Utc, serde_json::Value};
@@ -1,5 +1,5 @@
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_log::dto::log_item_dto::LogItemFindResp;
+use bios_spi_log::dto::log_item_dto::{LogChainVerifyResp, LogItemAggResp, LogItemFindResp, LogRetentionResp};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::serde_json::json;
@@ -553,5 +553,126 @@ pub async fn test_v2(app: &str, client: &mut TestHttpClient) -> TardisResult<()>
     assert_eq!(find_result.total_size, 1);
     assert_eq!(find_result.records[0].key, "001");
     assert_eq!(find_result.records[0].op, "init");
+
+    // aggregation
+    let mut agg_result: Vec<LogItemAggResp> = client
+        .put(
+            "/ci/v2/item/agg",
+            &json!({
+                "tag":"feed",
+                "group_by":[{"field":"op"}],
+                "time_bucket":"week",
+                "distinct_count":[{"field":"key"}]
+            }),
+        )
+        .await;
+    assert_eq!(agg_result.len(), 2);
+    agg_result.sort_by(|a, b| a.group["op"].cmp(&b.group["op"]));
+    assert!(agg_result.iter().all(|item| item.bucket.is_some()));
+    assert_eq!(agg_result[0].group["op"], Some("init".to_string()));
+    assert_eq!(agg_result[0].count, 2);
+    assert_eq!(agg_result[0].distinct_count["key"], 2);
+    assert_eq!(agg_result[1].group["op"], Some("modify".to_string()));
+    assert_eq!(agg_result[1].count, 2);
+    assert_eq!(agg_result[1].distinct_count["key"], 1);
+
+    let agg_result: Vec<LogItemAggResp> = client
+        .put(
+            "/ci/v2/item/agg",
+            &json!({
+                "tag":"project",
+                "group_by":[{"field":"kind"}, {"field":"assign_to", "in_ext":true}],
+                "ext":[{"field":"status","op":"=","value":1}]
+            }),
+        )
+        .await;
+    assert_eq!(agg_result.len(), 1);
+    assert!(agg_result[0].bucket.is_none());
+    assert_eq!(agg_result[0].group["kind"], Some("req".to_string()));
+    assert_eq!(agg_result[0].group["ext.assign_to"], Some("account002".to_string()));
+    assert_eq!(agg_result[0].count, 1);
+
+    // hash chain
+    for i in 0..3 {
+        let _: Void = client
+            .post(
+                "/ci/v2/item",
+                &json!({
+                    "tag":"chain_test",
+                    "key": format!("00{}", i + 1),
+                    "content": {"title":"chained", "idx": i},
+                    "op":"modify",
+                    "own_paths":"t1/app002",
+                    "push":false
+                }),
+            )
+            .await;
+    }
+    let verify_result: LogChainVerifyResp = client.get("/ci/v2/item/chain/verify/chain_test").await;
+    assert_eq!(verify_result.chain_tenant, "t1");
+    assert_eq!(verify_result.verified, 3);
+    assert_eq!(verify_result.first_seq, Some(1));
+    assert_eq!(verify_result.last_seq, Some(3));
+    assert!(verify_result.broken.is_none());
+    let verify_result: LogChainVerifyResp = client.get("/ci/v2/item/chain/verify/feed").await;
+    assert_eq!(verify_result.verified, 0);
+    assert!(verify_result.broken.is_none());
+
+    // retention
+    let _: Void = client
+        .post(
+            "/ci/v2/item",
+            &json!({
+                "tag":"retention_test",
+                "key": "001",
+                "content": {"title":"expired"},
+                "op":"init",
+                "ts":"2022-01-01T08:00:00.000Z",
+                "push":false
+            }),
+        )
+        .await;
+    for i in 0..3 {
+        let _: Void = client
+            .post(
+                "/ci/v2/item",
+                &json!({
+                    "tag":"retention_test",
+                    "key": format!("00{}", i + 2),
+                    "content": {"title":"recent"},
+                    "op":"init",
+                    "push":false
+                }),
+            )
+            .await;
+    }
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/v2/item/find",
+            &json!({
+                "tag":"retention_test",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 4);
+    let retention_result: Vec<LogRetentionResp> = client.put("/ci/v2/item/retention/apply?tag=retention_test", &Void {}).await;
+    assert_eq!(retention_result.len(), 1);
+    assert_eq!(retention_result[0].deleted_rows, 2);
+    assert!(retention_result[0].dropped_partitions.iter().any(|partition| partition.ends_with("_p20220101")));
+    assert!(retention_result[0].archived_objects.is_empty());
+    let find_result: TardisPage<LogItemFindResp> = client
+        .put(
+            "/ci/v2/item/find",
+            &json!({
+                "tag":"retention_test",
+                "page_number":1,
+                "page_size":10
+            }),
+        )
+        .await;
+    assert_eq!(find_result.total_size, 2);
+    assert!(find_result.records.iter().all(|record| record.key != "001" && record.key != "002"));
     Ok(())
 }
@@ -1 +1,3 @@
+pub mod object_ci_local_api;
 pub mod object_ci_obj_api;
+pub mod object_ci_proxy_api;
@@ -0,0 +1,111 @@
+use tardis::web::poem;
+use tardis::web::poem::Body;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Header, Query};
+use tardis::web::poem_openapi::payload::Binary;
+use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
+
+use crate::serv::local::object_local_obj_serv::LocalService;
+use crate::serv::local::{LocalOSClient, LocalPresignOp};
+
+#[derive(Clone)]
+pub struct ObjectCiLocalApi;
+
+/// Interface Console Local Object API
+///
+/// Serves the presigned urls of the local filesystem backend, requests are authorized by the signature of the url instead of the context.
+/// 接口控制台本地对象服务API
+///
+/// 为本地文件系统存储的预签名URL提供服务，请求通过URL的签名而非上下文鉴权。
+#[poem_openapi::OpenApi(prefix_path = "/ci/obj/local", tag = "bios_basic::ApiTag::Interface")]
+impl ObjectCiLocalApi {
+    /// Download object by presigned url
+    ///
+    /// 通过预签名URL下载对象
+    #[oai(path = "/object", method = "get")]
+    async fn get_object(
+        &self,
+        store: Query<String>,
+        bucket: Query<String>,
+        path: Query<String>,
+        part_number: Query<Option<String>>,
+        exp: Query<i64>,
+        sign: Query<String>,
+    ) -> poem::Result<Binary<Body>> {
+        let funs = crate::get_tardis_inst();
+        let client = LocalOSClient::verify_presign(LocalPresignOp::Get, &store.0, &bucket.0, &path.0, &part_number.0.unwrap_or_default(), exp.0, &sign.0, &funs)?;
+        let content = LocalService::get_object(&client, &bucket.0, &path.0).await?;
+        Ok(Binary(content))
+    }
+
+    /// Upload object by presigned url
+    ///
+    /// 通过预签名URL上传对象
+    #[oai(path = "/object", method = "put")]
+    async fn put_object(
+        &self,
+        store: Query<String>,
+        bucket: Query<String>,
+        path: Query<String>,
+        part_number: Query<Option<String>>,
+        exp: Query<i64>,
+        sign: Query<String>,
+        #[oai(name = "Content-Type")] content_type: Header<Option<String>>,
+        content: Binary<Body>,
+    ) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        let client = LocalOSClient::verify_presign(LocalPresignOp::Put, &store.0, &bucket.0, &path.0, &part_number.0.unwrap_or_default(), exp.0, &sign.0, &funs)?;
+        LocalService::put_object(&client, &bucket.0, &path.0, content_type.0, content.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Delete object by presigned url
+    ///
+    /// 通过预签名URL删除对象
+    #[oai(path = "/object", method = "delete")]
+    async fn delete_object(
+        &self,
+        store: Query<String>,
+        bucket: Query<String>,
+        path: Query<String>,
+        part_number: Query<Option<String>>,
+        exp: Query<i64>,
+        sign: Query<String>,
+    ) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        let client = LocalOSClient::verify_presign(
+            LocalPresignOp::Delete,
+            &store.0,
+            &bucket.0,
+            &path.0,
+            &part_number.0.unwrap_or_default(),
+            exp.0,
+            &sign.0,
+            &funs,
+        )?;
+        LocalService::delete_object(&client, &bucket.0, &path.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Multipart Upload:Upload part by presigned url, returns the ETag of the part
+    ///
+    /// 分片上传：通过预签名URL上传分片，返回分片的ETag
+    #[oai(path = "/part", method = "put")]
+    async fn put_part(
+        &self,
+        store: Query<String>,
+        bucket: Query<String>,
+        // 分片上传任务ID
+        // upload id of the multipart upload task
+        path: Query<String>,
+        part_number: Query<u32>,
+        exp: Query<i64>,
+        sign: Query<String>,
+        content: Binary<Body>,
+    ) -> TardisApiResult<String> {
+        let funs = crate::get_tardis_inst();
+        let client = LocalOSClient::verify_presign(LocalPresignOp::PutPart, &store.0, &bucket.0, &path.0, &part_number.0.to_string(), exp.0, &sign.0, &funs)?;
+        let etag = LocalService::put_part(&client, &path.0, part_number.0, content.0).await?;
+        TardisResp::ok(etag)
+    }
+}
@@ -15,7 +15,7 @@ use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
 use crate::dto::object_dto::{
     ClientCreateReq, ObjectBatchBuildCreatePresignUrlReq, ObjectBatchDeleteReq, ObjectCompleteMultipartUploadReq, ObjectCopyReq, ObjectInitiateMultipartUploadReq,
-    ObjectObjPresignKind, ObjectPresignBatchViewReq,
+    ObjectLifecycleModifyReq, ObjectLifecycleRule, ObjectListResp, ObjectMetaResp, ObjectObjPresignKind, ObjectPresignBatchViewReq, ObjectTagsModifyReq,
 };
 use crate::object_constants;
 use crate::serv::object_obj_serv;
@@ -322,6 +322,171 @@ impl ObjectCiObjApi {
         TardisResp::ok(object_obj_serv::object_exist(object_path.0, private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
     }
 
+    /// List objects
+    ///
+    /// 列举对象
+    #[oai(path = "/object/list", method = "get")]
+    async fn list_objects(
+        &self,
+        // 对象路径前缀
+        // prefix of the object path
+        prefix: Query<Option<String>>,
+        // 上一页返回的next_continuation_token
+        // next_continuation_token returned by the previous page
+        continuation_token: Query<Option<String>>,
+        // 每页最多返回的对象数，默认且最大为1000
+        // Max number of objects per page, 1000 by default and at most
+        max_keys: Query<Option<u32>>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ObjectListResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(
+            object_obj_serv::list_objects(
+                prefix.0,
+                continuation_token.0,
+                max_keys.0,
+                private.0,
+                special.0,
+                obj_exp.0,
+                bucket.0,
+                bs_id.0,
+                &funs,
+                &ctx.0,
+            )
+            .await?,
+        )
+    }
+
+    /// Get object metadata
+    ///
+    /// 获取对象元数据
+    #[oai(path = "/object/head", method = "get")]
+    async fn head_object(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ObjectMetaResp> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_obj_serv::head_object(object_path.0, private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
+    }
+
+    /// Get object tags
+    ///
+    /// 获取对象标签
+    #[oai(path = "/object/tags", method = "get")]
+    async fn get_object_tags(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<HashMap<String, String>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_obj_serv::get_object_tags(object_path.0, private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
+    }
+
+    /// Replace object tags
+    ///
+    /// 替换对象标签
+    #[oai(path = "/object/tags", method = "put")]
+    async fn put_object_tags(&self, req: Json<ObjectTagsModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        object_obj_serv::put_object_tags(req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void)
+    }
+
+    /// Get bucket lifecycle rules
+    ///
+    /// 获取桶的生命周期规则
+    #[oai(path = "/lifecycle", method = "get")]
+    async fn get_lifecycle_rules(
+        &self,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // 使用obs时，传入数值不生效，仅表示使用tamp桶。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        // When using obs, passing in a value does not take effect, it only indicates the use of the tamp bucket.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<Vec<ObjectLifecycleRule>> {
+        let funs = crate::get_tardis_inst();
+        TardisResp::ok(object_obj_serv::get_lifecycle_rules(private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?)
+    }
+
+    /// Replace bucket lifecycle rules
+    ///
+    /// 替换桶的生命周期规则
+    #[oai(path = "/lifecycle", method = "put")]
+    async fn put_lifecycle_rules(&self, req: Json<ObjectLifecycleModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        object_obj_serv::put_lifecycle_rules(req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void)
+    }
+
     /// Check object is exist
     ///
     /// 添加自定义服务实例
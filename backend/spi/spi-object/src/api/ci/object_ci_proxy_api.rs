@@ -0,0 +1,104 @@
+use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem;
+use tardis::web::poem::Body;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Header, Query};
+use tardis::web::poem_openapi::payload::Binary;
+use tardis::web::web_resp::{TardisApiResult, TardisResp};
+
+use crate::dto::object_dto::ObjectProxyUploadResp;
+use crate::serv::object_proxy_serv;
+
+#[derive(Clone)]
+pub struct ObjectCiProxyApi;
+
+#[derive(poem_openapi::ApiResponse)]
+pub enum ObjectProxyDownloadResp {
+    #[oai(status = 200)]
+    Ok(Binary<Body>, #[oai(header = "Content-Type")] String),
+}
+
+/// Interface Console Object Proxy API
+///
+/// Uploads and downloads objects through spi-object instead of the presigned urls, the uploaded content is validated by the policy of the tenant.
+/// 接口控制台对象代理API
+///
+/// 经由spi-object而非预签名URL上传下载对象，上传的内容按租户策略校验。
+#[poem_openapi::OpenApi(prefix_path = "/ci/obj/proxy", tag = "bios_basic::ApiTag::Interface")]
+impl ObjectCiProxyApi {
+    /// Upload object through the proxy
+    ///
+    /// 经由代理上传对象
+    #[oai(path = "/object", method = "put")]
+    async fn upload(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        #[oai(name = "Content-Type")] content_type: Header<Option<String>>,
+        content: Binary<Body>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ObjectProxyUploadResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = object_proxy_serv::upload(
+            object_path.0.trim(),
+            content_type.0,
+            content.0,
+            private.0,
+            special.0,
+            obj_exp.0,
+            bucket.0,
+            bs_id.0,
+            &funs,
+            &ctx.0,
+        )
+        .await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Download object through the proxy
+    ///
+    /// 经由代理下载对象
+    #[oai(path = "/object", method = "get")]
+    async fn download(
+        &self,
+        // 对象的路径
+        // path of object
+        object_path: Query<String>,
+        // 是否私有
+        // private or not
+        private: Query<Option<bool>>,
+        // 是否特殊
+        //Special or not
+        special: Query<Option<bool>>,
+        // 是否临时，数字表示文件生效时长。
+        // Whether or not it is temporary, the number indicates the length of time the file will be in effect.
+        obj_exp: Query<Option<u32>>,
+        // 服务ID，使用外部自定义服务时，传入该值。
+        // Service ID, pass this value when using an external custom service.
+        bs_id: Query<Option<String>>,
+        // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+        // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+        bucket: Query<Option<String>>,
+        ctx: TardisContextExtractor,
+    ) -> poem::Result<ObjectProxyDownloadResp> {
+        let funs = crate::get_tardis_inst();
+        let (content, content_type) = object_proxy_serv::download(object_path.0.trim(), private.0, special.0, obj_exp.0, bucket.0, bs_id.0, &funs, &ctx.0).await?;
+        Ok(ObjectProxyDownloadResp::Ok(Binary(content), content_type))
+    }
+}
@@ -1,6 +1,12 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use tardis::{basic::field::TrimString, web::poem_openapi};
+use tardis::{
+    basic::field::TrimString,
+    chrono::{DateTime, Utc},
+    web::poem_openapi,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ObjectObjPresignKind {
@@ -103,6 +109,82 @@ pub struct ObjectPresignBatchViewReq {
     pub bucket: Option<String>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectTagsModifyReq {
+    pub object_path: String,
+    // 对象的全部标签，覆盖原有标签
+    // All tags of the object, replacing the existing tags
+    pub tags: HashMap<String, String>,
+    pub private: Option<bool>,
+    pub special: Option<bool>,
+    pub obj_exp: Option<u32>,
+    // 服务ID，使用外部自定义服务时，传入该值。
+    // Service ID, pass this value when using an external custom service.
+    pub bs_id: Option<String>,
+    // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+    // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+    pub bucket: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectLifecycleModifyReq {
+    // 桶的全部生命周期规则，覆盖原有规则（包括临时对象自动创建的规则）
+    // All lifecycle rules of the bucket, replacing the existing rules (including the rules created automatically for temporary objects)
+    pub rules: Vec<ObjectLifecycleRule>,
+    pub private: Option<bool>,
+    pub special: Option<bool>,
+    pub obj_exp: Option<u32>,
+    // 服务ID，使用外部自定义服务时，传入该值。
+    // Service ID, pass this value when using an external custom service.
+    pub bs_id: Option<String>,
+    // 指定桶，当且仅当使用自定义服务ID时该参数有效。
+    // Specifies the bucket. This parameter is valid when and only when a custom service ID is used.
+    pub bucket: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ObjectLifecycleRule {
+    pub id: String,
+    pub enabled: bool,
+    // 规则作用的对象路径前缀，为空时作用于整个桶
+    // Object path prefix the rule applies to, the whole bucket when empty
+    pub prefix: Option<String>,
+    // 对象创建后多少天过期
+    // Days after the creation when the objects expire
+    pub expiration_days: Option<u32>,
+    // 未完成的分片上传在启动后多少天终止
+    // Days after the initiation when the incomplete multipart uploads are aborted
+    pub abort_incomplete_multipart_upload_days: Option<u32>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct ObjectMetaResp {
+    pub object_path: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    // 列举对象时不返回
+    // Not returned when listing objects
+    pub content_type: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectListResp {
+    pub objects: Vec<ObjectMetaResp>,
+    // 存在下一页时返回，作为下次请求的continuation_token
+    // Returned when there is a next page, used as the continuation_token of the next request
+    pub next_continuation_token: Option<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ObjectProxyUploadResp {
+    pub object_path: String,
+    pub size: u64,
+    pub content_type: String,
+    pub md5: String,
+    pub sha256: String,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct ClientCreateReq {
     pub kind: String,
@@ -7,3 +7,4 @@ pub mod object_constants;
 pub mod object_initializer;
 pub(crate) use crate::object_initializer::get_tardis_inst;
 mod serv;
+pub use serv::object_proxy_serv::{register_scanner, ObjectScanReq, ObjectScanResult, ObjectScanner};
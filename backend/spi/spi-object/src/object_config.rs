@@ -1,9 +1,97 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(default)]
 pub struct ObjectConfig {
     pub rbum: RbumConfig,
+    /// 本地存储预签名URL的访问地址，如 `http://127.0.0.1:8080/spi-object`
+    /// Base url of the presigned urls of the local backend, e.g. `http://127.0.0.1:8080/spi-object`
+    pub local_presign_base_url: String,
+    /// 本地存储预签名URL的签名密钥，使用本地存储时必须配置，多节点部署时需配置为相同的值
+    /// Secret to sign the presigned urls of the local backend, required when the local backend is used, it should be the same on all nodes
+    pub local_presign_secret: String,
+    pub proxy: ObjectProxyConfig,
+    pub invoke: InvokeConfig,
+}
+
+impl Default for ObjectConfig {
+    fn default() -> Self {
+        ObjectConfig {
+            rbum: Default::default(),
+            local_presign_base_url: "".to_string(),
+            local_presign_secret: "".to_string(),
+            proxy: Default::default(),
+            invoke: Default::default(),
+        }
+    }
+}
+
+/// 上传下载代理
+/// 开启后可经由spi-object上传下载对象，上传时按租户策略校验内容。
+/// Upload and download proxy
+/// When enabled, objects can be uploaded and downloaded through spi-object, and the uploaded content is validated by the policy of the tenant.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ObjectProxyConfig {
+    pub enabled: bool,
+    pub default_policy: ObjectProxyPolicy,
+    /// 租户（ak）的策略，覆盖默认策略
+    /// Policies of the tenants (by ak), overriding the default policy
+    pub tenant_policies: HashMap<String, ObjectProxyPolicy>,
+    /// 内置的HTTP扫描器地址，为空时不启用，此时需要扫描的上传会被拒绝，除非注册了其他扫描器
+    /// Url of the built-in http scanner, disabled when empty, in which case the uploads requiring scanning are rejected unless other scanners are registered
+    pub scanner_url: String,
+    pub scanner_timeout_ms: u64,
+    /// 上传记录写入spi-log的标签，为空时不记录
+    /// Tag of the upload records written to spi-log, not recorded when empty
+    pub log_tag: String,
+    /// 代理访问存储时预签名URL的有效时长
+    /// Expiration of the presigned urls used by the proxy to access the storage
+    pub presign_exp_sec: u32,
+}
+
+impl Default for ObjectProxyConfig {
+    fn default() -> Self {
+        ObjectProxyConfig {
+            enabled: false,
+            default_policy: Default::default(),
+            tenant_policies: HashMap::new(),
+            scanner_url: "".to_string(),
+            scanner_timeout_ms: 30000,
+            log_tag: "".to_string(),
+            presign_exp_sec: 300,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ObjectProxyPolicy {
+    /// 上传对象的最大字节数，代理会将不超过该大小的内容暂存在临时文件中
+    /// Max bytes of the uploaded object, the proxy spools content up to this size in a temporary file
+    pub max_size: u64,
+    /// 允许的内容类型，支持 `image/*` 形式，为空时不限制
+    /// Allowed content types, `image/*` is supported, not limited when empty
+    pub allowed_content_types: Vec<String>,
+    /// 是否校验内容的文件头与声明的内容类型一致
+    /// Whether to check that the magic bytes of the content match the declared content type
+    pub check_magic_bytes: bool,
+    /// 是否调用扫描器，默认不启用，启用后未注册任何扫描器时拒绝上传
+    /// Whether to call the scanners, disabled by default, once enabled the uploads are rejected when no scanner is registered
+    pub scan: bool,
+}
+
+impl Default for ObjectProxyPolicy {
+    fn default() -> Self {
+        ObjectProxyPolicy {
+            max_size: 100 * 1024 * 1024,
+            allowed_content_types: vec![],
+            check_magic_bytes: true,
+            scan: false,
+        }
+    }
 }
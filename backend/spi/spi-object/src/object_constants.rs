@@ -1,5 +1,31 @@
 pub const DOMAIN_CODE: &str = "spi-object";
 pub const SPI_S3_KIND_CODE: &str = "spi-bs-s3";
 pub const SPI_OBS_KIND_CODE: &str = "spi-bs-obs";
+pub const SPI_LOCAL_KIND_CODE: &str = "spi-bs-local";
 
 pub const USE_REGION_ENDPOINT: &str = "use_region_endpoint";
+
+/// Names starting with this prefix are reserved by the local backend and can not be used in object paths
+pub const LOCAL_RESERVED_PREFIX: &str = ".bios_";
+pub const LOCAL_MULTIPART_DIR: &str = ".bios_multipart";
+pub const LOCAL_MULTIPART_META_FILE: &str = "meta.json";
+pub const LOCAL_MULTIPART_MERGED_FILE: &str = "merged";
+/// Prefix of the temporary files written beside the objects, which are renamed to the objects once fully written
+pub const LOCAL_TEMP_FILE_PREFIX: &str = ".bios_tmp_";
+/// Expiration of the unfinished multipart uploads of the local backend
+pub const LOCAL_MULTIPART_EXP_SEC: u64 = 7 * 24 * 60 * 60;
+pub const LOCAL_SWEEP_INTERVAL_SEC: u64 = 60 * 60;
+/// Objects walked in each page when applying the lifecycle rules of the local backend
+pub const LOCAL_SWEEP_PAGE_SIZE: usize = 1000;
+/// Bucket name prefix of the private local backends, which have no isolation flag
+pub const LOCAL_DEFAULT_BUCKET_PREFIX: &str = "default";
+pub const LOCAL_TAMP_EXP_DIR_PREFIX: &str = "exp";
+/// Sidecar metadata (content type and tags) of the objects of the local backend, mirrors the bucket directories
+pub const LOCAL_META_DIR: &str = ".bios_meta";
+/// Lifecycle rules of the local backend, one `{bucket}.json` file per bucket
+pub const LOCAL_LIFECYCLE_DIR: &str = ".bios_lifecycle";
+
+pub const LIST_OBJECTS_DEFAULT_MAX_KEYS: u32 = 1000;
+pub const LIST_OBJECTS_MAX_KEYS: u32 = 1000;
+/// Expiration of the presigned urls used internally to call the s3 apis not covered by the client
+pub const S3_INTERNAL_PRESIGN_EXP_SEC: u32 = 60;
@@ -1,13 +1,15 @@
+use std::sync::Arc;
+
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
-    log::info,
+    log::{info, warn},
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
 };
 
 use crate::{
-    api::ci::object_ci_obj_api,
+    api::ci::{object_ci_local_api, object_ci_obj_api, object_ci_proxy_api},
     object_config::ObjectConfig,
     object_constants::{self, DOMAIN_CODE},
     serv,
@@ -17,6 +19,8 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.Object] Module initializing");
     let mut funs = crate::get_tardis_inst();
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<ObjectConfig>().rbum.clone()).await?;
+    bios_sdk_invoke::invoke_initializer::init(funs.module_code(), funs.conf::<ObjectConfig>().invoke.clone())?;
+    init_scanner(&funs);
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
     init_db(&funs, &ctx).await?;
@@ -28,14 +32,40 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
 
 async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     spi_initializer::add_kind(object_constants::SPI_S3_KIND_CODE, funs, ctx).await?;
+    spi_initializer::add_kind(object_constants::SPI_LOCAL_KIND_CODE, funs, ctx).await?;
     Ok(())
 }
 
 async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
-    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, object_ci_obj_api::ObjectCiObjApi)).await;
+    web_server
+        .add_module(
+            DOMAIN_CODE,
+            (
+                spi_ci_bs_api::SpiCiBsApi,
+                object_ci_obj_api::ObjectCiObjApi,
+                object_ci_local_api::ObjectCiLocalApi,
+                object_ci_proxy_api::ObjectCiProxyApi,
+            ),
+        )
+        .await;
     Ok(())
 }
 
+fn init_scanner(funs: &TardisFunsInst) {
+    let proxy = &funs.conf::<ObjectConfig>().proxy;
+    if !proxy.enabled {
+        return;
+    }
+    if !proxy.scanner_url.is_empty() {
+        serv::object_proxy_serv::register_scanner(Arc::new(serv::object_proxy_serv::HttpObjectScanner {
+            url: proxy.scanner_url.clone(),
+            timeout_ms: proxy.scanner_timeout_ms,
+        }));
+    } else if proxy.default_policy.scan || proxy.tenant_policies.values().any(|policy| policy.scan) {
+        warn!("[BIOS.Object] The proxy requires scanning but the scanner_url is empty, the uploads requiring scanning are rejected until a scanner is registered");
+    }
+}
+
 pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) -> TardisResult<SpiBsInst> {
     info!("[BIOS.Object] Fun [{}]({}) initializing", bs_cert.kind_code, bs_cert.conn_uri);
     let inst = match bs_cert.kind_code.as_str() {
@@ -43,6 +73,7 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
         object_constants::SPI_S3_KIND_CODE => serv::s3::object_s3_initializer::init(&bs_cert, ctx, mgr).await,
         #[cfg(feature = "spi-s3")]
         object_constants::SPI_OBS_KIND_CODE => serv::obs::object_obs_initializer::init(&bs_cert, ctx, mgr).await,
+        object_constants::SPI_LOCAL_KIND_CODE => serv::local::object_local_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
     }?;
     info!("[BIOS.Object] Fun [{}]({}) initialized", bs_cert.kind_code, bs_cert.conn_uri);
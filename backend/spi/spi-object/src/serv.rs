@@ -1,4 +1,6 @@
 pub mod custom_s3;
+pub mod local;
 pub mod object_obj_serv;
+pub mod object_proxy_serv;
 pub mod obs;
 pub mod s3;
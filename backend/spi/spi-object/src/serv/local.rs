@@ -0,0 +1,295 @@
+pub mod object_local_initializer;
+pub mod object_local_obj_serv;
+
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Component, Path, PathBuf};
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use tardis::{
+    basic::{error::TardisError, result::TardisResult},
+    chrono::Utc,
+    tokio::fs,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{object_config::ObjectConfig, object_constants};
+
+/// 本地文件系统客户端
+/// 每个桶对应根目录下的一个子目录，对象路径对应桶目录下的相对路径。
+/// Local filesystem client
+/// Each bucket is a sub directory of the root, and the object path is the relative path under the bucket directory.
+pub struct LocalOSClient {
+    pub root: PathBuf,
+}
+
+/// 对象的附加元数据，存放在 [`object_constants::LOCAL_META_DIR`] 下与对象同名的文件中
+/// Additional metadata of the object, stored in the file with the same path as the object under [`object_constants::LOCAL_META_DIR`]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct LocalObjectMeta {
+    pub content_type: Option<String>,
+    /// 写入时计算的内容MD5
+    /// MD5 of the content computed when written
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+}
+
+/// Operation authorized by a presigned url of the local backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalPresignOp {
+    Get,
+    Put,
+    Delete,
+    PutPart,
+}
+
+impl LocalPresignOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LocalPresignOp::Get => "get",
+            LocalPresignOp::Put => "put",
+            LocalPresignOp::Delete => "delete",
+            LocalPresignOp::PutPart => "put_part",
+        }
+    }
+}
+
+impl LocalOSClient {
+    pub fn new(conn_uri: &str) -> TardisResult<Self> {
+        let root = conn_uri.strip_prefix("file://").unwrap_or(conn_uri);
+        if root.is_empty() {
+            return Err(TardisError::bad_request(
+                "The root directory of the local object storage is empty",
+                "400-spi-object-local-invalid-root",
+            ));
+        }
+        Ok(LocalOSClient { root: PathBuf::from(root) })
+    }
+
+    /// 获取对象的文件路径，拒绝越出桶目录的路径
+    /// Get the file path of the object, paths escaping from the bucket directory are rejected
+    pub fn object_file(&self, bucket_name: &str, object_path: &str) -> TardisResult<PathBuf> {
+        Ok(self.root.join(check_relative_path(bucket_name)?).join(check_relative_path(object_path)?))
+    }
+
+    /// 获取桶的目录
+    /// Get the directory of the bucket
+    pub fn bucket_dir(&self, bucket_name: &str) -> TardisResult<PathBuf> {
+        Ok(self.root.join(check_relative_path(bucket_name)?))
+    }
+
+    /// 获取对象附加元数据的文件路径
+    /// Get the file path of the additional metadata of the object
+    pub fn meta_file(&self, bucket_name: &str, object_path: &str) -> TardisResult<PathBuf> {
+        Ok(self.root.join(object_constants::LOCAL_META_DIR).join(check_relative_path(bucket_name)?).join(check_relative_path(object_path)?))
+    }
+
+    /// 获取桶生命周期规则的文件路径
+    /// Get the file path of the lifecycle rules of the bucket
+    pub fn lifecycle_file(&self, bucket_name: &str) -> TardisResult<PathBuf> {
+        Ok(self.root.join(object_constants::LOCAL_LIFECYCLE_DIR).join(format!("{}.json", check_relative_path(bucket_name)?.display())))
+    }
+
+    /// 读取对象的附加元数据，不存在时返回默认值
+    /// Read the additional metadata of the object, the default value is returned when absent
+    pub async fn read_meta(&self, bucket_name: &str, object_path: &str) -> TardisResult<LocalObjectMeta> {
+        match fs::read_to_string(self.meta_file(bucket_name, object_path)?).await {
+            Ok(meta) => TardisFuns::json.str_to_obj(&meta),
+            Err(_) => Ok(LocalObjectMeta::default()),
+        }
+    }
+
+    pub async fn write_meta(&self, bucket_name: &str, object_path: &str, meta: &LocalObjectMeta) -> TardisResult<()> {
+        let file = self.meta_file(bucket_name, object_path)?;
+        let io_error = |e: std::io::Error| TardisError::internal_error(&format!("Local object storage failed to write metadata: {e}"), "500-spi-object-local-io-error");
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        fs::write(&file, TardisFuns::json.obj_to_string(meta)?).await.map_err(io_error)
+    }
+
+    pub async fn remove_meta(&self, bucket_name: &str, object_path: &str) -> TardisResult<()> {
+        match fs::remove_file(self.meta_file(bucket_name, object_path)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(TardisError::internal_error(
+                &format!("Local object storage failed to remove metadata: {e}"),
+                "500-spi-object-local-io-error",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// 分片上传任务的临时目录
+    /// Temporary directory of the multipart upload task
+    pub fn multipart_dir(&self, upload_id: &str) -> TardisResult<PathBuf> {
+        if upload_id.is_empty() || !upload_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(TardisError::bad_request("Invalid upload id", "400-spi-object-local-invalid-upload-id"));
+        }
+        Ok(self.root.join(object_constants::LOCAL_MULTIPART_DIR).join(upload_id))
+    }
+
+    /// 生成由spi-object自身提供服务的预签名URL
+    /// Generate a presigned url served by spi-object itself
+    ///
+    /// exp_secs: 0 表示永不过期 / 0 means never expire
+    /// 存储根目录以密文形式放在URL中，不暴露文件系统路径
+    /// The root directory is put into the url encrypted, so that the filesystem path is not exposed
+    pub fn presign_url(&self, op: LocalPresignOp, bucket_name: &str, object_path: &str, part_number: Option<u32>, exp_secs: u32, funs: &TardisFunsInst) -> TardisResult<String> {
+        let config = funs.conf::<ObjectConfig>();
+        if config.local_presign_base_url.is_empty() {
+            return Err(TardisError::internal_error(
+                "The presign base url of the local object storage is not configured",
+                "500-spi-object-local-presign-base-url-missing",
+            ));
+        }
+        let secret = presign_secret(&config)?;
+        let exp = if exp_secs == 0 { 0 } else { Utc::now().timestamp() + exp_secs as i64 };
+        let root = self.root.to_string_lossy();
+        let part_number = part_number.map(|part_number| part_number.to_string()).unwrap_or_default();
+        let sign = sign(op, &root, bucket_name, object_path, &part_number, exp, secret)?;
+        let store = encrypt_root(&root, secret)?;
+        let enc = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+        let path = if op == LocalPresignOp::PutPart { "part" } else { "object" };
+        Ok(format!(
+            "{}/ci/obj/local/{}?store={}&bucket={}&path={}&part_number={}&exp={}&sign={}",
+            config.local_presign_base_url.trim_end_matches('/'),
+            path,
+            enc(&store),
+            enc(bucket_name),
+            enc(object_path),
+            part_number,
+            exp,
+            sign
+        ))
+    }
+
+    /// 校验预签名URL的参数，返回对应的客户端
+    /// Verify the parameters of the presigned url, and return the corresponding client
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_presign(
+        op: LocalPresignOp,
+        store: &str,
+        bucket_name: &str,
+        object_path: &str,
+        part_number: &str,
+        exp: i64,
+        signature: &str,
+        funs: &TardisFunsInst,
+    ) -> TardisResult<Self> {
+        let config = funs.conf::<ObjectConfig>();
+        let secret = presign_secret(&config)?;
+        let root = decrypt_root(store, secret)?;
+        if sign(op, &root, bucket_name, object_path, part_number, exp, secret)? != signature {
+            return Err(TardisError::unauthorized("Invalid signature of the presigned url", "401-spi-object-local-invalid-sign"));
+        }
+        if exp != 0 && exp < Utc::now().timestamp() {
+            return Err(TardisError::unauthorized("The presigned url has expired", "401-spi-object-local-presign-expired"));
+        }
+        LocalOSClient::new(&root)
+    }
+}
+
+fn presign_secret(config: &ObjectConfig) -> TardisResult<&str> {
+    if config.local_presign_secret.is_empty() {
+        return Err(TardisError::internal_error(
+            "The presign secret of the local object storage is not configured",
+            "500-spi-object-local-presign-secret-missing",
+        ));
+    }
+    Ok(&config.local_presign_secret)
+}
+
+/// 由签名密钥派生根目录加密的密钥与向量
+/// Derive the key and iv to encrypt the root directory from the presign secret
+fn root_cipher(secret: &str) -> TardisResult<(String, String)> {
+    let key = TardisFuns::crypto.digest.md5(secret)?;
+    let iv = TardisFuns::crypto.digest.md5(format!("{secret}\nroot"))?[..16].to_string();
+    Ok((key, iv))
+}
+
+fn encrypt_root(root: &str, secret: &str) -> TardisResult<String> {
+    let (key, iv) = root_cipher(secret)?;
+    TardisFuns::crypto.aes.encrypt_cbc(root, &key, &iv)
+}
+
+fn decrypt_root(store: &str, secret: &str) -> TardisResult<String> {
+    let (key, iv) = root_cipher(secret)?;
+    TardisFuns::crypto.aes.decrypt_cbc(store, &key, &iv).map_err(|_| TardisError::unauthorized("Invalid store of the presigned url", "401-spi-object-local-invalid-sign"))
+}
+
+fn sign(op: LocalPresignOp, root: &str, bucket_name: &str, object_path: &str, part_number: &str, exp: i64, secret: &str) -> TardisResult<String> {
+    let data = format!("{}\n{}\n{}\n{}\n{}\n{}", op.as_str(), root, bucket_name, object_path, part_number, exp);
+    Ok(TardisFuns::crypto.hex.encode(TardisFuns::crypto.digest.hmac_sha256(data, secret)?))
+}
+
+/// 按对象路径顺序列举桶目录下以 prefix 开头、路径大于 start_after 的对象，最多返回 limit 个
+/// 目录按路径顺序深度优先遍历，整体不大于 start_after 的子目录直接跳过，因此每页只遍历所需的部分。
+/// List the objects starting with the prefix and greater than start_after under the bucket directory in the order of the object path, at most limit objects are returned
+/// The directories are walked depth first in the path order, sub directories entirely not greater than start_after are skipped, so that each page only walks the needed part.
+pub async fn walk_objects(bucket_dir: &Path, prefix: &str, start_after: Option<&str>, limit: Option<usize>) -> TardisResult<Vec<(String, Metadata)>> {
+    let prefix = prefix.trim_start_matches('/');
+    // 只遍历前缀中完整的目录部分
+    // only walk the complete directory part of the prefix
+    let (start_dir, start_key) = match prefix.rsplit_once('/') {
+        Some((dir, _)) if !dir.is_empty() => (bucket_dir.join(check_relative_path(dir)?), format!("{dir}/")),
+        _ => (bucket_dir.to_path_buf(), String::new()),
+    };
+    let io_error = |e: std::io::Error| TardisError::internal_error(&format!("Local object storage failed to list objects: {e}"), "500-spi-object-local-io-error");
+    let mut objects = Vec::new();
+    // 待访问的条目，栈顶为路径最小的条目；目录的键以 `/` 结尾，与对象路径的顺序一致
+    // entries to visit, the top is the smallest; keys of the directories end with `/`, consistent with the order of the object paths
+    let mut entries: Vec<(String, PathBuf, Option<Metadata>)> = vec![(start_key, start_dir, None)];
+    while let Some((key, path, metadata)) = entries.pop() {
+        if let Some(metadata) = metadata {
+            if key.starts_with(prefix) && start_after.map_or(true, |start_after| key.as_str() > start_after) {
+                objects.push((key, metadata));
+                if limit.is_some_and(|limit| objects.len() >= limit) {
+                    break;
+                }
+            }
+            continue;
+        }
+        let mut dir_entries = match fs::read_dir(&path).await {
+            Ok(dir_entries) => dir_entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(io_error(e)),
+        };
+        let mut children = Vec::new();
+        while let Some(entry) = dir_entries.next_entry().await.map_err(io_error)? {
+            let metadata = entry.metadata().await.map_err(io_error)?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if metadata.is_dir() {
+                let child_key = format!("{key}{name}/");
+                let in_prefix = child_key.starts_with(prefix) || prefix.starts_with(&child_key);
+                let after_start = start_after.map_or(true, |start_after| child_key.as_str() > start_after || start_after.starts_with(&child_key));
+                if in_prefix && after_start {
+                    children.push((child_key, entry.path(), None));
+                }
+            } else if !name.starts_with(object_constants::LOCAL_RESERVED_PREFIX) {
+                // 跳过正在写入的临时文件
+                // skip the temporary files being written
+                children.push((format!("{key}{name}"), entry.path(), Some(metadata)));
+            }
+        }
+        children.sort_by(|(a, _, _), (b, _, _)| b.cmp(a));
+        entries.extend(children);
+    }
+    Ok(objects)
+}
+
+fn check_relative_path(path: &str) -> TardisResult<&Path> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    let mut components = relative.components().peekable();
+    if components.peek().is_none() {
+        return Err(TardisError::bad_request("The object path is empty", "400-spi-object-local-invalid-path"));
+    }
+    for component in components {
+        match component {
+            Component::Normal(name) if !name.to_string_lossy().starts_with(object_constants::LOCAL_RESERVED_PREFIX) => {}
+            _ => return Err(TardisError::bad_request(&format!("Invalid object path {path}"), "400-spi-object-local-invalid-path")),
+        }
+    }
+    Ok(relative)
+}
@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    log::{trace, warn},
+    tokio::{self, fs},
+    TardisFuns,
+};
+
+use crate::object_constants;
+use crate::serv::local::object_local_obj_serv::{LocalMultipartMeta, LocalService};
+use crate::serv::local::{self as local_client, LocalOSClient};
+
+/// 本地存储初始化
+/// conn_uri 为存储根目录，如 `file:///data/bios/object` ，与s3一致每个租户或应用创建4个桶（目录）。
+/// Local storage initialization
+/// conn_uri is the root directory of the storage, e.g. `file:///data/bios/object`, 4 buckets (directories) are created for each tenant or app like s3.
+pub async fn init(bs_cert: &SpiBsCertResp, ctx: &TardisContext, _: bool) -> TardisResult<SpiBsInst> {
+    let client = LocalOSClient::new(&bs_cert.conn_uri)?;
+    let bucket_name_prefix = if bs_cert.private {
+        object_constants::LOCAL_DEFAULT_BUCKET_PREFIX.to_string()
+    } else {
+        spi_initializer::common::get_isolation_flag_from_context(ctx)
+    };
+    for bucket_suffix in ["pri", "pub", "spe", "tamp"] {
+        let bucket_dir = client.root.join(format!("{bucket_name_prefix}-{bucket_suffix}"));
+        fs::create_dir_all(&bucket_dir)
+            .await
+            .map_err(|e| TardisError::internal_error(&format!("Bucket {} creation failed: {e}", bucket_dir.display()), "500-spi-object-local-create-bucket-error"))?;
+    }
+    start_sweeper(client.root.clone());
+    let mut ext = HashMap::new();
+    spi_initializer::common::set_isolation_flag_to_ext(&bucket_name_prefix, &mut ext);
+    Ok(SpiBsInst { client: Box::new(client), ext })
+}
+
+/// 定期清理过期的临时对象及未完成的分片上传
+/// Periodically remove the expired temporary objects and unfinished multipart uploads
+fn start_sweeper(root: PathBuf) {
+    static SWEEP_ROOTS: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    let Ok(mut roots) = SWEEP_ROOTS.get_or_init(Default::default).lock() else {
+        return;
+    };
+    if !roots.insert(root.clone()) {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(object_constants::LOCAL_SWEEP_INTERVAL_SEC));
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep(&root).await {
+                warn!("[BIOS.Object] Sweep local storage {} failed: {}", root.display(), e);
+            }
+            if let Err(e) = sweep_by_lifecycle(&root).await {
+                warn!("[BIOS.Object] Apply lifecycle rules of local storage {} failed: {}", root.display(), e);
+            }
+        }
+    });
+}
+
+async fn sweep(root: &Path) -> std::io::Result<()> {
+    let now = SystemTime::now();
+    let mut buckets = fs::read_dir(root).await?;
+    while let Some(bucket) = buckets.next_entry().await? {
+        let bucket_name = bucket.file_name().to_string_lossy().to_string();
+        if bucket_name == object_constants::LOCAL_MULTIPART_DIR {
+            remove_expired(&bucket.path(), now, Duration::from_secs(object_constants::LOCAL_MULTIPART_EXP_SEC), true).await?;
+        } else if bucket_name == object_constants::LOCAL_META_DIR {
+            // 临时对象的附加元数据与对象一同过期
+            // the additional metadata of the temporary objects expires along with the objects
+            let mut meta_buckets = fs::read_dir(bucket.path()).await?;
+            while let Some(meta_bucket) = meta_buckets.next_entry().await? {
+                if meta_bucket.file_name().to_string_lossy().ends_with("-tamp") {
+                    sweep_tamp(&meta_bucket.path(), now).await?;
+                }
+            }
+        } else if bucket_name.ends_with("-tamp") {
+            sweep_tamp(&bucket.path(), now).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 临时对象按过期天数存放在 `exp{days}/` 目录下
+/// Temporary objects are stored under the `exp{days}/` directories by expiration days
+async fn sweep_tamp(bucket_dir: &Path, now: SystemTime) -> std::io::Result<()> {
+    let mut exp_dirs = fs::read_dir(bucket_dir).await?;
+    while let Some(exp_dir) = exp_dirs.next_entry().await? {
+        let exp_dir_name = exp_dir.file_name().to_string_lossy().to_string();
+        let Some(days) = exp_dir_name.strip_prefix(object_constants::LOCAL_TAMP_EXP_DIR_PREFIX).and_then(|days| days.parse::<u64>().ok()) else {
+            continue;
+        };
+        remove_expired(&exp_dir.path(), now, Duration::from_secs(days * 24 * 60 * 60), false).await?;
+    }
+    Ok(())
+}
+
+/// 执行各桶的生命周期规则，单个对象或桶处理失败时记录日志并继续
+/// Apply the lifecycle rules of each bucket, failures of a single object or bucket are logged and the sweep continues
+async fn sweep_by_lifecycle(root: &Path) -> TardisResult<()> {
+    let io_error = |e: std::io::Error| TardisError::internal_error(&format!("Local object storage failed to apply lifecycle rules: {e}"), "500-spi-object-local-io-error");
+    let mut lifecycle_files = match fs::read_dir(root.join(object_constants::LOCAL_LIFECYCLE_DIR)).await {
+        Ok(lifecycle_files) => lifecycle_files,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(io_error(e)),
+    };
+    while let Some(lifecycle_file) = lifecycle_files.next_entry().await.map_err(io_error)? {
+        let Some(bucket_name) = lifecycle_file.file_name().to_string_lossy().strip_suffix(".json").map(|bucket_name| bucket_name.to_string()) else {
+            continue;
+        };
+        if let Err(e) = sweep_bucket_by_lifecycle(root, &bucket_name, &lifecycle_file.path()).await {
+            warn!("[BIOS.Object] Apply lifecycle rules of local bucket {} failed: {}", bucket_name, e);
+        }
+    }
+    Ok(())
+}
+
+async fn sweep_bucket_by_lifecycle(root: &Path, bucket_name: &str, lifecycle_file: &Path) -> TardisResult<()> {
+    let io_error = |e: std::io::Error| TardisError::internal_error(&format!("Local object storage failed to apply lifecycle rules: {e}"), "500-spi-object-local-io-error");
+    let client = LocalOSClient { root: root.to_path_buf() };
+    let now = SystemTime::now();
+    let is_expired = |metadata: &std::fs::Metadata, days: u32| {
+        metadata.modified().ok().and_then(|modified| now.duration_since(modified).ok()).is_some_and(|elapsed| elapsed > Duration::from_secs(days as u64 * 24 * 60 * 60))
+    };
+    for rule in LocalService::read_lifecycle_rules(lifecycle_file).await?.into_iter().filter(|rule| rule.enabled) {
+        let prefix = rule.prefix.unwrap_or_default();
+        if let Some(days) = rule.expiration_days {
+            let bucket_dir = client.bucket_dir(bucket_name)?;
+            let mut start_after: Option<String> = None;
+            loop {
+                let objects = local_client::walk_objects(&bucket_dir, &prefix, start_after.as_deref(), Some(object_constants::LOCAL_SWEEP_PAGE_SIZE)).await?;
+                let Some((last_object_path, _)) = objects.last() else {
+                    break;
+                };
+                start_after = Some(last_object_path.clone());
+                let is_last_page = objects.len() < object_constants::LOCAL_SWEEP_PAGE_SIZE;
+                for (object_path, metadata) in objects {
+                    if is_expired(&metadata, days) {
+                        trace!("[BIOS.Object] Remove expired local object {}/{}", bucket_name, object_path);
+                        if let Err(e) = LocalService::delete_object(&client, bucket_name, &object_path).await {
+                            warn!("[BIOS.Object] Remove expired local object {}/{} failed: {}", bucket_name, object_path, e);
+                        }
+                    }
+                }
+                if is_last_page {
+                    break;
+                }
+            }
+        }
+        if let Some(days) = rule.abort_incomplete_multipart_upload_days {
+            let mut uploads = match fs::read_dir(root.join(object_constants::LOCAL_MULTIPART_DIR)).await {
+                Ok(uploads) => uploads,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(io_error(e)),
+            };
+            while let Some(upload) = uploads.next_entry().await.map_err(io_error)? {
+                let Ok(meta) = fs::read_to_string(upload.path().join(object_constants::LOCAL_MULTIPART_META_FILE)).await else {
+                    continue;
+                };
+                let Ok(meta) = TardisFuns::json.str_to_obj::<LocalMultipartMeta>(&meta) else {
+                    continue;
+                };
+                if meta.bucket_name == bucket_name && meta.object_path.starts_with(&prefix) && upload.metadata().await.is_ok_and(|metadata| is_expired(&metadata, days)) {
+                    trace!("[BIOS.Object] Abort incomplete local multipart upload {}", upload.path().display());
+                    if let Err(e) = fs::remove_dir_all(upload.path()).await {
+                        warn!("[BIOS.Object] Abort incomplete local multipart upload {} failed: {}", upload.path().display(), e);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 删除目录下修改时间早于 `now - exp` 的条目，`by_entry` 为true时整体删除直接子条目，否则逐个检查文件
+/// Remove the entries under the directory modified before `now - exp`, direct children are removed as a whole when `by_entry`, otherwise files are checked one by one
+async fn remove_expired(dir: &Path, now: SystemTime, exp: Duration, by_entry: bool) -> std::io::Result<()> {
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_dir() && !by_entry {
+                dirs.push(entry.path());
+                continue;
+            }
+            let expired = metadata.modified().ok().and_then(|modified| now.duration_since(modified).ok()).is_some_and(|elapsed| elapsed > exp);
+            if expired {
+                trace!("[BIOS.Object] Remove expired local entry {}", entry.path().display());
+                if metadata.is_dir() {
+                    fs::remove_dir_all(entry.path()).await?;
+                } else {
+                    fs::remove_file(entry.path()).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer::common};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use tardis::{
+    basic::{error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
+    futures::StreamExt,
+    tokio::{
+        fs::{self, File},
+        io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    },
+    web::poem::Body,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::object_dto::{ObjectLifecycleRule, ObjectListResp, ObjectMetaResp, ObjectObjPresignKind},
+    object_constants,
+    serv::local::{self as local_client, LocalOSClient, LocalObjectMeta, LocalPresignOp},
+};
+
+const COPY_BUF_SIZE: usize = 64 * 1024;
+
+/// 分片上传任务的元数据
+/// Metadata of the multipart upload task
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct LocalMultipartMeta {
+    pub bucket_name: String,
+    pub object_path: String,
+    pub content_type: Option<String>,
+}
+
+/// 本地文件系统存储
+/// 预签名URL由spi-object自身提供服务，见 [`crate::api::ci::object_ci_local_api`] 。
+/// Local filesystem storage
+/// The presigned urls are served by spi-object itself, see [`crate::api::ci::object_ci_local_api`].
+pub(crate) struct LocalService;
+impl LocalService {
+    ///
+    /// obj_exp: 设置obj的过期时间 单位为天
+    #[allow(clippy::too_many_arguments)]
+    pub async fn presign_obj_url(
+        presign_kind: ObjectObjPresignKind,
+        object_path: &str,
+        exp_secs: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        funs: &TardisFunsInst,
+        inst: &SpiBsInst,
+    ) -> TardisResult<String> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        client.object_file(&bucket_name, &path)?;
+        match presign_kind {
+            ObjectObjPresignKind::Upload => client.presign_url(LocalPresignOp::Put, &bucket_name, &path, None, exp_secs, funs),
+            ObjectObjPresignKind::Delete => client.presign_url(LocalPresignOp::Delete, &bucket_name, &path, None, exp_secs, funs),
+            ObjectObjPresignKind::View => {
+                if private.unwrap_or(true) || special.unwrap_or(false) || obj_exp.is_some() {
+                    client.presign_url(LocalPresignOp::Get, &bucket_name, &path, None, exp_secs, funs)
+                } else {
+                    // 公共桶的对象可永久访问
+                    // objects of the public bucket are always accessible
+                    client.presign_url(LocalPresignOp::Get, &bucket_name, &path, None, 0, funs)
+                }
+            }
+        }
+    }
+
+    pub async fn batch_get_presign_obj_url(
+        object_paths: Vec<String>,
+        exp_secs: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        funs: &TardisFunsInst,
+        inst: &SpiBsInst,
+    ) -> TardisResult<HashMap<String, String>> {
+        let mut result = HashMap::with_capacity(object_paths.len());
+        for object_path in object_paths {
+            if let Ok(url) = Self::presign_obj_url(ObjectObjPresignKind::View, &object_path, exp_secs, private, special, obj_exp, funs, inst).await {
+                result.insert(object_path, url);
+            }
+        }
+        Ok(result)
+    }
+
+    pub async fn initiate_multipart_upload(
+        object_path: &str,
+        content_type: Option<String>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<String> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        // 提前校验路径
+        // check the path in advance
+        client.object_file(&bucket_name, &path)?;
+        let upload_id = TardisFuns::field.nanoid();
+        let multipart_dir = client.multipart_dir(&upload_id)?;
+        fs::create_dir_all(&multipart_dir).await.map_err(|e| io_error("initiate multipart upload", e))?;
+        let meta = LocalMultipartMeta {
+            bucket_name,
+            object_path: path,
+            content_type,
+        };
+        fs::write(multipart_dir.join(object_constants::LOCAL_MULTIPART_META_FILE), TardisFuns::json.obj_to_string(&meta)?)
+            .await
+            .map_err(|e| io_error("initiate multipart upload", e))?;
+        Ok(upload_id)
+    }
+
+    /// 为每个分片生成上传URL，分片号从1开始
+    /// Generate the upload url of each part, part numbers start from 1
+    #[allow(clippy::too_many_arguments)]
+    pub async fn batch_build_create_presign_url(
+        object_path: &str,
+        upload_id: &str,
+        part_number: u32,
+        expire_sec: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        funs: &TardisFunsInst,
+        inst: &SpiBsInst,
+    ) -> TardisResult<Vec<String>> {
+        let client = inst.inst::<LocalOSClient>().0;
+        Self::get_multipart_meta(client, object_path, upload_id, private, special, obj_exp, inst).await?;
+        (1..=part_number).map(|part_number| client.presign_url(LocalPresignOp::PutPart, "", upload_id, Some(part_number), expire_sec, funs)).collect()
+    }
+
+    /// 按顺序合并分片，parts 为各分片上传时返回的ETag
+    /// Merge the parts in order, parts are the ETags returned by uploading each part
+    pub async fn complete_multipart_upload(
+        object_path: &str,
+        upload_id: &str,
+        parts: Vec<String>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let meta = Self::get_multipart_meta(client, object_path, upload_id, private, special, obj_exp, inst).await?;
+        if parts.is_empty() {
+            return Err(TardisError::bad_request("Parts of the multipart upload are empty", "400-spi-object-local-parts-empty"));
+        }
+        let multipart_dir = client.multipart_dir(upload_id)?;
+        let merged_file = multipart_dir.join(object_constants::LOCAL_MULTIPART_MERGED_FILE);
+        let mut merged = File::create(&merged_file).await.map_err(|e| io_error("complete multipart upload", e))?;
+        let mut merged_md5 = Md5::new();
+        for (idx, etag) in parts.iter().enumerate() {
+            let part = File::open(multipart_dir.join((idx + 1).to_string()))
+                .await
+                .map_err(|_| TardisError::bad_request(&format!("Part {} of upload {upload_id} does not exist", idx + 1), "400-spi-object-local-part-not-exist"))?;
+            let mut part_md5 = Md5::new();
+            copy_with_md5(part, &mut merged, &mut [&mut part_md5, &mut merged_md5], "complete multipart upload").await?;
+            if format!("{:x}", part_md5.finalize()) != etag.trim_matches('"') {
+                return Err(TardisError::bad_request(
+                    &format!("ETag of part {} of upload {upload_id} does not match", idx + 1),
+                    "400-spi-object-local-part-etag-mismatch",
+                ));
+            }
+        }
+        merged.sync_all().await.map_err(|e| io_error("complete multipart upload", e))?;
+        let file = client.object_file(&meta.bucket_name, &meta.object_path)?;
+        Self::create_parent_dir(&file).await?;
+        fs::rename(&merged_file, &file).await.map_err(|e| io_error("complete multipart upload", e))?;
+        fs::remove_dir_all(&multipart_dir).await.map_err(|e| io_error("complete multipart upload", e))?;
+        client
+            .write_meta(
+                &meta.bucket_name,
+                &meta.object_path,
+                &LocalObjectMeta {
+                    content_type: meta.content_type,
+                    etag: Some(format!("{:x}", merged_md5.finalize())),
+                    ..Default::default()
+                },
+            )
+            .await
+    }
+
+    pub async fn object_delete(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<()> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        Self::delete_object(client, &bucket_name, &Self::rebuild_path(object_path, obj_exp)).await
+    }
+
+    pub async fn batch_object_delete(object_paths: Vec<String>, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+        let mut failed_object_paths = Vec::new();
+        for object_path in object_paths {
+            if Self::object_delete(&object_path, private, special, obj_exp, inst).await.is_err() {
+                failed_object_paths.push(object_path);
+            }
+        }
+        Ok(failed_object_paths)
+    }
+
+    pub async fn object_copy(from: &str, to: &str, private: Option<bool>, special: Option<bool>, inst: &SpiBsInst) -> TardisResult<()> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, None, inst);
+        let from_file = client.object_file(&bucket_name, from)?;
+        let to_file = client.object_file(&bucket_name, to)?;
+        if from_file == to_file {
+            return Err(TardisError::bad_request(
+                &format!("Object {from} can not be copied to itself"),
+                "400-spi-object-local-copy-to-itself",
+            ));
+        }
+        if !fs::try_exists(&from_file).await.unwrap_or(false) {
+            return Err(TardisError::not_found(&format!("Object {from} does not exist"), "404-spi-object-local-not-exist"));
+        }
+        Self::create_parent_dir(&to_file).await?;
+        let temp_file = temp_file(&to_file);
+        let copied = fs::copy(&from_file, &temp_file).await.map(|_| ()).map_err(|e| io_error("copy object", e));
+        persist_temp_file(&temp_file, &to_file, copied, "copy object").await?;
+        client.write_meta(&bucket_name, to, &client.read_meta(&bucket_name, from).await?).await
+    }
+
+    pub async fn object_exist(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<bool> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let file = client.object_file(&bucket_name, &Self::rebuild_path(object_path, obj_exp))?;
+        Ok(fs::metadata(&file).await.is_ok_and(|metadata| metadata.is_file()))
+    }
+
+    /// 列举对象，continuation_token 为上一页最后一个对象的路径
+    /// List the objects, continuation_token is the path of the last object of the previous page
+    #[allow(clippy::too_many_arguments)]
+    pub async fn list_objects(
+        prefix: Option<&str>,
+        continuation_token: Option<&str>,
+        max_keys: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectListResp> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let base_path = Self::rebuild_path("", obj_exp);
+        let full_prefix = format!("{base_path}{}", prefix.unwrap_or_default().trim_start_matches('/'));
+        let mut objects = local_client::walk_objects(&client.bucket_dir(&bucket_name)?, &full_prefix, continuation_token, Some(max_keys as usize + 1)).await?;
+        let next_continuation_token = if objects.len() > max_keys as usize {
+            objects.truncate(max_keys as usize);
+            objects.last().map(|(object_path, _)| object_path.clone())
+        } else {
+            None
+        };
+        let objects = objects
+            .into_iter()
+            .map(|(object_path, metadata)| ObjectMetaResp {
+                object_path: object_path.strip_prefix(&base_path).unwrap_or(&object_path).to_string(),
+                size: metadata.len(),
+                etag: None,
+                content_type: None,
+                last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            })
+            .collect();
+        Ok(ObjectListResp { objects, next_continuation_token })
+    }
+
+    pub async fn head_object(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<ObjectMetaResp> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        let metadata = fs::metadata(client.object_file(&bucket_name, &path)?)
+            .await
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .ok_or_else(|| TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-local-not-exist"))?;
+        let meta = client.read_meta(&bucket_name, &path).await?;
+        Ok(ObjectMetaResp {
+            object_path: object_path.to_string(),
+            size: metadata.len(),
+            etag: Some(meta.etag.unwrap_or_else(|| Self::derived_etag(&metadata))),
+            content_type: meta.content_type,
+            last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        })
+    }
+
+    pub async fn get_object_tags(object_path: &str, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<HashMap<String, String>> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        Self::check_exist(client, &bucket_name, &path, object_path).await?;
+        Ok(client.read_meta(&bucket_name, &path).await?.tags)
+    }
+
+    pub async fn put_object_tags(
+        object_path: &str,
+        tags: HashMap<String, String>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        Self::check_exist(client, &bucket_name, &path, object_path).await?;
+        let mut meta = client.read_meta(&bucket_name, &path).await?;
+        meta.tags = tags;
+        client.write_meta(&bucket_name, &path, &meta).await
+    }
+
+    /// 生命周期规则由清理任务执行，见 [`super::object_local_initializer`]
+    /// The lifecycle rules are applied by the sweeper, see [`super::object_local_initializer`]
+    pub async fn get_lifecycle_rules(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<Vec<ObjectLifecycleRule>> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        Self::read_lifecycle_rules(&client.lifecycle_file(&bucket_name)?).await
+    }
+
+    pub async fn put_lifecycle_rules(rules: Vec<ObjectLifecycleRule>, private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> TardisResult<()> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let file = client.lifecycle_file(&bucket_name)?;
+        if rules.is_empty() {
+            return Self::delete_file(&file).await;
+        }
+        Self::create_parent_dir(&file).await?;
+        fs::write(&file, TardisFuns::json.obj_to_string(&rules)?).await.map_err(|e| io_error("put lifecycle rules", e))
+    }
+
+    pub(crate) async fn read_lifecycle_rules(file: &Path) -> TardisResult<Vec<ObjectLifecycleRule>> {
+        match fs::read_to_string(file).await {
+            Ok(rules) => TardisFuns::json.str_to_obj(&rules),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+            Err(e) => Err(io_error("get lifecycle rules", e)),
+        }
+    }
+
+    /// 从暂存文件复制对象内容，用于上传代理
+    /// Copy the object content from the spooled file, used by the upload proxy
+    pub async fn put_object_content(
+        object_path: &str,
+        content_type: Option<&str>,
+        content_file: &Path,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        let file = client.object_file(&bucket_name, &path)?;
+        Self::create_parent_dir(&file).await?;
+        let content = File::open(content_file).await.map_err(|e| io_error("put object", e))?;
+        let temp_file = temp_file(&file);
+        let mut md5 = Md5::new();
+        let written = async {
+            let mut target = File::create(&temp_file).await.map_err(|e| io_error("put object", e))?;
+            copy_with_md5(content, &mut target, &mut [&mut md5], "put object").await?;
+            target.sync_all().await.map_err(|e| io_error("put object", e))
+        }
+        .await;
+        persist_temp_file(&temp_file, &file, written, "put object").await?;
+        client
+            .write_meta(
+                &bucket_name,
+                &path,
+                &LocalObjectMeta {
+                    content_type: content_type.map(|content_type| content_type.to_string()),
+                    etag: Some(format!("{:x}", md5.finalize())),
+                    ..Default::default()
+                },
+            )
+            .await
+    }
+
+    /// 以流的方式读取对象内容及内容类型，用于下载代理
+    /// Read the object content as a stream and the content type, used by the download proxy
+    pub async fn get_object_content(
+        object_path: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<(Body, Option<String>)> {
+        let client = inst.inst::<LocalOSClient>().0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, inst);
+        let path = Self::rebuild_path(object_path, obj_exp);
+        let file = File::open(client.object_file(&bucket_name, &path)?)
+            .await
+            .map_err(|_| TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-local-not-exist"))?;
+        Ok((Body::from_async_read(file), client.read_meta(&bucket_name, &path).await?.content_type))
+    }
+
+    /// 以流的方式保存对象，用于预签名上传
+    /// Save the object as a stream, used by the presigned upload
+    pub async fn put_object(client: &LocalOSClient, bucket_name: &str, object_path: &str, content_type: Option<String>, content: Body) -> TardisResult<()> {
+        let file = client.object_file(bucket_name, object_path)?;
+        Self::create_parent_dir(&file).await?;
+        let etag = write_body(&file, content, "put object").await?;
+        // 覆盖对象时与s3一致清除原有标签
+        // like s3, the existing tags are cleared when the object is overwritten
+        client
+            .write_meta(
+                bucket_name,
+                object_path,
+                &LocalObjectMeta {
+                    content_type,
+                    etag: Some(etag),
+                    ..Default::default()
+                },
+            )
+            .await
+    }
+
+    /// 以流的方式读取对象，用于预签名下载
+    /// Read the object as a stream, used by the presigned download
+    pub async fn get_object(client: &LocalOSClient, bucket_name: &str, object_path: &str) -> TardisResult<Body> {
+        let file = File::open(client.object_file(bucket_name, object_path)?)
+            .await
+            .map_err(|_| TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-local-not-exist"))?;
+        Ok(Body::from_async_read(file))
+    }
+
+    /// 删除对象，用于预签名删除
+    /// Delete the object, used by the presigned deletion
+    pub async fn delete_object(client: &LocalOSClient, bucket_name: &str, object_path: &str) -> TardisResult<()> {
+        Self::delete_file(&client.object_file(bucket_name, object_path)?).await?;
+        client.remove_meta(bucket_name, object_path).await
+    }
+
+    /// 保存分片，返回分片的ETag
+    /// Save the part, and return the ETag of the part
+    pub async fn put_part(client: &LocalOSClient, upload_id: &str, part_number: u32, content: Body) -> TardisResult<String> {
+        let multipart_dir = client.multipart_dir(upload_id)?;
+        if part_number == 0 || !fs::try_exists(&multipart_dir).await.unwrap_or(false) {
+            return Err(TardisError::not_found(
+                &format!("Part {part_number} of upload {upload_id} is invalid"),
+                "404-spi-object-local-upload-not-exist",
+            ));
+        }
+        write_body(&multipart_dir.join(part_number.to_string()), content, "put part").await
+    }
+
+    async fn get_multipart_meta(
+        client: &LocalOSClient,
+        object_path: &str,
+        upload_id: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        inst: &SpiBsInst,
+    ) -> TardisResult<LocalMultipartMeta> {
+        let meta = fs::read_to_string(client.multipart_dir(upload_id)?.join(object_constants::LOCAL_MULTIPART_META_FILE))
+            .await
+            .map_err(|_| TardisError::not_found(&format!("Upload {upload_id} does not exist"), "404-spi-object-local-upload-not-exist"))?;
+        let meta = TardisFuns::json.str_to_obj::<LocalMultipartMeta>(&meta)?;
+        if meta.bucket_name != Self::get_bucket_name(private, special, obj_exp, inst) || meta.object_path != Self::rebuild_path(object_path, obj_exp) {
+            return Err(TardisError::bad_request(
+                &format!("Upload {upload_id} does not belong to object {object_path}"),
+                "400-spi-object-local-upload-mismatch",
+            ));
+        }
+        Ok(meta)
+    }
+
+    async fn check_exist(client: &LocalOSClient, bucket_name: &str, path: &str, object_path: &str) -> TardisResult<()> {
+        if fs::metadata(client.object_file(bucket_name, path)?).await.is_ok_and(|metadata| metadata.is_file()) {
+            Ok(())
+        } else {
+            Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-local-not-exist"))
+        }
+    }
+
+    async fn create_parent_dir(file: &Path) -> TardisResult<()> {
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).await.map_err(|e| io_error("create directory", e))?;
+        }
+        Ok(())
+    }
+
+    /// 与s3一致，删除不存在的对象不报错
+    /// Like s3, deleting a nonexistent object is not an error
+    async fn delete_file(file: &Path) -> TardisResult<()> {
+        match fs::remove_file(file).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error("delete object", e)),
+        }
+    }
+
+    /// 未记录ETag的对象（如直接放入存储目录的文件）由大小及修改时间派生ETag
+    /// The ETag of the objects without a recorded one (e.g. files put into the storage directory directly) is derived from the size and the modification time
+    fn derived_etag(metadata: &Metadata) -> String {
+        let modified = metadata.modified().ok().and_then(|modified| modified.duration_since(UNIX_EPOCH).ok()).map(|modified| modified.as_nanos()).unwrap_or_default();
+        format!("{:x}-{:x}", modified, metadata.len())
+    }
+
+    fn get_bucket_name(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, inst: &SpiBsInst) -> String {
+        let bs_inst = inst.inst::<LocalOSClient>();
+        format!(
+            "{}-{}",
+            common::get_isolation_flag_from_ext(bs_inst.1).unwrap_or_else(|| object_constants::LOCAL_DEFAULT_BUCKET_PREFIX.to_string()),
+            if special.unwrap_or(false) {
+                "spe"
+            } else if obj_exp.is_some() {
+                "tamp"
+            } else if private.unwrap_or(true) {
+                "pri"
+            } else {
+                "pub"
+            }
+        )
+    }
+
+    /// 临时对象按过期天数存放，由清理任务删除，见 [`super::object_local_initializer`]
+    /// Temporary objects are stored by expiration days and removed by the sweeper, see [`super::object_local_initializer`]
+    fn rebuild_path(origin_path: &str, obj_exp: Option<u32>) -> String {
+        if let Some(obj_exp) = obj_exp {
+            format!("{}{}/{}", object_constants::LOCAL_TAMP_EXP_DIR_PREFIX, obj_exp, origin_path.trim_start_matches('/'))
+        } else {
+            origin_path.to_string()
+        }
+    }
+}
+
+/// 将请求体写入文件，返回内容的MD5
+/// Write the request body to the file, and return the MD5 of the content
+async fn write_body(file: &Path, body: Body, op: &str) -> TardisResult<String> {
+    let temp_file = temp_file(file);
+    let written: TardisResult<String> = async {
+        let mut target = File::create(&temp_file).await.map_err(|e| io_error(op, e))?;
+        let mut md5 = Md5::new();
+        let mut stream = body.into_bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| TardisError::bad_request(&format!("Read the uploaded content failed: {e}"), "400-spi-object-local-read-error"))?;
+            md5.update(&chunk);
+            target.write_all(&chunk).await.map_err(|e| io_error(op, e))?;
+        }
+        target.sync_all().await.map_err(|e| io_error(op, e))?;
+        Ok(format!("{:x}", md5.finalize()))
+    }
+    .await;
+    persist_temp_file(&temp_file, file, written, op).await
+}
+
+/// 与目标文件同目录的临时文件，写完后重命名为目标文件，避免读到或留下写了一半的文件
+/// The temporary file in the same directory as the target, renamed to the target once fully written, so that a half written file is never read or left
+fn temp_file(file: &Path) -> PathBuf {
+    file.with_file_name(format!("{}{}", object_constants::LOCAL_TEMP_FILE_PREFIX, TardisFuns::field.nanoid()))
+}
+
+/// 写入成功时将临时文件重命名为目标文件，否则删除临时文件
+/// Rename the temporary file to the target if it is written, otherwise remove the temporary file
+async fn persist_temp_file<T>(temp_file: &Path, file: &Path, written: TardisResult<T>, op: &str) -> TardisResult<T> {
+    let result = match written {
+        Ok(value) => fs::rename(temp_file, file).await.map(|_| value).map_err(|e| io_error(op, e)),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(temp_file).await;
+    }
+    result
+}
+
+/// 分块复制内容并更新各MD5
+/// Copy the content chunk by chunk and update each MD5
+async fn copy_with_md5(mut source: impl AsyncRead + Unpin, target: &mut File, md5s: &mut [&mut Md5], op: &str) -> TardisResult<()> {
+    let mut buf = vec![0; COPY_BUF_SIZE];
+    loop {
+        let len = source.read(&mut buf).await.map_err(|e| io_error(op, e))?;
+        if len == 0 {
+            return Ok(());
+        }
+        for md5 in md5s.iter_mut() {
+            md5.update(&buf[..len]);
+        }
+        target.write_all(&buf[..len]).await.map_err(|e| io_error(op, e))?;
+    }
+}
+
+fn io_error(op: &str, e: std::io::Error) -> TardisError {
+    TardisError::internal_error(&format!("Local object storage failed to {op}: {e}"), "500-spi-object-local-io-error")
+}
@@ -1,20 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 
 use bios_basic::spi::serv::spi_bs_serv::SpiBsServ;
 use bios_basic::spi::spi_funs::{SpiBsInst, SpiBsInstExtractor};
 use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
 use tardis::tokio::sync::RwLock;
+use tardis::web::poem::Body;
 use tardis::TardisFunsInst;
 
-use crate::dto::object_dto::{ObjectBatchBuildCreatePresignUrlReq, ObjectCompleteMultipartUploadReq, ObjectInitiateMultipartUploadReq, ObjectObjPresignKind};
+use crate::dto::object_dto::{
+    ObjectBatchBuildCreatePresignUrlReq, ObjectCompleteMultipartUploadReq, ObjectInitiateMultipartUploadReq, ObjectLifecycleModifyReq, ObjectLifecycleRule, ObjectListResp,
+    ObjectMetaResp, ObjectObjPresignKind, ObjectTagsModifyReq,
+};
 use crate::object_constants::USE_REGION_ENDPOINT;
 use crate::{object_constants, object_initializer};
 
 use super::custom_s3::object_custom_s3_obj_serv::CustomS3Service;
 use super::s3::S3 as _;
-use super::{obs, s3};
+use super::{local, obs, s3};
 
 pub async fn presign_obj_url(
     presign_kind: ObjectObjPresignKind,
@@ -70,6 +76,9 @@ pub async fn presign_obj_url(
             )
             .await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::presign_obj_url(presign_kind, object_path, exp_secs, private, special, obj_exp, funs, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -108,6 +117,9 @@ pub async fn batch_get_presign_obj_url(
             )
             .await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::batch_get_presign_obj_url(object_paths, exp_secs, private, special, obj_exp, funs, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -147,6 +159,9 @@ pub async fn initiate_multipart_upload(req: ObjectInitiateMultipartUploadReq, fu
             )
             .await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::initiate_multipart_upload(&req.object_path, req.content_type, req.private, req.special, req.obj_exp, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -190,6 +205,20 @@ pub async fn batch_build_create_presign_url(req: ObjectBatchBuildCreatePresignUr
             )
             .await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::batch_build_create_presign_url(
+                &req.object_path,
+                &req.upload_id,
+                req.part_number,
+                req.expire_sec,
+                req.private,
+                req.special,
+                req.obj_exp,
+                funs,
+                &inst,
+            )
+            .await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -231,6 +260,9 @@ pub async fn complete_multipart_upload(req: ObjectCompleteMultipartUploadReq, fu
             )
             .await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::complete_multipart_upload(&req.object_path, &req.upload_id, req.parts, req.private, req.special, req.obj_exp, &inst).await
+        }
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -255,6 +287,7 @@ pub async fn object_delete(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::object_delete(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::object_delete(&object_path, private, special, obj_exp, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -279,6 +312,7 @@ pub async fn batch_object_delete(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::batch_object_delete(object_paths, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::batch_object_delete(object_paths, private, special, obj_exp, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -307,6 +341,7 @@ pub async fn object_copy(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::object_copy(&from, &to, private, special, bs_id.as_deref(), bucket.as_deref(), funs, &mock_ctx, &inst).await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::object_copy(&from, &to, private, special, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
@@ -331,10 +366,334 @@ pub async fn object_exist(
         object_constants::SPI_OBS_KIND_CODE => {
             obs::object_obs_obj_serv::OBSService::object_exist(&object_paths, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
         }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::object_exist(&object_paths, private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn list_objects(
+    prefix: Option<String>,
+    continuation_token: Option<String>,
+    max_keys: Option<u32>,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ObjectListResp> {
+    let max_keys = max_keys.unwrap_or(object_constants::LIST_OBJECTS_DEFAULT_MAX_KEYS).clamp(1, object_constants::LIST_OBJECTS_MAX_KEYS);
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::list_objects(
+                prefix.as_deref(),
+                continuation_token.as_deref(),
+                max_keys,
+                private,
+                special,
+                obj_exp,
+                bs_id.as_deref(),
+                bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::list_objects(
+                prefix.as_deref(),
+                continuation_token.as_deref(),
+                max_keys,
+                private,
+                special,
+                obj_exp,
+                bs_id.as_deref(),
+                bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::list_objects(prefix.as_deref(), continuation_token.as_deref(), max_keys, private, special, obj_exp, &inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn head_object(
+    object_path: String,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ObjectMetaResp> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::head_object(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::head_object(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::head_object(&object_path, private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn get_object_tags(
+    object_path: String,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<HashMap<String, String>> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::get_object_tags(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::get_object_tags(&object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::get_object_tags(&object_path, private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn put_object_tags(req: ObjectTagsModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if req.tags.keys().any(|key| key.trim().is_empty()) {
+        return Err(TardisError::bad_request("Tag key can not be empty", "400-spi-object-tag-key-empty"));
+    }
+    let inst = get_bs(req.bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::put_object_tags(
+                &req.object_path,
+                req.tags,
+                req.private,
+                req.special,
+                req.obj_exp,
+                req.bs_id.as_deref(),
+                req.bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::put_object_tags(
+                &req.object_path,
+                req.tags,
+                req.private,
+                req.special,
+                req.obj_exp,
+                req.bs_id.as_deref(),
+                req.bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::put_object_tags(&req.object_path, req.tags, req.private, req.special, req.obj_exp, &inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn get_lifecycle_rules(
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<Vec<ObjectLifecycleRule>> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::get_lifecycle_rules(private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::get_lifecycle_rules(private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::get_lifecycle_rules(private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn put_lifecycle_rules(req: ObjectLifecycleModifyReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    check_lifecycle_rules(&req.rules)?;
+    let inst = get_bs(req.bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::put_lifecycle_rules(
+                req.rules,
+                req.private,
+                req.special,
+                req.obj_exp,
+                req.bs_id.as_deref(),
+                req.bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::put_lifecycle_rules(
+                req.rules,
+                req.private,
+                req.special,
+                req.obj_exp,
+                req.bs_id.as_deref(),
+                req.bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::put_lifecycle_rules(req.rules, req.private, req.special, req.obj_exp, &inst).await,
         kind_code => Err(funs.bs_not_implemented(kind_code)),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
+pub async fn put_object_content(
+    object_path: &str,
+    content_type: Option<&str>,
+    content_file: &Path,
+    size: u64,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::put_object_content(
+                object_path,
+                content_type,
+                content_file,
+                size,
+                private,
+                special,
+                obj_exp,
+                bs_id.as_deref(),
+                bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::put_object_content(
+                object_path,
+                content_type,
+                content_file,
+                size,
+                private,
+                special,
+                obj_exp,
+                bs_id.as_deref(),
+                bucket.as_deref(),
+                funs,
+                ctx,
+                &inst,
+            )
+            .await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => {
+            local::object_local_obj_serv::LocalService::put_object_content(object_path, content_type, content_file, private, special, obj_exp, &inst).await
+        }
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+pub async fn get_object_content(
+    object_path: &str,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<(Body, Option<String>)> {
+    let inst = get_bs(bs_id.clone(), None, funs, ctx).await?;
+    match inst.kind_code() {
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_S3_KIND_CODE => {
+            s3::object_s3_obj_serv::S3Service::get_object_content(object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        #[cfg(feature = "spi-s3")]
+        object_constants::SPI_OBS_KIND_CODE => {
+            obs::object_obs_obj_serv::OBSService::get_object_content(object_path, private, special, obj_exp, bs_id.as_deref(), bucket.as_deref(), funs, ctx, &inst).await
+        }
+        object_constants::SPI_LOCAL_KIND_CODE => local::object_local_obj_serv::LocalService::get_object_content(object_path, private, special, obj_exp, &inst).await,
+        kind_code => Err(funs.bs_not_implemented(kind_code)),
+    }
+}
+
+fn check_lifecycle_rules(rules: &[ObjectLifecycleRule]) -> TardisResult<()> {
+    let mut ids = HashSet::new();
+    for rule in rules {
+        if rule.id.trim().is_empty() || !ids.insert(rule.id.as_str()) {
+            return Err(TardisError::bad_request(
+                &format!("Lifecycle rule id [{}] is empty or duplicated", rule.id),
+                "400-spi-object-lifecycle-rule-id-invalid",
+            ));
+        }
+        if rule.expiration_days.is_none() && rule.abort_incomplete_multipart_upload_days.is_none() {
+            return Err(TardisError::bad_request(
+                &format!("Lifecycle rule [{}] has no action", rule.id),
+                "400-spi-object-lifecycle-rule-no-action",
+            ));
+        }
+        if rule.expiration_days == Some(0) || rule.abort_incomplete_multipart_upload_days == Some(0) {
+            return Err(TardisError::bad_request(
+                &format!("Days of lifecycle rule [{}] must be positive", rule.id),
+                "400-spi-object-lifecycle-rule-days-invalid",
+            ));
+        }
+    }
+    Ok(())
+}
+
 async fn get_bs(spi_bs_id: Option<String>, custom_cache_key: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Arc<SpiBsInst>> {
     if let Some(spi_bs_id) = spi_bs_id {
         let spi_bs = SpiBsServ::get_bs(&spi_bs_id, funs, ctx).await?;
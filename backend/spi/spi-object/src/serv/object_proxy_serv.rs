@@ -0,0 +1,448 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use bios_sdk_invoke::clients::spi_log_client::{LogItemAddV2Req, SpiLogClient};
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tardis::async_trait::async_trait;
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::Utc,
+    futures::StreamExt,
+    log::{info, warn},
+    serde_json::json,
+    tokio::{fs::File, io::AsyncWriteExt},
+    web::poem::Body,
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::dto::object_dto::ObjectProxyUploadResp;
+use crate::object_config::{ObjectConfig, ObjectProxyPolicy};
+
+use super::object_obj_serv;
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+/// Bytes of the content head used to check the magic bytes
+const MAGIC_BYTES_HEAD_LEN: usize = 16;
+/// Known signatures of the content types: (content type, offset, signature)
+const MAGIC_BYTES: &[(&str, usize, &[u8])] = &[
+    ("image/png", 0, b"\x89PNG\r\n\x1a\n"),
+    ("image/jpeg", 0, b"\xFF\xD8\xFF"),
+    ("image/gif", 0, b"GIF87a"),
+    ("image/gif", 0, b"GIF89a"),
+    ("image/bmp", 0, b"BM"),
+    ("image/webp", 8, b"WEBP"),
+    ("application/pdf", 0, b"%PDF-"),
+    ("application/zip", 0, b"PK\x03\x04"),
+    ("application/zip", 0, b"PK\x05\x06"),
+    ("application/gzip", 0, b"\x1F\x8B"),
+    ("application/x-7z-compressed", 0, b"7z\xBC\xAF\x27\x1C"),
+    ("application/vnd.openxmlformats-officedocument.wordprocessingml.document", 0, b"PK\x03\x04"),
+    ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet", 0, b"PK\x03\x04"),
+    ("application/vnd.openxmlformats-officedocument.presentationml.presentation", 0, b"PK\x03\x04"),
+    ("application/msword", 0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1"),
+    ("application/vnd.ms-excel", 0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1"),
+    ("application/vnd.ms-powerpoint", 0, b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1"),
+];
+/// Signatures of the executables, which are only accepted when declared as executables
+const EXECUTABLE_MAGIC_BYTES: &[&[u8]] = &[b"MZ", b"\x7FELF", b"\xCF\xFA\xED\xFE", b"\xCE\xFA\xED\xFE", b"\xFE\xED\xFA\xCF"];
+const EXECUTABLE_CONTENT_TYPES: &[&str] = &[
+    DEFAULT_CONTENT_TYPE,
+    "application/x-msdownload",
+    "application/x-executable",
+    "application/x-elf",
+    "application/x-mach-binary",
+    "application/vnd.microsoft.portable-executable",
+];
+
+/// 待扫描的对象
+/// Object to scan
+pub struct ObjectScanReq<'a> {
+    pub object_path: &'a str,
+    pub content_type: &'a str,
+    pub size: u64,
+    pub sha256: &'a str,
+    /// 暂存对象内容的临时文件
+    /// Temporary file spooling the object content
+    pub content_file: &'a Path,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ObjectScanResult {
+    pub passed: bool,
+    pub reason: Option<String>,
+}
+
+/// 上传代理的扫描器，如病毒扫描，任一扫描器不通过或出错时拒绝上传
+/// Scanner of the upload proxy, e.g. virus scanning, the upload is rejected when any scanner does not pass or fails
+#[async_trait]
+pub trait ObjectScanner: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn scan(&self, req: &ObjectScanReq<'_>, ctx: &TardisContext) -> TardisResult<ObjectScanResult>;
+}
+
+static SCANNERS: OnceLock<RwLock<Vec<Arc<dyn ObjectScanner>>>> = OnceLock::new();
+
+/// 注册扫描器
+/// Register a scanner
+pub fn register_scanner(scanner: Arc<dyn ObjectScanner>) {
+    info!("[BIOS.Object] Register object scanner {}", scanner.name());
+    if let Ok(mut scanners) = SCANNERS.get_or_init(Default::default).write() {
+        scanners.retain(|exist| exist.name() != scanner.name());
+        scanners.push(scanner);
+    }
+}
+
+fn scanners() -> Vec<Arc<dyn ObjectScanner>> {
+    SCANNERS.get_or_init(Default::default).read().map(|scanners| scanners.clone()).unwrap_or_default()
+}
+
+/// 内置的HTTP扫描器
+/// 以POST请求发送对象内容，对象信息放在 `X-Object-*` 请求头中，响应为 [`ObjectScanResult`] 的JSON。
+/// Built-in http scanner
+/// The object content is sent by a POST request with the object information in the `X-Object-*` headers, and the response is the JSON of [`ObjectScanResult`].
+pub struct HttpObjectScanner {
+    pub url: String,
+    pub timeout_ms: u64,
+}
+
+#[async_trait]
+impl ObjectScanner for HttpObjectScanner {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    async fn scan(&self, req: &ObjectScanReq<'_>, _ctx: &TardisContext) -> TardisResult<ObjectScanResult> {
+        let scan_error = |e: String| TardisError::internal_error(&format!("Scan object {} failed: {e}", req.object_path), "500-spi-object-proxy-scan-error");
+        let content = File::open(req.content_file).await.map_err(|e| scan_error(e.to_string()))?;
+        let resp = TardisFuns::web_client()
+            .raw()
+            .post(&self.url)
+            .timeout(Duration::from_millis(self.timeout_ms))
+            .header("Content-Type", DEFAULT_CONTENT_TYPE)
+            .header("X-Object-Path", req.object_path)
+            .header("X-Object-Content-Type", req.content_type)
+            .header("X-Object-Size", req.size.to_string())
+            .header("X-Object-Sha256", req.sha256)
+            .header("Content-Length", req.size.to_string())
+            .body(content)
+            .send()
+            .await
+            .map_err(|e| scan_error(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(scan_error(format!("scanner responded {}", resp.status())));
+        }
+        let body = resp.text().await.map_err(|e| scan_error(e.to_string()))?;
+        TardisFuns::json.str_to_obj::<ObjectScanResult>(&body)
+    }
+}
+
+/// 经由代理上传对象
+/// 内容以流的方式暂存到临时文件，超出大小或文件头不符时立即拒绝，校验通过后以流的方式写入存储。
+/// Upload the object through the proxy
+/// The content is spooled to a temporary file as a stream and rejected as soon as it exceeds the size or the magic bytes do not match,
+/// it is written to the storage as a stream after passing the validation.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload(
+    object_path: &str,
+    content_type: Option<String>,
+    body: Body,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ObjectProxyUploadResp> {
+    let policy = get_policy(funs, ctx)?;
+    let content_type = normalize_content_type(content_type.as_deref());
+    let result = async {
+        check_content_type(&content_type, &policy)?;
+        let content = spool_body(body, &content_type, &policy).await?;
+        let resp = ObjectProxyUploadResp {
+            object_path: object_path.to_string(),
+            size: content.size,
+            content_type: content_type.clone(),
+            md5: content.md5.clone(),
+            sha256: content.sha256.clone(),
+        };
+        if policy.scan {
+            scan(&resp, &content.file, ctx).await?;
+        }
+        object_obj_serv::put_object_content(
+            object_path,
+            Some(&content_type),
+            &content.file,
+            content.size,
+            private,
+            special,
+            obj_exp,
+            bucket.clone(),
+            bs_id.clone(),
+            funs,
+            ctx,
+        )
+        .await?;
+        Ok(resp)
+    }
+    .await;
+    log_upload(object_path, &content_type, bucket.as_deref(), bs_id.as_deref(), result.as_ref(), funs, ctx);
+    result
+}
+
+/// 经由代理下载对象，返回内容流及内容类型
+/// Download the object through the proxy, returns the content stream and the content type
+#[allow(clippy::too_many_arguments)]
+pub async fn download(
+    object_path: &str,
+    private: Option<bool>,
+    special: Option<bool>,
+    obj_exp: Option<u32>,
+    bucket: Option<String>,
+    bs_id: Option<String>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<(Body, String)> {
+    get_policy(funs, ctx)?;
+    let (content, content_type) = object_obj_serv::get_object_content(object_path, private, special, obj_exp, bucket, bs_id, funs, ctx).await?;
+    Ok((content, content_type.unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string())))
+}
+
+fn get_policy(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ObjectProxyPolicy> {
+    let config = &funs.conf::<ObjectConfig>().proxy;
+    if !config.enabled {
+        return Err(TardisError::forbidden("The object proxy is not enabled", "403-spi-object-proxy-disabled"));
+    }
+    Ok(config.tenant_policies.get(&ctx.ak).unwrap_or(&config.default_policy).clone())
+}
+
+/// 暂存在临时文件中的上传内容，释放时删除临时文件
+/// Uploaded content spooled in a temporary file, which is deleted on drop
+struct SpooledContent {
+    file: PathBuf,
+    size: u64,
+    md5: String,
+    sha256: String,
+}
+
+impl Drop for SpooledContent {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("[BIOS.Object] remove the spooled file {:?} failed: {}", self.file, e);
+            }
+        }
+    }
+}
+
+async fn spool_body(body: Body, content_type: &str, policy: &ObjectProxyPolicy) -> TardisResult<SpooledContent> {
+    let spool_error = |e: std::io::Error| TardisError::internal_error(&format!("Spool the uploaded content failed: {e}"), "500-spi-object-proxy-spool-error");
+    let mut content = SpooledContent {
+        file: std::env::temp_dir().join(format!("bios-spi-object-proxy-{}", TardisFuns::field.nanoid())),
+        size: 0,
+        md5: String::new(),
+        sha256: String::new(),
+    };
+    let mut file = File::create(&content.file).await.map_err(spool_error)?;
+    let mut md5 = Md5::new();
+    let mut sha256 = Sha256::new();
+    let mut head = Vec::with_capacity(MAGIC_BYTES_HEAD_LEN);
+    let mut magic_bytes_checked = !policy.check_magic_bytes;
+    let mut stream = body.into_bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| TardisError::bad_request(&format!("Read the uploaded content failed: {e}"), "400-spi-object-proxy-read-error"))?;
+        content.size += chunk.len() as u64;
+        if content.size > policy.max_size {
+            return Err(TardisError::custom(
+                "413",
+                &format!("The uploaded content exceeds the max size {}", policy.max_size),
+                "413-spi-object-proxy-too-large",
+            ));
+        }
+        if !magic_bytes_checked {
+            head.extend_from_slice(&chunk[..chunk.len().min(MAGIC_BYTES_HEAD_LEN - head.len())]);
+            if head.len() >= MAGIC_BYTES_HEAD_LEN {
+                check_magic_bytes(content_type, &head)?;
+                magic_bytes_checked = true;
+            }
+        }
+        md5.update(&chunk);
+        sha256.update(&chunk);
+        file.write_all(&chunk).await.map_err(spool_error)?;
+    }
+    if !magic_bytes_checked {
+        check_magic_bytes(content_type, &head)?;
+    }
+    file.flush().await.map_err(spool_error)?;
+    content.md5 = format!("{:x}", md5.finalize());
+    content.sha256 = format!("{:x}", sha256.finalize());
+    Ok(content)
+}
+
+/// 依次调用扫描器，未注册任何扫描器时拒绝上传
+/// Call the scanners in order, the upload is rejected when no scanner is registered
+async fn scan(resp: &ObjectProxyUploadResp, content_file: &Path, ctx: &TardisContext) -> TardisResult<()> {
+    let scanners = scanners();
+    if scanners.is_empty() {
+        return Err(TardisError::internal_error(
+            "The upload requires scanning but no object scanner is registered",
+            "500-spi-object-proxy-scanner-missing",
+        ));
+    }
+    let req = ObjectScanReq {
+        object_path: &resp.object_path,
+        content_type: &resp.content_type,
+        size: resp.size,
+        sha256: &resp.sha256,
+        content_file,
+    };
+    for scanner in scanners {
+        let result = scanner.scan(&req, ctx).await?;
+        if !result.passed {
+            return Err(TardisError::bad_request(
+                &format!("The uploaded content is rejected by scanner {}: {}", scanner.name(), result.reason.unwrap_or_default()),
+                "400-spi-object-proxy-scan-rejected",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 将上传记录异步写入spi-log，记录失败不影响上传
+/// Write the upload record to spi-log asynchronously, failures of logging do not affect the upload
+fn log_upload(
+    object_path: &str,
+    content_type: &str,
+    bucket: Option<&str>,
+    bs_id: Option<&str>,
+    result: Result<&ObjectProxyUploadResp, &TardisError>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) {
+    let tag = funs.conf::<ObjectConfig>().proxy.log_tag.clone();
+    if tag.is_empty() {
+        return;
+    }
+    let content = match result {
+        Ok(resp) => json!({
+            "object_path": resp.object_path,
+            "content_type": resp.content_type,
+            "size": resp.size,
+            "md5": resp.md5,
+            "sha256": resp.sha256,
+            "bucket": bucket,
+            "bs_id": bs_id,
+            "success": true,
+        }),
+        Err(e) => json!({
+            "object_path": object_path,
+            "content_type": content_type,
+            "bucket": bucket,
+            "bs_id": bs_id,
+            "success": false,
+            "error": format!("{}: {}", e.code, e.message),
+        }),
+    };
+    let req = LogItemAddV2Req {
+        tag,
+        content,
+        kind: Some("object_upload".to_string()),
+        key: Some(object_path.to_string()),
+        op: Some("upload".to_string()),
+        ts: Some(Utc::now()),
+        owner: Some(ctx.owner.clone()),
+        own_paths: Some(ctx.own_paths.clone()),
+        ..Default::default()
+    };
+    let ctx = ctx.clone();
+    tardis::tokio::spawn(async move {
+        let funs = crate::get_tardis_inst();
+        if let Err(e) = SpiLogClient::addv2(req, &funs, &ctx).await {
+            warn!("[BIOS.Object] upload log failed: {}", e);
+        }
+    });
+}
+
+/// 去掉内容类型的参数并转为小写，如 `Text/Plain; charset=utf-8` 转为 `text/plain`
+/// Remove the parameters of the content type and convert it to lower case, e.g. `Text/Plain; charset=utf-8` to `text/plain`
+fn normalize_content_type(content_type: Option<&str>) -> String {
+    content_type
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|content_type| content_type.trim().to_lowercase())
+        .filter(|content_type| !content_type.is_empty())
+        .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string())
+}
+
+fn check_content_type(content_type: &str, policy: &ObjectProxyPolicy) -> TardisResult<()> {
+    if policy.allowed_content_types.is_empty()
+        || policy.allowed_content_types.iter().any(|allowed| match allowed.strip_suffix("/*") {
+            Some(allowed_type) => content_type.split('/').next().is_some_and(|content_type| content_type.eq_ignore_ascii_case(allowed_type)),
+            None => allowed.eq_ignore_ascii_case(content_type),
+        })
+    {
+        Ok(())
+    } else {
+        Err(TardisError::bad_request(
+            &format!("The content type {content_type} is not allowed"),
+            "400-spi-object-proxy-content-type-denied",
+        ))
+    }
+}
+
+/// 已知类型的内容须与其文件头相符，声明为其他类型的内容不能是可执行文件
+/// The content of the known types must match their magic bytes, and the content declared as other types can not be executables
+fn check_magic_bytes(content_type: &str, head: &[u8]) -> TardisResult<()> {
+    let mut signatures = MAGIC_BYTES.iter().filter(|(known_type, _, _)| *known_type == content_type).peekable();
+    let matched = if signatures.peek().is_some() {
+        signatures.any(|(_, offset, signature)| head.get(*offset..*offset + signature.len()).is_some_and(|bytes| bytes == *signature))
+    } else {
+        EXECUTABLE_CONTENT_TYPES.contains(&content_type) || !EXECUTABLE_MAGIC_BYTES.iter().any(|signature| head.starts_with(signature))
+    };
+    if matched {
+        Ok(())
+    } else {
+        Err(TardisError::bad_request(
+            &format!("The uploaded content does not match the content type {content_type}"),
+            "400-spi-object-proxy-magic-bytes-mismatch",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object_config::ObjectProxyPolicy;
+
+    use super::{check_content_type, check_magic_bytes, normalize_content_type};
+
+    #[test]
+    fn test_check_content_type() {
+        let policy = ObjectProxyPolicy {
+            allowed_content_types: vec!["image/*".to_string(), "text/plain".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(normalize_content_type(Some("Text/Plain; charset=utf-8")), "text/plain");
+        assert_eq!(normalize_content_type(None), "application/octet-stream");
+        assert!(check_content_type("image/png", &policy).is_ok());
+        assert!(check_content_type("text/plain", &policy).is_ok());
+        assert!(check_content_type("text/html", &policy).is_err());
+        assert!(check_content_type("imagex/png", &policy).is_err());
+        assert!(check_content_type("text/html", &ObjectProxyPolicy::default()).is_ok());
+    }
+
+    #[test]
+    fn test_check_magic_bytes() {
+        assert!(check_magic_bytes("image/png", b"\x89PNG\r\n\x1a\n0000").is_ok());
+        assert!(check_magic_bytes("image/png", b"GIF89a").is_err());
+        assert!(check_magic_bytes("image/png", b"").is_err());
+        assert!(check_magic_bytes("image/gif", b"GIF87a").is_ok());
+        assert!(check_magic_bytes("image/webp", b"RIFF\x00\x00\x00\x00WEBPVP8 ").is_ok());
+        assert!(check_magic_bytes("image/webp", b"RIFF").is_err());
+        assert!(check_magic_bytes("text/plain", b"hello").is_ok());
+        assert!(check_magic_bytes("text/plain", b"MZ\x90\x00").is_err());
+        assert!(check_magic_bytes("application/octet-stream", b"\x7FELF").is_ok());
+    }
+}
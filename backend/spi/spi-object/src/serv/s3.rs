@@ -2,18 +2,29 @@ pub mod object_s3_initializer;
 pub mod object_s3_obj_serv;
 
 use std::collections::HashMap;
+use std::path::Path;
 
 use bios_basic::spi::{dto::spi_bs_dto::SpiBsCertResp, serv::spi_bs_serv::SpiBsServ, spi_funs::SpiBsInst, spi_initializer::common};
 use itertools::Itertools;
 use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
-    futures::future::join_all,
-    os::os_client::TardisOSClient,
-    web::poem::http::{HeaderMap, HeaderValue},
+    chrono::{DateTime, Utc},
+    futures::{future::join_all, StreamExt},
+    os::{
+        os_client::TardisOSClient,
+        serde_types::{AbortIncompleteMultipartUpload, BucketLifecycleConfiguration, Expiration, LifecycleFilter, LifecycleRule},
+    },
+    tokio::fs::File,
+    web::poem::{
+        http::{HeaderMap, HeaderValue},
+        Body,
+    },
     TardisFunsInst,
 };
 
-use crate::dto::object_dto::ObjectObjPresignKind;
+use crate::dto::object_dto::{ObjectLifecycleRule, ObjectListResp, ObjectMetaResp, ObjectObjPresignKind};
+use crate::object_config::ObjectConfig;
+use crate::object_constants;
 
 pub trait S3 {
     ///
@@ -159,7 +170,9 @@ pub trait S3 {
         let bs_inst = inst.inst::<TardisOSClient>();
         let client = bs_inst.0;
         let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
-        let path = Self::rebuild_path(bucket_name.as_deref(), object_path, obj_exp, client).await?;
+        let Some(path) = Self::find_path(bucket_name.as_deref(), object_path, obj_exp, client).await? else {
+            return Ok(false);
+        };
         client.object_exist(&path, bucket_name.as_deref()).await
     }
 
@@ -278,6 +291,382 @@ pub trait S3 {
         client.complete_multipart_upload(&path, upload_id, parts, bucket_name.as_deref()).await
     }
 
+    /// 列举对象，使用预签名的 ListObjectsV2 请求
+    /// List the objects, using a presigned ListObjectsV2 request
+    #[allow(clippy::too_many_arguments)]
+    async fn list_objects(
+        prefix: Option<&str>,
+        continuation_token: Option<&str>,
+        max_keys: u32,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectListResp> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        // 临时对象存放在生命周期规则的前缀下，返回时去掉该前缀
+        // temporary objects are stored under the prefix of the lifecycle rule, which is stripped in the result
+        let Some(base_path) = Self::find_path(bucket_name.as_deref(), "", obj_exp, client).await? else {
+            return Ok(ObjectListResp {
+                objects: vec![],
+                next_continuation_token: None,
+            });
+        };
+        let mut queries = HashMap::from([
+            ("list-type".to_string(), "2".to_string()),
+            ("max-keys".to_string(), max_keys.to_string()),
+            ("prefix".to_string(), format!("{base_path}{}", prefix.unwrap_or_default())),
+        ]);
+        if let Some(continuation_token) = continuation_token {
+            queries.insert("continuation-token".to_string(), continuation_token.to_string());
+        }
+        let url = client.object_get_url("/", object_constants::S3_INTERNAL_PRESIGN_EXP_SEC, bucket_name.as_deref(), Some(queries)).await?;
+        let resp = funs.web_client().get_to_str(&url, Vec::<(String, String)>::new()).await?;
+        let body = resp.body.unwrap_or_default();
+        if resp.code != 200 {
+            return Err(TardisError::custom(
+                &resp.code.to_string(),
+                &format!("List objects failed: {body}"),
+                &format!("{}-spi-object-s3-list-objects-error", resp.code),
+            ));
+        }
+        let objects = xml_elements(&body, "Contents")
+            .into_iter()
+            .filter_map(|content| {
+                let object_path = xml_element(content, "Key")?;
+                Some(ObjectMetaResp {
+                    object_path: object_path.strip_prefix(&base_path).unwrap_or(&object_path).to_string(),
+                    size: xml_element(content, "Size").and_then(|size| size.parse().ok()).unwrap_or_default(),
+                    etag: xml_element(content, "ETag").map(|etag| etag.trim_matches('"').to_string()),
+                    content_type: None,
+                    last_modified: xml_element(content, "LastModified").and_then(|time| DateTime::parse_from_rfc3339(&time).ok()).map(|time| time.with_timezone(&Utc)),
+                })
+            })
+            .collect();
+        let next_continuation_token = if xml_element(&body, "IsTruncated").is_some_and(|truncated| truncated == "true") {
+            xml_element(&body, "NextContinuationToken")
+        } else {
+            None
+        };
+        Ok(ObjectListResp { objects, next_continuation_token })
+    }
+
+    /// 获取对象元数据，客户端不支持HEAD请求，使用预签名的范围GET请求代替
+    /// Get the metadata of the object, the client does not support HEAD requests, so a presigned ranged GET request is used instead
+    #[allow(clippy::too_many_arguments)]
+    async fn head_object(
+        object_path: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<ObjectMetaResp> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let Some(path) = Self::find_path(bucket_name.as_deref(), object_path, obj_exp, client).await? else {
+            return Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-not-exist"));
+        };
+        let url = client.object_get_url(&path, object_constants::S3_INTERNAL_PRESIGN_EXP_SEC, bucket_name.as_deref(), None).await?;
+        let mut resp = funs.web_client().get_to_str(&url, vec![("Range".to_string(), "bytes=0-0".to_string())]).await?;
+        if resp.code == 416 {
+            // 空对象不支持范围请求
+            // ranged requests are not supported by empty objects
+            resp = funs.web_client().get_to_str(&url, Vec::<(String, String)>::new()).await?;
+        }
+        let header = |name: &str| resp.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.to_string());
+        let size = match resp.code {
+            206 => header("Content-Range").and_then(|range| range.rsplit_once('/').and_then(|(_, size)| size.parse().ok())).unwrap_or_default(),
+            200 => header("Content-Length").and_then(|size| size.parse().ok()).unwrap_or_else(|| resp.body.as_ref().map(|body| body.len() as u64).unwrap_or_default()),
+            404 => return Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-not-exist")),
+            code => {
+                return Err(TardisError::custom(
+                    &code.to_string(),
+                    &format!("Head object {object_path} failed: {}", resp.body.clone().unwrap_or_default()),
+                    &format!("{code}-spi-object-s3-head-object-error"),
+                ))
+            }
+        };
+        Ok(ObjectMetaResp {
+            object_path: object_path.to_string(),
+            size,
+            etag: header("ETag").map(|etag| etag.trim_matches('"').to_string()),
+            content_type: header("Content-Type"),
+            last_modified: header("Last-Modified").and_then(|time| DateTime::parse_from_rfc2822(&time).ok()).map(|time| time.with_timezone(&Utc)),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_object_tags(
+        object_path: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<HashMap<String, String>> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let Some(path) = Self::find_path(bucket_name.as_deref(), object_path, obj_exp, client).await? else {
+            return Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-not-exist"));
+        };
+        let queries = HashMap::from([("tagging".to_string(), "".to_string())]);
+        let url = client.object_get_url(&path, object_constants::S3_INTERNAL_PRESIGN_EXP_SEC, bucket_name.as_deref(), Some(queries)).await?;
+        let resp = funs.web_client().get_to_str(&url, Vec::<(String, String)>::new()).await?;
+        let body = resp.body.unwrap_or_default();
+        match resp.code {
+            200 => Ok(xml_elements(&body, "Tag").into_iter().filter_map(|tag| Some((xml_element(tag, "Key")?, xml_element(tag, "Value").unwrap_or_default()))).collect()),
+            404 => Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-not-exist")),
+            code => Err(TardisError::custom(
+                &code.to_string(),
+                &format!("Get tags of object {object_path} failed: {body}"),
+                &format!("{code}-spi-object-s3-get-tags-error"),
+            )),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn put_object_tags(
+        object_path: &str,
+        tags: HashMap<String, String>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let path = Self::rebuild_path(bucket_name.as_deref(), object_path, obj_exp, client).await?;
+        let queries = HashMap::from([("tagging".to_string(), "".to_string())]);
+        let url = client.object_create_url(&path, object_constants::S3_INTERNAL_PRESIGN_EXP_SEC, bucket_name.as_deref(), None, Some(queries)).await?;
+        let body = format!(
+            "<Tagging><TagSet>{}</TagSet></Tagging>",
+            tags.iter().sorted().map(|(k, v)| format!("<Tag><Key>{}</Key><Value>{}</Value></Tag>", xml_escape(k), xml_escape(v))).join("")
+        );
+        let resp = funs.web_client().put_str_to_str(&url, &body, vec![("Content-Type".to_string(), "application/xml".to_string())]).await?;
+        match resp.code {
+            200 | 204 => Ok(()),
+            404 => Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-not-exist")),
+            code => Err(TardisError::custom(
+                &code.to_string(),
+                &format!("Put tags of object {object_path} failed: {}", resp.body.unwrap_or_default()),
+                &format!("{code}-spi-object-s3-put-tags-error"),
+            )),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_lifecycle_rules(
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<Vec<ObjectLifecycleRule>> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        match client.get_lifecycle(bucket_name.as_deref()).await {
+            Ok(config) => Ok(config
+                .rules
+                .into_iter()
+                .map(|rule| ObjectLifecycleRule {
+                    id: rule.id.unwrap_or_default(),
+                    enabled: rule.status == *"Enabled",
+                    prefix: rule.filter.and_then(|filter| filter.prefix),
+                    expiration_days: rule.expiration.and_then(|exp| exp.days),
+                    abort_incomplete_multipart_upload_days: rule.abort_incomplete_multipart_upload.and_then(|abort| abort.days_after_initiation).map(|days| days as u32),
+                })
+                .collect()),
+            Err(e) if e.code == "404" => Ok(vec![]),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 替换用户的生命周期规则，临时对象自动创建的规则会被保留
+    /// s3不支持空的生命周期配置，规则不能为空
+    /// Replace the lifecycle rules of the user, the rules created automatically for the temporary objects are kept
+    /// s3 does not support empty lifecycle configurations, so the rules can not be empty
+    #[allow(clippy::too_many_arguments)]
+    async fn put_lifecycle_rules(
+        rules: Vec<ObjectLifecycleRule>,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        _funs: &TardisFunsInst,
+        _ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let bs_inst = inst.inst::<TardisOSClient>();
+        let client = bs_inst.0;
+        let bucket_name = Self::get_bucket_name(private, special, obj_exp, bucket, bs_id, inst);
+        let mut lifecycle_rules = match client.get_lifecycle(bucket_name.as_deref()).await {
+            Ok(config) => config.rules.into_iter().filter(|rule| is_obj_exp_rule(rule) && !rules.iter().any(|r| rule.id.as_ref() == Some(&r.id))).collect::<Vec<_>>(),
+            Err(e) if e.code == "404" => vec![],
+            Err(e) => return Err(e),
+        };
+        if rules.is_empty() && lifecycle_rules.is_empty() {
+            return Err(TardisError::bad_request(
+                "Lifecycle rules of the s3 bucket can not be empty",
+                "400-spi-object-lifecycle-rules-empty",
+            ));
+        }
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let mut builder = LifecycleRule::builder(if rule.enabled { "Enabled" } else { "Disabled" }).id(&rule.id).filter(LifecycleFilter::new(
+                    None,
+                    None,
+                    None,
+                    Some(rule.prefix.unwrap_or_default()),
+                    None,
+                ));
+                if let Some(days) = rule.expiration_days {
+                    builder = builder.expiration(Expiration::new(None, Some(days), None));
+                }
+                if let Some(days) = rule.abort_incomplete_multipart_upload_days {
+                    builder = builder.abort_incomplete_multipart_upload(AbortIncompleteMultipartUpload::new(Some(days as i32)));
+                }
+                builder.build()
+            })
+            .collect::<Vec<_>>();
+        lifecycle_rules.extend(rules);
+        client.put_lifecycle(bucket_name.as_deref(), BucketLifecycleConfiguration::new(lifecycle_rules)).await
+    }
+
+    /// 经由预签名URL以流的方式上传暂存文件中的对象内容，用于上传代理
+    /// Upload the object content in the spooled file as a stream by a presigned url, used by the upload proxy
+    #[allow(clippy::too_many_arguments)]
+    async fn put_object_content(
+        object_path: &str,
+        content_type: Option<&str>,
+        content_file: &Path,
+        size: u64,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<()> {
+        let exp_secs = funs.conf::<ObjectConfig>().proxy.presign_exp_sec;
+        let url = Self::presign_obj_url(
+            ObjectObjPresignKind::Upload,
+            object_path,
+            None,
+            None,
+            exp_secs,
+            private,
+            special,
+            obj_exp,
+            bs_id,
+            bucket,
+            funs,
+            ctx,
+            inst,
+        )
+        .await?;
+        let content =
+            File::open(content_file).await.map_err(|e| TardisError::internal_error(&format!("Upload object {object_path} failed: {e}"), "500-spi-object-s3-put-object-error"))?;
+        // 流式请求体须指明长度，s3不支持分块传输的上传
+        // the length of the streaming body must be specified, s3 does not support uploads with chunked transfer encoding
+        let mut req = funs.web_client().raw().put(&url).header("Content-Length", size.to_string()).body(content);
+        if let Some(content_type) = content_type {
+            req = req.header("Content-Type", content_type);
+        }
+        if let Some(obj_exp) = obj_exp {
+            req = req.header("x-obs-expires", obj_exp.to_string());
+        }
+        let resp = req.send().await.map_err(|e| TardisError::internal_error(&format!("Upload object {object_path} failed: {e}"), "500-spi-object-s3-put-object-error"))?;
+        if !resp.status().is_success() {
+            let code = resp.status().as_u16();
+            return Err(TardisError::custom(
+                &code.to_string(),
+                &format!("Upload object {object_path} failed: {}", resp.text().await.unwrap_or_default()),
+                &format!("{code}-spi-object-s3-put-object-error"),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 经由预签名URL以流的方式下载对象内容，返回内容及内容类型，用于下载代理
+    /// Download the object content as a stream by a presigned url, returns the content and the content type, used by the download proxy
+    #[allow(clippy::too_many_arguments)]
+    async fn get_object_content(
+        object_path: &str,
+        private: Option<bool>,
+        special: Option<bool>,
+        obj_exp: Option<u32>,
+        bs_id: Option<&str>,
+        bucket: Option<&str>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+        inst: &SpiBsInst,
+    ) -> TardisResult<(Body, Option<String>)> {
+        let exp_secs = funs.conf::<ObjectConfig>().proxy.presign_exp_sec;
+        let url = Self::presign_obj_url(
+            ObjectObjPresignKind::View,
+            object_path,
+            None,
+            None,
+            exp_secs,
+            private,
+            special,
+            obj_exp,
+            bs_id,
+            bucket,
+            funs,
+            ctx,
+            inst,
+        )
+        .await?;
+        let resp = funs
+            .web_client()
+            .raw()
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| TardisError::internal_error(&format!("Download object {object_path} failed: {e}"), "500-spi-object-s3-get-object-error"))?;
+        match resp.status().as_u16() {
+            200 => {
+                let content_type = resp.headers().get("Content-Type").and_then(|content_type| content_type.to_str().ok()).map(|content_type| content_type.to_string());
+                Ok((Body::from_bytes_stream(resp.bytes_stream().map(|chunk| chunk.map_err(std::io::Error::other))), content_type))
+            }
+            404 => Err(TardisError::not_found(&format!("Object {object_path} does not exist"), "404-spi-object-not-exist")),
+            code => Err(TardisError::custom(
+                &code.to_string(),
+                &format!("Download object {object_path} failed: {}", resp.text().await.unwrap_or_default()),
+                &format!("{code}-spi-object-s3-get-object-error"),
+            )),
+        }
+    }
+
     fn get_bucket_name(private: Option<bool>, special: Option<bool>, obj_exp: Option<u32>, bucket_name: Option<&str>, bs_id: Option<&str>, inst: &SpiBsInst) -> Option<String> {
         let bs_inst = inst.inst::<TardisOSClient>();
         common::get_isolation_flag_from_ext(bs_inst.1).map(|bucket_name_prefix| {
@@ -298,4 +687,64 @@ pub trait S3 {
     }
 
     async fn rebuild_path(bucket_name: Option<&str>, origin_path: &str, obj_exp: Option<u32>, client: &TardisOSClient) -> TardisResult<String>;
+
+    /// 只读地获取对象路径，不存在过期天数对应的生命周期规则时返回None，不会创建规则
+    /// Get the object path read-only, returns None when there is no lifecycle rule of the expiration days, the rule is not created
+    async fn find_path(bucket_name: Option<&str>, origin_path: &str, obj_exp: Option<u32>, client: &TardisOSClient) -> TardisResult<Option<String>> {
+        Self::rebuild_path(bucket_name, origin_path, obj_exp, client).await.map(Some)
+    }
+}
+
+/// 是否为临时对象自动创建的生命周期规则，其id为随机数且前缀为 `{id}/`
+/// Whether the lifecycle rule is created automatically for the temporary objects, whose id is a random number and prefix is `{id}/`
+fn is_obj_exp_rule(rule: &LifecycleRule) -> bool {
+    rule.id.as_ref().is_some_and(|id| {
+        !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) && rule.filter.as_ref().and_then(|filter| filter.prefix.as_ref()).is_some_and(|prefix| *prefix == format!("{id}/"))
+    })
+}
+
+/// 获取xml中所有指定名称元素的内容，仅用于解析s3的简单响应
+/// Get the content of all elements with the name in the xml, only used to parse the simple responses of s3
+fn xml_elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let (start_tag, end_tag) = (format!("<{name}>"), format!("</{name}>"));
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&start_tag) {
+        rest = &rest[start + start_tag.len()..];
+        let Some(end) = rest.find(&end_tag) else {
+            break;
+        };
+        elements.push(&rest[..end]);
+        rest = &rest[end + end_tag.len()..];
+    }
+    elements
+}
+
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    xml_elements(xml, name).first().map(|content| xml_unescape(content))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&#34;", "\"").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xml_elements() {
+        let xml = r#"<ListBucketResult><IsTruncated>true</IsTruncated><Contents><Key>a&amp;b.txt</Key><Size>3</Size></Contents><Contents><Key>c.txt</Key><Size>5</Size></Contents><NextContinuationToken>t1</NextContinuationToken></ListBucketResult>"#;
+        let contents = xml_elements(xml, "Contents");
+        assert_eq!(contents.len(), 2);
+        assert_eq!(xml_element(contents[0], "Key").unwrap(), "a&b.txt");
+        assert_eq!(xml_element(contents[1], "Size").unwrap(), "5");
+        assert_eq!(xml_element(xml, "NextContinuationToken").unwrap(), "t1");
+        assert!(xml_element(xml, "Prefix").is_none());
+        assert_eq!(xml_unescape(&xml_escape("<a href=\"x\">'&'</a>")), "<a href=\"x\">'&'</a>");
+    }
 }
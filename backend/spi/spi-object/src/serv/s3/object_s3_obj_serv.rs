@@ -14,12 +14,7 @@ impl super::S3 for S3Service {
             match resp {
                 Ok(config) => {
                     let mut rules = config.rules;
-                    let prefix = if let Some(is_have_prefix) = rules
-                        .iter()
-                        .filter(|r| r.status == *"Enabled" && r.expiration.clone().is_some_and(|exp| exp.days.is_some_and(|days| days == obj_exp)))
-                        .filter_map(|r| r.filter.clone())
-                        .find_map(|f| f.prefix)
-                    {
+                    let prefix = if let Some(is_have_prefix) = find_exp_prefix(&rules, obj_exp) {
                         is_have_prefix
                     } else {
                         let rand_id = tardis::rand::random::<usize>().to_string();
@@ -58,4 +53,26 @@ impl super::S3 for S3Service {
             Ok(origin_path.to_string())
         }
     }
+
+    async fn find_path(bucket_name: Option<&str>, origin_path: &str, obj_exp: Option<u32>, client: &TardisOSClient) -> TardisResult<Option<String>> {
+        let Some(obj_exp) = obj_exp else {
+            return Ok(Some(origin_path.to_string()));
+        };
+        match client.get_lifecycle(bucket_name).await {
+            Ok(config) => Ok(find_exp_prefix(&config.rules, obj_exp).map(|prefix| format!("{}{}", prefix, origin_path))),
+            Err(e) if e.code == "404" => Ok(None),
+            Err(e) => Err(TardisError::internal_error(&format!("Bucket {:?} get lifecycle failed", bucket_name), &format!("{:?}", e))),
+        }
+    }
+}
+
+/// 查找过期天数对应的自动创建的生命周期规则的前缀，不匹配桶上其他用途的规则
+/// Find the prefix of the automatically created lifecycle rule of the expiration days, the rules of the bucket for other purposes are not matched
+fn find_exp_prefix(rules: &[LifecycleRule], obj_exp: u32) -> Option<String> {
+    rules
+        .iter()
+        .filter(|r| super::is_obj_exp_rule(r))
+        .filter(|r| r.status == *"Enabled" && r.expiration.clone().is_some_and(|exp| exp.days.is_some_and(|days| days == obj_exp)))
+        .filter_map(|r| r.filter.clone())
+        .find_map(|f| f.prefix)
 }
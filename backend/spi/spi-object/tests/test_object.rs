@@ -14,7 +14,9 @@ use tardis::test::test_container::TardisTestContainer;
 use tardis::tokio::time::sleep;
 use tardis::web::web_resp::Void;
 use tardis::{testcontainers, tokio, TardisFuns};
+mod test_object_local;
 mod test_object_obj;
+mod test_object_proxy;
 
 #[tokio::test]
 async fn test_object() -> TardisResult<()> {
@@ -75,5 +77,28 @@ async fn init_data(minio_url: &str) -> TardisResult<()> {
 
     test_object_obj::test(&mut client).await?;
 
+    let local_kind_id = RbumKindServ::get_rbum_kind_id_by_code(object_constants::SPI_LOCAL_KIND_CODE, &funs).await?.unwrap();
+    let local_root = env::temp_dir().join(format!("bios-spi-object-{}", TardisFuns::field.nanoid()));
+    client.set_auth(&ctx)?;
+    let local_bs_id: String = client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-local".to_string()),
+                kind_id: TrimString(local_kind_id),
+                conn_uri: format!("file://{}", local_root.display()),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = client.put(&format!("/ci/manage/bs/{}/rel/app002", local_bs_id), &Void {}).await;
+
+    test_object_local::test(&mut client).await?;
+    test_object_proxy::test(&mut client).await?;
+
     Ok(())
 }
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::{json, Value};
+use tardis::web::web_resp::{TardisResp, Void};
+use tardis::TardisFuns;
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_object_local】");
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "app002".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "".to_string(),
+        ..Default::default()
+    })?;
+
+    // presigned upload and view
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=a/001.txt&exp_secs=300&private=true").await;
+    // the root directory of the storage is not exposed
+    assert!(!upload_url.contains("bios-spi-object-"));
+    let resp = TardisFuns::web_client().put_str_to_str(&upload_url, "hello", vec![]).await?;
+    assert_eq!(resp.code, 200);
+    assert!(client.get::<bool>("/ci/obj/object/exist?object_path=a/001.txt&private=true").await);
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=a/001.txt&private=false").await);
+
+    let view_url: String = client.get("/ci/obj/presign/view?object_path=a/001.txt&exp_secs=300&private=true").await;
+    let resp = TardisFuns::web_client().get_to_str(&view_url, vec![]).await?;
+    assert_eq!(resp.body.unwrap(), "hello");
+    // the signature does not authorize other operations or objects
+    let resp = TardisFuns::web_client().delete_to_void(&view_url, vec![]).await;
+    assert!(resp.is_err() || resp.unwrap().code != 200);
+    let resp = TardisFuns::web_client().get_to_str(&view_url.replace("001.txt", "002.txt"), vec![]).await?;
+    assert_ne!(resp.code, 200);
+    // paths escaping from the bucket are rejected
+    let resp: TardisResp<String> = client.get_resp("/ci/obj/presign/put?object_path=../001.txt&exp_secs=300&private=true").await;
+    assert_eq!(resp.code, "400-spi-object-local-invalid-path");
+
+    // copy
+    let _: Void = client
+        .post(
+            "/ci/obj/object/copy",
+            &json!({
+                "from": "a/001.txt",
+                "to": "b/001.txt",
+                "private": true,
+            }),
+        )
+        .await;
+    let view_urls: HashMap<String, String> = client
+        .post(
+            "/ci/obj/presign/batch_view",
+            &json!({
+                "object_path": ["b/001.txt"],
+                "expire_sec": 300,
+                "private": true,
+            }),
+        )
+        .await;
+    let resp = TardisFuns::web_client().get_to_str(&view_urls["b/001.txt"], vec![]).await?;
+    assert_eq!(resp.body.unwrap(), "hello");
+    let resp: TardisResp<Void> = client.post_resp("/ci/obj/object/copy", &json!({"from": "b/001.txt", "to": "/b/001.txt", "private": true})).await;
+    assert_eq!(resp.code, "400-spi-object-local-copy-to-itself");
+    let resp = TardisFuns::web_client().get_to_str(&view_urls["b/001.txt"], vec![]).await?;
+    assert_eq!(resp.body.unwrap(), "hello");
+
+    // multipart upload
+    let upload_id: String = client
+        .post(
+            "/ci/obj/multi_upload/initiate_multipart_upload",
+            &json!({
+                "object_path": "c/001.txt",
+                "private": true,
+            }),
+        )
+        .await;
+    let part_urls: Vec<String> = client
+        .post(
+            "/ci/obj/multi_upload/batch_build_create_presign_url",
+            &json!({
+                "object_path": "c/001.txt",
+                "upload_id": upload_id,
+                "part_number": 2,
+                "expire_sec": 300,
+                "private": true,
+            }),
+        )
+        .await;
+    assert_eq!(part_urls.len(), 2);
+    let mut etags = vec![];
+    for (part_url, content) in part_urls.iter().zip(["hello ", "world"]) {
+        let resp = TardisFuns::web_client().put_str_to_str(part_url, content, vec![]).await?;
+        let resp = TardisFuns::json.str_to_obj::<TardisResp<String>>(&resp.body.unwrap())?;
+        etags.push(resp.data.unwrap());
+    }
+    let resp: TardisResp<Void> = client
+        .post_resp(
+            "/ci/obj/multi_upload/complete_multipart_upload",
+            &json!({
+                "object_path": "c/001.txt",
+                "upload_id": upload_id,
+                "parts": [etags[1], etags[0]],
+                "private": true,
+            }),
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-object-local-part-etag-mismatch");
+    let _: Void = client
+        .post(
+            "/ci/obj/multi_upload/complete_multipart_upload",
+            &json!({
+                "object_path": "c/001.txt",
+                "upload_id": upload_id,
+                "parts": etags,
+                "private": true,
+            }),
+        )
+        .await;
+    let view_url: String = client.get("/ci/obj/presign/view?object_path=c/001.txt&exp_secs=300&private=true").await;
+    let resp = TardisFuns::web_client().get_to_str(&view_url, vec![]).await?;
+    assert_eq!(resp.body.unwrap(), "hello world");
+
+    // temporary objects are stored in the tamp bucket
+    let tamp_upload_url: String = client.get("/ci/obj/presign/put?object_path=tamp/001.txt&exp_secs=300&obj_exp=1").await;
+    TardisFuns::web_client().put_str_to_str(&tamp_upload_url, "tamp", vec![]).await?;
+    assert!(client.get::<bool>("/ci/obj/object/exist?object_path=tamp/001.txt&obj_exp=1").await);
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=tamp/001.txt").await);
+
+    // list
+    let list: Value = client.get("/ci/obj/object/list?private=true&max_keys=2").await;
+    let paths = list["objects"].as_array().unwrap().iter().map(|o| o["object_path"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+    assert_eq!(paths, vec!["a/001.txt", "b/001.txt"]);
+    let token = list["next_continuation_token"].as_str().unwrap();
+    let list: Value = client.get(&format!("/ci/obj/object/list?private=true&max_keys=2&continuation_token={token}")).await;
+    assert_eq!(list["objects"][0]["object_path"], "c/001.txt");
+    assert_eq!(list["objects"][0]["size"], 11);
+    assert!(list["next_continuation_token"].is_null());
+    let list: Value = client.get("/ci/obj/object/list?private=true&prefix=b/").await;
+    assert_eq!(list["objects"].as_array().unwrap().len(), 1);
+    let list: Value = client.get("/ci/obj/object/list?obj_exp=1").await;
+    assert_eq!(list["objects"][0]["object_path"], "tamp/001.txt");
+
+    // head
+    let upload_url: String = client.get("/ci/obj/presign/put?object_path=d/001.json&exp_secs=300&private=true").await;
+    TardisFuns::web_client().put_str_to_str(&upload_url, "{}", vec![("Content-Type".to_string(), "application/json".to_string())]).await?;
+    let head: Value = client.get("/ci/obj/object/head?object_path=d/001.json&private=true").await;
+    assert_eq!(head["size"], 2);
+    assert_eq!(head["content_type"], "application/json");
+    assert_eq!(head["etag"], TardisFuns::crypto.digest.md5("{}")?);
+    assert!(!head["last_modified"].is_null());
+    let resp: TardisResp<Value> = client.get_resp("/ci/obj/object/head?object_path=d/002.json&private=true").await;
+    assert_eq!(resp.code, "404-spi-object-local-not-exist");
+
+    // tags
+    let _: Void = client
+        .put(
+            "/ci/obj/object/tags",
+            &json!({
+                "object_path": "d/001.json",
+                "tags": {"kind": "conf", "owner": "u001"},
+                "private": true,
+            }),
+        )
+        .await;
+    let tags: HashMap<String, String> = client.get("/ci/obj/object/tags?object_path=d/001.json&private=true").await;
+    assert_eq!(tags.len(), 2);
+    assert_eq!(tags["owner"], "u001");
+    // tags are kept by copy and cleared by overwriting
+    let _: Void = client.post("/ci/obj/object/copy", &json!({"from": "d/001.json", "to": "d/002.json", "private": true})).await;
+    let tags: HashMap<String, String> = client.get("/ci/obj/object/tags?object_path=d/002.json&private=true").await;
+    assert_eq!(tags["kind"], "conf");
+    TardisFuns::web_client().put_str_to_str(&upload_url, "{}", vec![]).await?;
+    let tags: HashMap<String, String> = client.get("/ci/obj/object/tags?object_path=d/001.json&private=true").await;
+    assert!(tags.is_empty());
+
+    // lifecycle
+    let rules: Vec<Value> = client.get("/ci/obj/lifecycle?private=true").await;
+    assert!(rules.is_empty());
+    let resp: TardisResp<Void> = client
+        .put_resp(
+            "/ci/obj/lifecycle",
+            &json!({
+                "rules": [{"id": "r1", "enabled": true, "prefix": "d/"}],
+                "private": true,
+            }),
+        )
+        .await;
+    assert_eq!(resp.code, "400-spi-object-lifecycle-rule-no-action");
+    let _: Void = client
+        .put(
+            "/ci/obj/lifecycle",
+            &json!({
+                "rules": [
+                    {"id": "r1", "enabled": true, "prefix": "d/", "expiration_days": 30},
+                    {"id": "r2", "enabled": false, "abort_incomplete_multipart_upload_days": 3},
+                ],
+                "private": true,
+            }),
+        )
+        .await;
+    let rules: Vec<Value> = client.get("/ci/obj/lifecycle?private=true").await;
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["expiration_days"], 30);
+    assert_eq!(rules[1]["enabled"], false);
+    let rules: Vec<Value> = client.get("/ci/obj/lifecycle?private=false").await;
+    assert!(rules.is_empty());
+    let _: Void = client.put("/ci/obj/lifecycle", &json!({"rules": [], "private": true})).await;
+    let rules: Vec<Value> = client.get("/ci/obj/lifecycle?private=true").await;
+    assert!(rules.is_empty());
+
+    // delete
+    client.delete("/ci/obj/object?object_path=a/001.txt&private=true").await;
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=a/001.txt&private=true").await);
+    let delete_url: String = client.get("/ci/obj/presign/delete?object_path=b/001.txt&exp_secs=300&private=true").await;
+    TardisFuns::web_client().delete_to_void(&delete_url, vec![]).await?;
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=b/001.txt&private=true").await);
+
+    Ok(())
+}
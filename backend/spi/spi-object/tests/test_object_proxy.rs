@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use bios_basic::test::test_http_client::TestHttpClient;
+use bios_spi_object::{register_scanner, ObjectScanReq, ObjectScanResult, ObjectScanner};
+use tardis::async_trait::async_trait;
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::log::info;
+use tardis::serde_json::Value;
+use tardis::web::web_resp::TardisResp;
+use tardis::TardisFuns;
+
+struct TestScanner;
+
+#[async_trait]
+impl ObjectScanner for TestScanner {
+    fn name(&self) -> &str {
+        "test"
+    }
+
+    async fn scan(&self, req: &ObjectScanReq<'_>, _ctx: &TardisContext) -> TardisResult<ObjectScanResult> {
+        let content = tardis::tokio::fs::read(req.content_file).await?;
+        let infected = content.windows(5).any(|window| window == b"EICAR");
+        Ok(ObjectScanResult {
+            passed: !infected,
+            reason: infected.then(|| "test signature found".to_string()),
+        })
+    }
+}
+
+pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_object_proxy】");
+    register_scanner(Arc::new(TestScanner));
+    // uses the local backend related to app002, see test_object_local
+    let upload = |object_path: &str, content_type: &str, content: Vec<u8>| {
+        let url = format!("https://127.0.0.1:8080/spi-object/ci/obj/proxy/object?object_path={object_path}&private=true");
+        let content_type = content_type.to_string();
+        let ctx = client.context().clone();
+        async move {
+            let resp = TardisFuns::web_client()
+                .raw()
+                .put(url)
+                .header(
+                    &TardisFuns::fw_config().web_server().context_conf.context_header_name,
+                    TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(&ctx)?),
+                )
+                .header("Content-Type", content_type)
+                .body(content)
+                .send()
+                .await
+                .expect("upload through the proxy failed");
+            TardisFuns::json.str_to_obj::<TardisResp<Value>>(&resp.text().await.expect("read the response failed"))
+        }
+    };
+
+    // validation
+    let resp = upload("proxy/001.html", "text/html", b"<html></html>".to_vec()).await?;
+    assert_eq!(resp.code, "400-spi-object-proxy-content-type-denied");
+    let resp = upload("proxy/001.png", "image/png", b"GIF89a0000000000".to_vec()).await?;
+    assert_eq!(resp.code, "400-spi-object-proxy-magic-bytes-mismatch");
+    let resp = upload("proxy/001.txt", "text/plain", b"MZ\x90\x00".to_vec()).await?;
+    assert_eq!(resp.code, "400-spi-object-proxy-magic-bytes-mismatch");
+    let resp = upload("proxy/001.txt", "text/plain", vec![b'a'; 2048]).await?;
+    assert_eq!(resp.code, "413-spi-object-proxy-too-large");
+    let resp = upload("proxy/001.txt", "text/plain", b"hello EICAR".to_vec()).await?;
+    assert_eq!(resp.code, "400-spi-object-proxy-scan-rejected");
+    assert!(!client.get::<bool>("/ci/obj/object/exist?object_path=proxy/001.txt&private=true").await);
+
+    // upload and download
+    let png = b"\x89PNG\r\n\x1a\n0000".to_vec();
+    let resp = upload("proxy/001.png", "image/png; charset=binary", png.clone()).await?;
+    let data = resp.data.unwrap();
+    assert_eq!(data["size"], png.len());
+    assert_eq!(data["content_type"], "image/png");
+    assert_eq!(data["md5"], TardisFuns::crypto.digest.md5(&png)?);
+    assert_eq!(data["sha256"], TardisFuns::crypto.digest.sha256(&png)?);
+    let head: Value = client.get("/ci/obj/object/head?object_path=proxy/001.png&private=true").await;
+    assert_eq!(head["content_type"], "image/png");
+
+    let resp = TardisFuns::web_client()
+        .raw()
+        .get("https://127.0.0.1:8080/spi-object/ci/obj/proxy/object?object_path=proxy/001.png&private=true")
+        .header(
+            &TardisFuns::fw_config().web_server().context_conf.context_header_name,
+            TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(client.context())?),
+        )
+        .send()
+        .await
+        .expect("download through the proxy failed");
+    assert_eq!(resp.headers().get("Content-Type").unwrap(), "image/png");
+    assert_eq!(resp.bytes().await.unwrap().to_vec(), png);
+
+    Ok(())
+}
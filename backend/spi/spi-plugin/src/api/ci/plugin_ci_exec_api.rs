@@ -3,11 +3,12 @@ use tardis::web::context_extractor::TardisContextExtractor;
 use tardis::web::poem::web::Json;
 use tardis::web::poem::Request;
 use tardis::web::poem_openapi;
-use tardis::web::poem_openapi::param::Path;
-use tardis::web::web_resp::{TardisApiResult, TardisResp};
+use tardis::web::poem_openapi::param::{Path, Query};
+use tardis::web::web_resp::{TardisApiResult, TardisPage, TardisResp};
 
-use crate::dto::plugin_exec_dto::{PluginExecReq, PluginExecResp};
+use crate::dto::plugin_exec_dto::{PluginExecHistoryResp, PluginExecReq, PluginExecResp, PluginExecTaskResp};
 use crate::serv::plugin_exec_serv::PluginExecServ;
+use crate::serv::plugin_exec_task_serv::PluginExecTaskServ;
 #[derive(Clone)]
 
 pub struct PluginExecApi;
@@ -31,6 +32,43 @@ impl PluginExecApi {
         })
     }
 
+    /// Put Plugin exec asynchronously
+    ///
+    /// 插件异步执行，返回任务 id
+    #[oai(path = "/:kind_code/api/:api_code/exec/async", method = "put")]
+    async fn plugin_exec_async(&self, kind_code: Path<String>, api_code: Path<String>, exec_req: Json<PluginExecReq>, ctx: TardisContextExtractor) -> TardisApiResult<String> {
+        let funs = crate::get_tardis_inst();
+        let task_id = PluginExecTaskServ::exec_async(&kind_code.0, &api_code.0, exec_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(task_id)
+    }
+
+    /// Get Plugin exec task
+    ///
+    /// 查询插件异步执行任务
+    #[oai(path = "/exec/task/:task_id", method = "get")]
+    async fn get_exec_task(&self, task_id: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<PluginExecTaskResp> {
+        let funs = crate::get_tardis_inst();
+        let result = PluginExecTaskServ::get_task(&task_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Find Plugin exec history by rel id
+    ///
+    /// 根据绑定 relId 查询插件执行记录（仅记录 save_message 为 true 的接口）
+    #[oai(path = "/exec/history/:rel_id", method = "get")]
+    async fn find_exec_history(
+        &self,
+        rel_id: Path<String>,
+        api_code: Query<Option<String>>,
+        page_number: Query<u32>,
+        page_size: Query<u16>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<TardisPage<PluginExecHistoryResp>> {
+        let funs = crate::get_tardis_inst();
+        let result = PluginExecServ::find_exec_history(&rel_id.0, api_code.0, page_number.0, page_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
     /// Put Plugin exec
     ///
     /// 测试插件执行
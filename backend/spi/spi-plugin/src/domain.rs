@@ -1 +1,2 @@
 pub mod plugin_api;
+pub mod plugin_exec_task;
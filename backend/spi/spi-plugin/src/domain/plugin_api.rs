@@ -17,6 +17,8 @@ pub struct Model {
     pub http_method: String,
     pub ext: String,
     pub save_message: bool,
+    pub req_mapping: Option<String>,
+    pub resp_mapping: Option<String>,
 
     pub own_paths: String,
 }
@@ -42,6 +44,8 @@ impl TardisActiveModel for ActiveModel {
             .col(ColumnDef::new(Column::HttpMethod).not_null().string())
             .col(ColumnDef::new(Column::Ext).not_null().string())
             .col(ColumnDef::new(Column::SaveMessage).not_null().boolean())
+            .col(ColumnDef::new(Column::ReqMapping).null().text())
+            .col(ColumnDef::new(Column::RespMapping).null().text())
             .col(ColumnDef::new(Column::OwnPaths).not_null().string());
         if db == DatabaseBackend::MySql {
             builder.engine("InnoDB").character_set("utf8mb4").collate("utf8mb4_0900_as_cs");
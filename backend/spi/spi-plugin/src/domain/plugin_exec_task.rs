@@ -0,0 +1,84 @@
+use tardis::basic::dto::TardisContext;
+use tardis::chrono::{self, Utc};
+use tardis::db::reldb_client::TardisActiveModel;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::sea_query::{ColumnDef, Index, IndexCreateStatement, Table, TableCreateStatement};
+use tardis::db::sea_orm::*;
+
+/// Asynchronous plugin exec task
+///
+/// 插件异步执行任务
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "plugin_exec_task")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub kind_code: String,
+    pub api_code: String,
+    pub rel_id: String,
+    // JSON 格式的执行请求，用于重新执行中断的任务
+    pub req: String,
+    // JSON 格式的请求上下文（仅 own_paths、ak 及 owner），用于重新执行中断的任务
+    pub ctx: String,
+    pub callback: String,
+    pub status: String,
+    pub code: Option<i32>,
+    // JSON 格式的响应头
+    pub headers: Option<String>,
+    pub body: Option<String>,
+    pub error: Option<String>,
+    // 结果投递方式：callback / event / none
+    pub delivery: String,
+    pub delivered: bool,
+    pub delivery_attempts: i32,
+    pub create_time: chrono::DateTime<Utc>,
+    pub update_time: chrono::DateTime<Utc>,
+
+    pub own_paths: String,
+}
+
+impl TardisActiveModel for ActiveModel {
+    fn fill_ctx(&mut self, ctx: &TardisContext, is_insert: bool) {
+        if is_insert {
+            self.own_paths = Set(ctx.own_paths.to_string());
+        }
+    }
+
+    fn create_table_statement(db: DbBackend) -> TableCreateStatement {
+        let mut builder = Table::create();
+        builder
+            .table(Entity.table_ref())
+            .if_not_exists()
+            .col(ColumnDef::new(Column::Id).not_null().string().primary_key())
+            .col(ColumnDef::new(Column::KindCode).not_null().string())
+            .col(ColumnDef::new(Column::ApiCode).not_null().string())
+            .col(ColumnDef::new(Column::RelId).not_null().string())
+            .col(ColumnDef::new(Column::Req).not_null().text())
+            .col(ColumnDef::new(Column::Ctx).not_null().text())
+            .col(ColumnDef::new(Column::Callback).not_null().string())
+            .col(ColumnDef::new(Column::Status).not_null().string())
+            .col(ColumnDef::new(Column::Code).null().integer())
+            .col(ColumnDef::new(Column::Headers).null().text())
+            .col(ColumnDef::new(Column::Body).null().text())
+            .col(ColumnDef::new(Column::Error).null().text())
+            .col(ColumnDef::new(Column::Delivery).not_null().string())
+            .col(ColumnDef::new(Column::Delivered).not_null().boolean())
+            .col(ColumnDef::new(Column::DeliveryAttempts).not_null().integer())
+            .col(ColumnDef::new(Column::CreateTime).not_null().timestamp_with_time_zone())
+            .col(ColumnDef::new(Column::UpdateTime).not_null().timestamp_with_time_zone())
+            .col(ColumnDef::new(Column::OwnPaths).not_null().string());
+        if db == DatabaseBackend::MySql {
+            builder.engine("InnoDB").character_set("utf8mb4").collate("utf8mb4_0900_as_cs");
+        }
+        builder.to_owned()
+    }
+
+    fn create_index_statement() -> Vec<IndexCreateStatement> {
+        vec![Index::create().name(&format!("idx-{}-status_update_time", Entity.table_name())).table(Entity).col(Column::Status).col(Column::UpdateTime).to_owned()]
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
@@ -1,10 +1,14 @@
+use std::collections::HashMap;
+
 use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumItemFilterFetcher};
 use serde::{Deserialize, Serialize};
 use tardis::{
     basic::field::TrimString,
     chrono::{self, Utc},
-    db::sea_orm::{self},
+    db::sea_orm::{self, DbErr, QueryResult, TryGetError, TryGetable},
+    serde_json::Value,
     web::poem_openapi,
+    TardisFuns,
 };
 
 use crate::plugin_enumeration::PluginApiMethodKind;
@@ -19,12 +23,18 @@ pub struct PluginApiAddOrModifyReq {
     pub kind_id: TrimString,
     pub callback: String,
     pub content_type: String,
+    // 执行超时时间（毫秒），小于等于 0 时使用 exec.default_timeout_ms
     pub timeout: i32,
     pub ext: String,
     pub http_method: PluginApiMethodKind,
     pub kind: String,
     pub path_and_query: String,
+    // 是否将请求/响应记录至 spi-log
     pub save_message: bool,
+    // 请求体映射模板
+    pub req_mapping: Option<PluginApiMapping>,
+    // 响应体映射模板，仅对 2xx 的 JSON 响应生效
+    pub resp_mapping: Option<PluginApiMapping>,
 }
 
 #[derive(poem_openapi::Object, sea_orm::FromQueryResult, Serialize, Deserialize, Debug)]
@@ -39,6 +49,8 @@ pub struct PluginApiSummaryResp {
     pub kind: String,
     pub path_and_query: String,
     pub save_message: bool,
+    pub req_mapping: Option<PluginApiMapping>,
+    pub resp_mapping: Option<PluginApiMapping>,
 }
 
 #[derive(poem_openapi::Object, sea_orm::FromQueryResult, Serialize, Deserialize, Debug)]
@@ -54,6 +66,40 @@ pub struct PluginApiDetailResp {
     pub kind: String,
     pub path_and_query: String,
     pub save_message: bool,
+    pub req_mapping: Option<PluginApiMapping>,
+    pub resp_mapping: Option<PluginApiMapping>,
+}
+
+/// Mapping template of plugin api request/response body
+///
+/// 插件接口请求/响应体映射模板，按 root -> fields -> renames -> inject 的顺序执行，路径均为 JSON Pointer（如 `/data/name`）
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PluginApiMapping {
+    // 源 JSON 中作为映射基础的节点，为空表示整个 JSON
+    #[oai(default)]
+    pub root: Option<String>,
+    // 字段提取：目标路径 -> 源路径（相对 root），不为空时仅输出提取的字段，源路径不存在时忽略
+    #[oai(default)]
+    pub fields: HashMap<String, String>,
+    // 字段重命名：原路径 -> 新路径
+    #[oai(default)]
+    pub renames: HashMap<String, String>,
+    // 静态注入：目标路径 -> 值，字符串中的 `{{name}}` 会替换为绑定的 rel 属性值及 rel_id、kind_code、api_code
+    #[oai(default)]
+    pub inject: HashMap<String, Value>,
+}
+
+impl TryGetable for PluginApiMapping {
+    fn try_get(res: &QueryResult, pre: &str, col: &str) -> Result<Self, TryGetError> {
+        let s = String::try_get(res, pre, col)?;
+        TardisFuns::json.str_to_obj(&s).map_err(|_| TryGetError::DbErr(DbErr::Type(format!("{pre}:{col} is not a valid mapping"))))
+    }
+
+    fn try_get_by<I: sea_orm::ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let s = String::try_get_by(res, index)?;
+        TardisFuns::json.str_to_obj(&s).map_err(|_| TryGetError::DbErr(DbErr::Type("invalid mapping".to_string())))
+    }
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone, Default)]
@@ -1,7 +1,13 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use tardis::{serde_json::Value, web::poem_openapi};
+use tardis::{
+    chrono::{DateTime, Utc},
+    serde_json::Value,
+    web::poem_openapi,
+};
+
+use crate::plugin_enumeration::PluginExecTaskStatusKind;
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct PluginExecReq {
@@ -21,3 +27,38 @@ pub struct PluginExecResp {
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
 }
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct PluginExecHistoryResp {
+    pub id: String,
+    pub rel_id: String,
+    pub kind_code: String,
+    pub api_code: String,
+    // 最后一次尝试的响应状态码，请求失败（如超时）时为空
+    pub code: Option<u16>,
+    pub success: bool,
+    pub attempts: u32,
+    pub elapsed_ms: u64,
+    // 脱敏后的请求/响应记录
+    pub content: Value,
+    pub owner: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct PluginExecTaskResp {
+    pub id: String,
+    pub kind_code: String,
+    pub api_code: String,
+    pub rel_id: String,
+    pub status: PluginExecTaskStatusKind,
+    pub code: Option<u16>,
+    pub headers: Option<HashMap<String, String>>,
+    pub body: Option<String>,
+    pub error: Option<String>,
+    // 结果投递方式：callback / event / none
+    pub delivery: String,
+    pub delivered: bool,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
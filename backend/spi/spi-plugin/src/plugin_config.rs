@@ -10,6 +10,9 @@ pub struct PluginConfig {
     pub kv_plugin_prefix: String,
     pub use_mq: bool,
     pub mq_topic_event_plugin_delete: String,
+    // 异步执行结果事件主题，插件接口未配置 callback 且 use_mq 为 true 时发布
+    pub mq_topic_event_plugin_exec_task: String,
+    pub exec: PluginExecConfig,
 }
 
 impl Default for PluginConfig {
@@ -19,7 +22,112 @@ impl Default for PluginConfig {
             invoke: InvokeConfig::default(),
             kv_plugin_prefix: "spi_plugin".to_string(),
             mq_topic_event_plugin_delete: "plugin:delete".to_string(),
+            mq_topic_event_plugin_exec_task: "plugin:exec_task".to_string(),
             use_mq: true,
+            exec: PluginExecConfig::default(),
+        }
+    }
+}
+
+/// Plugin exec configuration
+///
+/// 插件执行配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PluginExecConfig {
+    /// Timeout (milliseconds) used when the plugin api does not set one, 0 means no timeout
+    ///
+    /// 插件接口未设置超时时使用的超时时间（毫秒），0 表示不限制
+    pub default_timeout_ms: u64,
+    /// Max retries for idempotent methods, 0 means no retry
+    ///
+    /// 幂等方法的最大重试次数，0 表示不重试
+    pub max_retries: u32,
+    /// Initial backoff (milliseconds), doubled on every retry
+    ///
+    /// 初始退避时间（毫秒），每次重试翻倍
+    pub retry_backoff_ms: u64,
+    /// Max backoff (milliseconds)
+    ///
+    /// 最大退避时间（毫秒）
+    pub retry_backoff_max_ms: u64,
+    /// Response status codes that trigger a retry
+    ///
+    /// 触发重试的响应状态码
+    pub retry_on_status: Vec<u16>,
+    /// Methods that are allowed to retry
+    ///
+    /// 允许重试的方法
+    pub retry_methods: Vec<String>,
+    /// spi-log tag used to record exchanges of apis with `save_message`, empty means not recording even if `save_message` is set
+    ///
+    /// 记录 `save_message` 接口请求/响应的 spi-log 标签，为空表示即使设置了 `save_message` 也不记录
+    pub log_tag: String,
+    /// Headers whose values are masked in the log, case insensitive
+    ///
+    /// 日志中需要脱敏的请求/响应头，大小写不敏感
+    pub redacted_headers: Vec<String>,
+    /// Query parameters whose values are masked in the logged url, case insensitive
+    ///
+    /// 日志中url需要脱敏的查询参数，大小写不敏感
+    pub redacted_query_params: Vec<String>,
+    /// Max length of logged request/response bodies
+    ///
+    /// 日志中请求/响应体的最大长度
+    pub max_logged_body_len: usize,
+    /// Max retries of delivering the result of an async exec task, with the same backoff as the exec retries
+    ///
+    /// 异步执行任务结果投递的最大重试次数，退避时间同执行重试
+    pub task_delivery_max_retries: u32,
+    /// Seconds after which an unfinished async exec task that is not updated is considered interrupted (e.g. the node stopped) and re-queued,
+    /// the running tasks refresh their update time at a third of it
+    ///
+    /// 未完成且超过该秒数未更新的异步执行任务视为已中断（如节点停止）并重新执行，执行中的任务每隔其三分之一时间刷新更新时间
+    pub task_stale_sec: u64,
+    /// Interval (seconds) of re-queuing the interrupted async exec tasks, the first check runs at startup, 0 means no re-queuing
+    ///
+    /// 重新执行中断的异步执行任务的检查间隔（秒），启动时即检查一次，0 表示不重新执行
+    pub task_requeue_interval_sec: u64,
+    /// Seconds to keep the finished async exec tasks, purged along with the re-queuing check, 0 means keeping forever
+    ///
+    /// 已结束的异步执行任务的保留时间（秒），随重新执行的检查一并清理，0 表示永久保留
+    pub task_retention_sec: u64,
+}
+
+impl Default for PluginExecConfig {
+    fn default() -> Self {
+        PluginExecConfig {
+            default_timeout_ms: 0,
+            max_retries: 0,
+            retry_backoff_ms: 500,
+            retry_backoff_max_ms: 10000,
+            retry_on_status: vec![502, 503, 504],
+            retry_methods: vec!["GET".to_string(), "PUT".to_string(), "DELETE".to_string()],
+            log_tag: "spi_plugin_exec".to_string(),
+            redacted_headers: vec![
+                "Authorization".to_string(),
+                "Proxy-Authorization".to_string(),
+                "Cookie".to_string(),
+                "Set-Cookie".to_string(),
+                "Tardis-Context".to_string(),
+                "X-Api-Key".to_string(),
+            ],
+            redacted_query_params: vec![
+                "access_token".to_string(),
+                "token".to_string(),
+                "api_key".to_string(),
+                "apikey".to_string(),
+                "secret".to_string(),
+                "sk".to_string(),
+                "password".to_string(),
+                "signature".to_string(),
+                "sign".to_string(),
+            ],
+            max_logged_body_len: 4096,
+            task_delivery_max_retries: 5,
+            task_stale_sec: 600,
+            task_requeue_interval_sec: 60,
+            task_retention_sec: 7 * 24 * 60 * 60,
         }
     }
 }
@@ -1,2 +1,3 @@
 pub const DOMAIN_CODE: &str = "spi-plugin";
 pub const KIND_MODULE_CODE: &str = "spi-plugin";
+pub const EXEC_LOG_KIND: &str = "plugin_exec";
@@ -32,3 +32,11 @@ impl TryGetable for PluginApiMethodKind {
         panic!("not implemented")
     }
 }
+
+#[derive(Display, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, poem_openapi::Enum, strum::EnumString)]
+pub enum PluginExecTaskStatusKind {
+    Pending,
+    Running,
+    Success,
+    Failed,
+}
@@ -1,12 +1,22 @@
 use bios_basic::{rbum::serv::rbum_domain_serv::RbumDomainServ, spi::spi_initializer};
 use bios_sdk_invoke::invoke_initializer;
-use tardis::{basic::result::TardisResult, db::reldb_client::TardisActiveModel, log::info, web::web_server::TardisWebServer, TardisFuns, TardisFunsInst};
+use tardis::{
+    basic::result::TardisResult,
+    db::{
+        reldb_client::TardisActiveModel,
+        sea_orm::{DatabaseBackend, EntityName, Value},
+    },
+    log::info,
+    web::web_server::TardisWebServer,
+    TardisFuns, TardisFunsInst,
+};
 
 use crate::{
     api::ci::{plugin_ci_api_api, plugin_ci_bs_api, plugin_ci_exec_api, plugin_ci_kind_api},
-    domain::plugin_api,
+    domain::{plugin_api, plugin_exec_task},
     plugin_config::PluginConfig,
     plugin_constants::DOMAIN_CODE,
+    serv::plugin_exec_task_serv::PluginExecTaskServ,
 };
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
@@ -19,12 +29,35 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     spi_initializer::init(DOMAIN_CODE, &funs).await?;
     funs.commit().await?;
     init_api(web_server).await?;
+    let requeue_interval_sec = funs.conf::<PluginConfig>().exec.task_requeue_interval_sec;
+    if requeue_interval_sec > 0 {
+        PluginExecTaskServ::start_task_requeuer(requeue_interval_sec);
+    }
     info!("[BIOS.Plugin] Module initialized");
     Ok(())
 }
 
 async fn init_db(domain_code: String, funs: &TardisFunsInst) -> TardisResult<()> {
+    // The exec task table is created if not exists, so that it is also available for upgraded deployments
+    funs.db()
+        .init(plugin_exec_task::ActiveModel::init(
+            TardisFuns::reldb().backend(),
+            None,
+            TardisFuns::reldb().compatible_type(),
+        ))
+        .await?;
     if RbumDomainServ::get_rbum_domain_id_by_code(&domain_code, funs).await?.is_some() {
+        // Add the columns introduced after the table was created
+        // MySQL does not support `ADD COLUMN IF NOT EXISTS`, so the columns are checked first
+        let exist_sql = match TardisFuns::reldb().backend() {
+            DatabaseBackend::MySql => "SELECT 1 FROM information_schema.columns WHERE table_schema = DATABASE() AND table_name = ? AND column_name = ?",
+            _ => "SELECT 1 FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2",
+        };
+        for column in ["req_mapping", "resp_mapping"] {
+            if funs.db().count_by_sql(exist_sql, vec![Value::from(plugin_api::Entity.table_name()), Value::from(column)]).await? == 0 {
+                funs.db().execute_one(&format!("ALTER TABLE {} ADD COLUMN {column} TEXT", plugin_api::Entity.table_name()), vec![]).await?;
+            }
+        }
         return Ok(());
     }
     // Initialize plugin component RBUM item table and indexes
@@ -1,5 +1,7 @@
 pub mod plugin_api_serv;
 pub mod plugin_bs_serv;
 pub mod plugin_exec_serv;
+pub mod plugin_exec_task_serv;
 pub mod plugin_kind_serv;
+pub mod plugin_mapping_serv;
 pub mod plugin_rel_serv;
@@ -10,8 +10,9 @@ use tardis::basic::result::TardisResult;
 use tardis::db::sea_orm::sea_query::{Alias, Expr, SelectStatement};
 use tardis::db::sea_orm::{EntityName, Set};
 use tardis::web::poem_openapi::types::Type;
-use tardis::TardisFunsInst;
+use tardis::{TardisFuns, TardisFunsInst};
 
+use super::plugin_mapping_serv::PluginMappingServ;
 use crate::domain::plugin_api;
 use crate::dto::plugin_api_dto::{PluginApiAddOrModifyReq, PluginApiDetailResp, PluginApiFilterReq, PluginApiSummaryResp};
 
@@ -65,7 +66,8 @@ impl RbumItemCrudOperation<plugin_api::ActiveModel, PluginApiAddOrModifyReq, Plu
         })
     }
 
-    async fn package_ext_add(id: &str, add_req: &PluginApiAddOrModifyReq, _: &TardisFunsInst, _: &TardisContext) -> TardisResult<plugin_api::ActiveModel> {
+    async fn package_ext_add(id: &str, add_req: &PluginApiAddOrModifyReq, funs: &TardisFunsInst, _: &TardisContext) -> TardisResult<plugin_api::ActiveModel> {
+        PluginMappingServ::check_mappings([&add_req.req_mapping, &add_req.resp_mapping], funs)?;
         Ok(plugin_api::ActiveModel {
             id: Set(id.to_string()),
             callback: Set(add_req.callback.clone()),
@@ -76,6 +78,8 @@ impl RbumItemCrudOperation<plugin_api::ActiveModel, PluginApiAddOrModifyReq, Plu
             kind: Set(add_req.kind.clone()),
             path_and_query: Set(add_req.path_and_query.clone()),
             save_message: Set(add_req.save_message),
+            req_mapping: Set(add_req.req_mapping.as_ref().map(|mapping| TardisFuns::json.obj_to_string(mapping)).transpose()?),
+            resp_mapping: Set(add_req.resp_mapping.as_ref().map(|mapping| TardisFuns::json.obj_to_string(mapping)).transpose()?),
             ..Default::default()
         })
     }
@@ -92,7 +96,8 @@ impl RbumItemCrudOperation<plugin_api::ActiveModel, PluginApiAddOrModifyReq, Plu
         }))
     }
 
-    async fn package_ext_modify(id: &str, modify_req: &PluginApiAddOrModifyReq, _: &TardisFunsInst, _: &TardisContext) -> TardisResult<Option<plugin_api::ActiveModel>> {
+    async fn package_ext_modify(id: &str, modify_req: &PluginApiAddOrModifyReq, funs: &TardisFunsInst, _: &TardisContext) -> TardisResult<Option<plugin_api::ActiveModel>> {
+        PluginMappingServ::check_mappings([&modify_req.req_mapping, &modify_req.resp_mapping], funs)?;
         let plugin_api = plugin_api::ActiveModel {
             id: Set(id.to_string()),
             callback: Set(modify_req.callback.clone()),
@@ -103,6 +108,8 @@ impl RbumItemCrudOperation<plugin_api::ActiveModel, PluginApiAddOrModifyReq, Plu
             kind: Set(modify_req.kind.clone()),
             path_and_query: Set(modify_req.path_and_query.clone()),
             save_message: Set(modify_req.save_message),
+            req_mapping: Set(modify_req.req_mapping.as_ref().map(|mapping| TardisFuns::json.obj_to_string(mapping)).transpose()?),
+            resp_mapping: Set(modify_req.resp_mapping.as_ref().map(|mapping| TardisFuns::json.obj_to_string(mapping)).transpose()?),
             ..Default::default()
         };
         Ok(Some(plugin_api))
@@ -117,7 +124,9 @@ impl RbumItemCrudOperation<plugin_api::ActiveModel, PluginApiAddOrModifyReq, Plu
             .column((plugin_api::Entity, plugin_api::Column::Callback))
             .column((plugin_api::Entity, plugin_api::Column::HttpMethod))
             .column((plugin_api::Entity, plugin_api::Column::Ext))
-            .column((plugin_api::Entity, plugin_api::Column::SaveMessage));
+            .column((plugin_api::Entity, plugin_api::Column::SaveMessage))
+            .column((plugin_api::Entity, plugin_api::Column::ReqMapping))
+            .column((plugin_api::Entity, plugin_api::Column::RespMapping));
         if let Some(path_and_query) = &filter.path_and_query {
             query.and_where(Expr::col(plugin_api::Column::PathAndQuery).like(format!("%{path_and_query}%").as_str()));
         }
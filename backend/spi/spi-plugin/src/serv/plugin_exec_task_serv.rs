@@ -0,0 +1,435 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::rbum::serv::rbum_kind_serv::RbumKindServ;
+use serde::{Deserialize, Serialize};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::{self, DateTime, Utc};
+use tardis::db::sea_orm::sea_query::{Cond, Expr, Query};
+use tardis::db::sea_orm::{Iterable, Set};
+use tardis::log::{info, warn};
+use tardis::{TardisFuns, TardisFunsInst};
+
+use super::plugin_api_serv::PluginApiServ;
+use super::plugin_exec_serv::{retry_backoff_ms, PluginExecServ};
+use crate::domain::plugin_exec_task;
+use crate::dto::plugin_exec_dto::{PluginExecReq, PluginExecTaskResp};
+use crate::plugin_config::PluginConfig;
+use crate::plugin_enumeration::PluginExecTaskStatusKind;
+
+const DELIVERY_CALLBACK: &str = "callback";
+const DELIVERY_EVENT: &str = "event";
+const DELIVERY_NONE: &str = "none";
+
+/// Fields of the request context persisted with the task, the rest (e.g. roles and groups) are not needed to re-run the task
+///
+/// 随任务持久化的请求上下文字段，其余字段（如角色、群组）重新执行任务时不需要
+#[derive(Serialize, Deserialize)]
+struct PluginExecTaskCtx {
+    own_paths: String,
+    ak: String,
+    owner: String,
+}
+
+/// Ids of the tasks running on this node, which are not re-queued even if they look stale
+///
+/// 本节点正在执行的任务，即使看似已中断也不会被重新执行
+fn running_tasks() -> &'static RwLock<HashSet<String>> {
+    static RUNNING_TASKS: OnceLock<RwLock<HashSet<String>>> = OnceLock::new();
+    RUNNING_TASKS.get_or_init(Default::default)
+}
+
+/// Removes the task from the running tasks when the run ends, including by panic
+struct RunningTaskGuard(String);
+
+impl Drop for RunningTaskGuard {
+    fn drop(&mut self) {
+        running_tasks().write().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
+pub struct PluginExecTaskServ;
+
+impl PluginExecTaskServ {
+    /// Create an exec task and run it in the background, the result is delivered to the callback of the api,
+    /// or published to `mq_topic_event_plugin_exec_task` if the api has no callback.
+    /// The task is persisted with its request, so that it is re-queued if the node stops before it is finished or delivered.
+    ///
+    /// 创建异步执行任务并在后台执行，结果投递至接口的 callback，未配置 callback 时发布至 `mq_topic_event_plugin_exec_task`。
+    /// 任务连同请求一起持久化，节点在完成或投递前停止时会被重新执行。
+    pub async fn exec_async(kind_code: &str, api_code: &str, exec_req: PluginExecReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+        let Some(kind_id) = RbumKindServ::get_rbum_kind_id_by_code(kind_code, funs).await? else {
+            return Err(funs.err().not_found(&PluginApiServ::get_obj_name(), "exec_async", "exec kind is not fond", ""));
+        };
+        let Some(spi_api) = PluginApiServ::get_by_code(&kind_id, api_code, funs, ctx).await? else {
+            return Err(funs.err().not_found(&PluginApiServ::get_obj_name(), "exec_async", "exec api is not fond", ""));
+        };
+        let delivery = if !spi_api.callback.is_empty() {
+            DELIVERY_CALLBACK
+        } else if cfg!(feature = "with-mq") && funs.conf::<PluginConfig>().use_mq {
+            DELIVERY_EVENT
+        } else {
+            DELIVERY_NONE
+        };
+        let task_id = TardisFuns::field.nanoid();
+        let now = Utc::now();
+        let task = plugin_exec_task::Model {
+            id: task_id.clone(),
+            kind_code: kind_code.to_string(),
+            api_code: api_code.to_string(),
+            rel_id: exec_req.rel_id.clone().unwrap_or_default(),
+            req: TardisFuns::json.obj_to_string(&exec_req)?,
+            ctx: TardisFuns::json.obj_to_string(&PluginExecTaskCtx {
+                own_paths: ctx.own_paths.clone(),
+                ak: ctx.ak.clone(),
+                owner: ctx.owner.clone(),
+            })?,
+            callback: spi_api.callback.clone(),
+            status: PluginExecTaskStatusKind::Pending.to_string(),
+            code: None,
+            headers: None,
+            body: None,
+            error: None,
+            delivery: delivery.to_string(),
+            delivered: false,
+            delivery_attempts: 0,
+            create_time: now,
+            update_time: now,
+            own_paths: ctx.own_paths.clone(),
+        };
+        funs.db()
+            .insert_one(
+                plugin_exec_task::ActiveModel {
+                    id: Set(task.id.clone()),
+                    kind_code: Set(task.kind_code.clone()),
+                    api_code: Set(task.api_code.clone()),
+                    rel_id: Set(task.rel_id.clone()),
+                    req: Set(task.req.clone()),
+                    ctx: Set(task.ctx.clone()),
+                    callback: Set(task.callback.clone()),
+                    status: Set(task.status.clone()),
+                    code: Set(None),
+                    headers: Set(None),
+                    body: Set(None),
+                    error: Set(None),
+                    delivery: Set(task.delivery.clone()),
+                    delivered: Set(false),
+                    delivery_attempts: Set(0),
+                    create_time: Set(now),
+                    update_time: Set(now),
+                    ..Default::default()
+                },
+                ctx,
+            )
+            .await?;
+        Self::spawn_run(task);
+        Ok(task_id)
+    }
+
+    fn spawn_run(task: plugin_exec_task::Model) {
+        if !running_tasks().write().unwrap_or_else(|e| e.into_inner()).insert(task.id.clone()) {
+            return;
+        }
+        let guard = RunningTaskGuard(task.id.clone());
+        tardis::tokio::spawn(async move {
+            let funs = crate::get_tardis_inst();
+            let task_id = task.id.clone();
+            // The heartbeat keeps a long running task from being considered interrupted by other nodes
+            let result = tardis::tokio::select! {
+                result = Self::run(task, &funs) => result,
+                _ = Self::heartbeat(&task_id, &funs) => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("[SPI-Plugin] exec task {} failed: {}", task_id, e);
+            }
+            drop(guard);
+        });
+    }
+
+    /// Refresh the update time of the running task at a third of `task_stale_sec`, never returns
+    async fn heartbeat(task_id: &str, funs: &TardisFunsInst) {
+        let interval_sec = (funs.conf::<PluginConfig>().exec.task_stale_sec / 3).max(1);
+        let mut interval = tardis::tokio::time::interval(Duration::from_secs(interval_sec));
+        // The first tick completes immediately, the task is just created or claimed
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let mut query = Query::update();
+            query
+                .table(plugin_exec_task::Entity)
+                .value(plugin_exec_task::Column::UpdateTime, Utc::now())
+                .and_where(Expr::col(plugin_exec_task::Column::Id).eq(task_id))
+                .and_where(Expr::col(plugin_exec_task::Column::Delivered).eq(false));
+            if let Err(e) = funs.db().execute(&query).await {
+                warn!("[SPI-Plugin] Failed to refresh exec task {}: {:?}", task_id, e);
+            }
+        }
+    }
+
+    /// Periodically re-queue the async exec tasks interrupted by stopped nodes, which are not finished or not delivered and not updated for `task_stale_sec`,
+    /// and purge the finished tasks older than `task_retention_sec`
+    ///
+    /// 定期重新执行因节点停止而中断的异步执行任务，即未完成或未投递且超过 `task_stale_sec` 未更新的任务，并清理超过 `task_retention_sec` 的已结束任务
+    pub(crate) fn start_task_requeuer(interval_sec: u64) {
+        info!("[SPI-Plugin] Exec task requeuer started, interval: {}s", interval_sec);
+        tardis::tokio::spawn(async move {
+            let mut interval = tardis::tokio::time::interval(Duration::from_secs(interval_sec));
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::requeue_interrupted_tasks().await {
+                    warn!("[SPI-Plugin] Failed to requeue exec tasks: {:?}", e);
+                }
+                if let Err(e) = Self::purge_finished_tasks().await {
+                    warn!("[SPI-Plugin] Failed to purge exec tasks: {:?}", e);
+                }
+            }
+        });
+    }
+
+    async fn requeue_interrupted_tasks() -> TardisResult<()> {
+        let funs = crate::get_tardis_inst();
+        let exec_conf = &funs.conf::<PluginConfig>().exec;
+        let cutoff = Utc::now() - chrono::Duration::seconds(exec_conf.task_stale_sec as i64);
+        let mut query = Query::select();
+        query
+            .columns(plugin_exec_task::Column::iter())
+            .from(plugin_exec_task::Entity)
+            .and_where(Expr::col(plugin_exec_task::Column::Delivered).eq(false))
+            .and_where(Expr::col(plugin_exec_task::Column::UpdateTime).lt(cutoff))
+            .cond_where(
+                Cond::any()
+                    .add(Expr::col(plugin_exec_task::Column::Status).is_in([PluginExecTaskStatusKind::Pending.to_string(), PluginExecTaskStatusKind::Running.to_string()]))
+                    .add(
+                        Cond::all()
+                            .add(Expr::col(plugin_exec_task::Column::Delivery).ne(DELIVERY_NONE))
+                            .add(Expr::col(plugin_exec_task::Column::DeliveryAttempts).lte(exec_conf.task_delivery_max_retries as i32)),
+                    ),
+            );
+        for task in funs.db().find_dtos::<plugin_exec_task::Model>(&query).await? {
+            if running_tasks().read().unwrap_or_else(|e| e.into_inner()).contains(&task.id) {
+                continue;
+            }
+            // Only the node that claims the task re-queues it
+            if Self::claim(&task.id, cutoff, &funs).await? {
+                info!("[SPI-Plugin] requeue exec task {} with status {}", task.id, task.status);
+                Self::spawn_run(task);
+            }
+        }
+        Ok(())
+    }
+
+    async fn purge_finished_tasks() -> TardisResult<()> {
+        let funs = crate::get_tardis_inst();
+        let retention_sec = funs.conf::<PluginConfig>().exec.task_retention_sec;
+        if retention_sec == 0 {
+            return Ok(());
+        }
+        let mut query = Query::delete();
+        query
+            .from_table(plugin_exec_task::Entity)
+            .and_where(Expr::col(plugin_exec_task::Column::Status).is_in([PluginExecTaskStatusKind::Success.to_string(), PluginExecTaskStatusKind::Failed.to_string()]))
+            .and_where(Expr::col(plugin_exec_task::Column::UpdateTime).lt(Utc::now() - chrono::Duration::seconds(retention_sec as i64)));
+        let purged = funs.db().execute(&query).await?.rows_affected();
+        if purged > 0 {
+            info!("[SPI-Plugin] purged {} finished exec tasks", purged);
+        }
+        Ok(())
+    }
+
+    async fn claim(task_id: &str, cutoff: DateTime<Utc>, funs: &TardisFunsInst) -> TardisResult<bool> {
+        let mut query = Query::update();
+        query
+            .table(plugin_exec_task::Entity)
+            .value(plugin_exec_task::Column::UpdateTime, Utc::now())
+            .and_where(Expr::col(plugin_exec_task::Column::Id).eq(task_id))
+            .and_where(Expr::col(plugin_exec_task::Column::Delivered).eq(false))
+            .and_where(Expr::col(plugin_exec_task::Column::UpdateTime).lt(cutoff));
+        Ok(funs.db().execute(&query).await?.rows_affected() == 1)
+    }
+
+    async fn run(task: plugin_exec_task::Model, funs: &TardisFunsInst) -> TardisResult<()> {
+        let task_ctx = TardisFuns::json.str_to_obj::<PluginExecTaskCtx>(&task.ctx)?;
+        let ctx = TardisContext {
+            own_paths: task_ctx.own_paths,
+            ak: task_ctx.ak,
+            owner: task_ctx.owner,
+            ..Default::default()
+        };
+        if task.status == PluginExecTaskStatusKind::Pending.to_string() || task.status == PluginExecTaskStatusKind::Running.to_string() {
+            let exec_req = TardisFuns::json.str_to_obj::<PluginExecReq>(&task.req)?;
+            Self::exec(&task.id, &task.kind_code, &task.api_code, exec_req, funs, &ctx).await?;
+        }
+        if task.delivery == DELIVERY_NONE {
+            return Ok(());
+        }
+        Self::deliver(&task.id, &task.callback, task.delivery_attempts as u32, funs, &ctx).await
+    }
+
+    async fn exec(task_id: &str, kind_code: &str, api_code: &str, exec_req: PluginExecReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        funs.db()
+            .update_one(
+                plugin_exec_task::ActiveModel {
+                    id: Set(task_id.to_string()),
+                    status: Set(PluginExecTaskStatusKind::Running.to_string()),
+                    update_time: Set(Utc::now()),
+                    ..Default::default()
+                },
+                ctx,
+            )
+            .await?;
+        let mut task = plugin_exec_task::ActiveModel {
+            id: Set(task_id.to_string()),
+            update_time: Set(Utc::now()),
+            ..Default::default()
+        };
+        match PluginExecServ::exec(kind_code, api_code, exec_req, funs, ctx).await {
+            Ok(resp) => {
+                let status = if (200..300).contains(&resp.code) {
+                    PluginExecTaskStatusKind::Success
+                } else {
+                    PluginExecTaskStatusKind::Failed
+                };
+                task.status = Set(status.to_string());
+                task.code = Set(Some(resp.code as i32));
+                task.headers = Set(Some(TardisFuns::json.obj_to_string(&resp.headers)?));
+                task.body = Set(resp.body);
+            }
+            Err(e) => {
+                task.status = Set(PluginExecTaskStatusKind::Failed.to_string());
+                task.error = Set(Some(format!("{}: {}", e.code, e.message)));
+            }
+        }
+        funs.db().update_one(task, ctx).await?;
+        Ok(())
+    }
+
+    /// Deliver the result, retrying with backoff up to `task_delivery_max_retries` times in total (including the attempts before re-queuing)
+    async fn deliver(task_id: &str, callback: &str, mut attempts: u32, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+        let task = Self::get_task(task_id, funs, ctx).await?;
+        let exec_conf = &funs.conf::<PluginConfig>().exec;
+        let delivered = loop {
+            attempts += 1;
+            let result = match task.delivery.as_str() {
+                DELIVERY_CALLBACK => Self::deliver_to_callback(callback, &task, funs).await,
+                _ => Self::deliver_to_event(&task, funs).await,
+            };
+            let Err(e) = result else {
+                break true;
+            };
+            if attempts > exec_conf.task_delivery_max_retries {
+                warn!(
+                    "[SPI-Plugin] deliver exec task {} by {} failed after {} attempts, give up: {}",
+                    task_id, task.delivery, attempts, e
+                );
+                break false;
+            }
+            let backoff_ms = retry_backoff_ms(exec_conf.retry_backoff_ms, exec_conf.retry_backoff_max_ms, attempts);
+            warn!(
+                "[SPI-Plugin] deliver exec task {} by {} attempt {} failed, retry after {}ms: {}",
+                task_id, task.delivery, attempts, backoff_ms, e
+            );
+            funs.db()
+                .update_one(
+                    plugin_exec_task::ActiveModel {
+                        id: Set(task_id.to_string()),
+                        delivery_attempts: Set(attempts as i32),
+                        update_time: Set(Utc::now()),
+                        ..Default::default()
+                    },
+                    ctx,
+                )
+                .await?;
+            tardis::tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+        };
+        funs.db()
+            .update_one(
+                plugin_exec_task::ActiveModel {
+                    id: Set(task_id.to_string()),
+                    delivered: Set(delivered),
+                    delivery_attempts: Set(attempts as i32),
+                    update_time: Set(Utc::now()),
+                    ..Default::default()
+                },
+                ctx,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn deliver_to_callback(callback: &str, task: &PluginExecTaskResp, funs: &TardisFunsInst) -> TardisResult<()> {
+        let resp = funs
+            .web_client()
+            .post_str_to_str(
+                callback,
+                &TardisFuns::json.obj_to_string(task)?,
+                vec![
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                    ("Plugin-Task-Id".to_string(), task.id.clone()),
+                ],
+            )
+            .await?;
+        if !(200..300).contains(&resp.code) {
+            return Err(funs.err().error(
+                &format!("{}-spi-plugin-exec-task-callback", resp.code),
+                "plugin_exec_task",
+                "deliver",
+                &format!("callback {callback} responded with {}", resp.code),
+                "500-spi-plugin-exec-task-callback-failed",
+            ));
+        }
+        Ok(())
+    }
+
+    #[allow(unused_variables)]
+    async fn deliver_to_event(task: &PluginExecTaskResp, funs: &TardisFunsInst) -> TardisResult<()> {
+        #[cfg(feature = "with-mq")]
+        {
+            use std::collections::HashMap;
+            funs.mq()
+                .publish(
+                    &funs.conf::<PluginConfig>().mq_topic_event_plugin_exec_task,
+                    TardisFuns::json.obj_to_string(task)?,
+                    &HashMap::new(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_task(task_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<PluginExecTaskResp> {
+        let mut query = Query::select();
+        query
+            .columns(plugin_exec_task::Column::iter())
+            .from(plugin_exec_task::Entity)
+            .and_where(Expr::col(plugin_exec_task::Column::Id).eq(task_id))
+            .and_where(Expr::col(plugin_exec_task::Column::OwnPaths).like(format!("{}%", ctx.own_paths)));
+        let Some(task) = funs.db().get_dto::<plugin_exec_task::Model>(&query).await? else {
+            return Err(funs.err().not_found(
+                "plugin_exec_task",
+                "get",
+                &format!("exec task {task_id} is not found"),
+                "404-spi-plugin-exec-task-not-exist",
+            ));
+        };
+        Ok(PluginExecTaskResp {
+            status: PluginExecTaskStatusKind::from_str(&task.status)
+                .map_err(|_| funs.err().format_error("plugin_exec_task", "get", &format!("invalid exec task status {}", task.status), ""))?,
+            code: task.code.map(|code| code as u16),
+            headers: task.headers.map(|headers| TardisFuns::json.str_to_obj(&headers)).transpose()?,
+            id: task.id,
+            kind_code: task.kind_code,
+            api_code: task.api_code,
+            rel_id: task.rel_id,
+            body: task.body,
+            error: task.error,
+            delivery: task.delivery,
+            delivered: task.delivered,
+            create_time: task.create_time,
+            update_time: task.update_time,
+        })
+    }
+}
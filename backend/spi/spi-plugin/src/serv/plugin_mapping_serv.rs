@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use tardis::basic::result::TardisResult;
+use tardis::serde_json::{Map, Value};
+use tardis::TardisFunsInst;
+
+use crate::dto::plugin_api_dto::PluginApiMapping;
+
+/// Request/response body mapping of plugin apis
+///
+/// 插件接口请求/响应体映射
+pub struct PluginMappingServ;
+
+impl PluginMappingServ {
+    pub fn check_mappings<const N: usize>(mappings: [&Option<PluginApiMapping>; N], funs: &TardisFunsInst) -> TardisResult<()> {
+        for mapping in mappings.into_iter().flatten() {
+            let pointers = mapping
+                .root
+                .iter()
+                .chain(mapping.fields.iter().flat_map(|(target, source)| [target, source]))
+                .chain(mapping.renames.iter().flat_map(|(from, to)| [from, to]))
+                .chain(mapping.inject.keys());
+            for pointer in pointers {
+                if !is_valid_pointer(pointer) {
+                    return Err(funs.err().bad_request(
+                        "plugin_api",
+                        "check_mapping",
+                        &format!("mapping path {pointer} must be empty or start with '/'"),
+                        "400-spi-plugin-mapping-path-invalid",
+                    ));
+                }
+            }
+            if mapping.renames.keys().any(String::is_empty) {
+                return Err(funs.err().bad_request("plugin_api", "check_mapping", "the whole body can not be renamed", "400-spi-plugin-mapping-path-invalid"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the mapping to the source body, `vars` are used to render `{{name}}` placeholders of injected values
+    ///
+    /// 对源数据执行映射，`vars` 用于渲染注入值中的 `{{name}}` 占位符
+    pub fn apply(mapping: &PluginApiMapping, source: &Value, vars: &HashMap<String, String>) -> Value {
+        let root = match &mapping.root {
+            Some(root) if !root.is_empty() => source.pointer(root).cloned().unwrap_or(Value::Null),
+            _ => source.clone(),
+        };
+        let mut target = if mapping.fields.is_empty() {
+            root
+        } else {
+            let mut target = Value::Object(Map::new());
+            for (target_pointer, source_pointer) in &mapping.fields {
+                let value = if source_pointer.is_empty() { Some(&root) } else { root.pointer(source_pointer) };
+                if let Some(value) = value {
+                    set_pointer(&mut target, target_pointer, value.clone());
+                }
+            }
+            target
+        };
+        for (from, to) in &mapping.renames {
+            if let Some(value) = take_pointer(&mut target, from) {
+                set_pointer(&mut target, to, value);
+            }
+        }
+        for (target_pointer, value) in &mapping.inject {
+            set_pointer(&mut target, target_pointer, render_value(value, vars));
+        }
+        target
+    }
+}
+
+fn is_valid_pointer(pointer: &str) -> bool {
+    pointer.is_empty() || pointer.starts_with('/')
+}
+
+fn unescape_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Set the value at the pointer, missing or non-container nodes along the path are replaced by objects
+fn set_pointer(target: &mut Value, pointer: &str, value: Value) {
+    if pointer.is_empty() {
+        *target = value;
+        return;
+    }
+    let mut current = target;
+    for token in pointer[1..].split('/').map(unescape_token) {
+        let index = match &*current {
+            Value::Array(array) => token.parse::<usize>().ok().filter(|index| *index < array.len()),
+            _ => None,
+        };
+        current = match index {
+            Some(index) => &mut current[index],
+            None => {
+                if !current.is_object() {
+                    *current = Value::Object(Map::new());
+                }
+                let Value::Object(object) = current else {
+                    unreachable!("current node has just been replaced by an object")
+                };
+                object.entry(token).or_insert(Value::Null)
+            }
+        };
+    }
+    *current = value;
+}
+
+fn take_pointer(target: &mut Value, pointer: &str) -> Option<Value> {
+    let (parent, token) = pointer.rsplit_once('/')?;
+    let token = unescape_token(token);
+    match target.pointer_mut(parent)? {
+        Value::Object(object) => object.remove(&token),
+        Value::Array(array) => {
+            let index = token.parse::<usize>().ok().filter(|index| *index < array.len())?;
+            Some(array.remove(index))
+        }
+        _ => None,
+    }
+}
+
+fn render_value(value: &Value, vars: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(s) => Value::String(render_str(s, vars)),
+        Value::Array(array) => Value::Array(array.iter().map(|value| render_value(value, vars)).collect()),
+        Value::Object(object) => Value::Object(object.iter().map(|(k, v)| (k.clone(), render_value(v, vars))).collect()),
+        _ => value.clone(),
+    }
+}
+
+fn render_str(s: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        result.push_str(&rest[..start]);
+        match vars.get(name) {
+            Some(value) => result.push_str(value),
+            None => result.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use tardis::serde_json::json;
+
+    use super::{render_str, PluginMappingServ};
+    use crate::dto::plugin_api_dto::PluginApiMapping;
+
+    #[test]
+    fn test_render_str() {
+        let vars = HashMap::from([("token".to_string(), "abc".to_string()), ("rel_id".to_string(), "r1".to_string())]);
+        assert_eq!(render_str("Bearer {{token}}", &vars), "Bearer abc");
+        assert_eq!(render_str("{{ rel_id }}/{{token}}", &vars), "r1/abc");
+        assert_eq!(render_str("{{unknown}} {{token", &vars), "{{unknown}} {{token");
+    }
+
+    #[test]
+    fn test_apply() {
+        let vars = HashMap::from([("project".to_string(), "p1".to_string())]);
+        let source = json!({
+            "data": {
+                "items": [{"name": "a", "state": "opened"}, {"name": "b", "state": "closed"}],
+                "total": 2
+            },
+            "code": 0
+        });
+
+        // no mapping
+        assert_eq!(PluginMappingServ::apply(&PluginApiMapping::default(), &source, &vars), source);
+
+        // root, fields and inject
+        let mapping = PluginApiMapping {
+            root: Some("/data".to_string()),
+            fields: HashMap::from([
+                ("/records".to_string(), "/items".to_string()),
+                ("/page/total".to_string(), "/total".to_string()),
+                ("/missing".to_string(), "/not_exist".to_string()),
+            ]),
+            inject: HashMap::from([("/page/project".to_string(), json!("{{project}}")), ("/source".to_string(), json!({"kind": "gitlab"}))]),
+            ..Default::default()
+        };
+        assert_eq!(
+            PluginMappingServ::apply(&mapping, &source, &vars),
+            json!({
+                "records": [{"name": "a", "state": "opened"}, {"name": "b", "state": "closed"}],
+                "page": {"total": 2, "project": "p1"},
+                "source": {"kind": "gitlab"}
+            })
+        );
+
+        // renames keep the remaining fields
+        let mapping = PluginApiMapping {
+            renames: HashMap::from([
+                ("/data/total".to_string(), "/total_size".to_string()),
+                ("/data/items/0/name".to_string(), "/data/items/0/title".to_string()),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(
+            PluginMappingServ::apply(&mapping, &source, &vars),
+            json!({
+                "data": {
+                    "items": [{"title": "a", "state": "opened"}, {"name": "b", "state": "closed"}]
+                },
+                "total_size": 2,
+                "code": 0
+            })
+        );
+
+        // inject into an empty request body
+        let mapping = PluginApiMapping {
+            inject: HashMap::from([("/project_id".to_string(), json!("{{project}}"))]),
+            ..Default::default()
+        };
+        assert_eq!(PluginMappingServ::apply(&mapping, &tardis::serde_json::Value::Null, &vars), json!({"project_id": "p1"}));
+    }
+}
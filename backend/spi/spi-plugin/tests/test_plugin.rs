@@ -16,7 +16,9 @@ use bios_basic::spi::dto::spi_bs_dto::SpiBsAddReq;
 use bios_basic::spi::spi_initializer;
 use bios_basic::test::init_test_container;
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_plugin::dto::plugin_api_dto::PluginApiAddOrModifyReq;
+use bios_spi_log::log_constants::SPI_PG_V2_KIND_CODE;
+use bios_spi_log::log_initializer;
+use bios_spi_plugin::dto::plugin_api_dto::{PluginApiAddOrModifyReq, PluginApiMapping};
 use bios_spi_plugin::dto::plugin_bs_dto::{PluginBsAddReq, PluginBsInfoResp};
 use bios_spi_plugin::dto::plugin_kind_dto::PluginKindAddAggReq;
 use bios_spi_plugin::plugin_config::PluginConfig;
@@ -44,6 +46,8 @@ async fn test_plugin() -> TardisResult<()> {
 
 async fn init_data() -> TardisResult<()> {
     let web_server = TardisFuns::web_server();
+    // Initialize SPI log, which records the exec history
+    log_initializer::init(&web_server).await.unwrap();
     // Initialize SPI plugin
     plugin_initializer::init(&web_server).await.unwrap();
 
@@ -230,6 +234,25 @@ async fn init_data() -> TardisResult<()> {
         &ctx,
     )
     .await?;
+    let mut log_client = TestHttpClient::new("http://127.0.0.1:8080/spi-log".to_string());
+    log_client.set_auth(&ctx)?;
+    let log_bs_id: String = log_client
+        .post(
+            "/ci/manage/bs",
+            &SpiBsAddReq {
+                name: TrimString("test-spi-log".to_string()),
+                kind_id: TrimString(RbumKindServ::get_rbum_kind_id_by_code(SPI_PG_V2_KIND_CODE, &funs).await?.unwrap()),
+                conn_uri: env::var("TARDIS_FW.DB.URL").unwrap(),
+                ak: TrimString("".to_string()),
+                sk: TrimString("".to_string()),
+                ext: "{\"max_connections\":20,\"min_connections\":10}".to_string(),
+                private: false,
+                disabled: None,
+            },
+        )
+        .await;
+    let _: Void = log_client.put(&format!("/ci/manage/bs/{}/rel/app001", log_bs_id), &Void {}).await;
+
     let base_url = format!("http://127.0.0.1:8080/{}", DOMAIN_CODE);
     let mut client = TestHttpClient::new(base_url.clone());
 
@@ -288,6 +311,36 @@ async fn init_data() -> TardisResult<()> {
                 kind: "".to_string(),
                 path_and_query: "ci/spi/plugin/test/exec/:msg".to_string(),
                 save_message: true,
+                req_mapping: None,
+                resp_mapping: None,
+            },
+        )
+        .await;
+    let _: String = client
+        .put(
+            "/ci/spi/plugin/api",
+            &PluginApiAddOrModifyReq {
+                code: TrimString("test-api-mapping".to_string()),
+                name: TrimString("test-api-mapping".to_string()),
+                kind_id: TrimString(kind_id.clone()),
+                callback: "".to_string(),
+                content_type: "".to_string(),
+                timeout: 0,
+                ext: "".to_string(),
+                http_method: PluginApiMethodKind::DELETE,
+                kind: "".to_string(),
+                path_and_query: "ci/spi/plugin/test/exec/:msg".to_string(),
+                save_message: false,
+                req_mapping: Some(PluginApiMapping {
+                    fields: HashMap::from([("/msg".to_string(), "/text".to_string())]),
+                    ..Default::default()
+                }),
+                resp_mapping: Some(PluginApiMapping {
+                    root: Some("/data".to_string()),
+                    renames: HashMap::from([("/body".to_string(), "/msg".to_string())]),
+                    inject: HashMap::from([("/kind".to_string(), json!("{{kind_code}}"))]),
+                    ..Default::default()
+                }),
             },
         )
         .await;
@@ -346,13 +399,13 @@ async fn init_data() -> TardisResult<()> {
                 bs_id,
                 app_tenant_id: "app001".to_string(),
                 kind_id: kind_id.clone(),
-                rel_id: Some(rel_id),
+                rel_id: Some(rel_id.clone()),
                 bs_rel: None,
                 ignore_exist: None,
             },
         )
         .await;
     let _: TardisPage<PluginBsInfoResp> = client.get(&format!("/ci/manage/bs/rel?app_tenant_id=app001&page_number=1&page_size=10",)).await;
-    test_plugin_exec::test(&mut client).await?;
+    test_plugin_exec::test(&rel_id, &mut client).await?;
     Ok(())
 }
@@ -1,14 +1,18 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_plugin::dto::plugin_exec_dto::{PluginExecReq, PluginExecResp};
+use bios_spi_plugin::dto::plugin_exec_dto::{PluginExecHistoryResp, PluginExecReq, PluginExecResp, PluginExecTaskResp};
+use bios_spi_plugin::plugin_enumeration::PluginExecTaskStatusKind;
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::log::info;
-use tardis::serde_json::json;
+use tardis::serde_json::{json, Value};
+use tardis::tokio::time::sleep;
+use tardis::web::web_resp::TardisPage;
 use tardis::TardisFuns;
 
-pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
+pub async fn test(rel_id: &str, client: &mut TestHttpClient) -> TardisResult<()> {
     client.set_auth(&TardisContext {
         own_paths: "t1/app001".to_string(),
         ak: "".to_string(),
@@ -34,5 +38,93 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
         )
         .await;
     info!("resp: {},{}", resp.code, resp.body.unwrap());
+
+    let resp: PluginExecResp = client
+        .put(
+            &format!("/ci/spi/plugin/{}/api/{}/exec", "gitlib", "test-api-mapping"),
+            &PluginExecReq {
+                header: Some(HashMap::from([(
+                    "Tardis-Context".to_string(),
+                    TardisFuns::crypto.base64.encode(&TardisFuns::json.obj_to_string(&client.context()).unwrap()),
+                )])),
+                body: Some(json!({ "text": "mapped" })),
+                query: None,
+                percent_encode: None,
+                rel_id: None,
+            },
+        )
+        .await;
+    let body: Value = TardisFuns::json.str_to_obj(&resp.body.unwrap())?;
+    assert_eq!(body.get("msg").unwrap(), "mapped");
+    assert_eq!(body.get("kind").unwrap(), "gitlib");
+    assert!(body.get("rel_id").is_some());
+
+    // async exec
+    let task_id: String = client
+        .put(
+            &format!("/ci/spi/plugin/{}/api/{}/exec/async", "gitlib", "test-api"),
+            &PluginExecReq {
+                header: Some(HashMap::from([(
+                    "Tardis-Context".to_string(),
+                    TardisFuns::crypto.base64.encode(&TardisFuns::json.obj_to_string(&client.context()).unwrap()),
+                )])),
+                body: Some(json!({ "msg": "async" })),
+                query: None,
+                percent_encode: None,
+                rel_id: None,
+            },
+        )
+        .await;
+    let mut task: PluginExecTaskResp = client.get(&format!("/ci/spi/plugin/exec/task/{}", task_id)).await;
+    for _ in 0..20 {
+        if task.status != PluginExecTaskStatusKind::Pending && task.status != PluginExecTaskStatusKind::Running {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+        task = client.get(&format!("/ci/spi/plugin/exec/task/{}", task_id)).await;
+    }
+    assert_eq!(task.status, PluginExecTaskStatusKind::Success);
+    assert_eq!(task.code, Some(200));
+    assert!(task.body.unwrap().contains("async"));
+
+    // exec history, recorded with the default log tag
+    let context = TardisFuns::crypto.base64.encode(&TardisFuns::json.obj_to_string(&client.context()).unwrap());
+    let resp: PluginExecResp = client
+        .put(
+            &format!("/ci/spi/plugin/{}/api/{}/exec", "gitlib", "test-api"),
+            &PluginExecReq {
+                header: Some(HashMap::from([("Tardis-Context".to_string(), context.clone())])),
+                body: Some(json!({ "msg": "history" })),
+                query: Some(HashMap::from([("access_token".to_string(), "token123".to_string())])),
+                percent_encode: None,
+                rel_id: Some(rel_id.to_string()),
+            },
+        )
+        .await;
+    assert_eq!(resp.code, 200);
+    let mut history: TardisPage<PluginExecHistoryResp> = client.get(&format!("/ci/spi/plugin/exec/history/{}?page_number=1&page_size=10", rel_id)).await;
+    for _ in 0..20 {
+        if history.total_size > 0 {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+        history = client.get(&format!("/ci/spi/plugin/exec/history/{}?page_number=1&page_size=10", rel_id)).await;
+    }
+    assert_eq!(history.total_size, 1);
+    let record = &history.records[0];
+    assert_eq!(record.rel_id, rel_id);
+    assert_eq!(record.kind_code, "gitlib");
+    assert_eq!(record.api_code, "test-api");
+    assert_eq!(record.code, Some(200));
+    assert!(record.success);
+    assert_eq!(record.attempts, 1);
+    let url = record.content["url"].as_str().unwrap();
+    assert!(url.contains("access_token=******"));
+    assert!(!url.contains("token123"));
+    assert_eq!(record.content["req_headers"]["Tardis-Context"], "******");
+    assert!(!record.content.to_string().contains(&context));
+    // the api with save_message false is not recorded
+    let history: TardisPage<PluginExecHistoryResp> = client.get(&format!("/ci/spi/plugin/exec/history/{}?api_code=test-api-mapping&page_number=1&page_size=10", rel_id)).await;
+    assert_eq!(history.total_size, 0);
     Ok(())
 }
@@ -6,7 +6,7 @@ use tardis::web::poem_openapi::param::Query;
 use tardis::web::poem_openapi::payload::Json;
 use tardis::web::web_resp::{TardisApiResult, TardisResp, Void};
 
-use crate::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp};
+use crate::dto::reldb_exec_dto::{ReldbBatchReq, ReldbBatchResp, ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlCursorResp, ReldbDqlReq, ReldbTxInfoResp, ReldbTxResp};
 use crate::serv::reldb_exec_serv;
 
 #[derive(Clone)]
@@ -17,7 +17,7 @@ pub struct ReldbCiExecApi;
 impl ReldbCiExecApi {
     /// Fetch Transaction ID
     #[oai(path = "/tx", method = "get")]
-    async fn tx_begin(&self, auto_commit: Query<bool>, exp_sec: Query<Option<u8>>, ctx: TardisContextExtractor) -> TardisApiResult<ReldbTxResp> {
+    async fn tx_begin(&self, auto_commit: Query<bool>, exp_sec: Query<Option<u16>>, ctx: TardisContextExtractor) -> TardisApiResult<ReldbTxResp> {
         let funs = crate::get_tardis_inst();
         let resp = reldb_exec_serv::tx_begin(auto_commit.0, exp_sec.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
@@ -25,15 +25,35 @@ impl ReldbCiExecApi {
 
     /// Commit Transaction
     #[oai(path = "/tx", method = "put")]
-    async fn tx_commit(&self, tx_id: Query<String>) -> TardisApiResult<Void> {
-        reldb_exec_serv::tx_commit(tx_id.0).await?;
+    async fn tx_commit(&self, tx_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        reldb_exec_serv::tx_commit(tx_id.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
 
     /// Rollback Transaction
     #[oai(path = "/tx", method = "delete")]
-    async fn tx_rollback(&self, tx_id: Query<String>) -> TardisApiResult<Void> {
-        reldb_exec_serv::tx_rollback(tx_id.0).await?;
+    async fn tx_rollback(&self, tx_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        reldb_exec_serv::tx_rollback(tx_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// List Open Transactions of all nodes
+    #[oai(path = "/tx/list", method = "get")]
+    async fn tx_list(&self, ctx: TardisContextExtractor) -> TardisApiResult<Vec<ReldbTxInfoResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = reldb_exec_serv::tx_list(&funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Force Rollback Transaction on any node
+    ///
+    /// Fails if the node of the transaction can not be reached, the record of the transaction is removed anyway.
+    #[oai(path = "/tx/force", method = "delete")]
+    async fn tx_force_rollback(&self, tx_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        reldb_exec_serv::tx_force_rollback(tx_id.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
 
@@ -60,4 +80,42 @@ impl ReldbCiExecApi {
         let resp = reldb_exec_serv::dql(&mut dql_req.0, tx_id.0, &funs, &ctx.0).await?;
         TardisResp::ok(resp)
     }
+
+    /// Batch DML/DQL in one transaction
+    #[oai(path = "/batch", method = "post")]
+    async fn batch(&self, mut batch_req: Json<ReldbBatchReq>, tx_id: Query<Option<String>>, ctx: TardisContextExtractor) -> TardisApiResult<ReldbBatchResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = reldb_exec_serv::batch(&mut batch_req.0, tx_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Open DQL Cursor and fetch the first rows
+    #[oai(path = "/dql/cursor", method = "post")]
+    async fn dql_cursor_open(
+        &self,
+        mut dql_req: Json<ReldbDqlReq>,
+        fetch_size: Query<Option<u32>>,
+        exp_sec: Query<Option<u16>>,
+        ctx: TardisContextExtractor,
+    ) -> TardisApiResult<ReldbDqlCursorResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = reldb_exec_serv::dql_cursor_open(&mut dql_req.0, fetch_size.0, exp_sec.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Fetch the next rows of DQL Cursor
+    #[oai(path = "/dql/cursor", method = "get")]
+    async fn dql_cursor_fetch(&self, cursor_id: Query<String>, fetch_size: Query<Option<u32>>, ctx: TardisContextExtractor) -> TardisApiResult<ReldbDqlCursorResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = reldb_exec_serv::dql_cursor_fetch(cursor_id.0, fetch_size.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Close DQL Cursor
+    #[oai(path = "/dql/cursor", method = "delete")]
+    async fn dql_cursor_close(&self, cursor_id: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        reldb_exec_serv::dql_cursor_close(cursor_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
 }
@@ -7,6 +7,16 @@ pub struct ReldbTxResp {
     pub exp_ts_at: i64,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct ReldbTxInfoResp {
+    pub tx_id: String,
+    /// URL of the node that holds the transaction
+    pub node_url: String,
+    pub own_paths: String,
+    pub auto_commit: bool,
+    pub exp_ts_at: i64,
+}
+
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 pub struct ReldbDdlReq {
     #[oai(validator(min_length = "2"))]
@@ -33,6 +43,50 @@ pub struct ReldbDqlReq {
     pub params: Value,
 }
 
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReldbStatementKind {
+    Dml,
+    Dql,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbBatchStatementReq {
+    pub kind: ReldbStatementKind,
+    #[oai(validator(min_length = "2"))]
+    pub sql: String,
+    pub params: Value,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbBatchReq {
+    /// Statements executed in order in one transaction
+    #[oai(validator(min_items = "1"))]
+    pub statements: Vec<ReldbBatchStatementReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbBatchStatementResp {
+    /// Affected rows of the dml statement
+    pub affected_rows: Option<u64>,
+    /// Rows of the dql statement
+    pub rows: Option<Vec<Value>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbBatchResp {
+    /// Results in the same order as the statements
+    pub results: Vec<ReldbBatchStatementResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct ReldbDqlCursorResp {
+    pub cursor_id: String,
+    pub rows: Vec<Value>,
+    /// Whether there may be more rows, when false the cursor has been closed
+    pub has_more: bool,
+    pub exp_ts_at: i64,
+}
+
 // #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
 // pub struct ReldbUpsertReq {
 //     #[oai(validator(min_length = "2"))]
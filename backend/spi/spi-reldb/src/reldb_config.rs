@@ -1,5 +1,7 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
+use bios_sdk_invoke::invoke_config::InvokeConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -7,6 +9,17 @@ use std::fmt::Debug;
 pub struct ReldbConfig {
     pub rbum: RbumConfig,
     pub tx_clean_interval_sec: u8,
+    /// URL of this node that other nodes can reach, e.g. `http://10.0.0.1:8080/spi-reldb`,
+    /// requests of transactions and cursors owned by this node are forwarded to it.
+    /// Required in multi-node deployments, empty means single node deployment and
+    /// requests of transactions and cursors opened on other nodes are rejected.
+    pub tx_node_url: String,
+    /// Cache key of the open transactions of all nodes
+    pub cache_key_tx_info: String,
+    /// Cache key of the open dql cursors of all nodes
+    pub cache_key_cursor_info: String,
+    pub sql_guard: ReldbSqlGuardConfig,
+    pub invoke: InvokeConfig,
 }
 
 impl Default for ReldbConfig {
@@ -14,6 +27,62 @@ impl Default for ReldbConfig {
         ReldbConfig {
             rbum: Default::default(),
             tx_clean_interval_sec: 5,
+            tx_node_url: "".to_string(),
+            cache_key_tx_info: "spi-reldb:tx:info".to_string(),
+            cache_key_cursor_info: "spi-reldb:cursor:info".to_string(),
+            sql_guard: Default::default(),
+            invoke: Default::default(),
         }
     }
 }
+
+/// Guardrails of the statements executed by tenants
+///
+/// The guard and the audit are opt-in: set `enabled` and fill `denied_statements` (e.g. `DROP`, `TRUNCATE`, `CREATE SCHEMA`)
+/// to check the statements, and set `audit_log_tag` to write every executed statement to spi-log.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReldbSqlGuardConfig {
+    /// Check the statements against the policy, disabled by default
+    pub enabled: bool,
+    /// Denied statement types, matched by the leading keywords, e.g. `DROP` or `CREATE SCHEMA`
+    pub denied_statements: Vec<String>,
+    /// Denied identifier prefixes (case insensitive), e.g. `pg_` to deny the system catalogs and functions
+    pub denied_identifier_prefixes: Vec<String>,
+    /// Deny references to schemas other than the schema of the tenant
+    pub deny_cross_schema: bool,
+    /// Max rows of a dql result, larger results should be read with the dql cursor, 0 means unlimited
+    ///
+    /// Only applied to postgresql when the guard is enabled
+    pub max_rows: u32,
+    /// Statement timeout in milliseconds, 0 means no timeout, only applied when the guard is enabled
+    ///
+    /// Mysql only supports the timeout of the statements starting with `SELECT`
+    pub statement_timeout_ms: u32,
+    /// Tag of the audit logs written to spi-log, empty means no audit
+    pub audit_log_tag: String,
+    /// Policies overriding the defaults by tenant (ak)
+    pub tenant_policies: HashMap<String, ReldbTenantPolicyConfig>,
+}
+
+impl Default for ReldbSqlGuardConfig {
+    fn default() -> Self {
+        ReldbSqlGuardConfig {
+            enabled: false,
+            denied_statements: vec![],
+            denied_identifier_prefixes: vec!["pg_".to_string(), "information_schema".to_string()],
+            deny_cross_schema: true,
+            max_rows: 10000,
+            statement_timeout_ms: 30000,
+            audit_log_tag: "".to_string(),
+            tenant_policies: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ReldbTenantPolicyConfig {
+    pub max_rows: Option<u32>,
+    pub statement_timeout_ms: Option<u32>,
+}
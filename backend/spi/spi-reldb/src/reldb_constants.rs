@@ -1,2 +1,5 @@
 pub const DOMAIN_CODE: &str = "spi-reldb";
 pub const SPI_MYSQL_KIND_CODE: &str = "spi-bs-mysql";
+pub const DQL_CURSOR_DEFAULT_FETCH_SIZE: u32 = 1000;
+pub const DQL_CURSOR_MAX_FETCH_SIZE: u32 = 10000;
+pub const DQL_CURSOR_DEFAULT_EXP_SEC: u16 = 60;
@@ -9,7 +9,7 @@ use tardis::{
     basic::{dto::TardisContext, error::TardisError, result::TardisResult},
     config::config_dto::DBModuleConfig,
     db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
-    log::info,
+    log::{info, warn},
     serde_json::Value as JsonValue,
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
@@ -26,7 +26,11 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.Reldb] Module initializing");
     let mut funs = crate::get_tardis_inst();
     let clean_interval_sec = funs.conf::<ReldbConfig>().tx_clean_interval_sec;
+    if funs.conf::<ReldbConfig>().tx_node_url.is_empty() {
+        warn!("[BIOS.Reldb] tx_node_url is not configured, transactions and cursors can only be used on the node that opened them");
+    }
     bios_basic::rbum::rbum_initializer::init(funs.module_code(), funs.conf::<ReldbConfig>().rbum.clone()).await?;
+    bios_sdk_invoke::invoke_initializer::init(funs.module_code(), funs.conf::<ReldbConfig>().invoke.clone())?;
     funs.begin().await?;
     let ctx = spi_initializer::init(DOMAIN_CODE, &funs).await?;
     init_db(&funs, &ctx).await?;
@@ -3,3 +3,4 @@ pub mod mysql;
 #[cfg(feature = "spi-pg")]
 pub mod pg;
 pub mod reldb_exec_serv;
+pub mod reldb_sql_guard_serv;
@@ -1,23 +1,48 @@
-use crate::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp};
-use crate::reldb_initializer;
-use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use crate::dto::reldb_exec_dto::{
+    ReldbBatchReq, ReldbBatchResp, ReldbBatchStatementResp, ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlCursorResp, ReldbDqlReq, ReldbStatementKind, ReldbTxInfoResp,
+    ReldbTxResp,
+};
+use crate::reldb_config::ReldbConfig;
+use crate::serv::reldb_sql_guard_serv::{self, SqlAudit, SqlUsage};
+use crate::{reldb_constants, reldb_initializer};
+use bios_basic::spi::spi_constants;
+use bios_basic::spi::spi_funs::{SpiBsInstExtractor, TypedSpiBsInst};
 use lazy_static::lazy_static;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
 use tardis::basic::error::TardisError;
 use tardis::basic::result::TardisResult;
 use tardis::chrono::Utc;
 use tardis::db::reldb_client::TardisRelDBlConnection;
-use tardis::db::sea_orm::{DbErr, FromQueryResult, Value};
-use tardis::log::trace;
+use tardis::db::sea_orm::{DbErr, FromQueryResult, QueryResult, Value};
+use tardis::log::{trace, warn};
 use tardis::tokio::sync::RwLock;
 use tardis::tokio::time::{self, Duration};
+use tardis::web::poem_openapi::types::{ParseFromJSON, ToJSON};
+use tardis::web::web_client::TardisHttpResponse;
+use tardis::web::web_resp::{TardisResp, Void};
 use tardis::{basic::dto::TardisContext, db::reldb_client::TardisRelDBClient};
-use tardis::{serde_json::Value as JsonValue, TardisFuns, TardisFunsInst};
+use tardis::{serde_json::json, serde_json::Value as JsonValue, TardisFuns, TardisFunsInst};
 
 lazy_static! {
     static ref TX_CONTAINER: RwLock<HashMap<String, (TardisRelDBlConnection, i64, bool)>> = RwLock::new(HashMap::new());
+    // cursor id -> cursor
+    static ref CURSOR_CONTAINER: RwLock<HashMap<String, CursorEntry>> = RwLock::new(HashMap::new());
 }
 
+/// (connection holding the cursor in its transaction, expiration timestamp, expiration seconds, own_paths of the opener,
+/// the row read ahead by the last fetch to tell whether there are more rows)
+type CursorEntry = (TardisRelDBlConnection, i64, u16, String, Option<JsonValue>);
+
+/// Where the cursor lives, shared by all nodes so that the requests can be forwarded to the node holding the cursor
+#[derive(Serialize, Deserialize)]
+struct CursorInfo {
+    node_url: String,
+    own_paths: String,
+}
+
+const CURSOR_NAME: &str = "bios_dql_cursor";
+
 fn parse_params(params: &JsonValue) -> Vec<Value> {
     let Some(arr) = params.as_array() else {
         // this means params is not an array, just return an empty array
@@ -48,51 +73,215 @@ fn parse_params(params: &JsonValue) -> Vec<Value> {
         .collect::<Vec<Value>>()
 }
 
-pub async fn tx_begin(auto_commit: bool, exp_sec: Option<u8>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbTxResp> {
+/// Get a connection of the instance in a transaction with the statement timeout of the tenant
+async fn inst_guarded_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<TardisRelDBlConnection> {
+    let mut conn = reldb_initializer::inst_conn(bs_inst).await?;
+    if !conn.has_tx() {
+        conn.begin().await?;
+    }
+    reldb_sql_guard_serv::set_statement_timeout(&conn, bs_inst.2, funs, ctx).await?;
+    Ok(conn)
+}
+
+pub async fn tx_begin(auto_commit: bool, exp_sec: Option<u16>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbTxResp> {
     let tx_id = TardisFuns::crypto.hex.encode(TardisFuns::field.nanoid());
     let exp_ts_at = Utc::now().timestamp_millis() + (exp_sec.unwrap_or(5)) as i64 * 1000;
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
-    let mut conn = reldb_initializer::inst_conn(bs_inst).await?;
-    if !conn.has_tx() {
-        conn.begin().await?;
+    let conn = inst_guarded_conn(bs_inst, funs, ctx).await?;
+    {
+        let mut tx_container = TX_CONTAINER.write().await;
+        tx_container.insert(tx_id.clone(), (conn, exp_ts_at, auto_commit));
     }
-    let mut tx_container = TX_CONTAINER.write().await;
-    tx_container.insert(tx_id.clone(), (conn, exp_ts_at, auto_commit));
+    let tx_info = ReldbTxInfoResp {
+        tx_id: tx_id.clone(),
+        node_url: funs.conf::<ReldbConfig>().tx_node_url.clone(),
+        own_paths: ctx.own_paths.clone(),
+        auto_commit,
+        exp_ts_at,
+    };
+    funs.cache().hset(&funs.conf::<ReldbConfig>().cache_key_tx_info, &tx_id, &TardisFuns::json.obj_to_string(&tx_info)?).await?;
     Ok(ReldbTxResp { tx_id, exp_ts_at })
 }
 
-pub async fn tx_commit(tx_id: String) -> TardisResult<()> {
-    let mut tx_container = TX_CONTAINER.write().await;
-    match tx_container.remove(&tx_id) {
-        Some((conn, _, _)) => conn.commit().await?,
+pub async fn tx_commit(tx_id: String, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if let TxRoute::Remote(tx_info) = route_tx(&tx_id, funs, ctx).await? {
+        let _: Void = forward(ForwardMethod::Put, &tx_info.node_url, &format!("/ci/exec/tx?tx_id={tx_id}"), Some(&Void {}), funs, ctx).await?;
+        return Ok(());
+    }
+    let conn = TX_CONTAINER.write().await.remove(&tx_id);
+    match conn {
+        Some((conn, _, _)) => {
+            remove_tx_info(&tx_id, funs).await?;
+            conn.commit().await?
+        }
         None => return Err(TardisError::bad_request("tx not exist", "")),
     }
     Ok(())
 }
 
-pub async fn tx_rollback(tx_id: String) -> TardisResult<()> {
-    let mut tx_container = TX_CONTAINER.write().await;
-    match tx_container.remove(&tx_id) {
-        Some((conn, _, _)) => conn.rollback().await?,
+pub async fn tx_rollback(tx_id: String, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if let TxRoute::Remote(tx_info) = route_tx(&tx_id, funs, ctx).await? {
+        let _: Void = forward::<Void, _>(ForwardMethod::Delete, &tx_info.node_url, &format!("/ci/exec/tx?tx_id={tx_id}"), None, funs, ctx).await?;
+        return Ok(());
+    }
+    let conn = TX_CONTAINER.write().await.remove(&tx_id);
+    match conn {
+        Some((conn, _, _)) => {
+            remove_tx_info(&tx_id, funs).await?;
+            conn.rollback().await?
+        }
         None => return Err(TardisError::bad_request("tx not exist", "")),
     }
     Ok(())
 }
 
+/// List the open transactions of all nodes under the current own_paths
+pub async fn tx_list(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<ReldbTxInfoResp>> {
+    let tx_infos = funs.cache().hgetall(&funs.conf::<ReldbConfig>().cache_key_tx_info).await?;
+    let mut tx_infos = tx_infos
+        .into_values()
+        .map(|tx_info| TardisFuns::json.str_to_obj::<ReldbTxInfoResp>(&tx_info))
+        .collect::<TardisResult<Vec<_>>>()?
+        .into_iter()
+        .filter(|tx_info| tx_info.own_paths.starts_with(&ctx.own_paths))
+        .collect::<Vec<_>>();
+    tx_infos.sort_by_key(|tx_info| tx_info.exp_ts_at);
+    Ok(tx_infos)
+}
+
+/// Rollback the transaction regardless of the node it belongs to
+///
+/// If the owner node is unreachable, the transaction record is removed,
+/// the transaction itself has been aborted by the database when the connection was lost.
+pub async fn tx_force_rollback(tx_id: String, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let tx_info = get_tx_info(&tx_id, funs).await?;
+    if tx_info.as_ref().is_some_and(|tx_info| !tx_info.own_paths.starts_with(&ctx.own_paths)) {
+        return Err(TardisError::not_found("tx not exist", "404-spi-reldb-tx-not-exist"));
+    }
+    match route_tx(&tx_id, funs, ctx).await {
+        Ok(TxRoute::Local) => tx_rollback(tx_id, funs, ctx).await,
+        Ok(TxRoute::Remote(tx_info)) => {
+            if let Err(e) = forward::<Void, Void>(ForwardMethod::Delete, &tx_info.node_url, &format!("/ci/exec/tx?tx_id={tx_id}"), None, funs, ctx).await {
+                warn!("[SPI-Reldb] force rollback tx {} on node {} failed, remove the record: {}", tx_id, tx_info.node_url, e);
+                remove_tx_info(&tx_id, funs).await?;
+                // the transaction may still be open on the node, only its record is removed
+                return Err(TardisError::internal_error(
+                    &format!("force rollback tx {tx_id} on node {} failed, the record is removed: {}", tx_info.node_url, e.message),
+                    "500-spi-reldb-tx-force-rollback-failed",
+                ));
+            }
+            Ok(())
+        }
+        Err(e) => {
+            if tx_info.is_some() {
+                // stale record left by a node that has gone
+                remove_tx_info(&tx_id, funs).await
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+enum ForwardMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
+
+/// Where the transaction lives
+enum TxRoute {
+    Local,
+    Remote(ReldbTxInfoResp),
+}
+
+/// Check that the transaction belongs to the requester and find the node it lives on
+///
+/// Transactions of other own_paths are reported as not existing.
+async fn route_tx(tx_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<TxRoute> {
+    let tx_info = get_tx_info(tx_id, funs).await?;
+    if tx_info.as_ref().is_some_and(|tx_info| !tx_info.own_paths.starts_with(&ctx.own_paths)) {
+        return Err(TardisError::not_found("tx not exist", "404-spi-reldb-tx-not-exist"));
+    }
+    if TX_CONTAINER.read().await.contains_key(tx_id) {
+        return Ok(TxRoute::Local);
+    }
+    match tx_info {
+        Some(tx_info) if tx_info.node_url.is_empty() || funs.conf::<ReldbConfig>().tx_node_url.is_empty() => Err(TardisError::internal_error(
+            "tx belongs to another node which can not be reached, tx_node_url must be configured in multi-node deployments",
+            "500-spi-reldb-tx-node-url-missing",
+        )),
+        Some(tx_info) if tx_info.node_url != funs.conf::<ReldbConfig>().tx_node_url => Ok(TxRoute::Remote(tx_info)),
+        _ => Err(TardisError::bad_request("tx not exist", "")),
+    }
+}
+
+async fn get_tx_info(tx_id: &str, funs: &TardisFunsInst) -> TardisResult<Option<ReldbTxInfoResp>> {
+    match funs.cache().hget(&funs.conf::<ReldbConfig>().cache_key_tx_info, tx_id).await? {
+        Some(tx_info) => Ok(Some(TardisFuns::json.str_to_obj(&tx_info)?)),
+        None => Ok(None),
+    }
+}
+
+async fn remove_tx_info(tx_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    funs.cache().hdel(&funs.conf::<ReldbConfig>().cache_key_tx_info, tx_id).await?;
+    Ok(())
+}
+
+/// Forward the request to the node that owns the transaction or the cursor
+async fn forward<B, T>(method: ForwardMethod, node_url: &str, path: &str, body: Option<&B>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<T>
+where
+    B: Serialize,
+    T: ParseFromJSON + ToJSON + Serialize + DeserializeOwned + Send + Sync,
+{
+    trace!("[SPI-Reldb] forward {} to node {}", path, node_url);
+    let url = format!("{node_url}{path}");
+    let headers = vec![(
+        TardisFuns::fw_config().web_server().context_conf.context_header_name.clone(),
+        TardisFuns::crypto.base64.encode(TardisFuns::json.obj_to_string(ctx)?),
+    )];
+    let resp: TardisHttpResponse<TardisResp<T>> = match (method, body) {
+        (ForwardMethod::Post, Some(body)) => funs.web_client().post(&url, body, headers).await?,
+        (ForwardMethod::Put, Some(body)) => funs.web_client().put(&url, body, headers).await?,
+        (ForwardMethod::Get, None) => funs.web_client().get(&url, headers).await?,
+        (ForwardMethod::Delete, None) => funs.web_client().delete(&url, headers).await?,
+        _ => return Err(TardisError::internal_error("forward request body does not match the method", "")),
+    };
+    let Some(resp) = resp.body else {
+        return Err(TardisError::internal_error(&format!("forward to node {} failed, http status {}", node_url, resp.code), ""));
+    };
+    if !resp.code.starts_with("200") {
+        return Err(TardisError::custom(&resp.code, &resp.msg, ""));
+    }
+    resp.data.ok_or_else(|| TardisError::internal_error(&format!("forward to node {node_url} returned no data"), ""))
+}
+
 pub async fn ddl(ddl_req: &mut ReldbDdlReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
-    let conn = reldb_initializer::inst_conn(bs_inst).await?;
+    reldb_sql_guard_serv::check_sql(&ddl_req.sql, SqlUsage::Ddl, bs_inst, funs).await?;
+    let audit = SqlAudit::start(SqlUsage::Ddl, &ddl_req.sql, &ddl_req.params, None);
+    let conn = inst_guarded_conn(bs_inst, funs, ctx).await?;
     let params = parse_params(&ddl_req.params);
-    conn.execute_one(&ddl_req.sql, params).await?;
+    let resp = conn.execute_one(&ddl_req.sql, params).await;
+    audit.finish(resp.as_ref().map(|_| JsonValue::Null), funs, ctx);
+    resp?;
     conn.commit().await?;
     Ok(())
 }
 
 pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbDmlResp> {
+    if let Some(tx_id) = &tx_id {
+        if let TxRoute::Remote(tx_info) = route_tx(tx_id, funs, ctx).await? {
+            return forward(ForwardMethod::Post, &tx_info.node_url, &format!("/ci/exec/dml?tx_id={tx_id}"), Some(&*dml_req), funs, ctx).await;
+        }
+    }
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
+    reldb_sql_guard_serv::check_sql(&dml_req.sql, SqlUsage::Dml, bs_inst, funs).await?;
+    let audit = SqlAudit::start(SqlUsage::Dml, &dml_req.sql, &dml_req.params, tx_id.as_deref());
     let params = parse_params(&dml_req.params);
     let resp = if let Some(tx_id) = &tx_id {
         let tx_container = TX_CONTAINER.read().await;
@@ -101,18 +290,19 @@ pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, funs: &Tardis
             None => Err(TardisError::bad_request("tx not exist", "")),
         }
     } else {
-        let conn = reldb_initializer::inst_conn(bs_inst).await?;
+        let conn = inst_guarded_conn(bs_inst, funs, ctx).await?;
         let resp = conn.execute_one(&dml_req.sql, params).await;
         conn.commit().await?;
         resp
     };
+    audit.finish(resp.as_ref().map(|resp| json!({ "affected_rows": resp.rows_affected() })), funs, ctx);
     match resp {
         Ok(resp) => Ok(ReldbDmlResp {
             affected_rows: resp.rows_affected(),
         }),
         Err(e) => {
             if let Some(tx_id) = tx_id {
-                tx_rollback(tx_id).await?;
+                tx_rollback(tx_id, funs, ctx).await?;
             }
             trace!("[SPI-Reldb] dml error: {}", e);
             Err(e)
@@ -121,48 +311,335 @@ pub async fn dml(dml_req: &mut ReldbDmlReq, tx_id: Option<String>, funs: &Tardis
 }
 
 pub async fn dql(dql_req: &mut ReldbDqlReq, tx_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Vec<JsonValue>> {
+    if let Some(tx_id) = &tx_id {
+        if let TxRoute::Remote(tx_info) = route_tx(tx_id, funs, ctx).await? {
+            return forward(ForwardMethod::Put, &tx_info.node_url, &format!("/ci/exec/dql?tx_id={tx_id}"), Some(&*dql_req), funs, ctx).await;
+        }
+    }
     let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
     let bs_inst = inst_arc.inst::<TardisRelDBClient>();
+    reldb_sql_guard_serv::check_sql(&dql_req.sql, SqlUsage::Dql, bs_inst, funs).await?;
+    let audit = SqlAudit::start(SqlUsage::Dql, &dql_req.sql, &dql_req.params, tx_id.as_deref());
+    let max_rows = reldb_sql_guard_serv::max_rows(bs_inst.2, funs, ctx);
+    let sql = reldb_sql_guard_serv::guard_dql(&dql_req.sql, bs_inst.2, max_rows, funs, ctx);
     let params = parse_params(&dql_req.params);
     let resp = if let Some(tx_id) = tx_id {
         let tx_container = TX_CONTAINER.read().await;
         match tx_container.get(&tx_id) {
-            Some((conn, _, _)) => conn.query_all(&dql_req.sql, params).await,
+            Some((conn, _, _)) => conn.query_all(&sql, params).await,
             None => Err(TardisError::bad_request("tx not exist", "")),
         }
     } else {
-        let conn = reldb_initializer::inst_conn(bs_inst).await?;
-        let resp = conn.query_all(&dql_req.sql, params).await;
+        let conn = inst_guarded_conn(bs_inst, funs, ctx).await?;
+        reldb_sql_guard_serv::set_read_only(&conn, bs_inst.2).await?;
+        let resp = conn.query_all(&sql, params).await;
         conn.commit().await?;
         resp
-    }?;
-    let result = resp.iter().filter_map(|row| JsonValue::from_query_result_optional(row, "").transpose()).collect::<Result<Vec<JsonValue>, DbErr>>()?;
+    };
+    let resp = resp.and_then(|rows| reldb_sql_guard_serv::check_rows(&rows, max_rows).and_then(|_| rows_to_json(rows)));
+    audit.finish(resp.as_ref().map(|rows| json!({ "rows": rows.len() })), funs, ctx);
+    resp
+}
+
+fn rows_to_json(rows: Vec<QueryResult>) -> TardisResult<Vec<JsonValue>> {
+    let result = rows.iter().filter_map(|row| JsonValue::from_query_result_optional(row, "").transpose()).collect::<Result<Vec<JsonValue>, DbErr>>()?;
     Ok(result)
 }
 
+/// Execute the statements in order in one transaction
+///
+/// If [tx_id] is specified, the statements are executed in that transaction and it is rolled back on error,
+/// otherwise a new transaction is committed after all statements succeed.
+pub async fn batch(batch_req: &mut ReldbBatchReq, tx_id: Option<String>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbBatchResp> {
+    if let Some(tx_id) = &tx_id {
+        if let TxRoute::Remote(tx_info) = route_tx(tx_id, funs, ctx).await? {
+            return forward(
+                ForwardMethod::Post,
+                &tx_info.node_url,
+                &format!("/ci/exec/batch?tx_id={tx_id}"),
+                Some(&*batch_req),
+                funs,
+                ctx,
+            )
+            .await;
+        }
+    }
+    let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
+    let bs_inst = inst_arc.inst::<TardisRelDBClient>();
+    for (idx, statement) in batch_req.statements.iter().enumerate() {
+        let usage = match statement.kind {
+            ReldbStatementKind::Dml => SqlUsage::Dml,
+            ReldbStatementKind::Dql => SqlUsage::Dql,
+        };
+        if let Err(e) = reldb_sql_guard_serv::check_sql(&statement.sql, usage, bs_inst, funs).await {
+            return Err(TardisError::custom(&e.code, &format!("statement [{idx}] failed: {}", e.message), ""));
+        }
+    }
+    let resp = if let Some(tx_id) = &tx_id {
+        let tx_container = TX_CONTAINER.read().await;
+        match tx_container.get(tx_id) {
+            Some((conn, _, _)) => do_batch(batch_req, Some(tx_id), conn, bs_inst.2, funs, ctx).await,
+            None => Err(TardisError::bad_request("tx not exist", "")),
+        }
+    } else {
+        let conn = inst_guarded_conn(bs_inst, funs, ctx).await?;
+        let resp = do_batch(batch_req, None, &conn, bs_inst.2, funs, ctx).await;
+        if resp.is_ok() {
+            conn.commit().await?;
+        } else {
+            conn.rollback().await?;
+        }
+        resp
+    };
+    match resp {
+        Ok(results) => Ok(ReldbBatchResp { results }),
+        Err(e) => {
+            if let Some(tx_id) = tx_id {
+                tx_rollback(tx_id, funs, ctx).await?;
+            }
+            trace!("[SPI-Reldb] batch error: {}", e);
+            Err(e)
+        }
+    }
+}
+
+async fn do_batch(
+    batch_req: &ReldbBatchReq,
+    tx_id: Option<&str>,
+    conn: &TardisRelDBlConnection,
+    kind_code: &str,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<Vec<ReldbBatchStatementResp>> {
+    let max_rows = reldb_sql_guard_serv::max_rows(kind_code, funs, ctx);
+    let mut results = Vec::with_capacity(batch_req.statements.len());
+    for (idx, statement) in batch_req.statements.iter().enumerate() {
+        let params = parse_params(&statement.params);
+        let result = match statement.kind {
+            ReldbStatementKind::Dml => {
+                let audit = SqlAudit::start(SqlUsage::Dml, &statement.sql, &statement.params, tx_id);
+                let result = conn.execute_one(&statement.sql, params).await;
+                audit.finish(result.as_ref().map(|resp| json!({ "affected_rows": resp.rows_affected() })), funs, ctx);
+                result.map(|resp| ReldbBatchStatementResp {
+                    affected_rows: Some(resp.rows_affected()),
+                    rows: None,
+                })
+            }
+            ReldbStatementKind::Dql => {
+                let audit = SqlAudit::start(SqlUsage::Dql, &statement.sql, &statement.params, tx_id);
+                let result = conn
+                    .query_all(&reldb_sql_guard_serv::guard_dql(&statement.sql, kind_code, max_rows, funs, ctx), params)
+                    .await
+                    .and_then(|rows| reldb_sql_guard_serv::check_rows(&rows, max_rows).and_then(|_| rows_to_json(rows)));
+                audit.finish(result.as_ref().map(|rows| json!({ "rows": rows.len() })), funs, ctx);
+                result.map(|rows| ReldbBatchStatementResp {
+                    affected_rows: None,
+                    rows: Some(rows),
+                })
+            }
+        };
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => return Err(TardisError::custom(&e.code, &format!("statement [{idx}] failed: {}", e.message), "")),
+        }
+    }
+    Ok(results)
+}
+
+/// Open a cursor of the dql and fetch the first rows, so that large result sets are read page by page
+///
+/// The cursor holds a read transaction until all rows are fetched, it is closed or it expires.
+pub async fn dql_cursor_open(
+    dql_req: &mut ReldbDqlReq,
+    fetch_size: Option<u32>,
+    exp_sec: Option<u16>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<ReldbDqlCursorResp> {
+    let inst_arc = funs.init_bs(ctx, true, reldb_initializer::init_fun).await?;
+    let bs_inst = inst_arc.inst::<TardisRelDBClient>();
+    if bs_inst.2 != spi_constants::SPI_PG_KIND_CODE {
+        return Err(funs.bs_not_implemented(bs_inst.2));
+    }
+    reldb_sql_guard_serv::check_sql(&dql_req.sql, SqlUsage::Dql, bs_inst, funs).await?;
+    let conn = inst_guarded_conn(bs_inst, funs, ctx).await?;
+    reldb_sql_guard_serv::set_read_only(&conn, bs_inst.2).await?;
+    let audit = SqlAudit::start(SqlUsage::Dql, &dql_req.sql, &dql_req.params, None);
+    let params = parse_params(&dql_req.params);
+    let resp = conn.execute_one(&format!("DECLARE {CURSOR_NAME} NO SCROLL CURSOR FOR {}", dql_req.sql), params).await;
+    audit.finish(resp.as_ref().map(|_| json!({ "cursor": true })), funs, ctx);
+    if let Err(e) = resp {
+        conn.rollback().await?;
+        return Err(e);
+    }
+    let cursor_id = TardisFuns::crypto.hex.encode(TardisFuns::field.nanoid());
+    let exp_sec = exp_sec.unwrap_or(reldb_constants::DQL_CURSOR_DEFAULT_EXP_SEC);
+    let cursor_info = CursorInfo {
+        node_url: funs.conf::<ReldbConfig>().tx_node_url.clone(),
+        own_paths: ctx.own_paths.clone(),
+    };
+    funs.cache()
+        .hset(
+            &funs.conf::<ReldbConfig>().cache_key_cursor_info,
+            &cursor_id,
+            &TardisFuns::json.obj_to_string(&cursor_info)?,
+        )
+        .await?;
+    CURSOR_CONTAINER.write().await.insert(
+        cursor_id.clone(),
+        (conn, Utc::now().timestamp_millis() + exp_sec as i64 * 1000, exp_sec, ctx.own_paths.clone(), None),
+    );
+    dql_cursor_fetch(cursor_id, fetch_size, funs, ctx).await
+}
+
+/// Fetch the next rows of the cursor, the cursor is closed automatically after the last rows are fetched
+pub async fn dql_cursor_fetch(cursor_id: String, fetch_size: Option<u32>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<ReldbDqlCursorResp> {
+    if let Some(node_url) = route_cursor(&cursor_id, funs, ctx).await? {
+        let mut path = format!("/ci/exec/dql/cursor?cursor_id={cursor_id}");
+        if let Some(fetch_size) = fetch_size {
+            path = format!("{path}&fetch_size={fetch_size}");
+        }
+        return forward::<Void, _>(ForwardMethod::Get, &node_url, &path, None, funs, ctx).await;
+    }
+    let fetch_size = fetch_size.unwrap_or(reldb_constants::DQL_CURSOR_DEFAULT_FETCH_SIZE).clamp(1, reldb_constants::DQL_CURSOR_MAX_FETCH_SIZE);
+    let (exp_ts_at, read_ahead) = {
+        let mut cursor_container = CURSOR_CONTAINER.write().await;
+        match cursor_container.get_mut(&cursor_id) {
+            Some((_, exp_ts_at, exp_sec, _, read_ahead)) => {
+                *exp_ts_at = Utc::now().timestamp_millis() + *exp_sec as i64 * 1000;
+                (*exp_ts_at, read_ahead.take())
+            }
+            None => return Err(TardisError::bad_request("cursor not exist", "")),
+        }
+    };
+    // one more row is read ahead to tell whether there are more rows
+    let read_size = fetch_size + 1 - read_ahead.is_some() as u32;
+    let resp = {
+        let cursor_container = CURSOR_CONTAINER.read().await;
+        match cursor_container.get(&cursor_id) {
+            Some((conn, _, _, _, _)) => conn.query_all(&format!("FETCH FORWARD {read_size} FROM {CURSOR_NAME}"), vec![]).await,
+            None => return Err(TardisError::bad_request("cursor not exist", "")),
+        }
+    };
+    let mut rows = match resp.and_then(rows_to_json) {
+        Ok(rows) => read_ahead.into_iter().chain(rows).collect::<Vec<_>>(),
+        Err(e) => {
+            dql_cursor_close(cursor_id, funs, ctx).await?;
+            return Err(e);
+        }
+    };
+    let has_more = rows.len() as u32 > fetch_size;
+    if has_more {
+        let read_ahead = rows.pop();
+        if let Some((_, _, _, _, cursor_read_ahead)) = CURSOR_CONTAINER.write().await.get_mut(&cursor_id) {
+            *cursor_read_ahead = read_ahead;
+        }
+    } else {
+        dql_cursor_close(cursor_id.clone(), funs, ctx).await?;
+    }
+    Ok(ReldbDqlCursorResp {
+        cursor_id,
+        rows,
+        has_more,
+        exp_ts_at,
+    })
+}
+
+pub async fn dql_cursor_close(cursor_id: String, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    if let Some(node_url) = route_cursor(&cursor_id, funs, ctx).await? {
+        let _: Void = forward::<Void, _>(ForwardMethod::Delete, &node_url, &format!("/ci/exec/dql/cursor?cursor_id={cursor_id}"), None, funs, ctx).await?;
+        return Ok(());
+    }
+    let cursor = CURSOR_CONTAINER.write().await.remove(&cursor_id);
+    match cursor {
+        Some((conn, _, _, _, _)) => {
+            remove_cursor_info(&cursor_id, funs).await?;
+            // rollback ends the read transaction and releases the cursor
+            conn.rollback().await?
+        }
+        None => return Err(TardisError::bad_request("cursor not exist", "")),
+    }
+    Ok(())
+}
+
+/// Check that the cursor belongs to the requester, returns the url of the node holding the cursor if it is not this node
+///
+/// Cursors of other own_paths are reported as not existing.
+async fn route_cursor(cursor_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<String>> {
+    if let Some((_, _, _, own_paths, _)) = CURSOR_CONTAINER.read().await.get(cursor_id) {
+        return if own_paths.starts_with(&ctx.own_paths) {
+            Ok(None)
+        } else {
+            Err(TardisError::bad_request("cursor not exist", ""))
+        };
+    }
+    let cursor_info = match funs.cache().hget(&funs.conf::<ReldbConfig>().cache_key_cursor_info, cursor_id).await? {
+        Some(cursor_info) => TardisFuns::json.str_to_obj::<CursorInfo>(&cursor_info)?,
+        None => return Err(TardisError::bad_request("cursor not exist", "")),
+    };
+    if !cursor_info.own_paths.starts_with(&ctx.own_paths) {
+        Err(TardisError::bad_request("cursor not exist", ""))
+    } else if cursor_info.node_url.is_empty() || funs.conf::<ReldbConfig>().tx_node_url.is_empty() {
+        Err(TardisError::internal_error(
+            "cursor belongs to another node which can not be reached, tx_node_url must be configured in multi-node deployments",
+            "500-spi-reldb-tx-node-url-missing",
+        ))
+    } else if cursor_info.node_url != funs.conf::<ReldbConfig>().tx_node_url {
+        Ok(Some(cursor_info.node_url))
+    } else {
+        Err(TardisError::bad_request("cursor not exist", ""))
+    }
+}
+
+async fn remove_cursor_info(cursor_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    funs.cache().hdel(&funs.conf::<ReldbConfig>().cache_key_cursor_info, cursor_id).await?;
+    Ok(())
+}
+
 pub async fn clean(clean_interval_sec: u8) {
     tardis::tokio::spawn(async move {
         let mut interval = time::interval(Duration::from_secs(clean_interval_sec as u64));
+        let funs = crate::get_tardis_inst();
         loop {
             {
                 let mut tx_container = TX_CONTAINER.write().await;
                 let remove_keys = tx_container.iter().filter(|(_, v)| v.1 < Utc::now().timestamp_millis()).map(|(k, _)| k.to_string()).collect::<Vec<String>>();
                 for key in remove_keys {
                     trace!("[SPI-Reldb] tx {} expired", key);
+                    if let Err(e) = remove_tx_info(&key, &funs).await {
+                        warn!("[SPI-Reldb] remove tx {} record failed: {}", key, e);
+                    }
                     match tx_container.remove(&key) {
                         Some((conn, _, true)) => {
-                            conn.commit().await?;
+                            if let Err(e) = conn.commit().await {
+                                warn!("[SPI-Reldb] commit expired tx {} failed: {}", key, e);
+                            }
                         }
                         Some((conn, _, false)) => {
-                            conn.rollback().await?;
+                            if let Err(e) = conn.rollback().await {
+                                warn!("[SPI-Reldb] rollback expired tx {} failed: {}", key, e);
+                            }
                         }
                         _ => (),
                     }
                 }
             }
+            {
+                let mut cursor_container = CURSOR_CONTAINER.write().await;
+                let remove_keys = cursor_container.iter().filter(|(_, v)| v.1 < Utc::now().timestamp_millis()).map(|(k, _)| k.to_string()).collect::<Vec<String>>();
+                for key in remove_keys {
+                    trace!("[SPI-Reldb] cursor {} expired", key);
+                    if let Err(e) = remove_cursor_info(&key, &funs).await {
+                        warn!("[SPI-Reldb] remove cursor {} record failed: {}", key, e);
+                    }
+                    if let Some((conn, _, _, _, _)) = cursor_container.remove(&key) {
+                        if let Err(e) = conn.rollback().await {
+                            warn!("[SPI-Reldb] rollback expired cursor {} failed: {}", key, e);
+                        }
+                    }
+                }
+            }
             interval.tick().await;
         }
-        #[allow(unreachable_code)]
-        TardisResult::<()>::Ok(())
     });
 }
@@ -0,0 +1,487 @@
+//! SQL guardrails of the executed statements
+//!
+//! Statements are tokenized (comments, string literals and dollar-quoted bodies are skipped) and checked against the policy:
+//! statement types allowed by the endpoint, denied statement types, denied identifiers (e.g. `pg_` catalogs) and references to other schemas.
+use std::time::Instant;
+
+use bios_basic::spi::{spi_constants, spi_funs::TypedSpiBsInst, spi_initializer};
+use bios_sdk_invoke::clients::spi_log_client::{LogItemAddV2Req, SpiLogClient};
+use tardis::basic::dto::TardisContext;
+use tardis::basic::error::TardisError;
+use tardis::basic::result::TardisResult;
+use tardis::chrono::Utc;
+use tardis::db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection};
+use tardis::db::sea_orm::Value;
+use tardis::log::warn;
+use tardis::serde_json::{json, Value as JsonValue};
+use tardis::TardisFunsInst;
+
+use crate::reldb_config::{ReldbConfig, ReldbSqlGuardConfig};
+
+/// Which endpoint the statement is executed by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlUsage {
+    Ddl,
+    Dml,
+    Dql,
+}
+
+impl SqlUsage {
+    fn allowed_statements(&self) -> &'static [&'static str] {
+        match self {
+            SqlUsage::Ddl => &["CREATE", "ALTER", "COMMENT"],
+            SqlUsage::Dml => &["INSERT", "UPDATE", "DELETE", "MERGE", "WITH", "SELECT"],
+            SqlUsage::Dql => &["SELECT", "WITH", "VALUES"],
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SqlUsage::Ddl => "ddl",
+            SqlUsage::Dml => "dml",
+            SqlUsage::Dql => "dql",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    /// Keyword or identifier, quoted identifiers keep their case
+    Word {
+        value: String,
+        quoted: bool,
+    },
+    Symbol(char),
+}
+
+/// Check the statement against the guard policy
+pub async fn check_sql(sql: &str, usage: SqlUsage, bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, funs: &TardisFunsInst) -> TardisResult<()> {
+    let policy = &funs.conf::<ReldbConfig>().sql_guard;
+    if !policy.enabled {
+        return Ok(());
+    }
+    let qualifiers = check_statement(sql, usage, policy)?;
+    if policy.deny_cross_schema && !qualifiers.is_empty() {
+        check_qualifiers(&qualifiers, bs_inst).await?;
+    }
+    Ok(())
+}
+
+/// Check the statement without database access, returns the qualifiers of the two-part names (e.g. `a` of `a.b`)
+fn check_statement(sql: &str, usage: SqlUsage, policy: &ReldbSqlGuardConfig) -> TardisResult<Vec<String>> {
+    let tokens = tokenize(sql)?;
+    let mut statements = tokens.split(|token| *token == Token::Symbol(';')).filter(|statement| !statement.is_empty());
+    let Some(statement) = statements.next() else {
+        return Err(TardisError::bad_request("empty statement", "400-spi-reldb-statement-empty"));
+    };
+    if statements.next().is_some() {
+        return Err(TardisError::bad_request("multiple statements are not allowed", "400-spi-reldb-statement-multiple"));
+    }
+    let leading_words = statement
+        .iter()
+        .take(4)
+        .filter_map(|token| match token {
+            Token::Word { value, quoted: false } => Some(value.to_uppercase()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let Some(kind) = leading_words.first() else {
+        return Err(TardisError::bad_request("unrecognized statement", "400-spi-reldb-statement-not-allowed"));
+    };
+    if !usage.allowed_statements().contains(&kind.as_str()) {
+        return Err(TardisError::bad_request(
+            &format!("statement {kind} is not allowed in {}", usage.as_str()),
+            "400-spi-reldb-statement-not-allowed",
+        ));
+    }
+    if usage == SqlUsage::Dql {
+        check_read_only(statement)?;
+    }
+    let leading = leading_words.join(" ");
+    if let Some(denied) = policy.denied_statements.iter().map(|denied| denied.to_uppercase()).find(|denied| leading == *denied || leading.starts_with(&format!("{denied} "))) {
+        return Err(TardisError::bad_request(&format!("statement {denied} is denied"), "400-spi-reldb-statement-denied"));
+    }
+    let mut qualifiers = Vec::new();
+    for (idx, token) in statement.iter().enumerate() {
+        let Token::Word { value, .. } = token else {
+            continue;
+        };
+        let identifier = value.to_lowercase();
+        if let Some(prefix) = policy.denied_identifier_prefixes.iter().find(|prefix| identifier.starts_with(&prefix.to_lowercase())) {
+            return Err(TardisError::bad_request(
+                &format!("identifier {value} matching {prefix} is denied"),
+                "400-spi-reldb-identifier-denied",
+            ));
+        }
+        if statement.get(idx + 1) == Some(&Token::Symbol('.')) && matches!(statement.get(idx + 2), Some(Token::Word { .. })) {
+            if idx > 0 && statement.get(idx - 1) == Some(&Token::Symbol('.')) {
+                continue;
+            }
+            if statement.get(idx + 3) == Some(&Token::Symbol('.')) && matches!(statement.get(idx + 4), Some(Token::Word { .. })) {
+                return Err(TardisError::bad_request(
+                    &format!("cross schema or database reference {value} is denied"),
+                    "400-spi-reldb-cross-schema-denied",
+                ));
+            }
+            if !qualifiers.contains(value) {
+                qualifiers.push(value.clone());
+            }
+        }
+    }
+    Ok(qualifiers)
+}
+
+/// Reject the data-modifying parts of a dql, e.g. `WITH d AS (DELETE ... RETURNING *) SELECT ...` or `SELECT ... INTO t`
+fn check_read_only(statement: &[Token]) -> TardisResult<()> {
+    for (idx, token) in statement.iter().enumerate() {
+        let Token::Word { value, quoted: false } = token else {
+            continue;
+        };
+        let keyword = value.to_uppercase();
+        let modifying = match keyword.as_str() {
+            "INSERT" | "DELETE" | "MERGE" | "INTO" => true,
+            // row locking clauses: FOR UPDATE, FOR NO KEY UPDATE
+            "UPDATE" => {
+                !matches!(idx.checked_sub(1).and_then(|idx| statement.get(idx)), Some(Token::Word { value, quoted: false }) if value.eq_ignore_ascii_case("for") || value.eq_ignore_ascii_case("key"))
+            }
+            _ => false,
+        };
+        if modifying {
+            return Err(TardisError::bad_request(&format!("{keyword} is not allowed in dql"), "400-spi-reldb-statement-not-allowed"));
+        }
+    }
+    Ok(())
+}
+
+/// Reject the qualifiers that are schemas other than the schema of the tenant
+async fn check_qualifiers(qualifiers: &[String], bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>) -> TardisResult<()> {
+    if bs_inst.2 != spi_constants::SPI_PG_KIND_CODE {
+        return Ok(());
+    }
+    let own_schema = spi_initializer::common_pg::get_schema_name_from_ext(bs_inst.1).filter(|schema| !schema.is_empty()).unwrap_or_else(|| "public".to_string());
+    let placeholders = (1..=qualifiers.len()).map(|idx| format!("${idx}")).collect::<Vec<_>>().join(", ");
+    let result = bs_inst
+        .0
+        .conn()
+        .query_all(
+            &format!("SELECT nspname FROM pg_catalog.pg_namespace WHERE nspname IN ({placeholders})"),
+            qualifiers.iter().map(|qualifier| Value::from(qualifier.as_str())).collect(),
+        )
+        .await?;
+    for row in result {
+        let schema: String = row.try_get("", "nspname")?;
+        if schema != own_schema {
+            return Err(TardisError::bad_request(
+                &format!("cross schema reference {schema} is denied"),
+                "400-spi-reldb-cross-schema-denied",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn tokenize(sql: &str) -> TardisResult<Vec<Token>> {
+    let chars = sql.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    let unterminated = || TardisError::bad_request("unterminated literal or comment", "400-spi-reldb-statement-invalid");
+    while idx < chars.len() {
+        let c = chars[idx];
+        let next = chars.get(idx + 1).copied();
+        if c.is_whitespace() {
+            idx += 1;
+        } else if c == '-' && next == Some('-') {
+            while idx < chars.len() && chars[idx] != '\n' {
+                idx += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            // block comments can be nested in postgres
+            let mut depth = 0;
+            loop {
+                match (chars.get(idx), chars.get(idx + 1)) {
+                    (Some('/'), Some('*')) => {
+                        depth += 1;
+                        idx += 2;
+                    }
+                    (Some('*'), Some('/')) => {
+                        depth -= 1;
+                        idx += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    (Some(_), _) => idx += 1,
+                    (None, _) => return Err(unterminated()),
+                }
+            }
+        } else if c == '\'' {
+            let escapable = matches!(tokens.last(), Some(Token::Word { value, quoted: false }) if value.eq_ignore_ascii_case("e"));
+            idx += 1;
+            loop {
+                match (chars.get(idx), chars.get(idx + 1)) {
+                    (Some('\\'), _) if escapable => idx += 2,
+                    (Some('\''), Some('\'')) => idx += 2,
+                    (Some('\''), _) => {
+                        idx += 1;
+                        break;
+                    }
+                    (Some(_), _) => idx += 1,
+                    (None, _) => return Err(unterminated()),
+                }
+            }
+        } else if c == '"' || c == '`' {
+            let mut value = String::new();
+            idx += 1;
+            loop {
+                match (chars.get(idx), chars.get(idx + 1)) {
+                    (Some(&q), Some(&q2)) if q == c && q2 == c => {
+                        value.push(c);
+                        idx += 2;
+                    }
+                    (Some(&q), _) if q == c => {
+                        idx += 1;
+                        break;
+                    }
+                    (Some(&other), _) => {
+                        value.push(other);
+                        idx += 1;
+                    }
+                    (None, _) => return Err(unterminated()),
+                }
+            }
+            tokens.push(Token::Word { value, quoted: true });
+        } else if c == '$' {
+            // positional parameter ($1) or dollar-quoted string ($tag$...$tag$)
+            let mut end = idx + 1;
+            while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+            if chars.get(end) == Some(&'$') && !chars.get(idx + 1).is_some_and(|c| c.is_ascii_digit()) {
+                let tag = chars[idx..=end].iter().collect::<String>();
+                let body = chars[end + 1..].iter().collect::<String>();
+                let Some(pos) = body.find(&tag) else {
+                    return Err(unterminated());
+                };
+                idx = end + 1 + body[..pos].chars().count() + tag.chars().count();
+            } else {
+                idx = end;
+            }
+        } else if c.is_ascii_digit() {
+            while idx < chars.len() && (chars[idx].is_ascii_alphanumeric() || chars[idx] == '.') {
+                idx += 1;
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let mut value = String::new();
+            while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_' || chars[idx] == '$') {
+                value.push(chars[idx]);
+                idx += 1;
+            }
+            tokens.push(Token::Word { value, quoted: false });
+        } else {
+            tokens.push(Token::Symbol(c));
+            idx += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Max rows of the dql result of the tenant, `None` means unlimited
+///
+/// Only applied to postgresql with the guard enabled: mysql rejects the wrapper of a dql with duplicate column names
+/// and does not keep the order of the wrapped dql.
+pub fn max_rows(kind_code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> Option<u32> {
+    let policy = &funs.conf::<ReldbConfig>().sql_guard;
+    if !policy.enabled || kind_code != spi_constants::SPI_PG_KIND_CODE {
+        return None;
+    }
+    let max_rows = policy.tenant_policies.get(&ctx.ak).and_then(|tenant| tenant.max_rows).unwrap_or(policy.max_rows);
+    (max_rows > 0).then_some(max_rows)
+}
+
+/// Statement timeout of the tenant in milliseconds, 0 means no timeout
+fn statement_timeout_ms(funs: &TardisFunsInst, ctx: &TardisContext) -> u32 {
+    let policy = &funs.conf::<ReldbConfig>().sql_guard;
+    if !policy.enabled {
+        return 0;
+    }
+    policy.tenant_policies.get(&ctx.ak).and_then(|tenant| tenant.statement_timeout_ms).unwrap_or(policy.statement_timeout_ms)
+}
+
+/// Guard the dql of the tenant
+///
+/// For postgresql the dql is wrapped to fetch at most one row more than the limit, so that exceeding the limit can be detected.
+/// For mysql the statement timeout is attached as an optimizer hint, which only lives as long as the statement.
+pub fn guard_dql(sql: &str, kind_code: &str, max_rows: Option<u32>, funs: &TardisFunsInst, ctx: &TardisContext) -> String {
+    if kind_code == spi_constants::SPI_PG_KIND_CODE {
+        limit_dql(sql, max_rows)
+    } else {
+        hint_dql_timeout(sql, statement_timeout_ms(funs, ctx))
+    }
+}
+
+fn limit_dql(sql: &str, max_rows: Option<u32>) -> String {
+    match max_rows {
+        // the line break keeps a trailing line comment from swallowing the wrapper
+        Some(max_rows) => format!("SELECT * FROM ({}\n) AS bios_limited LIMIT {}", sql.trim().trim_end_matches(';'), max_rows as u64 + 1),
+        None => sql.to_string(),
+    }
+}
+
+/// Attach the `MAX_EXECUTION_TIME` hint to the leading `SELECT`, other forms of the dql run without the timeout
+fn hint_dql_timeout(sql: &str, timeout_ms: u32) -> String {
+    let trimmed = sql.trim_start();
+    match (trimmed.get(..6), trimmed.get(6..)) {
+        (Some(keyword), Some(rest)) if timeout_ms > 0 && keyword.eq_ignore_ascii_case("select") && rest.starts_with(char::is_whitespace) => {
+            format!("{keyword} /*+ MAX_EXECUTION_TIME({timeout_ms}) */{rest}")
+        }
+        _ => sql.to_string(),
+    }
+}
+
+pub fn check_rows<T>(rows: &[T], max_rows: Option<u32>) -> TardisResult<()> {
+    if let Some(max_rows) = max_rows {
+        if rows.len() > max_rows as usize {
+            return Err(TardisError::bad_request(
+                &format!("the result exceeds the max rows {max_rows}, please use the dql cursor"),
+                "400-spi-reldb-too-many-rows",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Set the statement timeout of the tenant to the transaction of the connection
+///
+/// Only postgresql is set here, with a transaction scoped setting that can not leak to other users of the pooled connection,
+/// mysql has no transaction scoped variables and gets the timeout per dql from [`guard_dql`].
+pub async fn set_statement_timeout(conn: &TardisRelDBlConnection, kind_code: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let timeout_ms = statement_timeout_ms(funs, ctx);
+    if timeout_ms > 0 && kind_code == spi_constants::SPI_PG_KIND_CODE {
+        conn.execute_one(&format!("SET LOCAL statement_timeout = {timeout_ms}"), vec![]).await?;
+    }
+    Ok(())
+}
+
+/// Make the transaction of the connection read only, so that the dql can not modify data in any form
+pub async fn set_read_only(conn: &TardisRelDBlConnection, kind_code: &str) -> TardisResult<()> {
+    // mysql only allows the access mode to be set before the transaction starts, the statement check is relied on instead
+    if kind_code == spi_constants::SPI_PG_KIND_CODE {
+        conn.execute_one("SET TRANSACTION READ ONLY", vec![]).await?;
+    }
+    Ok(())
+}
+
+/// Audit log of one executed statement, written to spi-log when the statement finishes
+pub struct SqlAudit {
+    usage: SqlUsage,
+    sql: String,
+    params: JsonValue,
+    tx_id: Option<String>,
+    start: Instant,
+}
+
+impl SqlAudit {
+    pub fn start(usage: SqlUsage, sql: &str, params: &JsonValue, tx_id: Option<&str>) -> Self {
+        SqlAudit {
+            usage,
+            sql: sql.to_string(),
+            params: params.clone(),
+            tx_id: tx_id.map(|tx_id| tx_id.to_string()),
+            start: Instant::now(),
+        }
+    }
+
+    /// Write the audit log asynchronously, failures of logging do not affect the statement
+    pub fn finish(self, outcome: Result<JsonValue, &TardisError>, funs: &TardisFunsInst, ctx: &TardisContext) {
+        let tag = funs.conf::<ReldbConfig>().sql_guard.audit_log_tag.clone();
+        if tag.is_empty() {
+            return;
+        }
+        let (success, outcome, error) = match outcome {
+            Ok(outcome) => (true, outcome, None),
+            Err(e) => (false, JsonValue::Null, Some(format!("{}: {}", e.code, e.message))),
+        };
+        let req = LogItemAddV2Req {
+            tag,
+            content: json!({
+                "sql": self.sql,
+                "params": self.params,
+                "tx_id": self.tx_id,
+                "success": success,
+                "outcome": outcome,
+                "error": error,
+                "elapsed_ms": self.start.elapsed().as_millis() as u64,
+            }),
+            kind: Some("reldb_sql".to_string()),
+            key: Some(ctx.ak.clone()),
+            op: Some(self.usage.as_str().to_string()),
+            ts: Some(Utc::now()),
+            owner: Some(ctx.owner.clone()),
+            own_paths: Some(ctx.own_paths.clone()),
+            ..Default::default()
+        };
+        let ctx = ctx.clone();
+        tardis::tokio::spawn(async move {
+            let funs = crate::get_tardis_inst();
+            if let Err(e) = SpiLogClient::addv2(req, &funs, &ctx).await {
+                warn!("[SPI-Reldb] audit log failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reldb_config::ReldbSqlGuardConfig;
+    use crate::serv::reldb_sql_guard_serv::{check_statement, hint_dql_timeout, limit_dql, SqlUsage};
+
+    #[test]
+    fn test_check_statement() {
+        let policy = ReldbSqlGuardConfig {
+            enabled: true,
+            denied_statements: ["DROP", "TRUNCATE", "CREATE SCHEMA", "CREATE FUNCTION", "CREATE OR REPLACE FUNCTION"].into_iter().map(String::from).collect(),
+            ..Default::default()
+        };
+        assert!(check_statement("create table test_table (id int primary key, name varchar)", SqlUsage::Ddl, &policy).is_ok());
+        assert!(check_statement("drop table test_table", SqlUsage::Ddl, &policy).is_err());
+        assert!(check_statement("select * from test_table", SqlUsage::Ddl, &policy).is_err());
+        assert!(check_statement("create schema other", SqlUsage::Ddl, &policy).is_err());
+        assert!(check_statement("create or replace function f() returns int as $$ select 1 $$ language sql", SqlUsage::Ddl, &policy).is_err());
+        assert!(check_statement("create or replace view v as select 1", SqlUsage::Ddl, &policy).is_ok());
+        assert!(check_statement("insert into t (id) values ($1)", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("select 1; drop table t", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("select 1;", SqlUsage::Dql, &policy).is_ok());
+        assert!(check_statement("with d as (delete from t returning *) select * from d", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("with u as (update t set a = 1 returning *) select * from u", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("select * into t2 from t", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("with c as (select * from t) select * from c for no key update", SqlUsage::Dql, &policy).is_ok());
+        assert!(check_statement("select 'insert into' as a from t for update", SqlUsage::Dql, &policy).is_ok());
+        assert!(check_statement("with d as (delete from t returning *) select * from d", SqlUsage::Dml, &policy).is_ok());
+        assert!(check_statement("select * from pg_catalog.pg_tables", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("select pg_sleep(10)", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("select * from information_schema.tables", SqlUsage::Dql, &policy).is_err());
+        assert!(check_statement("select * from db.other.t", SqlUsage::Dql, &policy).is_err());
+        // literals and comments are not inspected
+        assert!(check_statement("select 'drop table t; pg_sleep' as a -- pg_sleep\n from t", SqlUsage::Dql, &policy).is_ok());
+        assert!(check_statement("select $body$ ; pg_sleep $body$, e'\\' pg_' /* /* pg_ */ */ from t", SqlUsage::Dql, &policy).is_ok());
+        assert!(check_statement("select 'unterminated", SqlUsage::Dql, &policy).is_err());
+        assert_eq!(
+            check_statement("select a.id, b.name from t1 a join other.t2 b on a.id = b.id", SqlUsage::Dql, &policy).unwrap(),
+            vec!["a".to_string(), "b".to_string(), "other".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_limit_dql() {
+        assert_eq!(limit_dql("select * from t;", Some(10)), "SELECT * FROM (select * from t\n) AS bios_limited LIMIT 11");
+        assert_eq!(limit_dql("select * from t", None), "select * from t");
+    }
+
+    #[test]
+    fn test_hint_dql_timeout() {
+        assert_eq!(hint_dql_timeout(" select a from t", 100), "select /*+ MAX_EXECUTION_TIME(100) */ a from t");
+        assert_eq!(hint_dql_timeout("select a from t", 0), "select a from t");
+        assert_eq!(hint_dql_timeout("selection", 100), "selection");
+        assert_eq!(hint_dql_timeout("with a as (select 1) select * from a", 100), "with a as (select 1) select * from a");
+    }
+}
@@ -1,7 +1,10 @@
 use std::time::Duration;
 
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_reldb::dto::reldb_exec_dto::{ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlReq, ReldbTxResp};
+use bios_spi_reldb::dto::reldb_exec_dto::{
+    ReldbBatchReq, ReldbBatchResp, ReldbBatchStatementReq, ReldbDdlReq, ReldbDmlReq, ReldbDmlResp, ReldbDqlCursorResp, ReldbDqlReq, ReldbStatementKind, ReldbTxInfoResp,
+    ReldbTxResp,
+};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::log::info;
@@ -25,6 +28,10 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     test_tx_error(client).await?;
     test_tx_auto_commit(client).await?;
     test_tx_auto_rollback(client).await?;
+    test_batch(client).await?;
+    test_dql_cursor(client).await?;
+    test_tx_list(client).await?;
+    test_sql_guard(client).await?;
 
     Ok(())
 }
@@ -381,3 +388,285 @@ pub async fn test_tx_auto_rollback(client: &mut TestHttpClient) -> TardisResult<
 
     Ok(())
 }
+
+pub async fn test_batch(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_batch】");
+
+    let _: Void = client
+        .post(
+            "/ci/exec/ddl",
+            &ReldbDdlReq {
+                sql: "create table test_batch (id int primary key, name varchar)".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    let batch_resp: ReldbBatchResp = client
+        .post(
+            "/ci/exec/batch",
+            &ReldbBatchReq {
+                statements: vec![
+                    ReldbBatchStatementReq {
+                        kind: ReldbStatementKind::Dml,
+                        sql: "insert into test_batch (id,name) values ($1,$2),($3,$4)".to_string(),
+                        params: json!([1, "批量1", 2, "批量2"]),
+                    },
+                    ReldbBatchStatementReq {
+                        kind: ReldbStatementKind::Dml,
+                        sql: "update test_batch set name = $1 where id = $2".to_string(),
+                        params: json!(["批量3", 2]),
+                    },
+                    ReldbBatchStatementReq {
+                        kind: ReldbStatementKind::Dql,
+                        sql: "select * from test_batch order by id".to_string(),
+                        params: json!([]),
+                    },
+                ],
+            },
+        )
+        .await;
+    assert_eq!(batch_resp.results.len(), 3);
+    assert_eq!(batch_resp.results[0].affected_rows, Some(2));
+    assert_eq!(batch_resp.results[1].affected_rows, Some(1));
+    assert_eq!(json!(batch_resp.results[2].rows).to_string(), r#"[{"id":1,"name":"批量1"},{"id":2,"name":"批量3"}]"#);
+
+    // the whole batch is rolled back if any statement fails
+    let batch_resp: TardisResp<ReldbBatchResp> = client
+        .post_resp(
+            "/ci/exec/batch",
+            &ReldbBatchReq {
+                statements: vec![
+                    ReldbBatchStatementReq {
+                        kind: ReldbStatementKind::Dml,
+                        sql: "insert into test_batch (id,name) values ($1,$2)".to_string(),
+                        params: json!([3, "批量4"]),
+                    },
+                    ReldbBatchStatementReq {
+                        kind: ReldbStatementKind::Dml,
+                        sql: "insert into test_batch (id,name) values ($1,$2)".to_string(),
+                        params: json!([1, "批量5"]),
+                    },
+                ],
+            },
+        )
+        .await;
+    assert!(batch_resp.msg.contains("statement [1] failed"));
+    let dql_resp: Value = client
+        .put(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select count(*) as total from test_batch".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.to_string(), r#"[{"total":2}]"#);
+
+    Ok(())
+}
+
+pub async fn test_dql_cursor(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_dql_cursor】");
+
+    let _: Void = client
+        .post(
+            "/ci/exec/ddl",
+            &ReldbDdlReq {
+                sql: "create table test_dql_cursor (id int primary key, name varchar)".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    let _: ReldbDmlResp = client
+        .post(
+            "/ci/exec/dml",
+            &ReldbDmlReq {
+                sql: "insert into test_dql_cursor (id,name) select i, 'name' || i from generate_series(1, 5) as i".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+
+    let cursor_resp: ReldbDqlCursorResp = client
+        .post(
+            "/ci/exec/dql/cursor?fetch_size=2",
+            &ReldbDqlReq {
+                sql: "select * from test_dql_cursor where id > $1 order by id".to_string(),
+                params: json!([0]),
+            },
+        )
+        .await;
+    assert!(cursor_resp.has_more);
+    assert_eq!(json!(cursor_resp.rows).to_string(), r#"[{"id":1,"name":"name1"},{"id":2,"name":"name2"}]"#);
+    let cursor_id = cursor_resp.cursor_id;
+    let cursor_resp: ReldbDqlCursorResp = client.get(&format!("/ci/exec/dql/cursor?cursor_id={cursor_id}&fetch_size=2")).await;
+    assert!(cursor_resp.has_more);
+    assert_eq!(json!(cursor_resp.rows).to_string(), r#"[{"id":3,"name":"name3"},{"id":4,"name":"name4"}]"#);
+    let cursor_resp: ReldbDqlCursorResp = client.get(&format!("/ci/exec/dql/cursor?cursor_id={cursor_id}&fetch_size=2")).await;
+    assert!(!cursor_resp.has_more);
+    assert_eq!(json!(cursor_resp.rows).to_string(), r#"[{"id":5,"name":"name5"}]"#);
+    // the cursor is closed after the last rows are fetched
+    let cursor_resp: TardisResp<ReldbDqlCursorResp> = client.get_resp(&format!("/ci/exec/dql/cursor?cursor_id={cursor_id}")).await;
+    assert_eq!(cursor_resp.code, "400");
+    // no more rows is reported exactly when the rows end at a full page
+    let cursor_resp: ReldbDqlCursorResp = client
+        .post(
+            "/ci/exec/dql/cursor?fetch_size=2",
+            &ReldbDqlReq {
+                sql: "select * from test_dql_cursor where id > $1 order by id".to_string(),
+                params: json!([1]),
+            },
+        )
+        .await;
+    assert!(cursor_resp.has_more);
+    let cursor_resp: ReldbDqlCursorResp = client.get(&format!("/ci/exec/dql/cursor?cursor_id={}&fetch_size=2", cursor_resp.cursor_id)).await;
+    assert!(!cursor_resp.has_more);
+    assert_eq!(json!(cursor_resp.rows).to_string(), r#"[{"id":4,"name":"name4"},{"id":5,"name":"name5"}]"#);
+
+    let cursor_resp: ReldbDqlCursorResp = client
+        .post(
+            "/ci/exec/dql/cursor?fetch_size=1",
+            &ReldbDqlReq {
+                sql: "select * from test_dql_cursor order by id".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert!(cursor_resp.has_more);
+    // the cursor can not be read or closed by other own_paths
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+    let other_resp: TardisResp<ReldbDqlCursorResp> = client.get_resp(&format!("/ci/exec/dql/cursor?cursor_id={}", cursor_resp.cursor_id)).await;
+    assert_eq!(other_resp.code, "400");
+    let other_resp: TardisResp<Void> = client.delete_resp(&format!("/ci/exec/dql/cursor?cursor_id={}", cursor_resp.cursor_id)).await;
+    assert_eq!(other_resp.code, "400");
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+    client.delete(&format!("/ci/exec/dql/cursor?cursor_id={}", cursor_resp.cursor_id)).await;
+
+    Ok(())
+}
+
+pub async fn test_tx_list(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_tx_list】");
+
+    let tx_resp: ReldbTxResp = client.get("/ci/exec/tx?auto_commit=false&exp_sec=60").await;
+    let tx_id = tx_resp.tx_id;
+    let tx_list: Vec<ReldbTxInfoResp> = client.get("/ci/exec/tx/list").await;
+    let tx_info = tx_list.iter().find(|tx_info| tx_info.tx_id == tx_id).unwrap();
+    assert_eq!(tx_info.own_paths, "t1/app001");
+    assert!(!tx_info.auto_commit);
+    assert_eq!(tx_info.exp_ts_at, tx_resp.exp_ts_at);
+
+    // the transaction can not be committed or rolled back by other own_paths
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app002".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app002".to_string(),
+        ..Default::default()
+    })?;
+    let other_resp: TardisResp<Void> = client.put_resp(&format!("/ci/exec/tx?tx_id={}", tx_id), &Void {}).await;
+    assert_eq!(other_resp.code, "404-spi-reldb-tx-not-exist");
+    let other_resp: TardisResp<Void> = client.delete_resp(&format!("/ci/exec/tx?tx_id={}", tx_id)).await;
+    assert_eq!(other_resp.code, "404-spi-reldb-tx-not-exist");
+    client.set_auth(&TardisContext {
+        own_paths: "t1/app001".to_string(),
+        ak: "".to_string(),
+        roles: vec![],
+        groups: vec![],
+        owner: "app001".to_string(),
+        ..Default::default()
+    })?;
+
+    client.delete(&format!("/ci/exec/tx/force?tx_id={}", tx_id)).await;
+    let tx_list: Vec<ReldbTxInfoResp> = client.get("/ci/exec/tx/list").await;
+    assert!(!tx_list.iter().any(|tx_info| tx_info.tx_id == tx_id));
+    let dql_resp: TardisResp<Value> = client
+        .put_resp(
+            &format!("/ci/exec/dql?tx_id={}", tx_id),
+            &ReldbDqlReq {
+                sql: "select 1".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.code, "400");
+
+    Ok(())
+}
+
+pub async fn test_sql_guard(client: &mut TestHttpClient) -> TardisResult<()> {
+    info!("【test_sql_guard】");
+
+    let ddl_resp: TardisResp<Void> = client
+        .post_resp(
+            "/ci/exec/ddl",
+            &ReldbDdlReq {
+                sql: "drop table test_table".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(ddl_resp.code, "400-spi-reldb-statement-denied");
+
+    let dml_resp: TardisResp<ReldbDmlResp> = client
+        .post_resp(
+            "/ci/exec/dml",
+            &ReldbDmlReq {
+                sql: "delete from test_table; drop table test_table".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(dml_resp.code, "400-spi-reldb-statement-multiple");
+
+    let dql_resp: TardisResp<Value> = client
+        .put_resp(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select * from pg_class".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.code, "400-spi-reldb-identifier-denied");
+
+    let dql_resp: TardisResp<Value> = client
+        .put_resp(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select * from public.test_table".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.code, "400-spi-reldb-cross-schema-denied");
+
+    // literals are not inspected
+    let dql_resp: Value = client
+        .put(
+            "/ci/exec/dql",
+            &ReldbDqlReq {
+                sql: "select t.name from test_table t where t.name <> 'drop table; pg_class' -- select * from pg_class".to_string(),
+                params: json!([]),
+            },
+        )
+        .await;
+    assert_eq!(dql_resp.to_string(), r#"[{"name":"大大"}]"#);
+
+    Ok(())
+}
@@ -1,5 +1,7 @@
 CREATE EXTENSION IF NOT EXISTS pg_trgm;
 CREATE EXTENSION IF NOT EXISTS zhparser;
+-- required when vector search is enabled (`embedding.dimensions` > 0), needs the pgvector package
+-- CREATE EXTENSION IF NOT EXISTS vector;
 
 DO
 $$BEGIN
@@ -1 +1,2 @@
+pub mod search_ci_dict_api;
 pub mod search_ci_item_api;
@@ -0,0 +1,67 @@
+use tardis::basic::dto::TardisContext;
+use tardis::basic::result::TardisResult;
+use tardis::web::context_extractor::TardisContextExtractor;
+use tardis::web::poem_openapi;
+use tardis::web::poem_openapi::param::{Path, Query};
+use tardis::web::poem_openapi::payload::Json;
+use tardis::web::web_resp::{TardisApiResult, TardisResp};
+
+use crate::dto::search_dict_dto::{SearchDictResp, SearchDictSaveReq};
+use crate::dto::search_item_dto::SearchReindexReq;
+use crate::serv::{search_dict_serv, search_reindex_serv};
+
+#[derive(Clone)]
+pub struct SearchCiDictApi;
+
+/// Interface Console Search Dictionary API
+#[poem_openapi::OpenApi(prefix_path = "/ci/dict", tag = "bios_basic::ApiTag::Interface")]
+impl SearchCiDictApi {
+    /// Save Dictionary By Tag, Returns The Reindex Job Id
+    ///
+    /// 保存 tag 的词典，返回重建索引任务 id
+    #[oai(path = "/:tag", method = "put")]
+    async fn save(&self, tag: Path<String>, save_req: Json<SearchDictSaveReq>, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
+        let funs = crate::get_tardis_inst();
+        // the running job would apply the old dictionary to a part of the items
+        search_reindex_serv::check_no_running_job(&tag.0, &funs, &ctx.0).await?;
+        search_dict_serv::save(&tag.0, &save_req.0.entries, &funs, &ctx.0).await?;
+        let job_id = if save_req.0.reindex.unwrap_or(true) {
+            Some(do_reindex(&tag.0, &ctx.0).await?)
+        } else {
+            None
+        };
+        TardisResp::ok(job_id)
+    }
+
+    /// Get Dictionary By Tag
+    ///
+    /// 获取 tag 的词典
+    #[oai(path = "/:tag", method = "get")]
+    async fn get(&self, tag: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<SearchDictResp> {
+        let funs = crate::get_tardis_inst();
+        let result = search_dict_serv::get(&tag.0, &funs, &ctx.0).await?;
+        TardisResp::ok(result)
+    }
+
+    /// Delete Dictionary By Tag, Returns The Reindex Job Id
+    ///
+    /// 删除 tag 的词典，返回重建索引任务 id
+    #[oai(path = "/:tag", method = "delete")]
+    async fn delete(&self, tag: Path<String>, reindex: Query<Option<bool>>, ctx: TardisContextExtractor) -> TardisApiResult<Option<String>> {
+        let funs = crate::get_tardis_inst();
+        search_reindex_serv::check_no_running_job(&tag.0, &funs, &ctx.0).await?;
+        search_dict_serv::delete(&tag.0, &funs, &ctx.0).await?;
+        let job_id = if reindex.0.unwrap_or(true) { Some(do_reindex(&tag.0, &ctx.0).await?) } else { None };
+        TardisResp::ok(job_id)
+    }
+}
+
+/// Rebuild the tag to apply the dictionary to the existing items
+async fn do_reindex(tag: &str, ctx: &TardisContext) -> TardisResult<String> {
+    let funs = crate::get_tardis_inst();
+    let global_ctx = TardisContext {
+        own_paths: "".to_string(),
+        ..ctx.clone()
+    };
+    search_reindex_serv::reindex(tag, &SearchReindexReq::default(), &funs, &global_ctx).await
+}
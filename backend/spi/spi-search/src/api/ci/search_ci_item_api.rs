@@ -11,10 +11,10 @@ use tardis::{serde_json, tokio};
 
 use crate::dto::search_item_dto::{
     GroupSearchItemSearchReq, GroupSearchItemSearchResp, MultipleSearchItemSearchReq, SearchExportDataReq, SearchExportDataResp, SearchImportDataReq, SearchItemAddReq,
-    SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchReq, SearchItemSearchResp, SearchQueryMetricsReq,
-    SearchQueryMetricsResp, SearchSaveItemReq,
+    SearchItemFacetSearchReq, SearchItemFacetSearchResp, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchReq,
+    SearchItemSearchResp, SearchQueryMetricsReq, SearchQueryMetricsResp, SearchReindexJobResp, SearchReindexReq, SearchSaveItemReq,
 };
-use crate::serv::search_item_serv;
+use crate::serv::{search_item_serv, search_reindex_serv};
 use tardis::log::warn;
 
 #[derive(Clone)]
@@ -27,6 +27,7 @@ impl SearchCiItemApi {
     #[oai(path = "/", method = "put")]
     async fn add(&self, mut add_req: Json<SearchItemAddReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&add_req.0.tag, &funs, &ctx.0).await?;
         search_item_serv::add(&mut add_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
@@ -35,6 +36,7 @@ impl SearchCiItemApi {
     #[oai(path = "/:tag/:key", method = "put")]
     async fn modify(&self, tag: Path<String>, key: Path<String>, mut modify_req: Json<SearchItemModifyReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&tag.0, &funs, &ctx.0).await?;
         search_item_serv::modify(&tag.0, &key.0, &mut modify_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
@@ -43,6 +45,7 @@ impl SearchCiItemApi {
     #[oai(path = "/:tag/:key", method = "delete")]
     async fn delete(&self, tag: Path<String>, key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&tag.0, &funs, &ctx.0).await?;
         search_item_serv::delete(&tag.0, &key.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
@@ -51,6 +54,7 @@ impl SearchCiItemApi {
     #[oai(path = "/:tag/save", method = "put")]
     async fn save(&self, tag: Path<String>, mut save_req: Json<SearchSaveItemReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&tag.0, &funs, &ctx.0).await?;
         search_item_serv::save(&tag.0, &mut save_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
@@ -59,6 +63,7 @@ impl SearchCiItemApi {
     #[oai(path = "/:tag/batch/save", method = "put")]
     async fn batch_save(&self, tag: Path<String>, mut batch_req: Json<Vec<SearchSaveItemReq>>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let mut funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&tag.0, &funs, &ctx.0).await?;
         funs.begin().await?;
         search_item_serv::batch_save(&tag.0, &mut batch_req.0, &funs, &ctx.0).await?;
         funs.commit().await?;
@@ -69,6 +74,7 @@ impl SearchCiItemApi {
     #[oai(path = "/:tag/batch/delete", method = "put")]
     async fn batch_delete(&self, tag: Path<String>, batch_req: Json<Vec<String>>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let mut funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&tag.0, &funs, &ctx.0).await?;
         funs.begin().await?;
         search_item_serv::batch_delete(&tag.0, batch_req.0, &funs, &ctx.0).await?;
         funs.commit().await?;
@@ -82,6 +88,7 @@ impl SearchCiItemApi {
     #[oai(path = "/:tag/ownership", method = "delete")]
     async fn delete_by_ownership(&self, tag: Path<String>, own_paths: Query<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
         let funs = crate::get_tardis_inst();
+        search_reindex_serv::check_fence(&tag.0, &funs, &ctx.0).await?;
         search_item_serv::delete_by_ownership(&tag.0, &own_paths.0, &funs, &ctx.0).await?;
         TardisResp::ok(Void {})
     }
@@ -94,6 +101,14 @@ impl SearchCiItemApi {
         TardisResp::ok(resp)
     }
 
+    /// Search Items with the facet counts and suggestions
+    #[oai(path = "/search/facet", method = "put")]
+    async fn facet_search(&self, mut search_req: Json<SearchItemFacetSearchReq>, ctx: TardisContextExtractor) -> TardisApiResult<SearchItemFacetSearchResp> {
+        let funs = crate::get_tardis_inst();
+        let resp = search_item_serv::facet_search(&mut search_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
     ///Group Search Items
     #[oai(path = "/group/search", method = "put")]
     async fn group_search(&self, mut search_req: Json<GroupSearchItemSearchReq>, ctx: TardisContextExtractor) -> TardisApiResult<Vec<GroupSearchItemSearchResp>> {
@@ -138,6 +153,30 @@ impl SearchCiItemApi {
         TardisResp::ok(Void {})
     }
 
+    /// Reindex By Tag Without Downtime, Returns The Job Id
+    ///
+    /// 通过指定 tag 不停机重建索引，返回任务 id
+    #[oai(path = "/:tag/reindex", method = "put")]
+    async fn reindex(&self, tag: Path<String>, reindex_req: Json<SearchReindexReq>, ctx: TardisContextExtractor) -> TardisApiResult<String> {
+        let funs = crate::get_tardis_inst();
+        let global_ctx = TardisContext {
+            own_paths: "".to_string(),
+            ..ctx.0.clone()
+        };
+        let job_id = search_reindex_serv::reindex(&tag.0, &reindex_req.0, &funs, &global_ctx).await?;
+        TardisResp::ok(job_id)
+    }
+
+    /// Get Reindex Job
+    ///
+    /// 获取重建索引任务
+    #[oai(path = "/reindex/:job_id", method = "get")]
+    async fn get_reindex_job(&self, job_id: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<SearchReindexJobResp> {
+        let funs = crate::get_tardis_inst();
+        let job = search_reindex_serv::get_job(&job_id.0, &funs, &ctx.0).await?;
+        TardisResp::ok(job)
+    }
+
     #[oai(path = "/export", method = "put")]
     async fn export_data(&self, export_req: Json<SearchExportDataReq>, ctx: TardisContextExtractor) -> TardisApiResult<SearchExportDataResp> {
         let funs = crate::get_tardis_inst();
@@ -149,6 +188,9 @@ impl SearchCiItemApi {
     #[oai(path = "/import", method = "put")]
     async fn import_data(&self, import_req: Json<SearchImportDataReq>, ctx: TardisContextExtractor) -> TardisApiResult<bool> {
         let funs = crate::get_tardis_inst();
+        for tag in import_req.0.tag_data.keys() {
+            search_reindex_serv::check_fence(tag, &funs, &ctx.0).await?;
+        }
         let result = search_item_serv::import_data(&import_req.0, &funs, &ctx.0).await?;
         TardisResp::ok(result)
     }
@@ -0,0 +1 @@
+pub mod search_reindex_job;
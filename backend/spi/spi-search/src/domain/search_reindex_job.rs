@@ -0,0 +1,81 @@
+use tardis::basic::dto::TardisContext;
+use tardis::chrono::{self, Utc};
+use tardis::db::reldb_client::TardisActiveModel;
+use tardis::db::sea_orm;
+use tardis::db::sea_orm::sea_query::{ColumnDef, Index, IndexCreateStatement, Table, TableCreateStatement};
+use tardis::db::sea_orm::*;
+
+/// Reindex job
+///
+/// 重建索引任务
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "search_reindex_job")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    // 请求上下文的 ak，任务仅对同一应用/租户可见
+    pub ak: String,
+    pub tag: String,
+    pub source_kind: String,
+    pub target_kind: String,
+    // 目标后端服务 id，原地重建时为空
+    pub target_bs_id: String,
+    // 影子表/索引名称，用于清理中断的任务
+    pub shadow: String,
+    pub status: String,
+    pub total: i64,
+    pub processed: i64,
+    pub error: Option<String>,
+    // 运行中为 `{ak}:{tag}`，结束后为空，通过唯一索引保证集群内同一 tag 只有一个运行中的任务
+    pub running_key: Option<String>,
+    pub start_time: chrono::DateTime<Utc>,
+    pub end_time: Option<chrono::DateTime<Utc>>,
+    // 心跳时间，运行中的任务长时间未更新时视为已中断
+    pub update_time: chrono::DateTime<Utc>,
+
+    pub own_paths: String,
+}
+
+impl TardisActiveModel for ActiveModel {
+    fn fill_ctx(&mut self, ctx: &TardisContext, is_insert: bool) {
+        if is_insert {
+            self.own_paths = Set(ctx.own_paths.to_string());
+        }
+    }
+
+    fn create_table_statement(db: DbBackend) -> TableCreateStatement {
+        let mut builder = Table::create();
+        builder
+            .table(Entity.table_ref())
+            .if_not_exists()
+            .col(ColumnDef::new(Column::Id).not_null().string().primary_key())
+            .col(ColumnDef::new(Column::Ak).not_null().string())
+            .col(ColumnDef::new(Column::Tag).not_null().string())
+            .col(ColumnDef::new(Column::SourceKind).not_null().string())
+            .col(ColumnDef::new(Column::TargetKind).not_null().string())
+            .col(ColumnDef::new(Column::TargetBsId).not_null().string())
+            .col(ColumnDef::new(Column::Shadow).not_null().string())
+            .col(ColumnDef::new(Column::Status).not_null().string())
+            .col(ColumnDef::new(Column::Total).not_null().big_integer())
+            .col(ColumnDef::new(Column::Processed).not_null().big_integer())
+            .col(ColumnDef::new(Column::Error).null().text())
+            .col(ColumnDef::new(Column::RunningKey).null().string())
+            .col(ColumnDef::new(Column::StartTime).not_null().timestamp_with_time_zone())
+            .col(ColumnDef::new(Column::EndTime).null().timestamp_with_time_zone())
+            .col(ColumnDef::new(Column::UpdateTime).not_null().timestamp_with_time_zone())
+            .col(ColumnDef::new(Column::OwnPaths).not_null().string());
+        if db == DatabaseBackend::MySql {
+            builder.engine("InnoDB").character_set("utf8mb4").collate("utf8mb4_0900_as_cs");
+        }
+        builder.to_owned()
+    }
+
+    fn create_index_statement() -> Vec<IndexCreateStatement> {
+        vec![Index::create().name(&format!("idx-{}-running_key", Entity.table_name())).table(Entity).col(Column::RunningKey).unique().to_owned()]
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
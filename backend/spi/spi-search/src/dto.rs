@@ -1 +1,2 @@
+pub mod search_dict_dto;
 pub mod search_item_dto;
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use tardis::{
+    chrono::{DateTime, Utc},
+    web::poem_openapi,
+};
+
+/// Save dictionary request, replaces the whole dictionary of the tag
+///
+/// 保存词典请求，替换 tag 的整个词典
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchDictSaveReq {
+    pub entries: Vec<SearchDictEntryReq>,
+    // Whether to reindex the tag to apply the dictionary to the existing items, default is true
+    pub reindex: Option<bool>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct SearchDictEntryReq {
+    pub kind: SearchDictKind,
+    // Field the entry applies to, both the title and the content when it is empty
+    pub field: Option<SearchDictFieldKind>,
+    // Words of the entry, the equivalent words for the synonym kind
+    #[oai(validator(min_items = "1", max_items = "1000"))]
+    pub words: Vec<String>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct SearchDictResp {
+    pub tag: String,
+    pub entries: Vec<SearchDictEntryResp>,
+    pub update_time: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct SearchDictEntryResp {
+    pub kind: SearchDictKind,
+    pub field: Option<SearchDictFieldKind>,
+    // Trimmed and lowercased words
+    pub words: Vec<String>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDictKind {
+    // Equivalent words, an item containing one of them is found by any of them
+    #[oai(rename = "synonym")]
+    Synonym,
+    // Words ignored by the index and the query
+    #[oai(rename = "stop_word")]
+    StopWord,
+    // Words kept as a whole, e.g. ticket codes and product abbreviations
+    #[oai(rename = "user_word")]
+    UserWord,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDictFieldKind {
+    #[oai(rename = "title")]
+    Title,
+    #[oai(rename = "content")]
+    Content,
+}
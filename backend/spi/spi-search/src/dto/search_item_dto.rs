@@ -7,7 +7,7 @@ use tardis::{
     basic::field::TrimString,
     chrono::{DateTime, Utc},
     serde_json::{self, Value},
-    web::poem_openapi,
+    web::{poem_openapi, web_resp::TardisPage},
     TardisFuns,
 };
 
@@ -32,6 +32,8 @@ pub struct SearchItemAddReq {
     pub update_time: Option<DateTime<Utc>>,
     pub ext: Option<Value>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    // Embedding vector of the item, generated by the embedding provider when it is empty
+    pub embedding: Option<Vec<f32>>,
 }
 
 impl From<bios_sdk_invoke::dto::search_item_dto::SearchItemAddReq> for SearchItemAddReq {
@@ -49,6 +51,7 @@ impl From<bios_sdk_invoke::dto::search_item_dto::SearchItemAddReq> for SearchIte
             update_time: value.update_time,
             ext: value.ext,
             visit_keys: value.visit_keys.map(Into::into),
+            embedding: value.embedding,
         }
     }
 }
@@ -70,6 +73,8 @@ pub struct SearchItemModifyReq {
     // Overwrites the original content when it is true
     pub ext_override: Option<bool>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    // Embedding vector of the item, regenerated by the embedding provider when it is empty and the title or content changes
+    pub embedding: Option<Vec<f32>>,
 }
 impl From<bios_sdk_invoke::dto::search_item_dto::SearchItemModifyReq> for SearchItemModifyReq {
     fn from(value: bios_sdk_invoke::dto::search_item_dto::SearchItemModifyReq) -> Self {
@@ -84,6 +89,7 @@ impl From<bios_sdk_invoke::dto::search_item_dto::SearchItemModifyReq> for Search
             ext: value.ext,
             ext_override: value.ext_override,
             visit_keys: value.visit_keys.map(Into::into),
+            embedding: value.embedding,
         }
     }
 }
@@ -139,7 +145,7 @@ impl SearchItemVisitKeysReq {
     }
 }
 
-#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
 pub struct SearchItemSearchReq {
     #[oai(validator(pattern = r"^[a-z0-9-_]+$"))]
     pub tag: String,
@@ -154,6 +160,84 @@ pub struct SearchItemSearchReq {
     // When the record set is very large, it will seriously affect the performance, it is not recommended to use.
     pub sort: Option<Vec<SearchItemSearchSortReq>>,
     pub page: SearchItemSearchPageReq,
+    // Vector search, sorted by the similarity when `sort` is empty
+    pub vector: Option<SearchItemVectorQueryReq>,
+    // Highlight the matched keywords of `query.q` in the title and content
+    pub highlight: Option<SearchItemHighlightReq>,
+}
+
+/// Vector search request
+///
+/// 向量检索请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchItemVectorQueryReq {
+    // Search mode, default is hybrid
+    pub mode: Option<SearchItemVectorQueryModeKind>,
+    // Query vector, generated from `query.q` by the embedding provider when it is empty
+    pub embedding: Option<Vec<f32>>,
+    // Weight of the keyword rank in the hybrid mode, between 0 and 1, default is 0.5
+    // The vector similarity takes the rest
+    pub keyword_weight: Option<f32>,
+    // Minimum cosine similarity of the matched items
+    pub min_similarity: Option<f32>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchItemVectorQueryModeKind {
+    // Rank by the vector similarity only, `query.q` is only used to generate the query vector
+    #[oai(rename = "vector")]
+    Vector,
+    // Items matching the keywords or the vector, ranked by the blend of the keyword rank and the vector similarity
+    #[oai(rename = "hybrid")]
+    Hybrid,
+}
+
+/// Highlight request
+///
+/// 高亮请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchItemHighlightReq {
+    // Tag before the matched keywords, default is `<em>`
+    pub pre_tag: Option<String>,
+    // Tag after the matched keywords, default is `</em>`
+    pub post_tag: Option<String>,
+    // Max number of the content fragments, default is 3
+    pub max_fragments: Option<u16>,
+    // Max size of each content fragment, counted in words on pg and characters on ES, default is 35 / 100
+    pub fragment_size: Option<u16>,
+}
+
+/// Search request with the facets and suggestions
+///
+/// 带分面统计及搜索建议的搜索请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct SearchItemFacetSearchReq {
+    pub search: SearchItemSearchReq,
+    // Count the matched items by these fields
+    pub facets: Option<Vec<SearchItemFacetReq>>,
+    // Suggest the titles by `search.query.q`
+    pub suggest: Option<SearchItemSuggestReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct SearchItemFacetReq {
+    // Field name, `kind`, `owner`, `own_paths` and `data_source` are supported when `in_ext` is false
+    #[oai(validator(min_length = "1"))]
+    pub field: String,
+    // Whether it is an extended field, default is true
+    pub in_ext: Option<bool>,
+    // Whether the extended field is an array, each element is counted separately
+    pub multi_values: Option<bool>,
+    // Number of the returned values, default is 10
+    pub size: Option<u16>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchItemSuggestReq {
+    // Number of the returned suggestions, default is 5
+    pub size: Option<u16>,
+    // Whether to return the "did you mean" corrections when the prefix matches are not enough, default is true
+    pub correction: Option<bool>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -353,6 +437,57 @@ pub struct SearchItemSearchResp {
     pub ext: Value,
     pub rank_title: f32,
     pub rank_content: f32,
+    // Vector similarity, only available in vector search
+    pub rank_vector: f32,
+    // Highlighted fragments, only available when `highlight` is requested with `query.q`
+    pub highlight: Option<SearchItemHighlightResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchItemHighlightResp {
+    pub title: Option<String>,
+    // Fragments joined by ` ... `
+    pub content: Option<String>,
+}
+
+/// Search response with the facets and suggestions
+///
+/// 带分面统计及搜索建议的搜索响应
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemFacetSearchResp {
+    pub page: TardisPage<SearchItemSearchResp>,
+    pub facets: Vec<SearchItemFacetResp>,
+    pub suggestions: Vec<SearchItemSuggestResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemFacetResp {
+    pub field: String,
+    // Sorted by the count in descending order
+    pub values: Vec<SearchItemFacetValueResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemFacetValueResp {
+    // Empty when the field is missing
+    pub value: Option<String>,
+    pub count: i64,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct SearchItemSuggestResp {
+    pub text: String,
+    pub kind: SearchItemSuggestKind,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchItemSuggestKind {
+    // Title starting with the keywords
+    #[oai(rename = "prefix")]
+    Prefix,
+    // Title similar to the keywords, "did you mean"
+    #[oai(rename = "correction")]
+    Correction,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
@@ -669,4 +804,59 @@ pub struct SearchSaveItemReq {
     pub update_time: Option<DateTime<Utc>>,
     pub ext: Option<Value>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    // Embedding vector of the item, same as the add and modify requests
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Reindex request
+///
+/// 重建索引请求
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchReindexReq {
+    // Backend service to copy the tag to, e.g. moving the tag from pg to ES, the current one is rebuilt when it is empty
+    pub target_bs_id: Option<String>,
+    // Number of the items copied in each batch, default is 500
+    #[oai(validator(minimum(value = "1", exclusive = "false"), maximum(value = "10000", exclusive = "false")))]
+    pub batch_size: Option<u32>,
+}
+
+/// Reindex job
+///
+/// 重建索引任务
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct SearchReindexJobResp {
+    pub id: String,
+    pub tag: String,
+    pub source_kind: String,
+    pub target_kind: String,
+    pub status: SearchReindexStatusKind,
+    // Number of the items when the job starts
+    pub total: u64,
+    // Number of the copied items, including the ones changed during the job
+    pub processed: u64,
+    pub error: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString)]
+pub enum SearchReindexStatusKind {
+    // Copying the items to the shadow table/index in batches
+    #[oai(rename = "backfilling")]
+    Backfilling,
+    // Copying the items changed during the backfilling
+    #[oai(rename = "catching_up")]
+    CatchingUp,
+    // Rejecting the writes of the tag while copying the last changes, when moving the tag to another backend service or rebuilding it in ES
+    #[oai(rename = "fencing")]
+    Fencing,
+    // Replacing the live table/index with the shadow one
+    #[oai(rename = "swapping")]
+    Swapping,
+    // When the tag is moved to another backend service, its writes are rejected until the app/tenant is rebound to that service
+    #[oai(rename = "succeeded")]
+    Succeeded,
+    // The shadow table/index is dropped, the live one is untouched, including the jobs interrupted by the stopped nodes
+    #[oai(rename = "failed")]
+    Failed,
 }
@@ -1,6 +1,7 @@
 #![warn(clippy::unwrap_used)]
 
 mod api;
+mod domain;
 pub mod dto;
 pub mod search_config;
 pub mod search_constants;
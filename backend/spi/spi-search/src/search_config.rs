@@ -1,5 +1,6 @@
 use bios_basic::rbum::rbum_config::RbumConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -7,6 +8,8 @@ use std::fmt::Debug;
 pub struct SearchConfig {
     pub rbum: RbumConfig,
     pub split_strategy_rule_config: SplitStrategyRuleConfig,
+    pub embedding: SearchEmbeddingConfig,
+    pub reindex: SearchReindexConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -14,3 +17,52 @@ pub struct SearchConfig {
 pub struct SplitStrategyRuleConfig {
     pub specify_word_length: Option<usize>,
 }
+
+/// Vector search configuration
+///
+/// 向量检索配置
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct SearchEmbeddingConfig {
+    // Dimensions of the embedding vector, vector search is disabled when it is 0
+    // The pg backend requires the pgvector extension, which should be created when installing the database (see `config/init_script.sql`)
+    pub dimensions: usize,
+    // Embedding provider url, compatible with the OpenAI `embeddings` api, e.g. a local model service
+    // When it is set, the items and queries without embedding are embedded by the provider
+    pub provider_url: Option<String>,
+    pub provider_model: Option<String>,
+    pub provider_headers: HashMap<String, String>,
+}
+
+/// Reindex configuration
+///
+/// 重建索引配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SearchReindexConfig {
+    // Interval of updating the heartbeat of the running jobs
+    pub heartbeat_interval_sec: u64,
+    // The running jobs without heartbeat for this long are treated as interrupted, their shadow tables/indexes are dropped
+    pub stale_sec: u64,
+    // Interval of cleaning up the interrupted jobs, 0 means only cleaned up when a job starts
+    pub orphan_check_interval_sec: u64,
+    // Finished jobs are kept for querying for this long
+    pub finished_job_keep_sec: u64,
+    // Cache key prefix of the write fences of the tags being reindexed or moved to other backend services, and of the marks of these tags
+    pub cache_key_fence: String,
+    // Expiration of the fence left after a tag is moved, the nodes writing the tag in this period switch to the new backend service
+    pub moved_fence_exp_sec: u64,
+}
+
+impl Default for SearchReindexConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval_sec: 30,
+            stale_sec: 300,
+            orphan_check_interval_sec: 300,
+            finished_job_keep_sec: 24 * 60 * 60,
+            cache_key_fence: "spi-search:reindex:fence:".to_string(),
+            moved_fence_exp_sec: 7 * 24 * 60 * 60,
+        }
+    }
+}
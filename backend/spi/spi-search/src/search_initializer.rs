@@ -1,12 +1,19 @@
 use bios_basic::spi::{api::spi_ci_bs_api, dto::spi_bs_dto::SpiBsCertResp, spi_constants, spi_funs::SpiBsInst, spi_initializer};
 use tardis::{
     basic::{dto::TardisContext, result::TardisResult},
+    db::reldb_client::TardisActiveModel,
     log::info,
     web::web_server::TardisWebServer,
     TardisFuns, TardisFunsInst,
 };
 
-use crate::{api::ci::search_ci_item_api, search_config::SearchConfig, search_constants::DOMAIN_CODE, serv};
+use crate::{
+    api::ci::{search_ci_dict_api, search_ci_item_api},
+    domain::search_reindex_job,
+    search_config::SearchConfig,
+    search_constants::DOMAIN_CODE,
+    serv,
+};
 
 pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     info!("[BIOS.Search] Module initializing");
@@ -20,6 +27,10 @@ pub async fn init(web_server: &TardisWebServer) -> TardisResult<()> {
     funs.commit().await?;
 
     init_api(web_server).await?;
+    let orphan_check_interval_sec = funs.conf::<SearchConfig>().reindex.orphan_check_interval_sec;
+    if orphan_check_interval_sec > 0 {
+        serv::search_reindex_serv::start_orphaned_job_cleaner(orphan_check_interval_sec, ctx.clone());
+    }
     info!("[BIOS.Search] Module initialized");
     Ok(())
 }
@@ -29,11 +40,24 @@ async fn init_db(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()>
     spi_initializer::add_kind(spi_constants::SPI_PG_KIND_CODE, funs, ctx).await?;
     #[cfg(feature = "spi-es")]
     spi_initializer::add_kind(spi_constants::SPI_ES_KIND_CODE, funs, ctx).await?;
+    // The reindex job table is created if not exists, so that it is also available for upgraded deployments
+    funs.db()
+        .init(search_reindex_job::ActiveModel::init(
+            TardisFuns::reldb().backend(),
+            None,
+            TardisFuns::reldb().compatible_type(),
+        ))
+        .await?;
     Ok(())
 }
 
 async fn init_api(web_server: &TardisWebServer) -> TardisResult<()> {
-    web_server.add_module(DOMAIN_CODE, (spi_ci_bs_api::SpiCiBsApi, search_ci_item_api::SearchCiItemApi)).await;
+    web_server
+        .add_module(
+            DOMAIN_CODE,
+            (spi_ci_bs_api::SpiCiBsApi, search_ci_item_api::SearchCiItemApi, search_ci_dict_api::SearchCiDictApi),
+        )
+        .await;
     Ok(())
 }
 
@@ -41,7 +65,11 @@ pub async fn init_fun(bs_cert: SpiBsCertResp, ctx: &TardisContext, mgr: bool) ->
     info!("[BIOS.Search] Fun [{}]({}) initializing", bs_cert.kind_code, bs_cert.conn_uri);
     let inst = match bs_cert.kind_code.as_str() {
         #[cfg(feature = "spi-pg")]
-        spi_constants::SPI_PG_KIND_CODE => spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await,
+        spi_constants::SPI_PG_KIND_CODE => {
+            let inst = spi_initializer::common_pg::init(&bs_cert, ctx, mgr).await?;
+            serv::pg::search_pg_initializer::check_extensions(inst.inst::<tardis::db::reldb_client::TardisRelDBClient>().0).await?;
+            Ok(inst)
+        }
         #[cfg(feature = "spi-es")]
         spi_constants::SPI_ES_KIND_CODE => serv::es::search_es_initializer::init(&bs_cert, ctx, mgr).await,
         _ => Err(bs_cert.bs_not_implemented())?,
@@ -1,3 +1,6 @@
 pub mod es;
 pub mod pg;
+pub mod search_dict_serv;
+pub mod search_embedding_serv;
 pub mod search_item_serv;
+pub mod search_reindex_serv;
@@ -1,2 +1,4 @@
+pub mod search_es_dict_serv;
 pub mod search_es_initializer;
 pub mod search_es_item_serv;
+pub mod search_es_reindex_serv;
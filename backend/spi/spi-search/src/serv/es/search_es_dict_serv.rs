@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use bios_basic::spi::spi_funs::SpiBsInst;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    search::search_client::TardisSearchClient,
+    serde_json::{json, Value},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::search_dict_dto::{SearchDictEntryReq, SearchDictEntryResp, SearchDictResp},
+    serv::search_dict_serv::{self, SearchDict},
+};
+
+use super::{search_es_initializer, search_es_item_serv};
+
+// The dictionaries of all the tags are stored in one index, the tag is the document id
+const DICT_INDEX: &str = "search.dict";
+
+fn format_dict_index(ext: &HashMap<String, String>) -> String {
+    search_es_item_serv::format_index(DICT_INDEX, ext)
+}
+
+async fn init_dict_index(client: &TardisSearchClient, ext: &HashMap<String, String>) -> TardisResult<String> {
+    let index = format_dict_index(ext);
+    search_es_initializer::init_index(
+        client,
+        &index,
+        Some(r#"{"mappings": {"properties": {"tag": {"type": "keyword"}, "entries": {"type": "object", "enabled": false}, "update_time": {"type": "date"}}}}"#),
+    )
+    .await?;
+    Ok(index)
+}
+
+pub async fn save(tag: &str, entries: &[SearchDictEntryReq], funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let entries = search_dict_serv::normalize(entries, funs)?;
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = init_dict_index(client, ext).await?;
+    let body = json!({
+        "tag": tag,
+        "entries": entries,
+        "update_time": Utc::now().to_rfc3339(),
+    });
+    let resp = client
+        .client
+        .put_str_to_str(
+            &format!("{}/{index}/_doc/{tag}?refresh=true", client.server_url),
+            &body.to_string(),
+            vec![("Content-Type".to_string(), "application/json".to_string())],
+        )
+        .await?;
+    if !(200..300).contains(&resp.code) {
+        return Err(funs.err().internal_error(
+            "search_es_dict_serv",
+            "save",
+            &format!("save dictionary error: {}", resp.body.unwrap_or_default()),
+            "500-spi-search-es-dict-error",
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get(tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchDictResp> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let source = find_source(tag, client, ext, funs).await?;
+    Ok(match source {
+        Some(source) => SearchDictResp {
+            tag: tag.to_string(),
+            entries: TardisFuns::json.json_to_obj(source["entries"].clone())?,
+            update_time: source["update_time"].as_str().and_then(|update_time| DateTime::parse_from_rfc3339(update_time).ok()).map(|update_time| update_time.with_timezone(&Utc)),
+        },
+        None => SearchDictResp {
+            tag: tag.to_string(),
+            entries: vec![],
+            update_time: None,
+        },
+    })
+}
+
+pub async fn delete(tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    if find_source(tag, client, ext, funs).await?.is_some() {
+        client.client.delete_to_void(&format!("{}/{}/_doc/{tag}?refresh=true", client.server_url, format_dict_index(ext)), vec![]).await?;
+    }
+    Ok(())
+}
+
+/// Find the dictionary of the tag
+///
+/// 获取 tag 的词典
+pub(crate) async fn find_dict(tag: &str, client: &TardisSearchClient, ext: &HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<SearchDict> {
+    let entries = match find_source(tag, client, ext, funs).await? {
+        Some(source) => TardisFuns::json.json_to_obj::<Vec<SearchDictEntryResp>>(source["entries"].clone())?,
+        None => vec![],
+    };
+    Ok(SearchDict { entries })
+}
+
+async fn find_source(tag: &str, client: &TardisSearchClient, ext: &HashMap<String, String>, funs: &TardisFunsInst) -> TardisResult<Option<Value>> {
+    let index = format_dict_index(ext);
+    if !client.check_index_exist(&index).await? {
+        return Ok(None);
+    }
+    let resp = client.client.get_to_str(&format!("{}/{index}/_doc/{tag}", client.server_url), vec![]).await?;
+    match resp.code {
+        200 => Ok(Some(TardisFuns::json.str_to_json(&resp.body.unwrap_or_default())?["_source"].clone())),
+        404 => Ok(None),
+        _ => Err(funs.err().internal_error(
+            "search_es_dict_serv",
+            "find_dict",
+            &format!("find dictionary error: {}", resp.body.unwrap_or_default()),
+            "500-spi-search-es-dict-error",
+        )),
+    }
+}
@@ -13,16 +13,28 @@ use bios_basic::{
     spi::{spi_funs::SpiBsInst, spi_initializer::common},
 };
 
-use crate::dto::search_item_dto::{
-    GroupSearchItemSearchReq, GroupSearchItemSearchResp, MultipleSearchItemSearchReq, SearchExportDataReq, SearchExportDataResp, SearchImportDataReq, SearchItemAddReq,
-    SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind, SearchItemSearchReq, SearchItemSearchResp,
-    SearchQueryMetricsReq, SearchQueryMetricsResp, SearchSaveItemReq,
+use crate::{
+    dto::search_dict_dto::SearchDictFieldKind,
+    dto::search_item_dto::{
+        GroupSearchItemSearchReq, GroupSearchItemSearchResp, MultipleSearchItemSearchReq, SearchExportDataReq, SearchExportDataResp, SearchImportDataReq, SearchItemAddReq,
+        SearchItemFacetReq, SearchItemFacetResp, SearchItemFacetSearchReq, SearchItemFacetSearchResp, SearchItemFacetValueResp, SearchItemHighlightReq, SearchItemHighlightResp,
+        SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind, SearchItemSearchReq, SearchItemSearchResp,
+        SearchItemSuggestKind, SearchItemSuggestResp, SearchItemVectorQueryModeKind, SearchItemVectorQueryReq, SearchQueryMetricsReq, SearchQueryMetricsResp, SearchSaveItemReq,
+    },
+    search_config::SearchConfig,
+    serv::{search_dict_serv::SearchDict, search_embedding_serv},
 };
 
-use super::search_es_initializer;
+use super::{search_es_dict_serv, search_es_initializer};
 const INNER_FIELD: [&str; 7] = ["key", "title", "content", "owner", "own_paths", "create_time", "update_time"];
+const FACET_SIZE: u16 = 10;
+const SUGGEST_SIZE: u16 = 5;
+const HIGHLIGHT_PRE_TAG: &str = "<em>";
+const HIGHLIGHT_POST_TAG: &str = "</em>";
+const HIGHLIGHT_MAX_FRAGMENTS: u16 = 3;
+const HIGHLIGHT_FRAGMENT_CHARS: u16 = 100;
 
-fn format_index(req_index: &str, ext: &HashMap<String, String>) -> String {
+pub(crate) fn format_index(req_index: &str, ext: &HashMap<String, String>) -> String {
     if let Some(key_prefix) = common::get_isolation_flag_from_ext(ext) {
         format!("{key_prefix}{req_index}")
     } else {
@@ -30,7 +42,14 @@ fn format_index(req_index: &str, ext: &HashMap<String, String>) -> String {
     }
 }
 
-fn gen_data_mappings(ext: &Option<Value>) -> String {
+/// Generate the settings and the mappings of the index
+///
+/// The synonyms are expanded by the search analyzer, the stop words are removed by both the index and the search analyzers,
+/// the user words and the synonyms found in the text are kept as keywords in `dict_words`.
+///
+/// 生成索引的设置及映射。
+/// 同义词由查询分析器扩展，停用词由索引及查询分析器移除，文本中的自定义词及同义词以关键字形式保存在 `dict_words` 中。
+pub(crate) fn gen_data_mappings(ext: &Option<Value>, dict: &SearchDict, embedding_dimensions: usize) -> String {
     let mut ext_string = r#"{"type": "object"}"#.to_string();
     let mut ext_properties = vec![];
     if let Some(ext) = ext {
@@ -49,20 +68,65 @@ fn gen_data_mappings(ext: &Option<Value>) -> String {
         );
     }
 
+    let embedding_string = if embedding_dimensions > 0 {
+        format!(r#""embedding":{{"type": "dense_vector", "dims": {embedding_dimensions}, "index": true, "similarity": "cosine"}},"#)
+    } else {
+        "".to_string()
+    };
+
+    let mut filters = serde_json::Map::new();
+    let mut analyzers = serde_json::Map::new();
+    let mut text_strings = vec![];
+    for (field, field_kind) in [("title", SearchDictFieldKind::Title), ("content", SearchDictFieldKind::Content)] {
+        let synonyms = dict.synonyms(field_kind).iter().map(|words| words.join(", ")).collect::<Vec<_>>();
+        let stop_words = dict.stop_words(field_kind);
+        if synonyms.is_empty() && stop_words.is_empty() {
+            text_strings.push(format!(r#""{field}":{{"type": "text"}}"#));
+            continue;
+        }
+        let mut index_filters = vec!["lowercase".to_string()];
+        let mut search_filters = vec!["lowercase".to_string()];
+        if !synonyms.is_empty() {
+            filters.insert(format!("{field}_synonym"), json!({ "type": "synonym_graph", "synonyms": synonyms }));
+            search_filters.push(format!("{field}_synonym"));
+        }
+        if !stop_words.is_empty() {
+            filters.insert(format!("{field}_stop"), json!({ "type": "stop", "stopwords": stop_words }));
+            index_filters.push(format!("{field}_stop"));
+            search_filters.push(format!("{field}_stop"));
+        }
+        analyzers.insert(format!("{field}_index"), json!({ "type": "custom", "tokenizer": "standard", "filter": index_filters }));
+        analyzers.insert(format!("{field}_search"), json!({ "type": "custom", "tokenizer": "standard", "filter": search_filters }));
+        text_strings.push(format!(r#""{field}":{{"type": "text", "analyzer": "{field}_index", "search_analyzer": "{field}_search"}}"#));
+    }
+    let settings_string = if analyzers.is_empty() {
+        "".to_string()
+    } else {
+        format!(r#""settings": {},"#, json!({ "analysis": { "filter": filters, "analyzer": analyzers } }))
+    };
+    let text_string = text_strings.join(",");
+
     format!(
         r#"{{
+        {settings_string}
         "mappings": {{
             "properties": {{
+                {embedding_string}
                 "tag":{{"type": "keyword"}},
                 "kind":{{"type": "keyword"}},
                 "key":{{"type": "keyword"}},
-                "title":{{"type": "text"}},
-                "content":{{"type": "text"}},
+                {text_string},
                 "owner":{{"type": "keyword"}},
                 "own_paths":{{"type": "keyword"}},
                 "create_time":{{"type": "date"}},
                 "update_time":{{"type": "date"}},
                 "ext":{{{ext_string}}},
+                "dict_words":{{
+                    "properties": {{
+                        "title": {{ "type": "keyword" }},
+                        "content": {{ "type": "keyword" }}
+                    }}
+                }},
                 "visit_keys":{{
                     "properties": {{
                         "accounts": {{ "type": "keyword" }},
@@ -81,8 +145,16 @@ fn gen_data_mappings(ext: &Option<Value>) -> String {
 pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let (client, ext, _) = inst.inst::<TardisSearchClient>();
     let index = format_index(&add_req.tag, ext);
+    let dict = search_es_dict_serv::find_dict(&add_req.tag, client, ext, funs).await?;
 
-    if search_es_initializer::init_index(client, &index, Some(&gen_data_mappings(&add_req.ext))).await.is_err() {
+    if search_es_initializer::init_index(
+        client,
+        &index,
+        Some(&gen_data_mappings(&add_req.ext, &dict, funs.conf::<SearchConfig>().embedding.dimensions)),
+    )
+    .await
+    .is_err()
+    {
         return Err(funs.err().bad_request("search_es_item_serv", "add", "index not exist", "400-search-index-not-exist"));
     }
     if !search(
@@ -109,6 +181,8 @@ pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &Ta
             },
             adv_by_or: None,
             adv_query: None,
+            vector: None,
+            highlight: None,
         },
         funs,
         ctx,
@@ -120,12 +194,31 @@ pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &Ta
     {
         return Err(funs.err().conflict("search_es_item_serv", "add", "record already exists", "409-search-already-exist"));
     }
-    let data = TardisFuns::json.obj_to_string(add_req)?;
+    add_req.embedding = search_embedding_serv::item_embedding(add_req.embedding.take(), &add_req.title, &add_req.content, funs).await?;
+    let data = gen_record(add_req, &dict)?;
     client.create_record(&index, &data).await?;
 
     Ok(())
 }
 
+/// Serialize the item as the record of the index, with the words found by the dictionary
+///
+/// 将数据序列化为索引记录，附带词典匹配到的词语
+pub(crate) fn gen_record(item: &SearchItemAddReq, dict: &SearchDict) -> TardisResult<String> {
+    let mut record = TardisFuns::json.obj_to_json(item)?;
+    if !dict.is_empty() {
+        record["dict_words"] = gen_dict_words(dict, &item.title, &item.content);
+    }
+    Ok(record.to_string())
+}
+
+fn gen_dict_words(dict: &SearchDict, title: &str, content: &str) -> Value {
+    json!({
+        "title": dict.index_words(SearchDictFieldKind::Title, title),
+        "content": dict.index_words(SearchDictFieldKind::Content, content),
+    })
+}
+
 pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let (client, ext, _) = inst.inst::<TardisSearchClient>();
     let index = format_index(tag, ext);
@@ -155,6 +248,8 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
         },
         adv_by_or: None,
         adv_query: None,
+        vector: None,
+        highlight: None,
     })?;
     let mut search_result = client.raw_search(&index, &q, Some(1), Some(0), None).await?;
     let id = search_result.hits.hits.pop().ok_or_else(|| funs.err().conflict("search_es_item_serv", "modify", "not found record", "404-not-found-record"))?._id.clone();
@@ -180,6 +275,33 @@ pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq,
     if let Some(update_time) = &modify_req.update_time {
         query.insert("update_time".to_string(), json!(update_time.to_rfc3339()).to_string());
     }
+    let embedding = if modify_req.embedding.is_some() {
+        search_embedding_serv::item_embedding(modify_req.embedding.clone(), "", "", funs).await?
+    } else if (modify_req.title.is_some() || modify_req.content.is_some()) && search_embedding_serv::has_provider(funs) {
+        // Regenerate the embedding only when the text changes
+        let storage_item = TardisFuns::json.str_to_obj::<SearchItemAddReq>(&client.get_record(&index, &id).await?)?;
+        let title = modify_req.title.clone().unwrap_or_else(|| storage_item.title.clone());
+        let content = modify_req.content.clone().unwrap_or_else(|| storage_item.content.clone());
+        if title != storage_item.title || content != storage_item.content {
+            search_embedding_serv::item_embedding(None, &title, &content, funs).await?
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+    if let Some(embedding) = embedding {
+        query.insert("embedding".to_string(), json!(embedding).to_string());
+    }
+    if modify_req.title.is_some() || modify_req.content.is_some() {
+        let dict = search_es_dict_serv::find_dict(tag, client, ext, funs).await?;
+        if !dict.is_empty() {
+            let storage_item = TardisFuns::json.str_to_obj::<SearchItemAddReq>(&client.get_record(&index, &id).await?)?;
+            let title = modify_req.title.clone().unwrap_or(storage_item.title);
+            let content = modify_req.content.clone().unwrap_or(storage_item.content);
+            query.insert("dict_words".to_string(), gen_dict_words(&dict, &title, &content).to_string());
+        }
+    }
     if let Some(ext) = &modify_req.ext {
         let mut ext = ext.clone();
         if !modify_req.ext_override.unwrap_or(false) {
@@ -256,6 +378,8 @@ pub async fn delete(tag: &str, key: &str, funs: &TardisFunsInst, _ctx: &TardisCo
         },
         adv_by_or: None,
         adv_query: None,
+        vector: None,
+        highlight: None,
     })?;
     client.delete_by_query(&index, &q).await?;
 
@@ -290,6 +414,8 @@ pub async fn delete_by_ownership(tag: &str, onw_paths: &str, funs: &TardisFunsIn
         },
         adv_by_or: None,
         adv_query: None,
+        vector: None,
+        highlight: None,
     })?;
     client.delete_by_query(&index, &q).await?;
 
@@ -297,13 +423,12 @@ pub async fn delete_by_ownership(tag: &str, onw_paths: &str, funs: &TardisFunsIn
 }
 
 pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
-    let q = gen_query_dsl(search_req)?;
-    let mut track_scores = None;
-    if let Some(sorts) = &search_req.sort {
-        if sorts.iter().any(|sort| sort.field == "rank_title" || sort.field == "rank_content") {
-            track_scores = Some(true);
-        }
+    if search_req.highlight.is_some() {
+        // The highlight isn't returned by `raw_search`
+        let (page, _) = do_raw_search(search_req, json!({}), funs, inst).await?;
+        return Ok(page);
     }
+    let q = gen_search_dsl(search_req, funs, inst).await?;
     let (client, ext, _) = inst.inst::<TardisSearchClient>();
     let index = format_index(&search_req.tag, ext);
     if !client.check_index_exist(&index).await? {
@@ -316,7 +441,7 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
             &q,
             Some(search_req.page.size as i32),
             Some(((search_req.page.number - 1) * search_req.page.size as u32) as i32),
-            track_scores,
+            track_scores(search_req),
         )
         .await?;
 
@@ -324,31 +449,7 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
     if search_req.page.fetch_total && total_size == 0 {
         total_size = result.hits.total.value as i64;
     }
-    let records = result
-        .hits
-        .hits
-        .iter()
-        .map(|raw_item| {
-            if let Ok(item) = TardisFuns::json.str_to_obj::<SearchItemAddReq>(&raw_item._source.clone().to_string()) {
-                Ok(SearchItemSearchResp {
-                    kind: item.kind.clone(),
-                    key: item.key.to_string(),
-                    title: item.title.clone(),
-                    content: item.content.clone(),
-                    data_source: item.data_source.clone().unwrap_or_default(),
-                    owner: item.owner.clone().unwrap_or_default(),
-                    own_paths: item.own_paths.clone().unwrap_or_default(),
-                    create_time: item.create_time.unwrap_or_default(),
-                    update_time: item.update_time.unwrap_or_default(),
-                    ext: item.ext.unwrap_or_default(),
-                    rank_title: raw_item._score.unwrap_or_default(),
-                    rank_content: raw_item._score.unwrap_or_default(),
-                })
-            } else {
-                Err(funs.err().format_error("search_es_item_serv", "search", "search result format error", "500-result-format-error"))
-            }
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let records = result.hits.hits.iter().map(|raw_item| to_search_resp(&raw_item._source, raw_item._score, None, search_req, funs)).collect::<Result<Vec<_>, _>>()?;
     Ok(TardisPage {
         page_size: search_req.page.size as u64,
         page_number: search_req.page.number as u64,
@@ -357,6 +458,308 @@ pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst,
     })
 }
 
+async fn gen_search_dsl(search_req: &SearchItemSearchReq, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<String> {
+    let dsl = if let Some(vector) = &search_req.vector {
+        gen_vector_query_dsl(search_req, vector, funs).await?
+    } else {
+        gen_query_dsl(search_req)?
+    };
+    let Some(q) = &search_req.query.q else {
+        return Ok(dsl);
+    };
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let user_words = search_es_dict_serv::find_dict(&search_req.tag, client, ext, funs).await?.query_user_words(q);
+    if user_words.is_empty() {
+        return Ok(dsl);
+    }
+    let mut dsl = TardisFuns::json.str_to_json(&dsl)?;
+    let Some(query) = dsl.as_object_mut().and_then(|dsl| dsl.remove("query")) else {
+        return Ok(dsl.to_string());
+    };
+    // The standard tokenizer splits the user words, the items containing them as a whole are ranked higher
+    let fields = match search_req.query.q_scope.as_ref().unwrap_or(&SearchItemSearchQScopeKind::Title) {
+        SearchItemSearchQScopeKind::Title => vec!["dict_words.title"],
+        SearchItemSearchQScopeKind::Content => vec!["dict_words.content"],
+        SearchItemSearchQScopeKind::TitleContent => vec!["dict_words.title", "dict_words.content"],
+    };
+    dsl["query"] = json!({
+        "bool": {
+            "must": [query],
+            "should": fields.into_iter().map(|field| json!({ "terms": { (field): user_words } })).collect::<Vec<_>>(),
+        }
+    });
+    Ok(dsl.to_string())
+}
+
+fn track_scores(search_req: &SearchItemSearchReq) -> Option<bool> {
+    if let Some(sorts) = &search_req.sort {
+        if sorts.iter().any(|sort| sort.field == "rank_title" || sort.field == "rank_content" || sort.field == "rank_vector") {
+            return Some(true);
+        }
+    }
+    None
+}
+
+fn to_search_resp(
+    source: &Value,
+    score: Option<f32>,
+    highlight: Option<SearchItemHighlightResp>,
+    search_req: &SearchItemSearchReq,
+    funs: &TardisFunsInst,
+) -> TardisResult<SearchItemSearchResp> {
+    if let Ok(item) = TardisFuns::json.str_to_obj::<SearchItemAddReq>(&source.to_string()) {
+        Ok(SearchItemSearchResp {
+            kind: item.kind.clone(),
+            key: item.key.to_string(),
+            title: item.title.clone(),
+            content: item.content.clone(),
+            data_source: item.data_source.clone().unwrap_or_default(),
+            owner: item.owner.clone().unwrap_or_default(),
+            own_paths: item.own_paths.clone().unwrap_or_default(),
+            create_time: item.create_time.unwrap_or_default(),
+            update_time: item.update_time.unwrap_or_default(),
+            ext: item.ext.unwrap_or_default(),
+            rank_title: score.unwrap_or_default(),
+            rank_content: score.unwrap_or_default(),
+            rank_vector: if search_req.vector.is_some() { score.unwrap_or_default() } else { 0.0 },
+            highlight,
+        })
+    } else {
+        Err(funs.err().format_error("search_es_item_serv", "search", "search result format error", "500-result-format-error"))
+    }
+}
+
+/// Search by the raw request, the extras (aggregations, suggestions) are merged into the query,
+/// returns the page and the raw response
+///
+/// 以原始请求搜索，附加项（聚合、建议）会合并到查询中，返回分页结果及原始响应
+async fn do_raw_search(search_req: &SearchItemSearchReq, extras: Value, funs: &TardisFunsInst, inst: &SpiBsInst) -> TardisResult<(TardisPage<SearchItemSearchResp>, Value)> {
+    let mut q = TardisFuns::json.str_to_json(&gen_search_dsl(search_req, funs, inst).await?)?;
+    merge(&mut q, extras);
+    // Only the keywords can be highlighted
+    let highlight_req = search_req
+        .highlight
+        .as_ref()
+        .filter(|_| search_req.query.q.is_some() && !matches!(search_req.vector.as_ref().map(search_embedding_serv::mode), Some(SearchItemVectorQueryModeKind::Vector)));
+    if let Some(highlight_req) = highlight_req {
+        q["highlight"] = gen_highlight_dsl(highlight_req, funs)?;
+    }
+    q["size"] = json!(search_req.page.size);
+    q["from"] = json!((search_req.page.number - 1) * search_req.page.size as u32);
+    if let Some(track_scores) = track_scores(search_req) {
+        q["track_scores"] = json!(track_scores);
+    }
+
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(&search_req.tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Err(funs.err().bad_request("search_es_item_serv", "search", "index not exist", "400-search-index-not-exist"));
+    }
+    let resp = client
+        .client
+        .post_str_to_str(
+            &format!("{}/{}/_search", client.server_url, index),
+            &q.to_string(),
+            vec![("Content-Type".to_string(), "application/json".to_string())],
+        )
+        .await?;
+    if !(200..300).contains(&resp.code) {
+        return Err(funs.err().internal_error(
+            "search_es_item_serv",
+            "search",
+            &format!("search error: {}", resp.body.unwrap_or_default()),
+            "500-spi-search-es-search-error",
+        ));
+    }
+    let result = TardisFuns::json.str_to_json(&resp.body.unwrap_or_default())?;
+
+    let total_size = if search_req.page.fetch_total {
+        result["hits"]["total"]["value"].as_u64().unwrap_or_default()
+    } else {
+        0
+    };
+    let records = result["hits"]["hits"]
+        .as_array()
+        .map(|hits| hits.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .map(|hit| {
+            let highlight = highlight_req.map(|_| SearchItemHighlightResp {
+                title: hit["highlight"]["title"].as_array().map(|fragments| fragments.iter().filter_map(|fragment| fragment.as_str()).collect::<Vec<_>>().join(" ... ")),
+                content: hit["highlight"]["content"].as_array().map(|fragments| fragments.iter().filter_map(|fragment| fragment.as_str()).collect::<Vec<_>>().join(" ... ")),
+            });
+            to_search_resp(&hit["_source"], hit["_score"].as_f64().map(|score| score as f32), highlight, search_req, funs)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((
+        TardisPage {
+            page_size: search_req.page.size as u64,
+            page_number: search_req.page.number as u64,
+            total_size,
+            records,
+        },
+        result,
+    ))
+}
+
+fn gen_highlight_dsl(highlight_req: &SearchItemHighlightReq, funs: &TardisFunsInst) -> TardisResult<Value> {
+    let pre_tag = highlight_req.pre_tag.as_deref().unwrap_or(HIGHLIGHT_PRE_TAG);
+    let post_tag = highlight_req.post_tag.as_deref().unwrap_or(HIGHLIGHT_POST_TAG);
+    if [pre_tag, post_tag].iter().any(|tag| tag.contains('"') || tag.contains(',')) {
+        return Err(funs.err().bad_request(
+            "search_es_item_serv",
+            "search",
+            "highlight tags can't contain '\"' or ','",
+            "400-spi-search-highlight-tag-not-legal",
+        ));
+    }
+    // The whole title is returned, the content is split into fragments
+    Ok(json!({
+        "pre_tags": [pre_tag],
+        "post_tags": [post_tag],
+        "fields": {
+            "title": { "number_of_fragments": 0 },
+            "content": {
+                "fragment_size": highlight_req.fragment_size.unwrap_or(HIGHLIGHT_FRAGMENT_CHARS),
+                "number_of_fragments": highlight_req.max_fragments.unwrap_or(HIGHLIGHT_MAX_FRAGMENTS),
+            },
+        },
+    }))
+}
+
+/// Search with the facet counts and the title suggestions
+///
+/// 搜索并返回分面统计及标题建议
+pub async fn facet_search(search_req: &mut SearchItemFacetSearchReq, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchItemFacetSearchResp> {
+    let facet_reqs = search_req.facets.clone().unwrap_or_default();
+    let q = search_req.search.query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(str::to_string);
+    let mut extras = json!({});
+    for (idx, facet_req) in facet_reqs.iter().enumerate() {
+        extras["aggs"][format!("facet_{idx}")] = json!({
+            "terms": {
+                "field": facet_field(facet_req, funs)?,
+                "size": facet_req.size.unwrap_or(FACET_SIZE),
+            }
+        });
+    }
+    if let (Some(suggest_req), Some(q)) = (&search_req.suggest, &q) {
+        if suggest_req.correction.unwrap_or(true) {
+            // "did you mean", the keywords corrected by the terms of the titles
+            extras["suggest"] = json!({
+                "correction": {
+                    "text": q,
+                    "phrase": {
+                        "field": "title",
+                        "size": suggest_req.size.unwrap_or(SUGGEST_SIZE),
+                    }
+                }
+            });
+        }
+    }
+    let (page, result) = do_raw_search(&search_req.search, extras, funs, inst).await?;
+
+    let facets = facet_reqs
+        .iter()
+        .enumerate()
+        .map(|(idx, facet_req)| SearchItemFacetResp {
+            field: facet_req.field.clone(),
+            values: result["aggregations"][format!("facet_{idx}")]["buckets"]
+                .as_array()
+                .map(|buckets| buckets.as_slice())
+                .unwrap_or_default()
+                .iter()
+                .map(|bucket| SearchItemFacetValueResp {
+                    value: match &bucket["key"] {
+                        Value::Null => None,
+                        Value::String(key) => Some(key.clone()),
+                        key => Some(bucket["key_as_string"].as_str().map(str::to_string).unwrap_or_else(|| key.to_string())),
+                    },
+                    count: bucket["doc_count"].as_i64().unwrap_or_default(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    let mut suggestions = vec![];
+    if let (Some(suggest_req), Some(q)) = (&search_req.suggest, &q) {
+        let size = suggest_req.size.unwrap_or(SUGGEST_SIZE) as usize;
+        for title in prefix_suggest(&search_req.search, q, size, inst).await? {
+            suggestions.push(SearchItemSuggestResp {
+                text: title,
+                kind: SearchItemSuggestKind::Prefix,
+            });
+        }
+        let corrections = result["suggest"]["correction"]
+            .as_array()
+            .map(|entries| entries.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|entry| entry["options"].as_array().cloned().unwrap_or_default())
+            .filter_map(|option| option["text"].as_str().map(str::to_string))
+            .collect::<Vec<_>>();
+        for text in corrections {
+            if suggestions.len() < size && !suggestions.iter().any(|suggestion| suggestion.text == text) {
+                suggestions.push(SearchItemSuggestResp {
+                    text,
+                    kind: SearchItemSuggestKind::Correction,
+                });
+            }
+        }
+    }
+    Ok(SearchItemFacetSearchResp { page, facets, suggestions })
+}
+
+fn facet_field(facet_req: &SearchItemFacetReq, funs: &TardisFunsInst) -> TardisResult<String> {
+    if facet_req.in_ext.unwrap_or(true) {
+        if facet_req.field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            // Arrays are counted by each element natively
+            return Ok(format!("ext.{}", facet_req.field));
+        }
+    } else if facet_req.field == "data_source" {
+        // Not in the mappings, the dynamic mapping adds the keyword sub field
+        return Ok("data_source.keyword".to_string());
+    } else if ["kind", "owner", "own_paths"].contains(&facet_req.field.as_str()) {
+        return Ok(facet_req.field.clone());
+    }
+    Err(funs.err().bad_request(
+        "search_es_item_serv",
+        "facet_search",
+        &format!("facet field [{}] is not legal", facet_req.field),
+        "400-spi-search-facet-field-not-legal",
+    ))
+}
+
+/// Titles starting with the keywords, limited by the other conditions
+async fn prefix_suggest(search_req: &SearchItemSearchReq, q: &str, size: usize, inst: &SpiBsInst) -> TardisResult<Vec<String>> {
+    let mut filter_req = search_req.clone();
+    filter_req.query.q = None;
+    filter_req.vector = None;
+    let mut dsl = TardisFuns::json.str_to_json(&gen_query_dsl(&filter_req)?)?;
+    let prefix_q = json!({ "match_phrase_prefix": { "title": q } });
+    if let Some(must) = dsl["query"]["bool"]["must"].as_array_mut() {
+        must.push(prefix_q);
+    } else {
+        dsl["query"]["bool"]["must"] = json!([prefix_q]);
+    }
+    dsl["_source"] = json!(["title"]);
+    if let Some(dsl) = dsl.as_object_mut() {
+        dsl.remove("sort");
+    }
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = format_index(&search_req.tag, ext);
+    // Duplicate titles are merged, so more items are fetched
+    let result = client.raw_search(&index, &dsl.to_string(), Some((size * 3) as i32), Some(0), None).await?;
+    let mut titles: Vec<String> = vec![];
+    for hit in result.hits.hits {
+        if let Some(title) = hit._source["title"].as_str() {
+            if titles.len() < size && !titles.iter().any(|t| t == title) {
+                titles.push(title.to_string());
+            }
+        }
+    }
+    Ok(titles)
+}
+
 pub async fn group_search(search_req: &mut GroupSearchItemSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<GroupSearchItemSearchResp>> {
     Ok(vec![])
 }
@@ -856,7 +1259,7 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
                 || sort_item.field.to_lowercase() == "update_time"
             {
                 sort_q.push(json!({sort_item.field.clone(): { "order": sort_item.order.to_sql() }}));
-            } else if sort_item.field.to_lowercase() == "rank_title" || sort_item.field.to_lowercase() == "rank_content" {
+            } else if sort_item.field.to_lowercase() == "rank_title" || sort_item.field.to_lowercase() == "rank_content" || sort_item.field.to_lowercase() == "rank_vector" {
                 sort_q.push(json!({"_score": { "order": sort_item.order.to_sql() }}));
             } else {
                 let sort_ket = format!("ext.{}", sort_item.field.clone());
@@ -880,6 +1283,48 @@ fn gen_query_dsl(search_req: &SearchItemSearchReq) -> TardisResult<String> {
     Ok(q.to_string())
 }
 
+/// Generate the knn query, the other conditions are used as the knn filter
+///
+/// 生成 knn 查询，其它条件作为 knn 的过滤条件
+async fn gen_vector_query_dsl(search_req: &SearchItemSearchReq, vector: &SearchItemVectorQueryReq, funs: &TardisFunsInst) -> TardisResult<String> {
+    search_embedding_serv::dimensions(funs)?;
+    let embedding = search_embedding_serv::query_embedding(vector, search_req.query.q.as_deref(), funs).await?;
+    let keyword_weight = search_embedding_serv::keyword_weight(vector, funs)?;
+    let mut filter_req = search_req.clone();
+    filter_req.query.q = None;
+    let filter_q = TardisFuns::json.str_to_json(&gen_query_dsl(&filter_req)?)?;
+    // knn returns the top k items, which should cover the requested page
+    let k = (search_req.page.number * search_req.page.size as u32).clamp(1, 10000);
+    let mut knn = json!({
+        "field": "embedding",
+        "query_vector": embedding,
+        "k": k,
+        "num_candidates": (k * 10).clamp(100, 10000),
+        "filter": filter_q["query"],
+    });
+    if let Some(min_similarity) = vector.min_similarity {
+        knn["similarity"] = json!(min_similarity);
+    }
+    let mut q = match search_embedding_serv::mode(vector) {
+        SearchItemVectorQueryModeKind::Vector => json!({ "knn": knn }),
+        SearchItemVectorQueryModeKind::Hybrid => {
+            // The scores of the keyword query and the knn query are summed up by the weights
+            knn["boost"] = json!(1.0 - keyword_weight);
+            let mut q = TardisFuns::json.str_to_json(&gen_query_dsl(search_req)?)?;
+            q["query"]["bool"]["boost"] = json!(keyword_weight);
+            q["knn"] = knn;
+            q
+        }
+    };
+    // Sorted by the score when `sort` is empty
+    if search_req.sort.is_some() {
+        q["sort"] = filter_q["sort"].clone();
+    } else if let Some(q) = q.as_object_mut() {
+        q.remove("sort");
+    }
+    Ok(q.to_string())
+}
+
 fn merge(a: &mut serde_json::Value, b: serde_json::Value) {
     match (a, b) {
         (a @ &mut serde_json::Value::Object(_), serde_json::Value::Object(b)) => {
@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use bios_basic::spi::spi_funs::SpiBsInst;
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    chrono::{DateTime, Utc},
+    search::search_client::TardisSearchClient,
+    serde_json::{json, Value},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{dto::search_item_dto::SearchItemAddReq, search_config::SearchConfig, serv::search_embedding_serv};
+
+use super::{search_es_dict_serv, search_es_initializer, search_es_item_serv};
+
+// Max number of the hits in one search request
+const SCAN_SIZE: u32 = 5000;
+
+pub async fn count(tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = search_es_item_serv::format_index(tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Ok(0);
+    }
+    let result = do_post(client, &format!("{index}/_count"), &json!({ "query": { "match_all": {} } }), "count", funs).await?;
+    Ok(result["count"].as_u64().unwrap_or_default())
+}
+
+/// Fetch the items ordered by the key, starting after the given key
+///
+/// 按 key 顺序获取指定 key 之后的数据
+pub async fn scan(tag: &str, after_key: Option<String>, size: u32, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemAddReq>> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = search_es_item_serv::format_index(tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Ok(vec![]);
+    }
+    let hits = do_scan(client, &index, json!({ "match_all": {} }), after_key, size, None, funs).await?;
+    hits.into_iter().map(|hit| to_item(tag, hit)).collect()
+}
+
+/// Fetch the items updated since the given time, or with the given keys
+///
+/// 获取指定时间后更新的数据，或指定 key 的数据
+pub async fn scan_changed(
+    tag: &str,
+    since: DateTime<Utc>,
+    keys: Vec<String>,
+    funs: &TardisFunsInst,
+    _ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Vec<SearchItemAddReq>> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = search_es_item_serv::format_index(tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Ok(vec![]);
+    }
+    let mut should = vec![json!({ "range": { "update_time": { "gte": since.to_rfc3339() } } })];
+    if !keys.is_empty() {
+        should.push(json!({ "terms": { "key": keys } }));
+    }
+    let hits = do_scan_all(client, &index, json!({ "bool": { "should": should, "minimum_should_match": 1 } }), None, funs).await?;
+    hits.into_iter().map(|hit| to_item(tag, hit)).collect()
+}
+
+pub async fn keys(tag: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashSet<String>> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = search_es_item_serv::format_index(tag, ext);
+    if !client.check_index_exist(&index).await? {
+        return Ok(HashSet::new());
+    }
+    find_keys(client, &index, funs).await
+}
+
+/// Get the shadow index name of the tag, the index is created when the first items are written
+///
+/// 获取 tag 的影子索引名称，索引在首次写入数据时创建
+pub async fn create_shadow(tag: &str, job_id: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<String> {
+    let (_, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = search_es_item_serv::format_index(tag, ext);
+    Ok(format!("{index}-reindex-{}", &TardisFuns::crypto.digest.md5(job_id)?[..8]))
+}
+
+/// Write the items to the shadow index, the existing ones are replaced when `replace` is true
+///
+/// 写入数据到影子索引，`replace` 为 true 时替换已存在的数据
+pub async fn write_shadow(shadow: &str, items: &mut [SearchItemAddReq], replace: bool, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let Some(first) = items.first() else {
+        return Ok(());
+    };
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let dict = search_es_dict_serv::find_dict(&first.tag, client, ext, funs).await?;
+    search_es_initializer::init_index(
+        client,
+        shadow,
+        Some(&search_es_item_serv::gen_data_mappings(&first.ext, &dict, funs.conf::<SearchConfig>().embedding.dimensions)),
+    )
+    .await?;
+    if replace {
+        do_delete_shadow(client, shadow, items.iter().map(|item| item.key.to_string()).collect(), funs).await?;
+    }
+    for item in items {
+        item.embedding = search_embedding_serv::item_embedding(item.embedding.take(), &item.title, &item.content, funs).await?;
+        client.create_record(shadow, &search_es_item_serv::gen_record(item, &dict)?).await?;
+    }
+    Ok(())
+}
+
+pub async fn delete_shadow(shadow: &str, keys: Vec<String>, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (client, _, _) = inst.inst::<TardisSearchClient>();
+    if !client.check_index_exist(shadow).await? {
+        return Ok(());
+    }
+    do_delete_shadow(client, shadow, keys, funs).await
+}
+
+async fn do_delete_shadow(client: &TardisSearchClient, shadow: &str, keys: Vec<String>, funs: &TardisFunsInst) -> TardisResult<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    // make the written items visible to the deletion
+    do_post(client, &format!("{shadow}/_refresh"), &json!({}), "delete_shadow", funs).await?;
+    for keys in keys.chunks(1000) {
+        client.delete_by_query(shadow, &json!({ "query": { "terms": { "key": keys } } }).to_string()).await?;
+    }
+    Ok(())
+}
+
+pub async fn shadow_keys(shadow: &str, funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashSet<String>> {
+    let (client, _, _) = inst.inst::<TardisSearchClient>();
+    if !client.check_index_exist(shadow).await? {
+        return Ok(HashSet::new());
+    }
+    do_post(client, &format!("{shadow}/_refresh"), &json!({}), "shadow_keys", funs).await?;
+    find_keys(client, shadow, funs).await
+}
+
+/// Replace the live index of the tag with the shadow index
+///
+/// The live index name becomes an alias of the shadow index, the indexes previously behind it are removed in the same atomic action.
+/// When `since` is given, the shadow index is built from the live index itself,
+/// ES can't block the writes, so the writes of the tag are rejected by the job while
+/// the items changed since then or missing in the shadow index are copied again, and the ones deleted from the live index are deleted, right before the switch.
+///
+/// 用影子索引替换 tag 的在线索引。
+/// 在线索引名称会成为影子索引的别名，原索引在同一原子操作中删除。
+/// 传入 `since` 时表示影子索引由在线索引自身构建，由于 ES 无法阻塞写入，任务会拒绝该 tag 的写入，
+/// 并在切换前再次复制此后变更或影子索引中缺失的数据，删除在线索引中已删除的数据。
+pub async fn swap_shadow(tag: &str, shadow: &str, since: Option<DateTime<Utc>>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (client, ext, _) = inst.inst::<TardisSearchClient>();
+    let index = search_es_item_serv::format_index(tag, ext);
+    if let Some(since) = since {
+        if client.check_index_exist(&index).await? {
+            // make the last writes visible to the key scanning
+            do_post(client, &format!("{index}/_refresh"), &json!({}), "swap_shadow", funs).await?;
+        }
+        let live_keys = keys(tag, funs, ctx, inst).await?;
+        let shadow_keys = shadow_keys(shadow, funs, ctx, inst).await?;
+        let added_keys = live_keys.difference(&shadow_keys).cloned().collect::<Vec<_>>();
+        let deleted_keys = shadow_keys.difference(&live_keys).cloned().collect::<Vec<_>>();
+        let mut items = scan_changed(tag, since, added_keys, funs, ctx, inst).await?;
+        search_embedding_serv::reset_embeddings(&mut items, funs);
+        write_shadow(shadow, &mut items, true, funs, ctx, inst).await?;
+        delete_shadow(shadow, deleted_keys, funs, ctx, inst).await?;
+    }
+    let dict = search_es_dict_serv::find_dict(tag, client, ext, funs).await?;
+    search_es_initializer::init_index(
+        client,
+        shadow,
+        Some(&search_es_item_serv::gen_data_mappings(&None, &dict, funs.conf::<SearchConfig>().embedding.dimensions)),
+    )
+    .await?;
+    let mut actions = vec![json!({ "add": { "index": shadow, "alias": index } })];
+    if client.check_index_exist(&index).await? {
+        let resp = client.client.get_to_str(&format!("{}/_alias/{index}", client.server_url), vec![]).await?;
+        let aliased_indexes = if resp.code == 200 {
+            TardisFuns::json.str_to_json(&resp.body.unwrap_or_default())?.as_object().map(|indexes| indexes.keys().cloned().collect::<Vec<_>>()).unwrap_or_default()
+        } else {
+            vec![]
+        };
+        if aliased_indexes.is_empty() {
+            // the live index is a concrete index
+            actions.push(json!({ "remove_index": { "index": index } }));
+        } else {
+            actions.extend(aliased_indexes.into_iter().filter(|aliased_index| aliased_index != shadow).map(|aliased_index| json!({ "remove_index": { "index": aliased_index } })));
+        }
+    }
+    do_post(client, "_aliases", &json!({ "actions": actions }), "swap_shadow", funs).await?;
+    Ok(())
+}
+
+/// Drop the shadow index, unless it has replaced the live index
+///
+/// 删除影子索引，已替换在线索引时保留
+pub async fn drop_shadow(shadow: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let (client, _, _) = inst.inst::<TardisSearchClient>();
+    if !client.check_index_exist(shadow).await? {
+        return Ok(());
+    }
+    let resp = client.client.get_to_str(&format!("{}/{shadow}/_alias", client.server_url), vec![]).await?;
+    if resp.code == 200 {
+        let aliases = TardisFuns::json.str_to_json(&resp.body.unwrap_or_default())?;
+        if aliases[shadow]["aliases"].as_object().map(|aliases| !aliases.is_empty()).unwrap_or(false) {
+            return Ok(());
+        }
+    }
+    client.client.delete_to_void(&format!("{}/{shadow}", client.server_url), vec![]).await?;
+    Ok(())
+}
+
+async fn find_keys(client: &TardisSearchClient, index: &str, funs: &TardisFunsInst) -> TardisResult<HashSet<String>> {
+    let hits = do_scan_all(client, index, json!({ "match_all": {} }), Some(json!(["key"])), funs).await?;
+    Ok(hits.iter().filter_map(|hit| hit["_source"]["key"].as_str().map(|key| key.to_string())).collect())
+}
+
+async fn do_scan_all(client: &TardisSearchClient, index: &str, query: Value, source: Option<Value>, funs: &TardisFunsInst) -> TardisResult<Vec<Value>> {
+    let mut result = vec![];
+    let mut after_key = None;
+    loop {
+        let hits = do_scan(client, index, query.clone(), after_key, SCAN_SIZE, source.clone(), funs).await?;
+        let size = hits.len();
+        after_key = hits.last().and_then(|hit| hit["_source"]["key"].as_str().map(|key| key.to_string()));
+        result.extend(hits);
+        if size < SCAN_SIZE as usize || after_key.is_none() {
+            return Ok(result);
+        }
+    }
+}
+
+async fn do_scan(
+    client: &TardisSearchClient,
+    index: &str,
+    query: Value,
+    after_key: Option<String>,
+    size: u32,
+    source: Option<Value>,
+    funs: &TardisFunsInst,
+) -> TardisResult<Vec<Value>> {
+    let mut q = json!({
+        "query": query,
+        "sort": [{ "key": "asc" }],
+        "size": size,
+    });
+    if let Some(after_key) = after_key {
+        q["search_after"] = json!([after_key]);
+    }
+    if let Some(source) = source {
+        q["_source"] = source;
+    }
+    let result = do_post(client, &format!("{index}/_search"), &q, "scan", funs).await?;
+    Ok(result["hits"]["hits"].as_array().cloned().unwrap_or_default())
+}
+
+async fn do_post(client: &TardisSearchClient, path: &str, body: &Value, op: &str, funs: &TardisFunsInst) -> TardisResult<Value> {
+    let resp = client
+        .client
+        .post_str_to_str(
+            &format!("{}/{path}", client.server_url),
+            &body.to_string(),
+            vec![("Content-Type".to_string(), "application/json".to_string())],
+        )
+        .await?;
+    if !(200..300).contains(&resp.code) {
+        return Err(funs.err().internal_error(
+            "search_es_reindex_serv",
+            op,
+            &format!("reindex error: {}", resp.body.unwrap_or_default()),
+            "500-spi-search-es-reindex-error",
+        ));
+    }
+    TardisFuns::json.str_to_json(&resp.body.unwrap_or_default())
+}
+
+fn to_item(tag: &str, hit: Value) -> TardisResult<SearchItemAddReq> {
+    let mut item = TardisFuns::json.json_to_obj::<SearchItemAddReq>(hit["_source"].clone())?;
+    item.tag = tag.to_string();
+    Ok(item)
+}
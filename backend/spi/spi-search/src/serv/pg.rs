@@ -1,2 +1,4 @@
+pub mod search_pg_dict_serv;
 pub mod search_pg_initializer;
 pub mod search_pg_item_serv;
+pub mod search_pg_reindex_serv;
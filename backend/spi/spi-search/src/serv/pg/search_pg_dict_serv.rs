@@ -0,0 +1,145 @@
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, result::TardisResult},
+    db::{reldb_client::TardisRelDBClient, reldb_client::TardisRelDBlConnection, sea_orm::Value},
+    serde_json::{self, json},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::search_dict_dto::{SearchDictEntryReq, SearchDictEntryResp, SearchDictFieldKind, SearchDictResp},
+    serv::search_dict_serv::{self, SearchDict},
+};
+
+use super::search_pg_initializer;
+
+pub async fn save(tag: &str, entries: &[SearchDictEntryReq], funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let entries = search_dict_serv::normalize(entries, funs)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, schema_name) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    let dict_table_name = search_pg_initializer::init_dict_table(&conn, &schema_name).await?;
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {dict_table_name} (tag, entries, update_time) VALUES ($1, $2, now())
+ON CONFLICT (tag) DO UPDATE SET entries = EXCLUDED.entries, update_time = EXCLUDED.update_time"#
+        ),
+        vec![Value::from(tag), Value::from(TardisFuns::json.obj_to_json(&entries)?)],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn get(tag: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchDictResp> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, schema_name) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    let dict_table_name = search_pg_initializer::init_dict_table(&conn, &schema_name).await?;
+    let result = conn.query_one(&format!("SELECT entries, update_time FROM {dict_table_name} WHERE tag = $1"), vec![Value::from(tag)]).await?;
+    Ok(match result {
+        Some(row) => SearchDictResp {
+            tag: tag.to_string(),
+            entries: TardisFuns::json.json_to_obj(row.try_get::<serde_json::Value>("", "entries")?)?,
+            update_time: Some(row.try_get("", "update_time")?),
+        },
+        None => SearchDictResp {
+            tag: tag.to_string(),
+            entries: vec![],
+            update_time: None,
+        },
+    })
+}
+
+pub async fn delete(tag: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, schema_name) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    let dict_table_name = search_pg_initializer::init_dict_table(&conn, &schema_name).await?;
+    conn.execute_one(&format!("DELETE FROM {dict_table_name} WHERE tag = $1"), vec![Value::from(tag)]).await?;
+    Ok(())
+}
+
+/// Find the dictionary of the tag, the schema is taken from the table name
+///
+/// 获取 tag 的词典，schema 取自表名
+pub async fn find_dict(tag: &str, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<SearchDict> {
+    let (schema_name, _) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let dict_table_name = search_pg_initializer::init_dict_table(conn, schema_name).await?;
+    let result = conn.query_one(&format!("SELECT entries FROM {dict_table_name} WHERE tag = $1"), vec![Value::from(tag)]).await?;
+    let entries = match result {
+        Some(row) => TardisFuns::json.json_to_obj::<Vec<SearchDictEntryResp>>(row.try_get::<serde_json::Value>("", "entries")?)?,
+        None => vec![],
+    };
+    Ok(SearchDict { entries })
+}
+
+/// Apply the dictionary to the tsvector expression of the field, the params are appended
+///
+/// The user words and the synonyms are added as whole lexemes, the stop words are deleted.
+/// The dictionary is not built as a text search configuration: the synonym and stop word dictionaries of pg are loaded from the files
+/// in the `tsearch_data` directory of the database server, which the service can't write, and a configuration is shared by the whole database,
+/// while the dictionary belongs to a tag and is changed at runtime.
+/// Both the configurations used by the tables map the tokens with the `simple` dictionary, which only lowercases them,
+/// so the normalized words equal the lexemes and can be added or deleted directly.
+///
+/// 将词典应用到字段的 tsvector 表达式，参数追加到末尾。
+/// 自定义词及同义词作为完整词位加入，停用词被删除。
+/// 词典没有构建为全文检索配置：pg 的同义词及停用词词典从数据库服务器 `tsearch_data` 目录下的文件加载，服务无法写入这些文件，
+/// 且配置由整个数据库共享，而词典属于 tag 并会在运行时修改。
+/// 表使用的两种配置均以仅转为小写的 `simple` 词典映射词语，因此规范化后的词语与词位一致，可直接加入或删除。
+pub fn dict_tsv_sql(tsv_sql: String, dict: &SearchDict, field: SearchDictFieldKind, text: &str, params: &mut Vec<Value>) -> String {
+    if dict.is_empty() {
+        return tsv_sql;
+    }
+    let mut tsv_sql = tsv_sql;
+    let index_words = dict.index_words(field, text);
+    if !index_words.is_empty() {
+        params.push(Value::from(json!(index_words)));
+        tsv_sql = format!("({tsv_sql} || array_to_tsvector(ARRAY(SELECT jsonb_array_elements_text(${}::jsonb))))", params.len());
+    }
+    let stop_words = dict.stop_words(field);
+    if !stop_words.is_empty() {
+        params.push(Value::from(json!(stop_words)));
+        tsv_sql = format!("ts_delete({tsv_sql}, ARRAY(SELECT jsonb_array_elements_text(${}::jsonb)))", params.len());
+    }
+    tsv_sql
+}
+
+/// Build the tsquery expression of the keywords with the dictionary, the params are appended
+///
+/// The stop words are removed, the user words are matched as whole lexemes, the words are expanded with their synonyms.
+///
+/// 使用词典构建关键字的 tsquery 表达式，参数追加到末尾。
+/// 移除停用词，自定义词按完整词位匹配，词语扩展为其同义词。
+pub fn dict_tsquery_sql(config: &str, q_idx: usize, q: &str, dict: &SearchDict, sql_vals: &mut Vec<Value>) -> String {
+    let mut tsquery_sql = format!("plainto_tsquery('{config}', ${q_idx})");
+    if dict.is_empty() {
+        return tsquery_sql;
+    }
+    for word in dict.query_stop_words(q) {
+        sql_vals.push(Value::from(word));
+        // an empty substitute deletes the matched node
+        tsquery_sql = format!("ts_rewrite({tsquery_sql}, plainto_tsquery('{config}', ${}), ''::tsquery)", sql_vals.len());
+    }
+    for word in dict.query_user_words(q) {
+        sql_vals.push(Value::from(to_lexemes_tsquery(&[word.clone()])));
+        sql_vals.push(Value::from(word));
+        tsquery_sql = format!(
+            "ts_rewrite({tsquery_sql}, plainto_tsquery('{config}', ${}), ${}::tsquery)",
+            sql_vals.len(),
+            sql_vals.len() - 1
+        );
+    }
+    for (word, synonyms) in dict.query_synonyms(q) {
+        sql_vals.push(Value::from(to_lexemes_tsquery(&synonyms)));
+        sql_vals.push(Value::from(word));
+        tsquery_sql = format!(
+            "ts_rewrite({tsquery_sql}, plainto_tsquery('{config}', ${word_idx}), plainto_tsquery('{config}', ${word_idx}) || ${}::tsquery)",
+            sql_vals.len() - 1,
+            word_idx = sql_vals.len(),
+        );
+    }
+    tsquery_sql
+}
+
+/// Format the words as a tsquery matching any of them as a whole lexeme
+fn to_lexemes_tsquery(words: &[String]) -> String {
+    words.iter().map(|word| format!("'{}'", word.replace('\\', "\\\\").replace('\'', "''"))).collect::<Vec<_>>().join(" | ")
+}
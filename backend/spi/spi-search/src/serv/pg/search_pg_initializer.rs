@@ -1,9 +1,29 @@
 use bios_basic::spi::{spi_funs::TypedSpiBsInst, spi_initializer};
 use tardis::{
-    basic::{dto::TardisContext, result::TardisResult},
-    db::reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    TardisFuns,
 };
 
+use crate::{search_config::SearchConfig, search_constants::DOMAIN_CODE};
+
+const TABLE_INDEXES: [(&str, &str); 11] = [
+    ("kind", "btree"),
+    ("key", "btree"),
+    ("title_tsv", "gin"),
+    ("content_tsv", "gin"),
+    ("ext", "gin"),
+    ("data_source", "btree"),
+    ("owner", "btree"),
+    ("own_paths", "btree"),
+    ("create_time", "btree"),
+    ("update_time", "btree"),
+    ("visit_keys", "gin"),
+];
+
 pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, tag: &str, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
     spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
@@ -25,21 +45,207 @@ pub async fn init_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
     ext jsonb NOT NULL,
     visit_keys jsonb"#,
         None,
-        vec![
-            ("kind", "btree"),
-            ("key", "btree"),
-            ("title_tsv", "gin"),
-            ("content_tsv", "gin"),
-            ("ext", "gin"),
-            ("data_source", "btree"),
-            ("owner", "btree"),
-            ("own_paths", "btree"),
-            ("create_time", "btree"),
-            ("update_time", "btree"),
-            ("visit_keys", "gin"),
-        ],
+        TABLE_INDEXES.to_vec(),
         None,
         Some("update_time"),
     )
     .await
 }
+
+/// Check the extensions required by the enabled features when the backend service is initialized
+///
+/// 在初始化后端服务时检查已启用功能所需的扩展
+///
+/// The extensions are created when installing the database (see `config/init_script.sql`), not by the connections of the tenants.
+/// 扩展在安装数据库时创建（见 `config/init_script.sql`），而不是由租户的连接创建。
+pub async fn check_extensions(client: &TardisRelDBClient) -> TardisResult<()> {
+    if TardisFuns::cs_config::<SearchConfig>(DOMAIN_CODE).embedding.dimensions > 0 {
+        check_extension(&client.conn(), "vector").await?;
+    }
+    Ok(())
+}
+
+async fn check_extension(conn: &TardisRelDBlConnection, extension: &str) -> TardisResult<()> {
+    if conn.count_by_sql("SELECT 1 FROM pg_extension WHERE extname = $1", vec![Value::from(extension)]).await? == 0 {
+        return Err(TardisError::internal_error(
+            &format!("the {extension} extension is not installed, it should be created when installing the database"),
+            "500-spi-search-extension-not-installed",
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the relation (table or index) exists, checked in the catalog so that the tables swapped by other nodes are also seen
+///
+/// 关系（表或索引）是否存在，以数据库目录为准，其他节点替换的表也能感知
+async fn relation_exists(conn: &TardisRelDBlConnection, relation_name: &str) -> TardisResult<bool> {
+    Ok(conn.count_by_sql("SELECT 1 WHERE to_regclass($1) IS NOT NULL", vec![Value::from(relation_name)]).await? != 0)
+}
+
+/// Whether the index is valid, `None` if it doesn't exist
+///
+/// 索引是否有效，不存在时返回 `None`
+///
+/// A concurrent creation that failed or is still running leaves an invalid index.
+/// 并发创建失败或仍在进行时索引无效。
+async fn index_valid(conn: &TardisRelDBlConnection, index_name: &str) -> TardisResult<Option<bool>> {
+    let result = conn.query_one("SELECT indisvalid FROM pg_index WHERE indexrelid = to_regclass($1)", vec![Value::from(index_name)]).await?;
+    Ok(match result {
+        Some(result) => Some(result.try_get("", "indisvalid")?),
+        None => None,
+    })
+}
+
+/// Whether the index is being created by a session, checked in the progress of the index creations
+///
+/// 索引是否正由某个会话创建，以索引创建进度为准
+async fn index_building(conn: &TardisRelDBlConnection, index_name: &str) -> TardisResult<bool> {
+    Ok(conn
+        .count_by_sql(
+            "SELECT 1 FROM pg_stat_progress_create_index WHERE index_relid = to_regclass($1)",
+            vec![Value::from(index_name)],
+        )
+        .await?
+        != 0)
+}
+
+/// Create the index without blocking the writes of the table, must not be called in a transaction
+///
+/// 创建索引且不阻塞表的写入，不能在事务中调用
+///
+/// The index created by another session at the same time is taken as created, the invalid index left by a failed creation is created again.
+/// 其他会话同时创建的索引视为已创建，创建失败遗留的无效索引会重新创建。
+async fn create_index_concurrently(conn: &TardisRelDBlConnection, table_name: &str, index_name: &str, index_def: &str) -> TardisResult<()> {
+    let (schema_name, _) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let full_index_name = format!("{schema_name}.{index_name}");
+    match index_valid(conn, &full_index_name).await? {
+        Some(true) => return Ok(()),
+        // an index still being created is invalid as well
+        Some(false) if index_building(conn, &full_index_name).await? => return Ok(()),
+        Some(false) => {
+            conn.execute_one(&format!("DROP INDEX CONCURRENTLY IF EXISTS {full_index_name}"), vec![]).await?;
+        }
+        None => {}
+    }
+    if let Err(e) = conn.execute_one(&format!("CREATE INDEX CONCURRENTLY IF NOT EXISTS {index_name} ON {table_name} USING {index_def}"), vec![]).await {
+        // the concurrent creations of the same index conflict in the catalog, the one that fails leaves the index to the other
+        if index_valid(conn, &full_index_name).await?.is_none() {
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+fn embedding_index_name(table_name: &str) -> TardisResult<String> {
+    // index name shouldn't be longer than 63 characters
+    Ok(format!("idx_emb_{}", &TardisFuns::crypto.digest.md5(table_name)?[..24]))
+}
+
+fn suggest_index_name(table_name: &str) -> TardisResult<String> {
+    // index name shouldn't be longer than 63 characters
+    Ok(format!("idx_trgm_{}", &TardisFuns::crypto.digest.md5(table_name)?[..24]))
+}
+
+/// Add the embedding column and its HNSW index to the table, requires the pgvector extension, must not be called in a transaction
+///
+/// 为表添加向量字段及 HNSW 索引，需要 pgvector 扩展，不能在事务中调用
+pub async fn init_embedding_column(conn: &TardisRelDBlConnection, table_name: &str, dimensions: usize) -> TardisResult<()> {
+    let (schema_name, _) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let index_name = embedding_index_name(table_name)?;
+    if index_valid(conn, &format!("{schema_name}.{index_name}")).await? == Some(true) {
+        return Ok(());
+    }
+    check_extension(conn, "vector").await?;
+    conn.execute_one(&format!("ALTER TABLE {table_name} ADD COLUMN IF NOT EXISTS embedding vector({dimensions})"), vec![]).await?;
+    create_index_concurrently(conn, table_name, &index_name, "hnsw (embedding vector_cosine_ops)").await
+}
+
+/// Add the trigram index of the title to the table for the suggestions, requires the pg_trgm extension, must not be called in a transaction
+///
+/// 为表添加标题的三元组索引以支持搜索建议，需要 pg_trgm 扩展，不能在事务中调用
+pub async fn init_suggest_index(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
+    let (schema_name, _) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let index_name = suggest_index_name(table_name)?;
+    if index_valid(conn, &format!("{schema_name}.{index_name}")).await? == Some(true) {
+        return Ok(());
+    }
+    check_extension(conn, "pg_trgm").await?;
+    create_index_concurrently(conn, table_name, &index_name, "gin (title gin_trgm_ops)").await
+}
+
+/// Create the dictionary table of the schema if it doesn't exist, returns the table name
+///
+/// 创建 schema 的词典表（若不存在），返回表名
+pub async fn init_dict_table(conn: &TardisRelDBlConnection, schema_name: &str) -> TardisResult<String> {
+    // not prefixed with `starsys_search_` to avoid conflicting with the tables of the tags
+    let table_name = format!("{schema_name}.starsys_dict_search");
+    if relation_exists(conn, &table_name).await? {
+        return Ok(table_name);
+    }
+    conn.execute_one(
+        &format!(
+            r#"CREATE TABLE IF NOT EXISTS {table_name}
+(
+    tag character varying NOT NULL PRIMARY KEY,
+    entries jsonb NOT NULL,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+)"#
+        ),
+        vec![],
+    )
+    .await?;
+    Ok(table_name)
+}
+
+/// Create the shadow table of the live table for reindex, with the same columns and indexes,
+/// the embedding column is recreated by the configured dimensions, must not be called in a transaction
+///
+/// 为重建索引创建在线表的影子表，字段及索引与在线表相同，向量字段按配置的维度重建，不能在事务中调用
+pub async fn init_shadow_table(conn: &TardisRelDBlConnection, table_name: &str, shadow_table_name: &str, embedding_dimensions: usize) -> TardisResult<()> {
+    conn.execute_one(&format!("CREATE TABLE {shadow_table_name} (LIKE {table_name} INCLUDING DEFAULTS)"), vec![]).await?;
+    conn.execute_one(&format!("ALTER TABLE {shadow_table_name} ADD PRIMARY KEY (key)"), vec![]).await?;
+    // index name shouldn't be longer than 63 characters, and shouldn't conflict with the ones of the live table
+    let index_prefix = format!("idx_{}", &TardisFuns::crypto.digest.md5(shadow_table_name)?[..24]);
+    for (idx, (field_name, index_type)) in TABLE_INDEXES.iter().enumerate() {
+        conn.execute_one(
+            &format!("CREATE INDEX {index_prefix}_{idx} ON {shadow_table_name} USING {index_type}({field_name})"),
+            vec![],
+        )
+        .await?;
+    }
+    conn.execute_one(
+        &format!("CREATE TRIGGER TARDIS_AUTO_UPDATE_TIME_ON BEFORE UPDATE ON {shadow_table_name} FOR EACH ROW EXECUTE PROCEDURE TARDIS_AUTO_UPDATE_TIME_update_time()"),
+        vec![],
+    )
+    .await?;
+    conn.execute_one(&format!("ALTER TABLE {shadow_table_name} DROP COLUMN IF EXISTS embedding"), vec![]).await?;
+    if embedding_dimensions > 0 {
+        init_embedding_column(conn, shadow_table_name, embedding_dimensions).await?;
+    }
+    // The suggest index is created on demand, the shadow table gets it if the live table has it, so that it is available right after the swap
+    let (schema_name, _) = table_name.split_once('.').unwrap_or(("public", table_name));
+    if index_valid(conn, &format!("{schema_name}.{}", suggest_index_name(table_name)?)).await?.is_some() {
+        init_suggest_index(conn, shadow_table_name).await?;
+    }
+    Ok(())
+}
+
+/// Replace the live table with the shadow table, should be called in a transaction
+///
+/// 用影子表替换在线表，需在事务中调用
+pub async fn swap_shadow_table(conn: &TardisRelDBlConnection, table_name: &str, shadow_table_name: &str) -> TardisResult<()> {
+    let (schema_name, live_name) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let shadow_name = shadow_table_name.split_once('.').map(|(_, name)| name).unwrap_or(shadow_table_name);
+    let old_name = format!("{shadow_name}_old");
+    conn.execute_one(&format!("ALTER TABLE {table_name} RENAME TO {old_name}"), vec![]).await?;
+    conn.execute_one(&format!("ALTER TABLE {shadow_table_name} RENAME TO {live_name}"), vec![]).await?;
+    conn.execute_one(&format!("DROP TABLE {schema_name}.{old_name}"), vec![]).await?;
+    // The embedding and suggest indexes are looked up by the table name
+    for (shadow_index_name, index_name) in [
+        (embedding_index_name(shadow_table_name)?, embedding_index_name(table_name)?),
+        (suggest_index_name(shadow_table_name)?, suggest_index_name(table_name)?),
+    ] {
+        conn.execute_one(&format!("ALTER INDEX IF EXISTS {schema_name}.{shadow_index_name} RENAME TO {index_name}"), vec![]).await?;
+    }
+    Ok(())
+}
@@ -19,31 +19,51 @@ use tardis::{
 use bios_basic::{dto::BasicQueryCondInfo, enumeration::BasicQueryOpKind, helper::db_helper, spi::spi_funs::SpiBsInst};
 
 use crate::{
+    dto::search_dict_dto::SearchDictFieldKind,
     dto::search_item_dto::{
         AdvSearchItemQueryReq, GroupSearchItemSearchReq, GroupSearchItemSearchResp, MultipleSearchItemSearchReq, SearchExportAggResp, SearchExportDataReq, SearchExportDataResp,
-        SearchImportDataReq, SearchItemAddReq, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind,
-        SearchItemSearchReq, SearchItemSearchResp, SearchItemSearchSortKind, SearchItemSearchSortReq, SearchQueryMetricsReq, SearchQueryMetricsResp, SearchSaveItemReq,
-        SearchWordCombinationsRuleWay,
+        SearchImportDataReq, SearchItemAddReq, SearchItemFacetReq, SearchItemFacetResp, SearchItemFacetSearchReq, SearchItemFacetSearchResp, SearchItemFacetValueResp,
+        SearchItemHighlightReq, SearchItemHighlightResp, SearchItemModifyReq, SearchItemQueryReq, SearchItemSearchCtxReq, SearchItemSearchPageReq, SearchItemSearchQScopeKind,
+        SearchItemSearchReq, SearchItemSearchResp, SearchItemSearchSortKind, SearchItemSearchSortReq, SearchItemSuggestKind, SearchItemSuggestReq, SearchItemSuggestResp,
+        SearchItemVectorQueryModeKind, SearchQueryMetricsReq, SearchQueryMetricsResp, SearchSaveItemReq, SearchWordCombinationsRuleWay,
     },
     search_config::SearchConfig,
+    serv::{search_dict_serv::SearchDict, search_embedding_serv},
 };
 
-use super::search_pg_initializer;
+use super::{search_pg_dict_serv, search_pg_initializer};
 
 const FUNCTION_SUFFIX_FLAG: &str = "__";
 const FUNCTION_EXT_SUFFIX_FLAG: &str = "_ext_";
 const INNER_FIELD: [&str; 8] = ["key", "title", "kind", "content", "owner", "own_paths", "create_time", "update_time"];
+const FACET_INNER_FIELD: [&str; 4] = ["kind", "owner", "own_paths", "data_source"];
+const FACET_SIZE: u16 = 10;
+const SUGGEST_SIZE: u16 = 5;
+const HIGHLIGHT_PRE_TAG: &str = "<em>";
+const HIGHLIGHT_POST_TAG: &str = "</em>";
+const HIGHLIGHT_MAX_FRAGMENTS: u16 = 3;
+const HIGHLIGHT_FRAGMENT_WORDS: u16 = 35;
 
 pub async fn add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &add_req.tag, ctx, true).await?;
+    init_embedding(&conn, &table_name, funs).await?;
     conn.begin().await?;
     self::do_add(add_req, funs, ctx, &conn, &table_name).await?;
     conn.commit().await?;
     Ok(())
 }
 
+/// Add the embedding column to the table when vector search is enabled
+async fn init_embedding(conn: &TardisRelDBlConnection, table_name: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    if search_embedding_serv::enabled(funs) {
+        search_pg_initializer::init_embedding_column(conn, table_name, search_embedding_serv::dimensions(funs)?).await?;
+    }
+    Ok(())
+}
+
 pub async fn do_add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, _ctx: &TardisContext, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
+    let embedding = search_embedding_serv::item_embedding(add_req.embedding.clone(), &add_req.title, &add_req.content, funs).await?;
     let mut params = Vec::new();
     params.push(Value::from(add_req.kind.to_string()));
     params.push(Value::from(add_req.key.to_string()));
@@ -70,19 +90,40 @@ pub async fn do_add(add_req: &mut SearchItemAddReq, funs: &TardisFunsInst, _ctx:
     if let Some(visit_keys) = &add_req.visit_keys {
         params.push(Value::from(visit_keys.to_sql()));
     };
+    let embedding_value = if let Some(embedding) = &embedding {
+        params.push(Value::from(search_embedding_serv::to_pg_vector(embedding)));
+        format!(", ${}::vector", params.len())
+    } else {
+        "".to_string()
+    };
 
     let word_combinations_way = if add_req.title.chars().count() > funs.conf::<SearchConfig>().split_strategy_rule_config.specify_word_length.unwrap_or(30) {
         get_tokenizer()
     } else {
         "simple".to_string()
     };
+    let dict = search_pg_dict_serv::find_dict(&add_req.tag, conn, table_name).await?;
+    let title_tsv_value = search_pg_dict_serv::dict_tsv_sql(
+        format!("to_tsvector('{word_combinations_way}', $4)"),
+        &dict,
+        SearchDictFieldKind::Title,
+        &add_req.title,
+        &mut params,
+    );
+    let content_tsv_value = search_pg_dict_serv::dict_tsv_sql(
+        format!("to_tsvector('{}', $6)", get_tokenizer()),
+        &dict,
+        SearchDictFieldKind::Content,
+        &add_req.content,
+        &mut params,
+    );
     conn.execute_one(
         &format!(
             r#"INSERT INTO {table_name} 
-    (kind, key, title, title_tsv, content, content_tsv, data_source, owner, own_paths, create_time, update_time, ext, visit_keys)
+    (kind, key, title, title_tsv, content, content_tsv, data_source, owner, own_paths, create_time, update_time, ext, visit_keys{})
 VALUES
-    ($1, $2, $3, to_tsvector('{word_combinations_way}', $4), $5, to_tsvector('{}', $6), $7, $8, $9, $10, $11, $12, {})"#,
-            get_tokenizer(),
+    ($1, $2, $3, {title_tsv_value}, $5, {content_tsv_value}, $7, $8, $9, $10, $11, $12, {}{embedding_value})"#,
+            if embedding.is_some() { ", embedding" } else { "" },
             if add_req.visit_keys.is_some() { "$13" } else { "null" },
         ),
         params,
@@ -95,17 +136,23 @@ VALUES
 pub async fn modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
+    init_embedding(&conn, &table_name, funs).await?;
     conn.begin().await?;
-    self::do_modify(key, modify_req, funs, &conn, &table_name).await?;
+    self::do_modify(tag, key, modify_req, funs, &conn, &table_name).await?;
     conn.commit().await?;
     Ok(())
 }
 
-async fn do_modify(key: &str, modify_req: &mut SearchItemModifyReq, funs: &TardisFunsInst, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
+async fn do_modify(tag: &str, key: &str, modify_req: &mut SearchItemModifyReq, funs: &TardisFunsInst, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<()> {
     let mut params: Vec<Value> = vec![Value::from(key)];
     params.push(Value::from(key));
 
     let mut sql_sets: Vec<String> = Vec::new();
+    let dict = if modify_req.title.is_some() || modify_req.content.is_some() {
+        search_pg_dict_serv::find_dict(tag, conn, table_name).await?
+    } else {
+        SearchDict::default()
+    };
 
     if let Some(kind) = &modify_req.kind {
         sql_sets.push(format!("kind = ${}", params.len() + 1));
@@ -119,13 +166,27 @@ async fn do_modify(key: &str, modify_req: &mut SearchItemModifyReq, funs: &Tardi
         } else {
             "simple".to_string()
         };
-        sql_sets.push(format!("title_tsv = to_tsvector('{word_combinations_way}', ${})", params.len() + 1));
         params.push(Value::from(title_tsv(title, funs).await?));
+        let title_tsv_value = search_pg_dict_serv::dict_tsv_sql(
+            format!("to_tsvector('{word_combinations_way}', ${})", params.len()),
+            &dict,
+            SearchDictFieldKind::Title,
+            title,
+            &mut params,
+        );
+        sql_sets.push(format!("title_tsv = {title_tsv_value}"));
     };
     if let Some(content) = &modify_req.content {
         sql_sets.push(format!("content = ${}", params.len() + 1));
-        sql_sets.push(format!("content_tsv = to_tsvector('{}', ${})", get_tokenizer(), params.len() + 1));
         params.push(Value::from(content));
+        let content_tsv_value = search_pg_dict_serv::dict_tsv_sql(
+            format!("to_tsvector('{}', ${})", get_tokenizer(), params.len()),
+            &dict,
+            SearchDictFieldKind::Content,
+            content,
+            &mut params,
+        );
+        sql_sets.push(format!("content_tsv = {content_tsv_value}"));
         // params.push(Value::from(format!("{},{}", content, to_pinyin_vec(content, Pinyin::plain).join(","))));
     };
     if let Some(owner) = &modify_req.owner {
@@ -156,6 +217,31 @@ async fn do_modify(key: &str, modify_req: &mut SearchItemModifyReq, funs: &Tardi
         sql_sets.push(format!("visit_keys = ${}", params.len() + 1));
         params.push(Value::from(visit_keys.to_sql()));
     };
+    let embedding = if modify_req.embedding.is_some() {
+        search_embedding_serv::item_embedding(modify_req.embedding.clone(), "", "", funs).await?
+    } else if (modify_req.title.is_some() || modify_req.content.is_some()) && search_embedding_serv::has_provider(funs) {
+        // Regenerate the embedding only when the text changes
+        match conn.query_one(&format!("SELECT title, content FROM {table_name} WHERE key = $1"), vec![Value::from(key)]).await? {
+            Some(item) => {
+                let old_title: String = item.try_get("", "title")?;
+                let old_content: String = item.try_get::<Option<String>>("", "content")?.unwrap_or_default();
+                let title = modify_req.title.clone().unwrap_or_else(|| old_title.clone());
+                let content = modify_req.content.clone().unwrap_or_else(|| old_content.clone());
+                if title != old_title || content != old_content {
+                    search_embedding_serv::item_embedding(None, &title, &content, funs).await?
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    } else {
+        None
+    };
+    if let Some(embedding) = &embedding {
+        sql_sets.push(format!("embedding = ${}::vector", params.len() + 1));
+        params.push(Value::from(search_embedding_serv::to_pg_vector(embedding)));
+    }
 
     conn.execute_one(
         &format!(
@@ -174,6 +260,7 @@ WHERE key = $1
 pub async fn save(tag: &str, save_req: &mut SearchSaveItemReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
+    init_embedding(&conn, &table_name, funs).await?;
     conn.begin().await?;
     let exists_items = self::search(
         &mut SearchItemSearchReq {
@@ -191,6 +278,8 @@ pub async fn save(tag: &str, save_req: &mut SearchSaveItemReq, funs: &TardisFuns
                 size: 1,
                 fetch_total: false,
             },
+            vector: None,
+            highlight: None,
         },
         funs,
         ctx,
@@ -225,8 +314,9 @@ async fn do_save(
             ext: save_req.ext.clone(),
             visit_keys: save_req.visit_keys.clone(),
             ext_override: Some(false),
+            embedding: save_req.embedding.clone(),
         };
-        self::do_modify(&save_req.key, &mut modify_req, funs, conn, table_name).await?;
+        self::do_modify(tag, &save_req.key, &mut modify_req, funs, conn, table_name).await?;
     } else {
         let mut add_req = SearchItemAddReq {
             tag: tag.to_string(),
@@ -241,6 +331,7 @@ async fn do_save(
             update_time: save_req.update_time,
             ext: save_req.ext.clone(),
             visit_keys: save_req.visit_keys.clone(),
+            embedding: save_req.embedding.clone(),
         };
         self::do_add(&mut add_req, funs, ctx, conn, table_name).await?;
     }
@@ -250,6 +341,7 @@ async fn do_save(
 pub async fn batch_save(tag: &str, batch_req: &mut [SearchSaveItemReq], funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
+    init_embedding(&conn, &table_name, funs).await?;
     conn.begin().await?;
     let exists_items = self::search(
         &mut SearchItemSearchReq {
@@ -267,6 +359,8 @@ pub async fn batch_save(tag: &str, batch_req: &mut [SearchSaveItemReq], funs: &T
                 size: batch_req.len() as u16,
                 fetch_total: false,
             },
+            vector: None,
+            highlight: None,
         },
         funs,
         ctx,
@@ -399,61 +493,187 @@ async fn get_ext(tag: &str, key: &str, table_name: &str, conn: &TardisRelDBlConn
     Ok(result.try_get("", "ext")?)
 }
 
-pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
+/// The dictionary is only used by the keywords
+async fn find_query_dict(query: &SearchItemQueryReq, tag: &str, conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<SearchDict> {
+    if query.q.is_some() {
+        search_pg_dict_serv::find_dict(tag, conn, table_name).await
+    } else {
+        Ok(SearchDict::default())
+    }
+}
+
+/// Conditions of the search request, shared by the search, facets and suggestions
+struct SearchSqlFragments {
+    select_fragments: String,
+    from_fragments: String,
+    // Including the advanced query
+    where_fragments: String,
+    sql_vals: Vec<Value>,
+    // Whether `query1` and `query2` of the keywords are available
+    keyword_query: bool,
+    // Mode and keyword weight of the vector query
+    vector_query: Option<(SearchItemVectorQueryModeKind, f32)>,
+}
+
+async fn package_search(search_req: &SearchItemSearchReq, table_alias_name: &str, dict: &SearchDict, funs: &TardisFunsInst) -> TardisResult<SearchSqlFragments> {
     let mut where_fragments: Vec<String> = vec!["1=1".to_string()];
     let mut sql_vals: Vec<Value> = vec![];
-    let table_alias_name = "search_item";
+    // vector query: (mode, keyword weight, min similarity, query vector)
+    let vector_query = if let Some(vector) = &search_req.vector {
+        search_embedding_serv::dimensions(funs)?;
+        let embedding = search_embedding_serv::query_embedding(vector, search_req.query.q.as_deref(), funs).await?;
+        Some((
+            search_embedding_serv::mode(vector),
+            search_embedding_serv::keyword_weight(vector, funs)?,
+            vector.min_similarity,
+            embedding,
+        ))
+    } else {
+        None
+    };
+    let mut query = search_req.query.clone();
+    if matches!(vector_query, Some((SearchItemVectorQueryModeKind::Vector, ..))) {
+        // The keywords are only used to generate the query vector
+        query.q = None;
+    }
+    let keyword_query = query.q.is_some();
+    let keyword_where_idx = where_fragments.len();
     // query
-    let (select_fragments, from_fragments) = package_query(table_alias_name, search_req.query.clone(), &mut sql_vals, &mut where_fragments, funs)?;
+    let (mut select_fragments, from_fragments) = package_query(table_alias_name, query, &mut sql_vals, &mut where_fragments, dict, funs)?;
+
+    // vector
+    if let Some((mode, _, min_similarity, embedding)) = &vector_query {
+        sql_vals.push(Value::from(search_embedding_serv::to_pg_vector(embedding)));
+        let similarity = format!("(1 - ({}.embedding <=> ${}::vector))", table_alias_name, sql_vals.len());
+        select_fragments.push_str(&format!(", {similarity}::float4 AS rank_vector"));
+        let mut vector_where = format!("{}.embedding IS NOT NULL", table_alias_name);
+        if let Some(min_similarity) = min_similarity {
+            sql_vals.push(Value::from(*min_similarity));
+            vector_where.push_str(&format!(" AND {similarity} >= ${}", sql_vals.len()));
+        }
+        if *mode == SearchItemVectorQueryModeKind::Hybrid && search_req.query.q.is_some() && where_fragments.len() > keyword_where_idx {
+            // The keyword condition is the first one added by `package_query`, items matching the keywords or the vector are both returned
+            let keyword_where = where_fragments.remove(keyword_where_idx);
+            where_fragments.insert(keyword_where_idx, format!("({keyword_where} OR ({vector_where}))"));
+        } else {
+            where_fragments.push(format!("({vector_where})"));
+        }
+    }
 
     // Add visit_keys filter
     package_visit_filter(table_alias_name, search_req.ctx.clone(), &mut sql_vals, &mut where_fragments)?;
 
-    let order_fragments = package_order(table_alias_name, search_req.sort.clone())?;
-
     // advanced query
     let sql_adv_query = package_adv_query(table_alias_name, search_req.adv_query.clone(), &mut sql_vals, funs)?;
 
-    // page
-    let page_fragments = package_page(search_req.page.clone(), &mut sql_vals)?;
+    Ok(SearchSqlFragments {
+        select_fragments,
+        from_fragments,
+        where_fragments: format!(
+            "{}\n    {}",
+            where_fragments.join(" AND "),
+            if sql_adv_query.is_empty() {
+                "".to_string()
+            } else {
+                format!(
+                    " {} ( 1=1 {})",
+                    if search_req.adv_by_or.unwrap_or(false) { " OR " } else { " AND " },
+                    sql_adv_query.join(" ")
+                )
+            },
+        ),
+        sql_vals,
+        keyword_query,
+        vector_query: vector_query.map(|(mode, keyword_weight, _, _)| (mode, keyword_weight)),
+    })
+}
 
+pub async fn search(search_req: &mut SearchItemSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<TardisPage<SearchItemSearchResp>> {
+    let table_alias_name = "search_item";
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
-    let result = conn
-        .query_all(
-            format!(
-                r#"SELECT kind, key, title, data_source, owner, own_paths, create_time, update_time, ext{}{}{}
+    let dict = find_query_dict(&search_req.query, &search_req.tag, &conn, &table_name).await?;
+    let fragments = package_search(search_req, table_alias_name, &dict, funs).await?;
+    do_search(search_req, &fragments, table_alias_name, &conn, &table_name, funs).await
+}
+
+async fn do_search(
+    search_req: &SearchItemSearchReq,
+    fragments: &SearchSqlFragments,
+    table_alias_name: &str,
+    conn: &TardisRelDBlConnection,
+    table_name: &str,
+    funs: &TardisFunsInst,
+) -> TardisResult<TardisPage<SearchItemSearchResp>> {
+    let mut select_fragments = fragments.select_fragments.clone();
+    let mut sql_vals = fragments.sql_vals.clone();
+    let vector_query = fragments.vector_query;
+
+    // highlight, only the keywords can be highlighted
+    let highlight = search_req.highlight.is_some() && fragments.keyword_query;
+    if let Some(highlight_req) = search_req.highlight.as_ref().filter(|_| highlight) {
+        let (title_options, content_options) = package_highlight_options(highlight_req, funs)?;
+        sql_vals.push(Value::from(title_options));
+        sql_vals.push(Value::from(content_options));
+        select_fragments.push_str(&format!(
+            ", ts_headline('{tokenizer}', {t}.title, query1 || query2, ${}) AS highlight_title, ts_headline('{tokenizer}', COALESCE({t}.content, ''), query1 || query2, ${}) AS highlight_content",
+            sql_vals.len() - 1,
+            sql_vals.len(),
+            tokenizer = get_tokenizer(),
+            t = table_alias_name
+        ));
+    }
+
+    let order_fragments = if vector_query.is_some() && search_req.sort.is_none() {
+        vec![
+            "rank_score DESC".to_string(),
+            format!("{}.{} {}", table_alias_name, "key", SearchItemSearchSortKind::Asc.to_sql()),
+        ]
+    } else {
+        package_order(table_alias_name, search_req.sort.clone())?
+    };
+
+    // page
+    let page_fragments = package_page(search_req.page.clone(), &mut sql_vals)?;
+
+    if vector_query.is_some() {
+        init_embedding(conn, table_name, funs).await?;
+    }
+    let sql = format!(
+        r#"SELECT kind, key, title, data_source, owner, own_paths, create_time, update_time, ext{}{}{}
 FROM {table_name} {table_alias_name}{}
 WHERE 
-    {}
-    {}
-    {}
-{}"#,
-                if search_req.page.fetch_total { ", count(*) OVER() AS total" } else { "" },
-                if search_req.query.in_q_content.unwrap_or(false) { ", content" } else { "" },
-                select_fragments,
-                from_fragments,
-                where_fragments.join(" AND "),
-                if sql_adv_query.is_empty() {
-                    "".to_string()
-                } else {
-                    format!(
-                        " {} ( 1=1 {})",
-                        if search_req.adv_by_or.unwrap_or(false) { " OR " } else { " AND " },
-                        sql_adv_query.join(" ")
-                    )
-                },
-                if order_fragments.is_empty() {
-                    "".to_string()
-                } else {
-                    format!("ORDER BY {}", order_fragments.join(", "))
-                },
-                page_fragments
-            )
-            .as_str(),
-            sql_vals,
-        )
-        .await?;
+    {}"#,
+        if search_req.page.fetch_total { ", count(*) OVER() AS total" } else { "" },
+        if search_req.query.in_q_content.unwrap_or(false) { ", content" } else { "" },
+        select_fragments,
+        fragments.from_fragments,
+        fragments.where_fragments,
+    );
+    let order_and_page = format!(
+        "{}\n{}",
+        if order_fragments.is_empty() {
+            "".to_string()
+        } else {
+            format!("ORDER BY {}", order_fragments.join(", "))
+        },
+        page_fragments
+    );
+    let sql = if let Some((mode, keyword_weight)) = &vector_query {
+        // The keyword rank is normalized by the max one of the matched items, then blended with the vector similarity
+        let rank_score = match mode {
+            SearchItemVectorQueryModeKind::Vector => format!("{table_alias_name}.rank_vector"),
+            SearchItemVectorQueryModeKind::Hybrid => format!(
+                "{keyword_weight} * COALESCE(({t}.rank_title + {t}.rank_content) / NULLIF(max({t}.rank_title + {t}.rank_content) OVER (), 0), 0) + {} * COALESCE({t}.rank_vector, 0)",
+                1.0 - keyword_weight,
+                t = table_alias_name
+            ),
+        };
+        format!("SELECT *, {rank_score} AS rank_score\nFROM ({sql}) {table_alias_name}\n{order_and_page}")
+    } else {
+        format!("{sql}\n{order_and_page}")
+    };
+    let result = conn.query_all(&sql, sql_vals).await?;
 
     let mut total_size: i64 = 0;
     let result = result
@@ -475,6 +695,15 @@ WHERE
                 ext: item.try_get("", "ext")?,
                 rank_title: item.try_get("", "rank_title")?,
                 rank_content: item.try_get("", "rank_content")?,
+                rank_vector: item.try_get("", "rank_vector").unwrap_or_default(),
+                highlight: if highlight {
+                    Some(SearchItemHighlightResp {
+                        title: item.try_get("", "highlight_title")?,
+                        content: item.try_get::<Option<String>>("", "highlight_content")?.filter(|content| !content.is_empty()),
+                    })
+                } else {
+                    None
+                },
             })
         })
         .collect::<TardisResult<Vec<SearchItemSearchResp>>>()?;
@@ -487,6 +716,216 @@ WHERE
     })
 }
 
+/// Options of `ts_headline` for the title and the content
+fn package_highlight_options(highlight_req: &SearchItemHighlightReq, funs: &TardisFunsInst) -> TardisResult<(String, String)> {
+    let pre_tag = highlight_req.pre_tag.as_deref().unwrap_or(HIGHLIGHT_PRE_TAG);
+    let post_tag = highlight_req.post_tag.as_deref().unwrap_or(HIGHLIGHT_POST_TAG);
+    if [pre_tag, post_tag].iter().any(|tag| tag.contains('"') || tag.contains(',')) {
+        return Err(funs.err().bad_request(
+            "search_pg_item_serv",
+            "search",
+            "highlight tags can't contain '\"' or ','",
+            "400-spi-search-highlight-tag-not-legal",
+        ));
+    }
+    let max_words = highlight_req.fragment_size.unwrap_or(HIGHLIGHT_FRAGMENT_WORDS).max(2);
+    let tags = format!(r#"StartSel="{pre_tag}", StopSel="{post_tag}", HighlightAll=false"#);
+    // The whole title is returned, the content is split into fragments
+    Ok((
+        format!("{tags}, MaxWords={}, MinWords=1", u16::MAX),
+        format!(
+            r#"{tags}, MaxWords={max_words}, MinWords={}, MaxFragments={}, FragmentDelimiter=" ... ""#,
+            max_words / 2,
+            highlight_req.max_fragments.unwrap_or(HIGHLIGHT_MAX_FRAGMENTS)
+        ),
+    ))
+}
+
+/// Search with the facet counts and the title suggestions
+///
+/// 搜索并返回分面统计及标题建议
+pub async fn facet_search(search_req: &mut SearchItemFacetSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<SearchItemFacetSearchResp> {
+    let table_alias_name = "search_item";
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.search.tag, ctx, false).await?;
+    let dict = find_query_dict(&search_req.search.query, &search_req.search.tag, &conn, &table_name).await?;
+    let fragments = package_search(&search_req.search, table_alias_name, &dict, funs).await?;
+    let page = do_search(&search_req.search, &fragments, table_alias_name, &conn, &table_name, funs).await?;
+    let mut facets = vec![];
+    for facet_req in search_req.facets.as_deref().unwrap_or_default() {
+        facets.push(do_facet(facet_req, &fragments, table_alias_name, &conn, &table_name, funs).await?);
+    }
+    let suggestions = if let Some(suggest_req) = &search_req.suggest {
+        do_suggest(&search_req.search, suggest_req, table_alias_name, &conn, &table_name, funs).await?
+    } else {
+        vec![]
+    };
+    Ok(SearchItemFacetSearchResp { page, facets, suggestions })
+}
+
+async fn do_facet(
+    facet_req: &SearchItemFacetReq,
+    fragments: &SearchSqlFragments,
+    table_alias_name: &str,
+    conn: &TardisRelDBlConnection,
+    table_name: &str,
+    funs: &TardisFunsInst,
+) -> TardisResult<SearchItemFacetResp> {
+    let (value_column, lateral_fragments) = if facet_req.in_ext.unwrap_or(true) {
+        if !facet_req.field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(funs.err().bad_request(
+                "search_pg_item_serv",
+                "facet_search",
+                &format!("facet field [{}] is not legal", facet_req.field),
+                "400-spi-search-facet-field-not-legal",
+            ));
+        }
+        if facet_req.multi_values.unwrap_or(false) {
+            // Each element of the array is counted separately, the lateral join must follow the table to reference it
+            (
+                "facet_item.item".to_string(),
+                format!(
+                    " CROSS JOIN LATERAL jsonb_array_elements_text(CASE WHEN jsonb_typeof({t}.ext -> '{field}') = 'array' THEN {t}.ext -> '{field}' ELSE '[]'::jsonb END) AS facet_item(item)",
+                    t = table_alias_name,
+                    field = facet_req.field
+                ),
+            )
+        } else {
+            (format!("{}.ext ->> '{}'", table_alias_name, facet_req.field), "".to_string())
+        }
+    } else if FACET_INNER_FIELD.contains(&facet_req.field.as_str()) {
+        (format!("{}.{}", table_alias_name, facet_req.field), "".to_string())
+    } else {
+        return Err(funs.err().bad_request(
+            "search_pg_item_serv",
+            "facet_search",
+            &format!("facet field [{}] is not legal", facet_req.field),
+            "400-spi-search-facet-field-not-legal",
+        ));
+    };
+    let mut sql_vals = fragments.sql_vals.clone();
+    sql_vals.push(Value::from(facet_req.size.unwrap_or(FACET_SIZE) as i64));
+    let result = conn
+        .query_all(
+            &format!(
+                r#"SELECT {value_column} AS facet_value, count(*) AS count
+FROM {table_name} {table_alias_name}{lateral_fragments}{}
+WHERE 
+    {}
+GROUP BY 1
+ORDER BY 2 DESC, 1 ASC
+LIMIT ${}"#,
+                fragments.from_fragments,
+                fragments.where_fragments,
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?;
+    Ok(SearchItemFacetResp {
+        field: facet_req.field.clone(),
+        values: result
+            .into_iter()
+            .map(|item| {
+                Ok(SearchItemFacetValueResp {
+                    value: item.try_get("", "facet_value")?,
+                    count: item.try_get("", "count")?,
+                })
+            })
+            .collect::<TardisResult<Vec<_>>>()?,
+    })
+}
+
+async fn do_suggest(
+    search_req: &SearchItemSearchReq,
+    suggest_req: &SearchItemSuggestReq,
+    table_alias_name: &str,
+    conn: &TardisRelDBlConnection,
+    table_name: &str,
+    funs: &TardisFunsInst,
+) -> TardisResult<Vec<SearchItemSuggestResp>> {
+    let Some(q) = search_req.query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) else {
+        return Ok(vec![]);
+    };
+    let size = suggest_req.size.unwrap_or(SUGGEST_SIZE) as usize;
+    // The suggestions are limited by the other conditions, but not the keywords
+    let mut filter_req = search_req.clone();
+    filter_req.query.q = None;
+    filter_req.vector = None;
+    let fragments = package_search(&filter_req, table_alias_name, &SearchDict::default(), funs).await?;
+    search_pg_initializer::init_suggest_index(conn, table_name).await?;
+
+    let mut sql_vals = fragments.sql_vals.clone();
+    sql_vals.push(Value::from(format!("{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))));
+    sql_vals.push(Value::from(size as i64));
+    let mut suggestions = conn
+        .query_all(
+            &format!(
+                r#"SELECT {t}.title
+FROM {table_name} {t}{}
+WHERE 
+    ({}) AND {t}.title ILIKE ${}
+GROUP BY {t}.title
+ORDER BY length({t}.title) ASC, {t}.title ASC
+LIMIT ${}"#,
+                fragments.from_fragments,
+                fragments.where_fragments,
+                sql_vals.len() - 1,
+                sql_vals.len(),
+                t = table_alias_name
+            ),
+            sql_vals,
+        )
+        .await?
+        .into_iter()
+        .map(|item| {
+            Ok(SearchItemSuggestResp {
+                text: item.try_get("", "title")?,
+                kind: SearchItemSuggestKind::Prefix,
+            })
+        })
+        .collect::<TardisResult<Vec<_>>>()?;
+
+    // "did you mean", the titles similar to the keywords
+    if suggestions.len() < size && suggest_req.correction.unwrap_or(true) {
+        let mut sql_vals = fragments.sql_vals;
+        sql_vals.push(Value::from(q));
+        sql_vals.push(Value::from(size as i64));
+        let corrections = conn
+            .query_all(
+                &format!(
+                    r#"SELECT {t}.title, similarity({t}.title, ${}) AS score
+FROM {table_name} {t}{}
+WHERE 
+    ({}) AND {t}.title % ${}
+GROUP BY {t}.title
+ORDER BY score DESC, {t}.title ASC
+LIMIT ${}"#,
+                    sql_vals.len() - 1,
+                    fragments.from_fragments,
+                    fragments.where_fragments,
+                    sql_vals.len() - 1,
+                    sql_vals.len(),
+                    t = table_alias_name
+                ),
+                sql_vals,
+            )
+            .await?
+            .into_iter()
+            .map(|item| item.try_get::<String>("", "title"))
+            .collect::<Result<Vec<_>, _>>()?;
+        for title in corrections {
+            if suggestions.len() < size && !suggestions.iter().any(|suggestion| suggestion.text == title) {
+                suggestions.push(SearchItemSuggestResp {
+                    text: title,
+                    kind: SearchItemSuggestKind::Correction,
+                });
+            }
+        }
+    }
+    Ok(suggestions)
+}
+
 pub async fn group_search(search_req: &mut GroupSearchItemSearchReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<GroupSearchItemSearchResp>> {
     let mut where_fragments: Vec<String> = vec!["1=1".to_string()];
     let mut sql_vals: Vec<Value> = vec![];
@@ -498,7 +937,10 @@ pub async fn group_search(search_req: &mut GroupSearchItemSearchReq, funs: &Tard
     };
 
     // query
-    let (_, from_fragments) = package_query(table_alias_name, search_req.query.clone(), &mut sql_vals, &mut where_fragments, funs)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
+    let dict = find_query_dict(&search_req.query, &search_req.tag, &conn, &table_name).await?;
+    let (_, from_fragments) = package_query(table_alias_name, search_req.query.clone(), &mut sql_vals, &mut where_fragments, &dict, funs)?;
 
     // Add visit_keys filter
     package_visit_filter(table_alias_name, search_req.ctx.clone(), &mut sql_vals, &mut where_fragments)?;
@@ -506,8 +948,6 @@ pub async fn group_search(search_req: &mut GroupSearchItemSearchReq, funs: &Tard
     // advanced query
     let sql_adv_query = package_adv_query(table_alias_name, search_req.adv_query.clone(), &mut sql_vals, funs)?;
 
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
     let result = conn
         .query_all(
             format!(
@@ -560,7 +1000,10 @@ pub async fn multiple_search(
     let mut join_select_fragments = "".to_string();
     let mut local_cross_join = "".to_string();
     // query
-    let (select_fragments, from_fragments) = package_query(table_alias_name, search_req.query.clone(), &mut sql_vals, &mut where_fragments, funs)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
+    let dict = find_query_dict(&search_req.query, &search_req.tag, &conn, &table_name).await?;
+    let (select_fragments, from_fragments) = package_query(table_alias_name, search_req.query.clone(), &mut sql_vals, &mut where_fragments, &dict, funs)?;
     // Add visit_keys filter
     package_visit_filter(table_alias_name, search_req.ctx.clone(), &mut sql_vals, &mut where_fragments)?;
     let order_fragments = package_order(table_alias_name, search_req.sort.clone())?;
@@ -652,8 +1095,6 @@ pub async fn multiple_search(
         ));
         join_index += 1;
     }
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &search_req.tag, ctx, false).await?;
     let result = conn
         .query_all(
             format!(
@@ -715,12 +1156,13 @@ fn package_query(
     query: SearchItemQueryReq,
     sql_vals: &mut Vec<Value>,
     where_fragments: &mut Vec<String>,
+    dict: &SearchDict,
     funs: &TardisFunsInst,
 ) -> TardisResult<(String, String)> {
     let select_fragments;
     let mut from_fragments = "".to_string();
-    if let Some(q) = &query.q {
-        let q = q
+    if let Some(raw_q) = &query.q {
+        let q = raw_q
             .chars()
             // Fixed like `syntax error in tsquery: "吴 林"`
             .filter(|c| !c.is_whitespace())
@@ -732,12 +1174,11 @@ fn package_query(
             })
             .collect::<String>();
         sql_vals.push(Value::from(q.as_str()));
-        from_fragments = format!(
-            ", plainto_tsquery('{}', ${}) AS query1, plainto_tsquery('simple', ${}) AS query2",
-            get_tokenizer(),
-            sql_vals.len(),
-            sql_vals.len()
-        );
+        let q_idx = sql_vals.len();
+        // the dictionary words are matched on the token boundaries of the keywords before the whitespaces are removed
+        let query1 = search_pg_dict_serv::dict_tsquery_sql(&get_tokenizer(), q_idx, raw_q, dict, sql_vals);
+        let query2 = search_pg_dict_serv::dict_tsquery_sql("simple", q_idx, raw_q, dict, sql_vals);
+        from_fragments = format!(", {query1} AS query1, {query2} AS query2");
 
         let rank_title = format!(
             "GREATEST(COALESCE(ts_rank({}.title_tsv, query1), 0 :: float4), COALESCE(ts_rank({}.title_tsv, query2), 0 :: float4)) AS rank_title",
@@ -1289,7 +1730,10 @@ pub async fn query_metrics(query_req: &SearchQueryMetricsReq, funs: &TardisFunsI
     // Package filter
     let mut sql_part_wheres = vec![];
 
-    let (select_fragments, from_fragments) = package_query(table_alias_name, query_req.query.clone(), &mut params, &mut sql_part_wheres, funs)?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &query_req.tag, ctx, false).await?;
+    let dict = find_query_dict(&query_req.query, &query_req.tag, &conn, &table_name).await?;
+    let (select_fragments, from_fragments) = package_query(table_alias_name, query_req.query.clone(), &mut params, &mut sql_part_wheres, &dict, funs)?;
 
     if let Some(wheres) = &query_req._where {
         let mut sql_part_or_wheres = vec![];
@@ -1531,8 +1975,6 @@ pub async fn query_metrics(query_req: &SearchQueryMetricsReq, funs: &TardisFunsI
     } else {
         format!(" AND ( 1=1 {})", sql_adv_query.join(" "))
     };
-    let bs_inst = inst.inst::<TardisRelDBClient>();
-    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, &query_req.tag, ctx, false).await?;
     let ignore_group_agg = sql_part_groups.is_empty() || !query_req.group_agg.unwrap_or(false);
     let final_sql = format!(
         r#"SELECT {sql_part_outer_selects}{}
@@ -1862,6 +2304,7 @@ pub async fn import_data(import_req: &SearchImportDataReq, funs: &TardisFunsInst
                         update_time: Some(data.update_time),
                         ext: Some(data.ext.clone()),
                         visit_keys: data.visit_keys.clone(),
+                        embedding: None,
                     },
                     funs,
                     ctx,
@@ -1883,6 +2326,7 @@ pub async fn import_data(import_req: &SearchImportDataReq, funs: &TardisFunsInst
                         visit_keys: data.visit_keys.clone(),
                         kind: Some(data.kind.clone()),
                         ext_override: Some(true),
+                        embedding: None,
                     },
                     funs,
                     ctx,
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+
+use bios_basic::spi::{spi_funs::SpiBsInst, spi_initializer};
+use tardis::{
+    basic::{dto::TardisContext, field::TrimString, result::TardisResult},
+    chrono::{DateTime, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::{QueryResult, Value},
+    },
+    serde_json, TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    dto::search_item_dto::{SearchItemAddReq, SearchItemVisitKeysReq},
+    search_config::SearchConfig,
+    serv::search_embedding_serv,
+};
+
+use super::{search_pg_initializer, search_pg_item_serv};
+
+const ITEM_COLUMNS: &str = "kind, key, title, content, data_source, owner, own_paths, create_time, update_time, ext, visit_keys";
+
+pub async fn count(tag: &str, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<u64> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    Ok(conn.count_by_sql(&format!("SELECT 1 FROM {table_name}"), vec![]).await?)
+}
+
+/// Fetch the items ordered by the key, starting after the given key
+///
+/// 按 key 顺序获取指定 key 之后的数据
+pub async fn scan(tag: &str, after_key: Option<String>, size: u32, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Vec<SearchItemAddReq>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    let mut sql_vals = vec![];
+    let after_key_where = if let Some(after_key) = after_key {
+        sql_vals.push(Value::from(after_key));
+        "WHERE key > $1"
+    } else {
+        ""
+    };
+    sql_vals.push(Value::from(size as i64));
+    let embedding_column = embedding_column(&conn, &table_name).await?;
+    let result = conn
+        .query_all(
+            &format!(
+                "SELECT {ITEM_COLUMNS}{embedding_column} FROM {table_name} {after_key_where} ORDER BY key ASC LIMIT ${}",
+                sql_vals.len()
+            ),
+            sql_vals,
+        )
+        .await?;
+    result.iter().map(|row| to_item(tag, row)).collect()
+}
+
+/// Fetch the items updated since the given time, or with the given keys
+///
+/// 获取指定时间后更新的数据，或指定 key 的数据
+pub async fn scan_changed(
+    tag: &str,
+    since: DateTime<Utc>,
+    keys: Vec<String>,
+    _funs: &TardisFunsInst,
+    ctx: &TardisContext,
+    inst: &SpiBsInst,
+) -> TardisResult<Vec<SearchItemAddReq>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    let mut sql_vals = vec![Value::from(since)];
+    let keys_where = if keys.is_empty() {
+        "".to_string()
+    } else {
+        let keys_where = format!(
+            " OR key IN ({})",
+            (0..keys.len()).map(|idx| format!("${}", sql_vals.len() + idx + 1)).collect::<Vec<String>>().join(",")
+        );
+        sql_vals.extend(keys.into_iter().map(Value::from));
+        keys_where
+    };
+    let embedding_column = embedding_column(&conn, &table_name).await?;
+    let result = conn
+        .query_all(
+            &format!("SELECT {ITEM_COLUMNS}{embedding_column} FROM {table_name} WHERE update_time >= $1{keys_where} ORDER BY key ASC"),
+            sql_vals,
+        )
+        .await?;
+    result.iter().map(|row| to_item(tag, row)).collect()
+}
+
+pub async fn keys(tag: &str, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashSet<String>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, false).await?;
+    find_keys(&conn, &table_name).await
+}
+
+/// Create the shadow table of the tag, the live table is created if it doesn't exist
+///
+/// 创建 tag 的影子表，在线表不存在时一并创建
+pub async fn create_shadow(tag: &str, job_id: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<String> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
+    let (schema_name, _) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    // table name shouldn't be longer than 63 characters
+    let shadow_table_name = format!(
+        "{schema_name}.starsys_search_shadow_{}",
+        &TardisFuns::crypto.digest.md5(&format!("{table_name}{job_id}"))?[..16]
+    );
+    search_pg_initializer::init_shadow_table(&conn, &table_name, &shadow_table_name, funs.conf::<SearchConfig>().embedding.dimensions).await?;
+    Ok(shadow_table_name)
+}
+
+/// Write the items to the shadow table, the existing ones are replaced when `replace` is true
+///
+/// 写入数据到影子表，`replace` 为 true 时替换已存在的数据
+pub async fn write_shadow(shadow: &str, items: &mut [SearchItemAddReq], replace: bool, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, _) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    conn.begin().await?;
+    do_write_shadow(&conn, shadow, items, replace, funs, ctx).await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+async fn do_write_shadow(
+    conn: &TardisRelDBlConnection,
+    shadow: &str,
+    items: &mut [SearchItemAddReq],
+    replace: bool,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    if replace {
+        do_delete_shadow(conn, shadow, items.iter().map(|item| item.key.to_string()).collect()).await?;
+    }
+    for item in items {
+        search_pg_item_serv::do_add(item, funs, ctx, conn, shadow).await?;
+    }
+    Ok(())
+}
+
+pub async fn delete_shadow(shadow: &str, keys: Vec<String>, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    do_delete_shadow(&conn, shadow, keys).await
+}
+
+async fn do_delete_shadow(conn: &TardisRelDBlConnection, shadow: &str, keys: Vec<String>) -> TardisResult<()> {
+    for keys in keys.chunks(1000) {
+        conn.execute_one(
+            &format!(
+                "DELETE FROM {shadow} WHERE key IN ({})",
+                (1..=keys.len()).map(|idx| format!("${idx}")).collect::<Vec<String>>().join(",")
+            ),
+            keys.iter().map(|key| Value::from(key.as_str())).collect(),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn shadow_keys(shadow: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<HashSet<String>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    find_keys(&conn, shadow).await
+}
+
+/// Replace the live table of the tag with the shadow table
+///
+/// When `since` is given, the shadow table is built from the live table itself,
+/// the writes of the live table are blocked while the items changed since then are copied, the reads are only blocked by the renaming.
+///
+/// 用影子表替换 tag 的在线表。
+/// 传入 `since` 时表示影子表由在线表自身构建，复制此后变更的数据时会阻塞在线表的写入，读取仅在重命名表时阻塞。
+pub async fn swap_shadow(tag: &str, shadow: &str, since: Option<DateTime<Utc>>, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = search_pg_initializer::init_table_and_conn(bs_inst, tag, ctx, true).await?;
+    conn.begin().await?;
+    if let Some(since) = since {
+        conn.execute_one(&format!("LOCK TABLE {table_name} IN SHARE MODE"), vec![]).await?;
+        conn.execute_one(
+            &format!("DELETE FROM {shadow} shadow WHERE NOT EXISTS (SELECT 1 FROM {table_name} live WHERE live.key = shadow.key)"),
+            vec![],
+        )
+        .await?;
+        let embedding_column = embedding_column(&conn, &table_name).await?;
+        let result = conn
+            .query_all(
+                &format!(
+                    "SELECT {ITEM_COLUMNS}{embedding_column} FROM {table_name} live WHERE live.update_time >= $1 OR NOT EXISTS (SELECT 1 FROM {shadow} shadow WHERE shadow.key = live.key)"
+                ),
+                vec![Value::from(since)],
+            )
+            .await?;
+        let mut items = result.iter().map(|row| to_item(tag, row)).collect::<TardisResult<Vec<_>>>()?;
+        search_embedding_serv::reset_embeddings(&mut items, funs);
+        do_write_shadow(&conn, shadow, &mut items, true, funs, ctx).await?;
+    }
+    search_pg_initializer::swap_shadow_table(&conn, &table_name, shadow).await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+pub async fn drop_shadow(shadow: &str, _funs: &TardisFunsInst, _ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = spi_initializer::common_pg::init_conn(bs_inst).await?;
+    conn.execute_one(&format!("DROP TABLE IF EXISTS {shadow}"), vec![]).await?;
+    Ok(())
+}
+
+async fn find_keys(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<HashSet<String>> {
+    let result = conn.query_all(&format!("SELECT key FROM {table_name}"), vec![]).await?;
+    result.iter().map(|row| Ok(row.try_get("", "key")?)).collect()
+}
+
+/// The embedding column as text when the table has it
+async fn embedding_column(conn: &TardisRelDBlConnection, table_name: &str) -> TardisResult<&'static str> {
+    let (schema_name, rel_name) = table_name.split_once('.').unwrap_or(("public", table_name));
+    let exists = conn
+        .count_by_sql(
+            "SELECT 1 FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2 AND column_name = 'embedding'",
+            vec![Value::from(schema_name), Value::from(rel_name)],
+        )
+        .await?
+        > 0;
+    Ok(if exists { ", embedding::text AS embedding" } else { "" })
+}
+
+fn to_item(tag: &str, row: &QueryResult) -> TardisResult<SearchItemAddReq> {
+    let visit_keys: Option<serde_json::Value> = row.try_get("", "visit_keys")?;
+    let embedding: Option<String> = row.try_get("", "embedding").unwrap_or_default();
+    Ok(SearchItemAddReq {
+        tag: tag.to_string(),
+        kind: row.try_get("", "kind")?,
+        key: TrimString(row.try_get("", "key")?),
+        title: row.try_get("", "title")?,
+        content: row.try_get::<Option<String>>("", "content")?.unwrap_or_default(),
+        data_source: row.try_get("", "data_source")?,
+        owner: row.try_get("", "owner")?,
+        own_paths: row.try_get("", "own_paths")?,
+        create_time: row.try_get("", "create_time")?,
+        update_time: row.try_get("", "update_time")?,
+        ext: row.try_get("", "ext")?,
+        visit_keys: visit_keys.map(|visit_keys| TardisFuns::json.json_to_obj::<SearchItemVisitKeysReq>(visit_keys)).transpose()?,
+        // pgvector outputs the vector as `[1,2,3]`
+        embedding: embedding.map(|embedding| TardisFuns::json.str_to_obj::<Vec<f32>>(&embedding)).transpose()?,
+    })
+}
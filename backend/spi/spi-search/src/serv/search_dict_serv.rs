@@ -0,0 +1,192 @@
+use crate::dto::search_dict_dto::{SearchDictEntryReq, SearchDictEntryResp, SearchDictFieldKind, SearchDictKind, SearchDictResp};
+use crate::search_initializer;
+use bios_basic::spi::spi_constants;
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use bios_basic::spi_dispatch_service;
+use tardis::basic::result::TardisResult;
+use tardis::TardisFunsInst;
+
+#[cfg(feature = "spi-es")]
+use super::es;
+#[cfg(feature = "spi-pg")]
+use super::pg;
+spi_dispatch_service! {
+    @mgr: true,
+    @init: search_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::search_pg_dict_serv,
+        #[cfg(feature = "spi-es")]
+        spi_constants::SPI_ES_KIND_CODE => es::search_es_dict_serv,
+    },
+    @method: {
+        save(tag: &str, entries: &[SearchDictEntryReq]) -> TardisResult<()>;
+        get(tag: &str) -> TardisResult<SearchDictResp>;
+        delete(tag: &str) -> TardisResult<()>;
+    }
+}
+
+/// Trim and lowercase the words of the entries, the empty entries are dropped
+///
+/// 去除词条中词语的空白并转为小写，丢弃空词条
+pub(crate) fn normalize(entries: &[SearchDictEntryReq], funs: &TardisFunsInst) -> TardisResult<Vec<SearchDictEntryResp>> {
+    let mut result = vec![];
+    for entry in entries {
+        let mut words: Vec<String> = vec![];
+        for word in entry.words.iter().map(|word| word.trim().to_lowercase()).filter(|word| !word.is_empty()) {
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+        if words.is_empty() {
+            continue;
+        }
+        if entry.kind == SearchDictKind::Synonym && words.len() < 2 {
+            return Err(funs.err().bad_request(
+                "search_dict_serv",
+                "normalize",
+                &format!("synonym entry [{}] should contain at least two words", words.join(",")),
+                "400-spi-search-dict-synonym-not-legal",
+            ));
+        }
+        result.push(SearchDictEntryResp {
+            kind: entry.kind,
+            field: entry.field,
+            words,
+        });
+    }
+    Ok(result)
+}
+
+/// Whether the text contains the word as a whole token
+///
+/// The word should not be a part of a longer run of letters or digits, e.g. `go` is not in `google`, `bios-12` is not in `bios-123`.
+/// Chinese and Japanese text is not delimited, so any position next to their characters is a token boundary.
+///
+/// 文本是否包含完整的词语。
+/// 词语不能是更长的连续字母或数字的一部分，如 `google` 不包含 `go`，`bios-123` 不包含 `bios-12`。
+/// 中文及日文文本没有分隔符，因此其字符的两侧均视为词语边界。
+pub fn contains_word(text: &str, word: &str) -> bool {
+    let (Some(first), Some(last)) = (word.chars().next(), word.chars().last()) else {
+        return false;
+    };
+    text.match_indices(word).any(|(idx, _)| {
+        let before = text[..idx].chars().last();
+        let after = text[idx + word.len()..].chars().next();
+        before.map(|before| !(is_delimited_char(before) && is_delimited_char(first))).unwrap_or(true)
+            && after.map(|after| !(is_delimited_char(after) && is_delimited_char(last))).unwrap_or(true)
+    })
+}
+
+// Letters and digits of the scripts delimited by spaces or punctuations
+fn is_delimited_char(c: char) -> bool {
+    c.is_alphanumeric() && !matches!(c, '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{2FA1F}')
+}
+
+/// Dictionary of a tag, applied when indexing and querying
+///
+/// The entries of a field apply to the indexing of that field, the query uses the entries of all the fields,
+/// except that the stop words of a single field are only removed when indexing.
+/// The words are matched on the token boundaries, see [`contains_word`].
+///
+/// tag 的词典，在索引及查询时使用。
+/// 字段的词条用于该字段的索引，查询使用所有字段的词条，但仅作用于单个字段的停用词只在索引时移除。
+/// 词语按词语边界匹配，见 [`contains_word`]。
+#[derive(Debug, Default, Clone)]
+pub struct SearchDict {
+    pub entries: Vec<SearchDictEntryResp>,
+}
+
+impl SearchDict {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn field_entries(&self, kind: SearchDictKind, field: SearchDictFieldKind) -> impl Iterator<Item = &SearchDictEntryResp> {
+        self.entries.iter().filter(move |entry| entry.kind == kind && entry.field.map(|entry_field| entry_field == field).unwrap_or(true))
+    }
+
+    pub fn stop_words(&self, field: SearchDictFieldKind) -> Vec<String> {
+        self.field_entries(SearchDictKind::StopWord, field).flat_map(|entry| entry.words.clone()).collect()
+    }
+
+    pub fn user_words(&self, field: SearchDictFieldKind) -> Vec<String> {
+        self.field_entries(SearchDictKind::UserWord, field).flat_map(|entry| entry.words.clone()).collect()
+    }
+
+    pub fn synonyms(&self, field: SearchDictFieldKind) -> Vec<Vec<String>> {
+        self.field_entries(SearchDictKind::Synonym, field).map(|entry| entry.words.clone()).collect()
+    }
+
+    /// User words in the text, and the synonyms of the words in the text, excluding the stop words
+    ///
+    /// 文本中的自定义词，及文本中词语的同义词，不含停用词
+    pub fn index_words(&self, field: SearchDictFieldKind, text: &str) -> Vec<String> {
+        let text = text.to_lowercase();
+        let stop_words = self.stop_words(field);
+        let mut words = self.user_words(field).into_iter().filter(|word| contains_word(&text, word)).collect::<Vec<_>>();
+        for synonyms in self.synonyms(field) {
+            if synonyms.iter().any(|word| contains_word(&text, word)) {
+                words.extend(synonyms);
+            }
+        }
+        let mut result: Vec<String> = vec![];
+        for word in words {
+            if !stop_words.contains(&word) && !result.contains(&word) {
+                result.push(word);
+            }
+        }
+        result
+    }
+
+    /// Stop words of all the fields in the keywords
+    ///
+    /// 关键字中作用于所有字段的停用词
+    pub fn query_stop_words(&self, q: &str) -> Vec<String> {
+        let q = q.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| entry.kind == SearchDictKind::StopWord && entry.field.is_none())
+            .flat_map(|entry| entry.words.iter())
+            .filter(|word| contains_word(&q, word))
+            .cloned()
+            .collect()
+    }
+
+    /// User words in the keywords
+    ///
+    /// 关键字中的自定义词
+    pub fn query_user_words(&self, q: &str) -> Vec<String> {
+        let q = q.to_lowercase();
+        let mut result: Vec<String> = vec![];
+        for word in self.entries.iter().filter(|entry| entry.kind == SearchDictKind::UserWord).flat_map(|entry| entry.words.iter()) {
+            if contains_word(&q, word) && !result.contains(word) {
+                result.push(word.clone());
+            }
+        }
+        result
+    }
+
+    /// Words in the keywords with their synonyms
+    ///
+    /// 关键字中的词语及其同义词
+    pub fn query_synonyms(&self, q: &str) -> Vec<(String, Vec<String>)> {
+        let q = q.to_lowercase();
+        let mut result: Vec<(String, Vec<String>)> = vec![];
+        for synonyms in self.entries.iter().filter(|entry| entry.kind == SearchDictKind::Synonym).map(|entry| &entry.words) {
+            for word in synonyms.iter().filter(|word| contains_word(&q, word)) {
+                let others = synonyms.iter().filter(|other| *other != word).cloned().collect::<Vec<_>>();
+                if let Some((_, existing)) = result.iter_mut().find(|(existing_word, _)| existing_word == word) {
+                    for other in others {
+                        if !existing.contains(&other) {
+                            existing.push(other);
+                        }
+                    }
+                } else {
+                    result.push((word.clone(), others));
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,156 @@
+use tardis::{
+    basic::result::TardisResult,
+    serde_json::{json, Value},
+    web::web_client::TardisHttpResponse,
+    TardisFunsInst,
+};
+
+use crate::{
+    dto::search_item_dto::{SearchItemAddReq, SearchItemVectorQueryModeKind, SearchItemVectorQueryReq},
+    search_config::SearchConfig,
+};
+
+const DEFAULT_KEYWORD_WEIGHT: f32 = 0.5;
+
+/// Get the dimensions of the embedding vector, error if vector search is not enabled
+///
+/// 获取向量维度，未启用向量检索时报错
+pub(crate) fn dimensions(funs: &TardisFunsInst) -> TardisResult<usize> {
+    let dimensions = funs.conf::<SearchConfig>().embedding.dimensions;
+    if dimensions == 0 {
+        return Err(funs.err().bad_request(
+            "search_embedding_serv",
+            "dimensions",
+            "vector search is not enabled",
+            "400-spi-search-embedding-not-enabled",
+        ));
+    }
+    Ok(dimensions)
+}
+
+pub(crate) fn enabled(funs: &TardisFunsInst) -> bool {
+    funs.conf::<SearchConfig>().embedding.dimensions > 0
+}
+
+/// Whether the items and queries without embedding can be embedded by the provider
+///
+/// 是否可由嵌入服务为没有向量的数据及查询生成向量
+pub(crate) fn has_provider(funs: &TardisFunsInst) -> bool {
+    enabled(funs) && funs.conf::<SearchConfig>().embedding.provider_url.is_some()
+}
+
+/// Get the embedding of the item, the given one is checked, otherwise it is generated by the provider if configured
+///
+/// 获取数据的向量，传入的向量会校验维度，否则在配置了嵌入服务时生成
+pub(crate) async fn item_embedding(embedding: Option<Vec<f32>>, title: &str, content: &str, funs: &TardisFunsInst) -> TardisResult<Option<Vec<f32>>> {
+    if let Some(embedding) = embedding {
+        check(&embedding, funs)?;
+        return Ok(Some(embedding));
+    }
+    if !has_provider(funs) {
+        return Ok(None);
+    }
+    Ok(Some(embed(&format!("{title}\n{content}"), funs).await?))
+}
+
+/// Drop the embeddings not matching the configured dimensions, they are regenerated by the provider if configured
+///
+/// 丢弃与配置维度不一致的向量，配置了嵌入服务时会重新生成
+pub(crate) fn reset_embeddings(items: &mut [SearchItemAddReq], funs: &TardisFunsInst) {
+    let dimensions = funs.conf::<SearchConfig>().embedding.dimensions;
+    for item in items {
+        if item.embedding.as_ref().is_some_and(|embedding| embedding.len() != dimensions) {
+            item.embedding = None;
+        }
+    }
+}
+
+/// Get the query vector, generated from the keywords by the provider when it is not given
+///
+/// 获取查询向量，未传入时由嵌入服务根据关键字生成
+pub(crate) async fn query_embedding(vector: &SearchItemVectorQueryReq, q: Option<&str>, funs: &TardisFunsInst) -> TardisResult<Vec<f32>> {
+    if let Some(embedding) = &vector.embedding {
+        check(embedding, funs)?;
+        return Ok(embedding.clone());
+    }
+    match q.filter(|q| !q.trim().is_empty()) {
+        Some(q) if has_provider(funs) => embed(q, funs).await,
+        _ => Err(funs.err().bad_request(
+            "search_embedding_serv",
+            "query_embedding",
+            "vector search requires the query embedding, or the keywords with an embedding provider",
+            "400-spi-search-vector-query-missing",
+        )),
+    }
+}
+
+pub(crate) fn mode(vector: &SearchItemVectorQueryReq) -> SearchItemVectorQueryModeKind {
+    vector.mode.unwrap_or(SearchItemVectorQueryModeKind::Hybrid)
+}
+
+pub(crate) fn keyword_weight(vector: &SearchItemVectorQueryReq, funs: &TardisFunsInst) -> TardisResult<f32> {
+    let keyword_weight = vector.keyword_weight.unwrap_or(DEFAULT_KEYWORD_WEIGHT);
+    if !(0.0..=1.0).contains(&keyword_weight) {
+        return Err(funs.err().bad_request(
+            "search_embedding_serv",
+            "keyword_weight",
+            "keyword weight should be between 0 and 1",
+            "400-spi-search-vector-weight-not-legal",
+        ));
+    }
+    Ok(keyword_weight)
+}
+
+/// Format the vector as the pgvector text representation
+///
+/// 转换为 pgvector 的文本格式
+pub(crate) fn to_pg_vector(embedding: &[f32]) -> String {
+    format!("[{}]", embedding.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","))
+}
+
+fn check(embedding: &[f32], funs: &TardisFunsInst) -> TardisResult<()> {
+    let dimensions = dimensions(funs)?;
+    if embedding.len() != dimensions {
+        return Err(funs.err().bad_request(
+            "search_embedding_serv",
+            "check",
+            &format!("embedding dimensions should be {dimensions}, but got {}", embedding.len()),
+            "400-spi-search-embedding-dimensions-not-match",
+        ));
+    }
+    Ok(())
+}
+
+async fn embed(text: &str, funs: &TardisFunsInst) -> TardisResult<Vec<f32>> {
+    let config = funs.conf::<SearchConfig>();
+    let Some(provider_url) = &config.embedding.provider_url else {
+        return Err(funs.err().bad_request(
+            "search_embedding_serv",
+            "embed",
+            "embedding provider is not configured",
+            "400-spi-search-embedding-not-enabled",
+        ));
+    };
+    let mut body = json!({ "input": [text] });
+    if let Some(model) = &config.embedding.provider_model {
+        body["model"] = json!(model);
+    }
+    let headers = config.embedding.provider_headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>();
+    let resp: TardisHttpResponse<Value> = funs.web_client().post(provider_url, &body, headers).await?;
+    let embedding = resp
+        .body
+        .as_ref()
+        .filter(|_| (200..300).contains(&resp.code))
+        .and_then(|body| body.get("data")?.get(0)?.get("embedding")?.as_array().cloned())
+        .map(|embedding| embedding.iter().map(|v| v.as_f64().unwrap_or_default() as f32).collect::<Vec<_>>())
+        .ok_or_else(|| {
+            funs.err().internal_error(
+                "search_embedding_serv",
+                "embed",
+                &format!("embedding provider responded with {}", resp.code),
+                "500-spi-search-embedding-provider-error",
+            )
+        })?;
+    check(&embedding, funs)?;
+    Ok(embedding)
+}
@@ -1,6 +1,7 @@
 use crate::dto::search_item_dto::{
     GroupSearchItemSearchReq, GroupSearchItemSearchResp, MultipleSearchItemSearchReq, SearchExportDataReq, SearchExportDataResp, SearchImportDataReq, SearchItemAddReq,
-    SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp, SearchQueryMetricsReq, SearchQueryMetricsResp, SearchSaveItemReq,
+    SearchItemFacetSearchReq, SearchItemFacetSearchResp, SearchItemModifyReq, SearchItemSearchReq, SearchItemSearchResp, SearchQueryMetricsReq, SearchQueryMetricsResp,
+    SearchSaveItemReq,
 };
 use crate::search_initializer;
 use bios_basic::spi::spi_constants;
@@ -31,6 +32,7 @@ spi_dispatch_service! {
         batch_delete(tag: &str, key: Vec<String>) -> TardisResult<()>;
         delete_by_ownership(tag: &str, own_paths: &str) -> TardisResult<()>;
         search(search_req: &mut SearchItemSearchReq) -> TardisResult<TardisPage<SearchItemSearchResp>>;
+        facet_search(search_req: &mut SearchItemFacetSearchReq) -> TardisResult<SearchItemFacetSearchResp>;
         group_search(search_req: &mut GroupSearchItemSearchReq) -> TardisResult<Vec<GroupSearchItemSearchResp>>;
         multiple_search(search_req: &mut MultipleSearchItemSearchReq) -> TardisResult<TardisPage<tardis::serde_json::Value>>;
         query_metrics(query_req: &SearchQueryMetricsReq) -> TardisResult<SearchQueryMetricsResp>;
@@ -0,0 +1,641 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration as StdDuration, Instant};
+
+use bios_basic::rbum::dto::rbum_filer_dto::{RbumBasicFilterReq, RbumItemRelFilterReq};
+use bios_basic::rbum::rbum_enumeration::RbumRelFromKind;
+use bios_basic::rbum::serv::rbum_item_serv::RbumItemCrudOperation;
+use bios_basic::spi::{
+    dto::spi_bs_dto::{SpiBsCertResp, SpiBsFilterReq},
+    serv::spi_bs_serv::SpiBsServ,
+    spi_constants,
+    spi_funs::{SpiBsInst, SpiBsInstExtractor},
+};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Duration, Utc},
+    db::sea_orm::{
+        sea_query::{Expr, Query},
+        Iterable, Set,
+    },
+    log::{error, info, warn},
+    tokio::{self, task::JoinHandle, time},
+    TardisFuns, TardisFunsInst,
+};
+
+use crate::{
+    domain::search_reindex_job,
+    dto::search_item_dto::{SearchReindexJobResp, SearchReindexReq, SearchReindexStatusKind},
+    search_config::SearchConfig,
+    search_initializer,
+    serv::search_embedding_serv,
+};
+
+#[cfg(feature = "spi-es")]
+use super::es;
+#[cfg(feature = "spi-pg")]
+use super::pg;
+
+const DEFAULT_BATCH_SIZE: u32 = 500;
+// Tolerance of the clock difference between the service and the backend when catching up the changes
+const CATCH_UP_TOLERANCE_SECS: i64 = 60;
+// Time for the writes that passed the fence check before the fence is set to finish
+const FENCE_GRACE_SECS: u64 = 5;
+const FENCE_FENCED_PREFIX: &str = "fenced:";
+const FENCE_MOVED_PREFIX: &str = "moved:";
+// Interval of refreshing the tags with active reindex jobs cached by each node,
+// the fence is set at least twice this long after the job is marked active so that all the nodes see it
+const ACTIVE_REFRESH_SECS: u64 = 1;
+const ACTIVE_CACHE_KEY_SUFFIX: &str = "active";
+const ACTIVE_RUNNING: &str = "running";
+const ACTIVE_MOVED: &str = "moved";
+
+macro_rules! dispatch {
+    ($funs:expr, $inst:expr, $fun:ident($($arg:expr),*)) => {
+        match $inst.kind_code() {
+            #[cfg(feature = "spi-pg")]
+            spi_constants::SPI_PG_KIND_CODE => pg::search_pg_reindex_serv::$fun($($arg),*).await,
+            #[cfg(feature = "spi-es")]
+            spi_constants::SPI_ES_KIND_CODE => es::search_es_reindex_serv::$fun($($arg),*).await,
+            kind_code => Err($funs.bs_not_implemented(kind_code)),
+        }
+    };
+}
+
+// The moved fences this node has switched to the new backend service
+fn applied_moved_fences() -> &'static RwLock<HashSet<String>> {
+    static APPLIED: OnceLock<RwLock<HashSet<String>>> = OnceLock::new();
+    APPLIED.get_or_init(|| RwLock::new(HashSet::new()))
+}
+
+// The tags with running reindex jobs or moved fences, cached by this node with the time of refreshing
+fn active_tags() -> &'static RwLock<Option<(Instant, HashMap<String, String>)>> {
+    static ACTIVE: OnceLock<RwLock<Option<(Instant, HashMap<String, String>)>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| RwLock::new(None))
+}
+
+/// Start a job to rebuild the tag without downtime, returns the job id
+///
+/// The items are copied into a shadow table/index in batches, then the changes made during the copying are caught up,
+/// finally the shadow one replaces the live one atomically.
+/// When `target_bs_id` is given, the tag is copied to that backend service (e.g. moving it from pg to ES),
+/// the writes of the tag are rejected while the last changes are copied, and after the job succeeds they keep being rejected
+/// until the app/tenant is rebound to that service by the backend service API, so that no write is left in the old one.
+/// ES can't block the writes while swapping, so the writes are also rejected for a short time when rebuilding a tag in ES.
+/// The jobs are persisted, only one job of a tag can run in the cluster, the jobs interrupted by the stopped nodes are marked as failed
+/// and their shadow tables/indexes are dropped.
+///
+/// 启动不停机重建 tag 的任务，返回任务 id。
+/// 数据会分批复制到影子表/索引，然后追平复制期间的变更，最后以原子方式替换在线表/索引。
+/// 传入 `target_bs_id` 时会将 tag 复制到该后端服务（如从 pg 迁移到 ES），复制最后的变更时会拒绝该 tag 的写入，
+/// 任务成功后仍会拒绝写入，直到通过后端服务接口将应用/租户绑定到该后端服务，以避免写入遗留在原后端服务中。
+/// 由于 ES 无法在替换时阻塞写入，在 ES 中重建 tag 时也会短暂拒绝写入。
+/// 任务会持久化，集群内同一 tag 只能运行一个任务，因节点停止而中断的任务会被标记为失败并删除其影子表/索引。
+pub async fn reindex(tag: &str, reindex_req: &SearchReindexReq, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<String> {
+    check_fence(tag, funs, ctx).await?;
+    check_no_running_job(tag, funs, ctx).await?;
+    let running_key = active_field(tag, ctx);
+    let source = funs.init(None, ctx, true, search_initializer::init_fun).await?;
+    let target = if let Some(target_bs_id) = &reindex_req.target_bs_id {
+        init_bs(target_bs_id, funs, ctx).await?
+    } else {
+        source.clone()
+    };
+    let job_id = TardisFuns::field.nanoid();
+    let now = Utc::now();
+    let insert_result = funs
+        .db()
+        .insert_one(
+            search_reindex_job::ActiveModel {
+                id: Set(job_id.clone()),
+                ak: Set(ctx.ak.clone()),
+                tag: Set(tag.to_string()),
+                source_kind: Set(source.kind_code().to_string()),
+                target_kind: Set(target.kind_code().to_string()),
+                target_bs_id: Set(reindex_req.target_bs_id.clone().unwrap_or_default()),
+                shadow: Set("".to_string()),
+                status: Set(SearchReindexStatusKind::Backfilling.to_string()),
+                total: Set(0),
+                processed: Set(0),
+                error: Set(None),
+                running_key: Set(Some(running_key.clone())),
+                start_time: Set(now),
+                end_time: Set(None),
+                update_time: Set(now),
+                ..Default::default()
+            },
+            ctx,
+        )
+        .await;
+    if let Err(err) = insert_result {
+        // the unique index of the running key rejects the job started by another node at the same time
+        return Err(if exist_running_job(&running_key, funs).await? { running_error(tag, funs) } else { err });
+    }
+    mark_active(&running_key, ACTIVE_RUNNING, funs).await?;
+    let marked_time = Instant::now();
+
+    let target_bs_id = reindex_req.target_bs_id.clone();
+    let batch_size = reindex_req.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+    let tag = tag.to_string();
+    let ctx = ctx.clone();
+    let spawned_job_id = job_id.clone();
+    tokio::spawn(async move {
+        let funs = crate::get_tardis_inst();
+        let fence_key = fence_key(&tag, &funs, &ctx);
+        let heartbeat = start_heartbeat(spawned_job_id.clone(), fence_key.clone());
+        let result = do_reindex(&spawned_job_id, &tag, batch_size, marked_time, &source, &target, target_bs_id.as_deref(), &funs, &ctx).await;
+        heartbeat.abort();
+        if let Err(err) = &result {
+            error!("[BIOS.Search] failed to reindex tag {}: {}", tag, err);
+        }
+        if let Err(err) = release_fence(&fence_key, &spawned_job_id, &funs).await {
+            error!("[BIOS.Search] failed to release the fence of tag {}: {}", tag, err);
+        }
+        // the moved fence is checked until it expires
+        let active_result = if result.is_ok() && target_bs_id.is_some() {
+            mark_active(&running_key, ACTIVE_MOVED, &funs).await
+        } else {
+            unmark_active(&running_key, &funs).await
+        };
+        if let Err(err) = active_result {
+            error!("[BIOS.Search] failed to update the active mark of tag {}: {}", tag, err);
+        }
+        if let Err(err) = finish_job(&spawned_job_id, result.err().map(|err| err.message), &funs).await {
+            error!("[BIOS.Search] failed to finish the reindex job {}: {}", spawned_job_id, err);
+        }
+    });
+    Ok(job_id)
+}
+
+pub async fn get_job(job_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<SearchReindexJobResp> {
+    let mut query = Query::select();
+    query
+        .columns(search_reindex_job::Column::iter())
+        .from(search_reindex_job::Entity)
+        .and_where(Expr::col(search_reindex_job::Column::Id).eq(job_id))
+        .and_where(Expr::col(search_reindex_job::Column::Ak).eq(ctx.ak.as_str()));
+    let Some(job) = funs.db().get_dto::<search_reindex_job::Model>(&query).await? else {
+        return Err(funs.err().not_found(
+            "search_reindex_serv",
+            "get_job",
+            &format!("reindex job {job_id} not found"),
+            "404-spi-search-reindex-job-not-found",
+        ));
+    };
+    Ok(SearchReindexJobResp {
+        status: SearchReindexStatusKind::from_str(&job.status)
+            .map_err(|_| funs.err().format_error("search_reindex_serv", "get_job", &format!("invalid reindex job status {}", job.status), ""))?,
+        id: job.id,
+        tag: job.tag,
+        source_kind: job.source_kind,
+        target_kind: job.target_kind,
+        total: job.total as u64,
+        processed: job.processed as u64,
+        error: job.error,
+        start_time: job.start_time,
+        end_time: job.end_time,
+    })
+}
+
+/// Check that no job of the tag is running, e.g. before changing the dictionary applied by the jobs
+///
+/// 检查 tag 没有运行中的任务，如修改任务所使用的词典前
+pub async fn check_no_running_job(tag: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    clean_orphaned_jobs(Some(&ctx.ak), funs, ctx).await?;
+    if exist_running_job(&format!("{}:{tag}", ctx.ak), funs).await? {
+        return Err(running_error(tag, funs));
+    }
+    Ok(())
+}
+
+/// Check whether the tag can be written
+///
+/// The writes are rejected while a job is copying the last changes of the tag,
+/// and after the tag is moved to another backend service until the app/tenant is rebound to it,
+/// once it is rebound, this node switches to the new backend service.
+///
+/// 检查 tag 是否可写入。
+/// 任务复制 tag 最后的变更时会拒绝写入，tag 迁移到其他后端服务后，在应用/租户重新绑定到该后端服务前也会拒绝写入，
+/// 重新绑定后当前节点会切换到新的后端服务。
+pub async fn check_fence(tag: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    // the fence is only set for the tags marked active, so that the writes of the other tags don't query the cache
+    let active_field = active_field(tag, ctx);
+    let Some(active_state) = get_active_state(&active_field, funs).await? else {
+        return Ok(());
+    };
+    let fence_key = fence_key(tag, funs, ctx);
+    let Some(fence) = funs.cache().get(&fence_key).await? else {
+        if active_state == ACTIVE_MOVED {
+            // the moved fence has expired
+            unmark_active(&active_field, funs).await?;
+        }
+        return Ok(());
+    };
+    let Some(target_bs_id) = fence.strip_prefix(FENCE_MOVED_PREFIX) else {
+        return Err(funs.err().conflict(
+            "search_reindex_serv",
+            "check_fence",
+            &format!("tag {tag} is being reindexed, please retry later"),
+            "409-spi-search-reindex-fenced",
+        ));
+    };
+    let applied_key = format!("{fence_key}:{target_bs_id}");
+    if applied_moved_fences().read().unwrap_or_else(|e| e.into_inner()).contains(&applied_key) {
+        return Ok(());
+    }
+    if find_bound_bs_id(funs, ctx).await?.as_deref() != Some(target_bs_id) {
+        return Err(funs.err().conflict(
+            "search_reindex_serv",
+            "check_fence",
+            &format!("tag {tag} has been moved to the backend service {target_bs_id}, please rebind the app/tenant to it"),
+            "409-spi-search-reindex-moved",
+        ));
+    }
+    // the backend service instance of the app/tenant is cached by each node,
+    // the fence is kept for a while after the rebinding so that the other nodes also switch
+    funs.remove_bs_inst_cache(ctx).await?;
+    applied_moved_fences().write().unwrap_or_else(|e| e.into_inner()).insert(applied_key);
+    funs.cache().expire(&fence_key, funs.conf::<SearchConfig>().reindex.moved_fence_exp_sec as i64).await?;
+    Ok(())
+}
+
+/// Periodically clean up the jobs interrupted by the stopped nodes and the expired finished jobs
+///
+/// 定期清理因节点停止而中断的任务及过期的已结束任务
+pub(crate) fn start_orphaned_job_cleaner(interval_sec: u64, ctx: TardisContext) {
+    info!("[BIOS.Search] Orphaned reindex job cleaner started, interval: {}s", interval_sec);
+    tokio::spawn(async move {
+        let mut interval = time::interval(StdDuration::from_secs(interval_sec));
+        loop {
+            interval.tick().await;
+            let funs = crate::get_tardis_inst();
+            if let Err(err) = clean_orphaned_jobs(None, &funs, &ctx).await {
+                warn!("[BIOS.Search] Failed to clean up the orphaned reindex jobs: {:?}", err);
+            }
+        }
+    });
+}
+
+/// Mark the running jobs without heartbeat as failed, drop their shadow tables/indexes and release their fences
+async fn clean_orphaned_jobs(ak: Option<&str>, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let conf = funs.conf::<SearchConfig>();
+    let now = Utc::now();
+    let mut query = Query::delete();
+    query
+        .from_table(search_reindex_job::Entity)
+        .and_where(Expr::col(search_reindex_job::Column::EndTime).lt(now - Duration::seconds(conf.reindex.finished_job_keep_sec as i64)));
+    funs.db().execute(&query).await?;
+
+    let cutoff = now - Duration::seconds(conf.reindex.stale_sec as i64);
+    let mut query = Query::select();
+    query
+        .columns(search_reindex_job::Column::iter())
+        .from(search_reindex_job::Entity)
+        .and_where(Expr::col(search_reindex_job::Column::RunningKey).is_not_null())
+        .and_where(Expr::col(search_reindex_job::Column::UpdateTime).lt(cutoff));
+    if let Some(ak) = ak {
+        query.and_where(Expr::col(search_reindex_job::Column::Ak).eq(ak));
+    }
+    for job in funs.db().find_dtos::<search_reindex_job::Model>(&query).await? {
+        // Only the node that claims the job cleans it up
+        let mut query = Query::update();
+        query
+            .table(search_reindex_job::Entity)
+            .value(search_reindex_job::Column::Status, SearchReindexStatusKind::Failed.to_string())
+            .value(search_reindex_job::Column::Error, Some("the job is interrupted".to_string()))
+            .value(search_reindex_job::Column::RunningKey, Option::<String>::None)
+            .value(search_reindex_job::Column::EndTime, Some(now))
+            .value(search_reindex_job::Column::UpdateTime, now)
+            .and_where(Expr::col(search_reindex_job::Column::Id).eq(job.id.as_str()))
+            .and_where(Expr::col(search_reindex_job::Column::RunningKey).is_not_null())
+            .and_where(Expr::col(search_reindex_job::Column::UpdateTime).lt(cutoff));
+        if funs.db().execute(&query).await?.rows_affected() != 1 {
+            continue;
+        }
+        info!("[BIOS.Search] clean up the interrupted reindex job {} of tag {}", job.id, job.tag);
+        let job_ctx = TardisContext {
+            ak: job.ak.clone(),
+            owner: ctx.owner.clone(),
+            ..Default::default()
+        };
+        if let Err(err) = release_fence(&fence_key(&job.tag, funs, &job_ctx), &job.id, funs).await {
+            warn!("[BIOS.Search] Failed to release the fence of tag {}: {:?}", job.tag, err);
+        }
+        if let Err(err) = unmark_active(&active_field(&job.tag, &job_ctx), funs).await {
+            warn!("[BIOS.Search] Failed to remove the active mark of tag {}: {:?}", job.tag, err);
+        }
+        if job.shadow.is_empty() {
+            continue;
+        }
+        let target = if job.target_bs_id.is_empty() {
+            funs.init(None, &job_ctx, false, search_initializer::init_fun).await
+        } else {
+            init_bs(&job.target_bs_id, funs, &job_ctx).await
+        };
+        // the shadow table/index that has replaced the live one is kept by the backend
+        let result = match target {
+            Ok(target) => dispatch!(funs, target, drop_shadow(&job.shadow, funs, &job_ctx, &target)),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            warn!("[BIOS.Search] Failed to drop the shadow {}: {:?}", job.shadow, err);
+        }
+    }
+    Ok(())
+}
+
+async fn init_bs(bs_id: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Arc<SpiBsInst>> {
+    let bs = SpiBsServ::get_bs(bs_id, funs, ctx).await?;
+    let mut inst = search_initializer::init_fun(
+        SpiBsCertResp {
+            kind_code: bs.kind_code.clone(),
+            conn_uri: bs.conn_uri,
+            ak: bs.ak,
+            sk: bs.sk.unwrap_or_default(),
+            ext: bs.ext,
+            private: bs.private,
+        },
+        ctx,
+        true,
+    )
+    .await?;
+    inst.ext.insert(spi_constants::SPI_KIND_CODE_FLAG.to_string(), bs.kind_code);
+    Ok(Arc::new(inst))
+}
+
+async fn find_bound_bs_id(funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<Option<String>> {
+    let bs = SpiBsServ::find_one_item(
+        &SpiBsFilterReq {
+            basic: RbumBasicFilterReq {
+                enabled: Some(true),
+                ..Default::default()
+            },
+            rel: Some(RbumItemRelFilterReq {
+                rel_by_from: true,
+                tag: Some(spi_constants::SPI_IDENT_REL_TAG.to_string()),
+                from_rbum_kind: Some(RbumRelFromKind::Item),
+                rel_item_id: Some(ctx.ak.clone()),
+                ..Default::default()
+            }),
+            domain_code: Some(funs.module_code().to_string()),
+            ..Default::default()
+        },
+        funs,
+        ctx,
+    )
+    .await?;
+    Ok(bs.map(|bs| bs.id))
+}
+
+fn fence_key(tag: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> String {
+    format!("{}{}:{tag}", funs.conf::<SearchConfig>().reindex.cache_key_fence, ctx.ak)
+}
+
+fn active_field(tag: &str, ctx: &TardisContext) -> String {
+    format!("{}:{tag}", ctx.ak)
+}
+
+fn active_key(funs: &TardisFunsInst) -> String {
+    format!("{}{ACTIVE_CACHE_KEY_SUFFIX}", funs.conf::<SearchConfig>().reindex.cache_key_fence)
+}
+
+/// Get the active state of the tag, from the marks cached by this node if they are refreshed recently
+async fn get_active_state(active_field: &str, funs: &TardisFunsInst) -> TardisResult<Option<String>> {
+    if let Some((refreshed_time, active_tags)) = active_tags().read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        if refreshed_time.elapsed() < StdDuration::from_secs(ACTIVE_REFRESH_SECS) {
+            return Ok(active_tags.get(active_field).cloned());
+        }
+    }
+    let tags = funs.cache().hgetall(&active_key(funs)).await?;
+    let state = tags.get(active_field).cloned();
+    *active_tags().write().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), tags));
+    Ok(state)
+}
+
+async fn mark_active(active_field: &str, state: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    funs.cache().hset(&active_key(funs), active_field, state).await?;
+    Ok(())
+}
+
+async fn unmark_active(active_field: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    funs.cache().hdel(&active_key(funs), active_field).await?;
+    if let Some((_, active_tags)) = active_tags().write().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        active_tags.remove(active_field);
+    }
+    Ok(())
+}
+
+async fn set_fence(fence_key: &str, job_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    // the fence expires if the node stops, the heartbeat keeps it
+    funs.cache().set_ex(fence_key, &format!("{FENCE_FENCED_PREFIX}{job_id}"), funs.conf::<SearchConfig>().reindex.stale_sec).await?;
+    Ok(())
+}
+
+async fn release_fence(fence_key: &str, job_id: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    if funs.cache().get(fence_key).await?.as_deref() == Some(format!("{FENCE_FENCED_PREFIX}{job_id}").as_str()) {
+        funs.cache().del(fence_key).await?;
+    }
+    Ok(())
+}
+
+async fn exist_running_job(running_key: &str, funs: &TardisFunsInst) -> TardisResult<bool> {
+    let mut query = Query::select();
+    query.column(search_reindex_job::Column::Id).from(search_reindex_job::Entity).and_where(Expr::col(search_reindex_job::Column::RunningKey).eq(running_key));
+    Ok(funs.db().count(&query).await? > 0)
+}
+
+fn running_error(tag: &str, funs: &TardisFunsInst) -> TardisError {
+    funs.err().conflict("search_reindex_serv", "reindex", &format!("tag {tag} is being reindexed"), "409-spi-search-reindex-running")
+}
+
+fn start_heartbeat(job_id: String, fence_key: String) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let funs = crate::get_tardis_inst();
+        let mut interval = time::interval(StdDuration::from_secs(funs.conf::<SearchConfig>().reindex.heartbeat_interval_sec.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(err) = heartbeat(&job_id, &fence_key, &funs).await {
+                warn!("[BIOS.Search] Failed to update the heartbeat of the reindex job {}: {:?}", job_id, err);
+            }
+        }
+    })
+}
+
+async fn heartbeat(job_id: &str, fence_key: &str, funs: &TardisFunsInst) -> TardisResult<()> {
+    let mut query = Query::update();
+    query
+        .table(search_reindex_job::Entity)
+        .value(search_reindex_job::Column::UpdateTime, Utc::now())
+        .and_where(Expr::col(search_reindex_job::Column::Id).eq(job_id))
+        .and_where(Expr::col(search_reindex_job::Column::RunningKey).is_not_null());
+    funs.db().execute(&query).await?;
+    if funs.cache().get(fence_key).await?.as_deref() == Some(format!("{FENCE_FENCED_PREFIX}{job_id}").as_str()) {
+        funs.cache().expire(fence_key, funs.conf::<SearchConfig>().reindex.stale_sec as i64).await?;
+    }
+    Ok(())
+}
+
+/// Update the running job, fails if the job has been cleaned up as an interrupted one
+async fn update_job(job_id: &str, status: Option<SearchReindexStatusKind>, total: Option<u64>, processed: u64, funs: &TardisFunsInst) -> TardisResult<()> {
+    let mut query = Query::update();
+    query
+        .table(search_reindex_job::Entity)
+        .value(
+            search_reindex_job::Column::Processed,
+            Expr::col(search_reindex_job::Column::Processed).add(processed as i64),
+        )
+        .value(search_reindex_job::Column::UpdateTime, Utc::now())
+        .and_where(Expr::col(search_reindex_job::Column::Id).eq(job_id))
+        .and_where(Expr::col(search_reindex_job::Column::RunningKey).is_not_null());
+    if let Some(status) = status {
+        query.value(search_reindex_job::Column::Status, status.to_string());
+    }
+    if let Some(total) = total {
+        query.value(search_reindex_job::Column::Total, total as i64);
+    }
+    if funs.db().execute(&query).await?.rows_affected() != 1 {
+        return Err(funs.err().conflict(
+            "search_reindex_serv",
+            "update_job",
+            &format!("reindex job {job_id} is interrupted"),
+            "409-spi-search-reindex-interrupted",
+        ));
+    }
+    Ok(())
+}
+
+async fn finish_job(job_id: &str, error: Option<String>, funs: &TardisFunsInst) -> TardisResult<()> {
+    let status = if error.is_some() {
+        SearchReindexStatusKind::Failed
+    } else {
+        SearchReindexStatusKind::Succeeded
+    };
+    let now = Utc::now();
+    let mut query = Query::update();
+    query
+        .table(search_reindex_job::Entity)
+        .value(search_reindex_job::Column::Status, status.to_string())
+        .value(search_reindex_job::Column::Error, error)
+        .value(search_reindex_job::Column::RunningKey, Option::<String>::None)
+        .value(search_reindex_job::Column::EndTime, Some(now))
+        .value(search_reindex_job::Column::UpdateTime, now)
+        .and_where(Expr::col(search_reindex_job::Column::Id).eq(job_id))
+        .and_where(Expr::col(search_reindex_job::Column::RunningKey).is_not_null());
+    funs.db().execute(&query).await?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn do_reindex(
+    job_id: &str,
+    tag: &str,
+    batch_size: u32,
+    marked_time: Instant,
+    source: &SpiBsInst,
+    target: &SpiBsInst,
+    target_bs_id: Option<&str>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let start_time = Utc::now();
+    let total = dispatch!(funs, source, count(tag, funs, ctx, source))?;
+    update_job(job_id, None, Some(total), 0, funs).await?;
+    let shadow = dispatch!(funs, target, create_shadow(tag, job_id, funs, ctx, target))?;
+    let mut query = Query::update();
+    query.table(search_reindex_job::Entity).value(search_reindex_job::Column::Shadow, shadow.as_str()).and_where(Expr::col(search_reindex_job::Column::Id).eq(job_id));
+    funs.db().execute(&query).await?;
+    let result = do_copy(job_id, tag, &shadow, start_time, batch_size, marked_time, source, target, target_bs_id, funs, ctx).await;
+    if result.is_err() {
+        if let Err(err) = dispatch!(funs, target, drop_shadow(&shadow, funs, ctx, target)) {
+            error!("[BIOS.Search] failed to drop the shadow {}: {}", shadow, err);
+        }
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn do_copy(
+    job_id: &str,
+    tag: &str,
+    shadow: &str,
+    start_time: DateTime<Utc>,
+    batch_size: u32,
+    marked_time: Instant,
+    source: &SpiBsInst,
+    target: &SpiBsInst,
+    target_bs_id: Option<&str>,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let mut after_key = None;
+    loop {
+        let mut items = dispatch!(funs, source, scan(tag, after_key.clone(), batch_size, funs, ctx, source))?;
+        let size = items.len();
+        if size == 0 {
+            break;
+        }
+        after_key = items.last().map(|item| item.key.to_string());
+        search_embedding_serv::reset_embeddings(&mut items, funs);
+        dispatch!(funs, target, write_shadow(shadow, &mut items, false, funs, ctx, target))?;
+        update_job(job_id, None, None, size as u64, funs).await?;
+        if size < batch_size as usize {
+            break;
+        }
+    }
+
+    update_job(job_id, Some(SearchReindexStatusKind::CatchingUp), None, 0, funs).await?;
+    let catch_up_time = Utc::now();
+    catch_up(job_id, tag, shadow, start_time, source, target, funs, ctx).await?;
+
+    let in_place = target_bs_id.is_none();
+    // pg blocks the writes of the live table itself when swapping in place
+    let fenced = !in_place || target.kind_code() == spi_constants::SPI_ES_KIND_CODE;
+    if fenced {
+        update_job(job_id, Some(SearchReindexStatusKind::Fencing), None, 0, funs).await?;
+        // wait until all the nodes see the tag active and check the fence
+        time::sleep_until(time::Instant::from_std(marked_time + StdDuration::from_secs(2 * ACTIVE_REFRESH_SECS))).await;
+        let fence_key = fence_key(tag, funs, ctx);
+        set_fence(&fence_key, job_id, funs).await?;
+        tokio::time::sleep(StdDuration::from_secs(FENCE_GRACE_SECS)).await;
+        if !in_place {
+            catch_up(job_id, tag, shadow, catch_up_time, source, target, funs, ctx).await?;
+        }
+    }
+
+    update_job(job_id, Some(SearchReindexStatusKind::Swapping), None, 0, funs).await?;
+    let since = in_place.then(|| catch_up_time - Duration::seconds(CATCH_UP_TOLERANCE_SECS));
+    dispatch!(funs, target, swap_shadow(tag, shadow, since, funs, ctx, target))?;
+    if let Some(target_bs_id) = target_bs_id {
+        // keep rejecting the writes of the tag until the app/tenant is rebound to the target backend service
+        funs.cache().set(&fence_key(tag, funs, ctx), &format!("{FENCE_MOVED_PREFIX}{target_bs_id}")).await?;
+    }
+    Ok(())
+}
+
+/// Copy the items changed since the given time, and the ones added or deleted regardless of their update time
+#[allow(clippy::too_many_arguments)]
+async fn catch_up(
+    job_id: &str,
+    tag: &str,
+    shadow: &str,
+    since: DateTime<Utc>,
+    source: &SpiBsInst,
+    target: &SpiBsInst,
+    funs: &TardisFunsInst,
+    ctx: &TardisContext,
+) -> TardisResult<()> {
+    let source_keys = dispatch!(funs, source, keys(tag, funs, ctx, source))?;
+    let shadow_keys = dispatch!(funs, target, shadow_keys(shadow, funs, ctx, target))?;
+    // the items added during the backfilling may have an earlier update time, so they are fetched by the keys
+    let added_keys = source_keys.difference(&shadow_keys).cloned().collect::<Vec<_>>();
+    let deleted_keys = shadow_keys.difference(&source_keys).cloned().collect::<Vec<_>>();
+    let since = since - Duration::seconds(CATCH_UP_TOLERANCE_SECS);
+    let mut items = dispatch!(funs, source, scan_changed(tag, since, added_keys, funs, ctx, source))?;
+    let size = items.len();
+    search_embedding_serv::reset_embeddings(&mut items, funs);
+    dispatch!(funs, target, write_shadow(shadow, &mut items, true, funs, ctx, target))?;
+    dispatch!(funs, target, delete_shadow(shadow, deleted_keys, funs, ctx, target))?;
+    update_job(job_id, None, None, size as u64, funs).await?;
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use bios_basic::test::test_http_client::TestHttpClient;
-use bios_spi_search::dto::search_item_dto::SearchItemSearchResp;
+use bios_spi_search::dto::search_dict_dto::{SearchDictFieldKind, SearchDictResp};
+use bios_spi_search::dto::search_item_dto::{SearchItemFacetSearchResp, SearchItemSearchResp, SearchItemSuggestKind, SearchReindexJobResp, SearchReindexStatusKind};
 use tardis::basic::dto::TardisContext;
 use tardis::basic::result::TardisResult;
 use tardis::log;
@@ -187,6 +188,21 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
         .await;
     assert!(search_result.code.starts_with("400"));
 
+    // Vector search is disabled when the embedding dimensions are not configured
+    let search_result: TardisResp<TardisPage<SearchItemSearchResp>> = client
+        .put_resp(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{},
+                "query":{"q":"搜索"},
+                "page":{"number":1,"size":10,"fetch_total":true},
+                "vector":{"mode":"hybrid","embedding":[0.1,0.2,0.3]}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.code, "400-spi-search-embedding-not-enabled");
+
     let search_result: TardisPage<SearchItemSearchResp> = client
         .put(
             "/ci/item/search",
@@ -224,6 +240,125 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     assert_eq!(search_result.total_size, 2);
     assert_eq!(search_result.records[0].key, "002");
     assert_eq!(search_result.records[1].key, "003");
+    assert!(search_result.records[0].highlight.is_none());
+
+    // Search with highlight, facets and suggestions
+    let search_result: SearchItemFacetSearchResp = client
+        .put(
+            "/ci/item/search/facet",
+            &json!({
+                "search":{
+                    "tag":"feed",
+                    "ctx":{
+                        "apps":["003"]
+                    },
+                    "query":{
+                        "q": "新增"
+                    },
+                    "sort":[{"field":"key","order":"asc"}],
+                    "page":{"number":1,"size":10,"fetch_total":true},
+                    "highlight":{}
+                },
+                "facets":[{"field":"kind","in_ext":false},{"field":"owner","in_ext":false},{"field":"version"}],
+                "suggest":{"size":5,"correction":false}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.page.total_size, 2);
+    assert!(search_result.page.records[0].highlight.as_ref().unwrap().title.is_some());
+    assert_eq!(search_result.facets.len(), 3);
+    assert_eq!(search_result.facets[0].field, "kind");
+    assert_eq!(search_result.facets[0].values.len(), 2);
+    assert_eq!(search_result.facets[0].values[0].value, Some("req".to_string()));
+    assert_eq!(search_result.facets[0].values[0].count, 1);
+    assert_eq!(search_result.facets[1].values.len(), 1);
+    assert_eq!(search_result.facets[1].values[0].value, Some("account002".to_string()));
+    assert_eq!(search_result.facets[1].values[0].count, 2);
+    assert_eq!(search_result.facets[2].field, "version");
+    assert_eq!(search_result.suggestions.len(), 2);
+    assert!(search_result.suggestions.iter().all(|suggestion| suggestion.kind == SearchItemSuggestKind::Prefix && suggestion.text.starts_with("新增")));
+
+    // Reindex without downtime
+    let job_id: String = client.put("/ci/item/feed/reindex", &json!({"batch_size":2})).await;
+    let mut job: SearchReindexJobResp = client.get(&format!("/ci/item/reindex/{job_id}")).await;
+    for _ in 0..30 {
+        if job.end_time.is_some() {
+            break;
+        }
+        sleep(std::time::Duration::from_secs(1)).await;
+        job = client.get(&format!("/ci/item/reindex/{job_id}")).await;
+    }
+    assert_eq!(job.status, SearchReindexStatusKind::Succeeded);
+    assert_eq!(job.processed, job.total);
+    let search_result: TardisPage<SearchItemSearchResp> = client
+        .put(
+            "/ci/item/search",
+            &json!({
+                "tag":"feed",
+                "ctx":{
+                    "apps":["003"]
+                },
+                "query":{
+                    "q": "新增"
+                },
+                "page":{"number":1,"size":10,"fetch_total":true}
+            }),
+        )
+        .await;
+    assert_eq!(search_result.total_size, 2);
+
+    // Dictionary
+    let job_id: Option<String> = client
+        .put(
+            "/ci/dict/feed",
+            &json!({
+                "entries":[
+                    {"kind":"synonym","words":["新增"," 创建 ","新增"]},
+                    {"kind":"stop_word","field":"content","words":["的"]},
+                    {"kind":"user_word","words":["BIOS-123"]}
+                ]
+            }),
+        )
+        .await;
+    let job_id = job_id.unwrap();
+    let mut job: SearchReindexJobResp = client.get(&format!("/ci/item/reindex/{job_id}")).await;
+    for _ in 0..30 {
+        if job.end_time.is_some() {
+            break;
+        }
+        sleep(std::time::Duration::from_secs(1)).await;
+        job = client.get(&format!("/ci/item/reindex/{job_id}")).await;
+    }
+    assert_eq!(job.status, SearchReindexStatusKind::Succeeded);
+    let dict: SearchDictResp = client.get("/ci/dict/feed").await;
+    assert_eq!(dict.entries.len(), 3);
+    assert_eq!(dict.entries[0].words, vec!["新增".to_string(), "创建".to_string()]);
+    assert_eq!(dict.entries[1].field, Some(SearchDictFieldKind::Content));
+    assert_eq!(dict.entries[2].words, vec!["bios-123".to_string()]);
+    assert!(dict.update_time.is_some());
+    let resp: TardisResp<Option<String>> = client.put_resp("/ci/dict/feed", &json!({"entries":[{"kind":"synonym","words":["新增"]}],"reindex":false})).await;
+    assert_eq!(resp.code, "400-spi-search-dict-synonym-not-legal");
+    client.delete("/ci/dict/feed?reindex=false").await;
+    let dict: SearchDictResp = client.get("/ci/dict/feed").await;
+    assert!(dict.entries.is_empty());
+    assert!(dict.update_time.is_none());
+
+    let search_result: TardisResp<SearchItemFacetSearchResp> = client
+        .put_resp(
+            "/ci/item/search/facet",
+            &json!({
+                "search":{
+                    "tag":"feed",
+                    "ctx":{},
+                    "query":{},
+                    "page":{"number":1,"size":10,"fetch_total":true}
+                },
+                "facets":[{"field":"ext->>'version'"}]
+            }),
+        )
+        .await;
+    assert_eq!(search_result.code, "400-spi-search-facet-field-not-legal");
+
     let search_result: TardisPage<SearchItemSearchResp> = client
         .put(
             "/ci/item/search",
@@ -11,11 +11,11 @@ use crate::dto::stats_conf_dto::{
     StatsConfDimGroupInfoResp,
     StatsConfDimGroupModifyReq, StatsConfDimInfoResp, StatsConfDimModifyReq, StatsConfFactAddReq, StatsConfFactColAddReq, StatsConfFactColInfoResp,
     StatsConfFactColModifyReq, StatsConfFactDetailAddReq, StatsConfFactDetailInfoResp, StatsConfFactDetailModifyReq, StatsConfFactInfoResp, StatsConfFactModifyReq,
-    StatsSyncDbConfigAddReq, StatsSyncDbConfigInfoResp, StatsSyncDbConfigModifyReq,
+    StatsConfFactRollupInfoResp, StatsConfFactRollupSaveReq, StatsSyncDbConfigAddReq, StatsSyncDbConfigInfoResp, StatsSyncDbConfigModifyReq,
 };
 use crate::serv::{
     stats_cert_serv, stats_conf_dim_col_serv, stats_conf_dim_group_serv, stats_conf_dim_serv, stats_conf_fact_col_serv, stats_conf_fact_detail_serv,
-    stats_conf_fact_serv, stats_sync_serv,
+    stats_conf_fact_rollup_serv, stats_conf_fact_serv, stats_sync_serv,
 };
 use crate::stats_enumeration::StatsFactColKind;
 
@@ -633,6 +633,53 @@ impl StatsCiConfApi {
         TardisResp::ok(Void {})
     }
 
+    /// Save Fact Rollup Configuration
+    ///
+    /// The rollup tables are rebuilt from the fact records in the background, then kept up to date by the record writes,
+    /// the metric queries answerable by them are routed to them once the rebuild finishes.
+    ///
+    /// 保存事实汇总配置。
+    /// 汇总表在后台根据事实记录重建，之后随记录的写入更新，重建完成后可由汇总表响应的指标查询会路由到汇总表。
+    #[oai(path = "/fact/:fact_key/rollup", method = "put")]
+    async fn fact_rollup_save(&self, fact_key: Path<String>, save_req: Json<StatsConfFactRollupSaveReq>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_fact_rollup_serv::save(&fact_key.0, &save_req.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Get Fact Rollup Configuration
+    ///
+    /// 获取事实汇总配置
+    #[oai(path = "/fact/:fact_key/rollup", method = "get")]
+    async fn fact_rollup_get(&self, fact_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Option<StatsConfFactRollupInfoResp>> {
+        let funs = crate::get_tardis_inst();
+        let resp = stats_conf_fact_rollup_serv::get(&fact_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(resp)
+    }
+
+    /// Delete Fact Rollup Configuration
+    ///
+    /// 删除事实汇总配置
+    #[oai(path = "/fact/:fact_key/rollup", method = "delete")]
+    async fn fact_rollup_delete(&self, fact_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_fact_rollup_serv::delete(&fact_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
+    /// Rebuild Fact Rollup
+    ///
+    /// The rollup is rebuilt in the background, the metric queries use the fact records until it finishes.
+    ///
+    /// 重建事实汇总。
+    /// 汇总在后台重建，完成前指标查询使用事实记录。
+    #[oai(path = "/fact/:fact_key/rollup/rebuild", method = "put")]
+    async fn fact_rollup_rebuild(&self, fact_key: Path<String>, ctx: TardisContextExtractor) -> TardisApiResult<Void> {
+        let funs = crate::get_tardis_inst();
+        stats_conf_fact_rollup_serv::rebuild(&fact_key.0, &funs, &ctx.0).await?;
+        TardisResp::ok(Void {})
+    }
+
     /// Add Sync DateBase Config
     ///
     /// 添加同步数据库配置
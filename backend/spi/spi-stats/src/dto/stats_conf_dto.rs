@@ -5,7 +5,7 @@ use tardis::{
     web::poem_openapi,
 };
 
-use crate::stats_enumeration::{StatsDataTypeKind, StatsFactColKind, StatsFactDetailKind, StatsFactDetailMethodKind};
+use crate::stats_enumeration::{StatsDataTypeKind, StatsFactColKind, StatsFactDetailKind, StatsFactDetailMethodKind, StatsQueryTimeWindowKind};
 
 /// Add Dimension Group Configuration Request Object
 ///
@@ -752,6 +752,45 @@ pub struct StatsConfFactDetailInfoResp {
     pub sort: Option<i32>,
 }
 
+/// Save Fact Rollup Configuration Request Object
+///
+/// 保存事实汇总配置请求对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug)]
+pub struct StatsConfFactRollupSaveReq {
+    /// Dimension column keys the rollup is grouped by, the columns of external ids are not supported
+    ///
+    /// 汇总分组使用的维度列key，不支持外部id的列
+    pub dim_keys: Vec<String>,
+    /// Time granularities of the rollup, one rollup table is created for each granularity
+    ///
+    /// 汇总的时间粒度，每个粒度创建一个汇总表
+    #[oai(validator(min_items = "1"))]
+    pub time_windows: Vec<StatsQueryTimeWindowKind>,
+}
+
+/// Fact Rollup Configuration Response Object
+///
+/// 事实汇总配置响应对象
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
+pub struct StatsConfFactRollupInfoResp {
+    /// Associated fact key
+    ///
+    /// 关联的事实key
+    pub rel_conf_fact_key: String,
+    pub dim_keys: Vec<String>,
+    /// Measure column keys aggregated by the rollup, all the measures of the fact when it is saved
+    ///
+    /// 汇总聚合的度量列key，为保存时事实的所有度量
+    pub mes_keys: Vec<String>,
+    pub time_windows: Vec<StatsQueryTimeWindowKind>,
+    /// Whether the rollup is being rebuilt, the metric queries use the fact records meanwhile
+    ///
+    /// 汇总是否正在重建，期间指标查询使用事实记录
+    pub stale: bool,
+    pub create_time: DateTime<Utc>,
+    pub update_time: DateTime<Utc>,
+}
+
 /// Add Sync DateBase Config Request Object
 ///
 /// 添加同步数据库配置请求对象
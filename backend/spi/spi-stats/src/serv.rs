@@ -5,6 +5,7 @@ pub mod stats_conf_dim_col_serv;
 pub mod stats_conf_dim_serv;
 pub mod stats_conf_fact_col_serv;
 pub mod stats_conf_fact_detail_serv;
+pub mod stats_conf_fact_rollup_serv;
 pub mod stats_conf_fact_serv;
 pub mod stats_metric_serv;
 pub mod stats_record_serv;
@@ -3,6 +3,7 @@ pub mod stats_pg_conf_dim_col_serv;
 pub mod stats_pg_conf_dim_serv;
 pub mod stats_pg_conf_fact_col_serv;
 pub mod stats_pg_conf_fact_detail_serv;
+pub mod stats_pg_conf_fact_rollup_serv;
 pub mod stats_pg_conf_fact_serv;
 pub mod stats_pg_initializer;
 pub mod stats_pg_metric_serv;
@@ -22,7 +22,7 @@ use crate::{
     stats_enumeration::{StatsDataTypeKind, StatsFactColKind},
 };
 
-use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_serv, stats_pg_initializer, stats_pg_sync_serv};
+use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_rollup_serv, stats_pg_conf_fact_serv, stats_pg_initializer, stats_pg_sync_serv};
 
 pub(crate) async fn add(fact_conf_key: &str, add_req: &StatsConfFactColAddReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
@@ -345,6 +345,20 @@ pub(crate) async fn delete(
         } else {
             find_by_fact_conf_key(fact_conf_key, funs, ctx, inst).await?
         };
+        // The rollup tables are maintained with the columns of the rollup
+        if let Some(rollup) = stats_pg_conf_fact_rollup_serv::find(fact_conf_key, &conn, ctx).await? {
+            if let Some(fact_col_conf) = fact_col_confs.iter().find(|fact_col_conf| rollup.dim_keys.contains(&fact_col_conf.key) || rollup.mes_keys.contains(&fact_col_conf.key)) {
+                return Err(funs.err().conflict(
+                    "fact_col_conf",
+                    "delete",
+                    &format!(
+                        "The fact column [{}] is used by the rollup, please delete the rollup and then delete it.",
+                        fact_col_conf.key
+                    ),
+                    "409-spi-stats-fact-col-used-by-rollup",
+                ));
+            }
+        }
         for fact_col_conf in fact_col_confs {
             alter_inst_table_column(fact_conf_key, fact_col_conf, &AlterColumnKind::Delete, &conn, funs, ctx, inst).await?;
         }
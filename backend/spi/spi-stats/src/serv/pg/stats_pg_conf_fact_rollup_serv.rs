@@ -0,0 +1,839 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use bios_basic::spi::{
+    spi_funs::{SpiBsInst, SpiBsInstExtractor},
+    spi_initializer::common_pg::{self, package_table_name},
+};
+use tardis::{
+    basic::{dto::TardisContext, error::TardisError, result::TardisResult},
+    chrono::{DateTime, Utc},
+    db::{
+        reldb_client::{TardisRelDBClient, TardisRelDBlConnection},
+        sea_orm::Value,
+    },
+    log::{error, warn},
+    tokio, TardisFunsInst,
+};
+
+use crate::{
+    dto::stats_conf_dto::{StatsConfFactRollupInfoResp, StatsConfFactRollupSaveReq},
+    stats_enumeration::{StatsDataTypeKind, StatsFactColKind, StatsQueryAggFunKind, StatsQueryTimeWindowKind},
+    stats_initializer,
+};
+
+use super::{stats_pg_conf_fact_col_serv, stats_pg_conf_fact_serv, stats_pg_initializer};
+
+// Time zone of the buckets, the same as the one used by the time window of the query
+const TIME_ZONE: &str = "Asia/Shanghai";
+// Bucket units from the coarsest to the finest, the coarsest one able to answer a query is preferred
+const BUCKET_UNITS: [&str; 5] = ["year", "month", "week", "day", "hour"];
+// Seconds the facts without a rollup are cached by each node, the rebuild of a new rollup waits this long (with a grace time)
+// so that the records written by the nodes not seeing it yet are aggregated
+const NO_ROLLUP_CACHE_SEC: u64 = 10;
+const NO_ROLLUP_GRACE_SEC: u64 = 5;
+// Retries of a failed rebuild, the backoff doubles from the initial one, the rollup stays stale after the last one until it is rebuilt again
+const REBUILD_MAX_RETRIES: u32 = 5;
+const REBUILD_RETRY_BACKOFF_SEC: u64 = 5;
+
+// The facts without a rollup, by the config table and the fact key, with the time of caching
+fn facts_without_rollup() -> &'static RwLock<HashMap<String, Instant>> {
+    static FACTS: OnceLock<RwLock<HashMap<String, Instant>>> = OnceLock::new();
+    FACTS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn no_rollup_cache_key(fact_conf_key: &str, ctx: &TardisContext) -> String {
+    format!("{}:{fact_conf_key}", package_table_name("stats_conf_fact_rollup", ctx))
+}
+
+pub(crate) async fn save(fact_conf_key: &str, save_req: &StatsConfFactRollupSaveReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if !stats_pg_conf_fact_serv::online(fact_conf_key, &conn, ctx).await? {
+        return Err(funs.err().conflict("fact_rollup_conf", "save", "The fact config not online.", "409-spi-stats-fact-conf-not-online"));
+    }
+    let fact_col_conf_set = stats_pg_conf_fact_col_serv::find_by_fact_conf_key(fact_conf_key, funs, ctx, inst)
+        .await?
+        .into_iter()
+        .filter(|fact_col_conf| fact_col_conf.rel_external_id.as_ref().map(|rel_external_id| rel_external_id.is_empty()).unwrap_or(true))
+        .collect::<Vec<_>>();
+    let mut dim_keys: Vec<String> = vec![];
+    for dim_key in &save_req.dim_keys {
+        if !fact_col_conf_set.iter().any(|fact_col_conf| &fact_col_conf.key == dim_key && fact_col_conf.kind == StatsFactColKind::Dimension) {
+            return Err(funs.err().bad_request(
+                "fact_rollup_conf",
+                "save",
+                &format!("The dimension column [{dim_key}] of the fact [{fact_conf_key}] does not exist."),
+                "400-spi-stats-fact-rollup-dim-not-legal",
+            ));
+        }
+        if !dim_keys.contains(dim_key) {
+            dim_keys.push(dim_key.clone());
+        }
+    }
+    let mut mes_keys: Vec<String> = vec![];
+    // only the numeric measures can be aggregated
+    for fact_col_conf in fact_col_conf_set.iter().filter(|fact_col_conf| {
+        fact_col_conf.kind == StatsFactColKind::Measure
+            && matches!(
+                fact_col_conf.mes_data_type,
+                Some(StatsDataTypeKind::Int) | Some(StatsDataTypeKind::Float) | Some(StatsDataTypeKind::Double)
+            )
+    }) {
+        if !mes_keys.contains(&fact_col_conf.key) {
+            mes_keys.push(fact_col_conf.key.clone());
+        }
+    }
+    let mut time_windows: Vec<StatsQueryTimeWindowKind> = vec![];
+    for time_window in &save_req.time_windows {
+        if !time_windows.contains(time_window) {
+            time_windows.push(time_window.clone());
+        }
+    }
+
+    // the config is locked before the tables are replaced, the writes of the records wait for it and then only mark their keys as dirty
+    let old_rollup = find_with_version(fact_conf_key, "FOR UPDATE", &conn, ctx).await?;
+    let rollup = StatsConfFactRollupInfoResp {
+        rel_conf_fact_key: fact_conf_key.to_string(),
+        dim_keys,
+        mes_keys,
+        time_windows,
+        stale: true,
+        create_time: Utc::now(),
+        update_time: Utc::now(),
+    };
+    conn.execute_one(
+        &format!(
+            r#"INSERT INTO {table_name}
+(rel_conf_fact_key, dim_keys, mes_keys, time_windows, stale)
+VALUES
+($1, $2, $3, $4, TRUE)
+ON CONFLICT (rel_conf_fact_key) DO UPDATE SET dim_keys = EXCLUDED.dim_keys, mes_keys = EXCLUDED.mes_keys, time_windows = EXCLUDED.time_windows,
+    stale = TRUE, stale_version = {table_name}.stale_version + 1, update_time = now()
+"#,
+        ),
+        vec![
+            Value::from(fact_conf_key),
+            Value::from(rollup.dim_keys.clone()),
+            Value::from(rollup.mes_keys.clone()),
+            Value::from(rollup.time_windows.iter().map(|time_window| time_window.to_string()).collect::<Vec<_>>()),
+        ],
+    )
+    .await?;
+    if let Some((old_rollup, _)) = &old_rollup {
+        drop_rollup_tables(fact_conf_key, old_rollup, &conn, ctx).await?;
+    }
+    create_rollup_tables(fact_conf_key, &rollup, &conn, ctx).await?;
+    conn.commit().await?;
+    facts_without_rollup().write().unwrap_or_else(|e| e.into_inner()).remove(&no_rollup_cache_key(fact_conf_key, ctx));
+    // the other nodes may skip the rollup of a new config until their cache expires
+    let delay = if old_rollup.is_none() {
+        Duration::from_secs(NO_ROLLUP_CACHE_SEC + NO_ROLLUP_GRACE_SEC)
+    } else {
+        Duration::ZERO
+    };
+    do_spawn_rebuild(fact_conf_key, delay, ctx);
+    Ok(())
+}
+
+pub(crate) async fn get(fact_conf_key: &str, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<Option<StatsConfFactRollupInfoResp>> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (conn, _) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    find(fact_conf_key, &conn, ctx).await
+}
+
+pub(crate) async fn delete(fact_conf_key: &str, _funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, _) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    delete_by_fact_conf_key(fact_conf_key, &conn, ctx).await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+/// Mark the rollup of the fact as stale and rebuild it from the fact records in the background
+///
+/// 将事实的汇总标记为过期，并在后台根据事实记录重建
+pub(crate) async fn rebuild(fact_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<()> {
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, _) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    conn.begin().await?;
+    if !mark_stale(fact_conf_key, &conn, ctx).await? {
+        return Err(funs.err().not_found(
+            "fact_rollup_conf",
+            "rebuild",
+            "The fact rollup config does not exist.",
+            "404-spi-stats-fact-rollup-conf-not-exist",
+        ));
+    }
+    conn.commit().await?;
+    spawn_rebuild(fact_conf_key, ctx);
+    Ok(())
+}
+
+/// Find the rollup config of the fact, the config table is not created when it does not exist
+///
+/// 获取事实的汇总配置，配置表不存在时不会创建
+pub(in crate::serv::pg) async fn find(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<Option<StatsConfFactRollupInfoResp>> {
+    Ok(find_with_version(fact_conf_key, "", conn, ctx).await?.map(|(rollup, _)| rollup))
+}
+
+/// Find the rollup config with its stale version, the row is locked by the locking clause when it is not empty
+async fn find_with_version(
+    fact_conf_key: &str,
+    locking_clause: &str,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<Option<(StatsConfFactRollupInfoResp, i64)>> {
+    if !common_pg::check_table_exit("stats_conf_fact_rollup", conn, ctx).await? {
+        return Ok(None);
+    }
+    let result = conn
+        .query_one(
+            &format!(
+                "SELECT rel_conf_fact_key, dim_keys, mes_keys, time_windows, stale, stale_version, create_time, update_time FROM {} WHERE rel_conf_fact_key = $1 {locking_clause}",
+                package_table_name("stats_conf_fact_rollup", ctx)
+            ),
+            vec![Value::from(fact_conf_key)],
+        )
+        .await?;
+    let Some(item) = result else {
+        return Ok(None);
+    };
+    let rollup = StatsConfFactRollupInfoResp {
+        rel_conf_fact_key: item.try_get("", "rel_conf_fact_key")?,
+        dim_keys: item.try_get("", "dim_keys")?,
+        mes_keys: item.try_get("", "mes_keys")?,
+        time_windows: item
+            .try_get::<Vec<String>>("", "time_windows")?
+            .iter()
+            .map(|time_window| {
+                StatsQueryTimeWindowKind::from_str(time_window)
+                    .map_err(|_| TardisError::internal_error(&format!("Fail to parse time window [{time_window}]"), "500-spi-stats-internal-error"))
+            })
+            .collect::<TardisResult<Vec<_>>>()?,
+        stale: item.try_get("", "stale")?,
+        create_time: item.try_get("", "create_time")?,
+        update_time: item.try_get("", "update_time")?,
+    };
+    Ok(Some((rollup, item.try_get("", "stale_version")?)))
+}
+
+/// Delete the rollup config and the rollup tables of the fact
+///
+/// 删除事实的汇总配置及汇总表
+pub(in crate::serv::pg) async fn delete_by_fact_conf_key(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    let Some(rollup) = find(fact_conf_key, conn, ctx).await? else {
+        return Ok(());
+    };
+    drop_rollup_tables(fact_conf_key, &rollup, conn, ctx).await?;
+    conn.execute_one(
+        &format!("DELETE FROM {} WHERE rel_conf_fact_key = $1", package_table_name("stats_conf_fact_rollup", ctx)),
+        vec![Value::from(fact_conf_key)],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Refresh the rollup with the latest records of the keys, called in the transaction that changes the records of the keys
+///
+/// The config is read with a shared lock, so the rollup is not marked as stale or replaced until the transaction ends.
+/// While the rollup is stale, the keys are only marked as dirty and replayed by the rebuild.
+///
+/// 使用 key 的最新记录刷新汇总，在修改这些 key 记录的事务中调用。
+/// 配置以共享锁读取，在事务结束前汇总不会被标记为过期或被替换。
+/// 汇总过期时仅将 key 标记为脏数据，由重建过程重放。
+pub(in crate::serv::pg) async fn refresh_keys(fact_conf_key: &str, keys: &[String], conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let cache_key = no_rollup_cache_key(fact_conf_key, ctx);
+    if facts_without_rollup()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&cache_key)
+        .is_some_and(|cached_time| cached_time.elapsed() < Duration::from_secs(NO_ROLLUP_CACHE_SEC))
+    {
+        return Ok(());
+    }
+    let Some((rollup, _)) = find_with_version(fact_conf_key, "FOR SHARE", conn, ctx).await? else {
+        facts_without_rollup().write().unwrap_or_else(|e| e.into_inner()).insert(cache_key, Instant::now());
+        return Ok(());
+    };
+    if rollup.stale {
+        conn.execute_one(
+            &format!(
+                "INSERT INTO {} (key) SELECT DISTINCT unnest($1::text[]) ON CONFLICT DO NOTHING",
+                dirty_table_name(fact_conf_key, ctx)
+            ),
+            vec![Value::from(keys.to_vec())],
+        )
+        .await?;
+        return Ok(());
+    }
+    do_refresh_keys(fact_conf_key, &rollup, keys, conn, ctx).await
+}
+
+/// Mark the rollup of the fact as stale if it exists, the metric queries use the fact records until it is rebuilt
+///
+/// 将事实的汇总（如存在）标记为过期，重建完成前指标查询使用事实记录
+pub(in crate::serv::pg) async fn mark_stale(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<bool> {
+    if !common_pg::check_table_exit("stats_conf_fact_rollup", conn, ctx).await? {
+        return Ok(false);
+    }
+    let result = conn
+        .execute_one(
+            &format!(
+                "UPDATE {} SET stale = TRUE, stale_version = stale_version + 1 WHERE rel_conf_fact_key = $1",
+                package_table_name("stats_conf_fact_rollup", ctx)
+            ),
+            vec![Value::from(fact_conf_key)],
+        )
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rebuild the stale rollup of the fact in the background, called after the transaction marking it as stale is committed
+///
+/// 在后台重建事实的过期汇总，在将其标记为过期的事务提交后调用
+pub(in crate::serv::pg) fn spawn_rebuild(fact_conf_key: &str, ctx: &TardisContext) {
+    do_spawn_rebuild(fact_conf_key, Duration::ZERO, ctx);
+}
+
+/// A failed rebuild is retried with backoff, the rollup stays stale after the last retry until it is rebuilt by the rebuild api
+fn do_spawn_rebuild(fact_conf_key: &str, delay: Duration, ctx: &TardisContext) {
+    let fact_conf_key = fact_conf_key.to_string();
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let funs = crate::get_tardis_inst();
+        let mut retries = 0;
+        while let Err(err) = do_rebuild(&fact_conf_key, &funs, &ctx).await {
+            if retries >= REBUILD_MAX_RETRIES {
+                error!(
+                    "[BIOS.Stats] Failed to rebuild the rollup of the fact [{}] after {} retries, it stays stale until rebuilt again: {:?}",
+                    fact_conf_key, retries, err
+                );
+                return;
+            }
+            let backoff = Duration::from_secs(REBUILD_RETRY_BACKOFF_SEC << retries);
+            warn!(
+                "[BIOS.Stats] Failed to rebuild the rollup of the fact [{}], retry in {}s: {:?}",
+                fact_conf_key,
+                backoff.as_secs(),
+                err
+            );
+            tokio::time::sleep(backoff).await;
+            retries += 1;
+        }
+    });
+}
+
+/// The records are aggregated in the first transaction and the keys changed meanwhile are replayed in the following ones.
+/// The last one holds the config while clearing the stale flag, so no write is missed.
+/// A rebuild stops when the rollup is saved or marked as stale again, the later rebuild takes over.
+async fn do_rebuild(fact_conf_key: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
+    let inst = funs.init(None, ctx, true, stats_initializer::init_fun).await?;
+    let bs_inst = inst.inst::<TardisRelDBClient>();
+    let (mut conn, table_name) = stats_pg_initializer::init_conf_fact_rollup_table_and_conn(bs_inst, ctx, true).await?;
+    let Some((_, version)) = find_with_version(fact_conf_key, "", &conn, ctx).await? else {
+        return Ok(());
+    };
+
+    conn.begin().await?;
+    let Some(rollup) = lock_rebuilding(fact_conf_key, version, "", &conn, ctx).await? else {
+        return Ok(());
+    };
+    rebuild_rollup(fact_conf_key, &rollup, &conn, ctx).await?;
+    conn.commit().await?;
+
+    // most of the dirty keys are replayed without blocking the writes
+    conn.begin().await?;
+    let Some(rollup) = lock_rebuilding(fact_conf_key, version, "", &conn, ctx).await? else {
+        return Ok(());
+    };
+    replay_dirty_keys(fact_conf_key, &rollup, &conn, ctx).await?;
+    conn.commit().await?;
+
+    conn.begin().await?;
+    let Some(rollup) = lock_rebuilding(fact_conf_key, version, "FOR UPDATE", &conn, ctx).await? else {
+        return Ok(());
+    };
+    replay_dirty_keys(fact_conf_key, &rollup, &conn, ctx).await?;
+    conn.execute_one(
+        &format!("UPDATE {table_name} SET stale = FALSE WHERE rel_conf_fact_key = $1"),
+        vec![Value::from(fact_conf_key)],
+    )
+    .await?;
+    conn.commit().await?;
+    Ok(())
+}
+
+/// Serialize the rebuilds of the fact, `None` means the rollup is deleted, not stale or taken over by a later rebuild
+async fn lock_rebuilding(
+    fact_conf_key: &str,
+    version: i64,
+    locking_clause: &str,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<Option<StatsConfFactRollupInfoResp>> {
+    conn.execute_one("SELECT pg_advisory_xact_lock(hashtext($1))", vec![Value::from(key_table_name(fact_conf_key, ctx))]).await?;
+    Ok(find_with_version(fact_conf_key, locking_clause, conn, ctx).await?.filter(|(rollup, stale_version)| rollup.stale && *stale_version == version).map(|(rollup, _)| rollup))
+}
+
+async fn replay_dirty_keys(fact_conf_key: &str, rollup: &StatsConfFactRollupInfoResp, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    let keys = conn
+        .query_all(&format!("DELETE FROM {} RETURNING key", dirty_table_name(fact_conf_key, ctx)), vec![])
+        .await?
+        .into_iter()
+        .map(|item| item.try_get("", "key"))
+        .collect::<Result<Vec<String>, _>>()?;
+    do_refresh_keys(fact_conf_key, rollup, &keys, conn, ctx).await
+}
+
+/// The keys and the groups they belong to are locked in one statement in a fixed order,
+/// so the writes of different groups run concurrently and no group row is inserted twice.
+/// The contributions of the old records are subtracted and those of the new records are added, with one statement for all the bucket units.
+/// When the maximum or the minimum of a group may be subtracted, the group is marked as stale and recomputed from the key table.
+async fn do_refresh_keys(fact_conf_key: &str, rollup: &StatsConfFactRollupInfoResp, keys: &[String], conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let fact_table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}"), ctx);
+    let key_table_name = key_table_name(fact_conf_key, ctx);
+    let units = bucket_units(rollup);
+    let keys = Value::from(keys.to_vec());
+    let sql_group_lock = format!(
+        "hashtext($1 || ':group:' || row({})::text)",
+        ["fact.own_paths".to_string()].into_iter().chain(rollup.dim_keys.iter().map(|dim_key| format!("fact.{dim_key}"))).collect::<Vec<_>>().join(", ")
+    );
+    conn.execute_one(
+        &format!(
+            r#"SELECT pg_advisory_xact_lock(l.h)
+FROM (
+    SELECT hashtext($1 || ':key:' || k) AS h FROM unnest($2::text[]) k
+    UNION
+    SELECT {sql_group_lock} FROM {fact_table_name} fact WHERE fact.key = ANY($2)
+    UNION
+    SELECT {sql_group_lock} FROM {key_table_name} fact WHERE fact.key = ANY($2)
+    ORDER BY 1
+) l"#
+        ),
+        vec![Value::from(key_table_name.clone()), keys.clone()],
+    )
+    .await?;
+
+    let subtracts = units
+        .iter()
+        .map(|unit| {
+            format!(
+                r#"s_{unit} AS (
+    UPDATE {} r
+    SET _rows = r._rows - s._rows{}
+    FROM ({}) s
+    WHERE {}
+)"#,
+                rollup_table_name(fact_conf_key, unit, ctx),
+                rollup
+                    .mes_keys
+                    .iter()
+                    .map(|mes_key| format!(", {mes_key}__sum = r.{mes_key}__sum - s.{mes_key}__sum, {mes_key}__count = r.{mes_key}__count - s.{mes_key}__count"))
+                    .chain(rollup.mes_keys.first().map(|_| {
+                        format!(
+                            ", _stale = r._stale OR {}",
+                            rollup
+                                .mes_keys
+                                .iter()
+                                .map(|mes_key| format!("r.{mes_key}__max <= s.{mes_key}__max OR r.{mes_key}__min >= s.{mes_key}__min"))
+                                .collect::<Vec<_>>()
+                                .join(" OR ")
+                        )
+                    }))
+                    .collect::<String>(),
+                sql_group_select(rollup, unit, &key_table_name, "fact.key = ANY($1)"),
+                sql_group_match(rollup, "r", "s"),
+            )
+        })
+        .collect::<Vec<_>>();
+    conn.execute_one(&sql_with(&subtracts, &format!("DELETE FROM {key_table_name} WHERE key = ANY($1)")), vec![keys.clone()]).await?;
+
+    let columns = rollup_table_columns(rollup);
+    let mut adds = vec![format!(
+        "k AS (\n    INSERT INTO {key_table_name} ({})\n    {}\n    RETURNING *\n)",
+        key_table_columns(rollup).join(", "),
+        sql_latest_records(fact_conf_key, rollup, "fact.key = ANY($1)", ctx)
+    )];
+    for unit in &units {
+        let table_name = rollup_table_name(fact_conf_key, unit, ctx);
+        adds.push(format!("s_{unit} AS ({})", sql_group_select(rollup, unit, "k", "1 = 1")));
+        adds.push(format!(
+            r#"u_{unit} AS (
+    UPDATE {table_name} r
+    SET _rows = r._rows + s._rows{}
+    FROM s_{unit} s
+    WHERE {}
+    RETURNING r.*
+)"#,
+            rollup
+                .mes_keys
+                .iter()
+                .map(|mes_key| format!(
+                    ", {mes_key}__sum = r.{mes_key}__sum + s.{mes_key}__sum, {mes_key}__count = r.{mes_key}__count + s.{mes_key}__count, {mes_key}__max = GREATEST(r.{mes_key}__max, s.{mes_key}__max), {mes_key}__min = LEAST(r.{mes_key}__min, s.{mes_key}__min)"
+                ))
+                .collect::<String>(),
+            sql_group_match(rollup, "r", "s"),
+        ));
+        adds.push(format!(
+            r#"i_{unit} AS (
+    INSERT INTO {table_name} ({})
+    SELECT {} FROM s_{unit} s
+    WHERE NOT EXISTS (SELECT 1 FROM u_{unit} u WHERE {})
+)"#,
+            columns.join(", "),
+            columns.iter().map(|column| format!("s.{column}")).collect::<Vec<_>>().join(", "),
+            sql_group_match(rollup, "u", "s"),
+        ));
+    }
+    conn.execute_one(&sql_with(&adds, "SELECT 1"), vec![keys]).await?;
+
+    // the emptied groups are deleted and the stale ones are recomputed, the two never touch the same row
+    let mut cleans = vec![];
+    for unit in &units {
+        let table_name = rollup_table_name(fact_conf_key, unit, ctx);
+        cleans.push(format!("d_{unit} AS (DELETE FROM {table_name} WHERE _rows <= 0)"));
+        if !rollup.mes_keys.is_empty() {
+            cleans.push(format!(
+                r#"m_{unit} AS (
+    UPDATE {table_name} r
+    SET _stale = FALSE{}
+    FROM (
+        SELECT s.ctid AS _ctid, agg.*
+        FROM {table_name} s
+        CROSS JOIN LATERAL (
+            SELECT {}
+            FROM {key_table_name} fact
+            WHERE {} AND fact.own_paths = s.own_paths AND fact.ct >= s.ct AND fact.ct < s._ct_end{}
+        ) agg
+        WHERE s._stale AND s._rows > 0
+    ) x
+    WHERE r.ctid = x._ctid
+)"#,
+                rollup.mes_keys.iter().map(|mes_key| format!(", {mes_key}__max = x.{mes_key}__max, {mes_key}__min = x.{mes_key}__min")).collect::<String>(),
+                rollup
+                    .mes_keys
+                    .iter()
+                    .map(|mes_key| format!("max(COALESCE(fact.{mes_key}::decimal,0)) AS {mes_key}__max, min(COALESCE(fact.{mes_key}::decimal,0)) AS {mes_key}__min"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                sql_live_key(),
+                rollup.dim_keys.iter().map(|dim_key| format!(" AND fact.{dim_key} IS NOT DISTINCT FROM s.{dim_key}")).collect::<String>(),
+            ));
+        }
+    }
+    conn.execute_one(&sql_with(&cleans, "SELECT 1"), vec![]).await?;
+    Ok(())
+}
+
+/// Find the rollup table able to answer the query of the fact, `None` means the query should use the fact records
+///
+/// The start time should be the start of a bucket, the end time should not be earlier than any record or deletion,
+/// and no key deleted within the time range should have been loaded again,
+/// so that the latest record of each key in the time range is the one in the rollup.
+///
+/// 查找能够响应事实查询的汇总表，`None` 表示查询应使用事实记录。
+/// 开始时间需为分桶的开始，结束时间不能早于任何记录或删除，且时间范围内被删除的 key 没有被重新加载，
+/// 以保证时间范围内每个 key 的最新记录即为汇总中的记录。
+pub(in crate::serv::pg) async fn find_query_table(
+    fact_conf_key: &str,
+    rollup: &StatsConfFactRollupInfoResp,
+    time_windows: &[StatsQueryTimeWindowKind],
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<Option<String>> {
+    let units = bucket_units(rollup).into_iter().filter(|unit| time_windows.iter().all(|time_window| unit_answers(unit, time_window))).collect::<Vec<_>>();
+    if units.is_empty() {
+        return Ok(None);
+    }
+    let key_table_name = key_table_name(fact_conf_key, ctx);
+    let result = conn
+        .query_one(
+            &format!(
+                r#"SELECT
+    COALESCE((SELECT max(ct) FROM {key_table_name}) <= $2, TRUE) AS ct_covered,
+    COALESCE((SELECT max(_del_ct) FROM {key_table_name}) <= $2, TRUE) AS del_ct_covered,
+    NOT EXISTS (SELECT 1 FROM {key_table_name} WHERE _del_ct >= $1 AND _del_ct < ct) AS no_reloaded,
+    {}"#,
+                units
+                    .iter()
+                    .map(|unit| format!(
+                        "{} = $1::timestamp with time zone AS {unit}_aligned",
+                        sql_bucket_start(unit, "$1::timestamp with time zone")
+                    ))
+                    .collect::<Vec<_>>()
+                    .join(",\n    ")
+            ),
+            vec![Value::from(start_time), Value::from(end_time)],
+        )
+        .await?;
+    let Some(result) = result else {
+        return Ok(None);
+    };
+    if !result.try_get::<bool>("", "ct_covered")? || !result.try_get::<bool>("", "del_ct_covered")? || !result.try_get::<bool>("", "no_reloaded")? {
+        return Ok(None);
+    }
+    for unit in units {
+        if result.try_get::<bool>("", &format!("{unit}_aligned"))? {
+            return Ok(Some(rollup_table_name(fact_conf_key, unit, ctx)));
+        }
+    }
+    Ok(None)
+}
+
+/// Aggregate expression of the measure on the rollup table, the result is the same as the one on the fact records
+///
+/// 度量在汇总表上的聚合表达式，结果与基于事实记录的聚合一致
+pub(in crate::serv::pg) fn agg_sql(mes_key: &str, fun: &StatsQueryAggFunKind) -> String {
+    if mes_key == "_count" {
+        // every record counts as 1
+        return match fun {
+            StatsQueryAggFunKind::Sum => "sum(_._rows)".to_string(),
+            StatsQueryAggFunKind::Avg => "ROUND(sum(_._rows)::decimal/NULLIF(sum(_._rows),0),2)".to_string(),
+            StatsQueryAggFunKind::Max => "max(1::decimal)".to_string(),
+            StatsQueryAggFunKind::Min => "min(1::decimal)".to_string(),
+            StatsQueryAggFunKind::Count => "sum(_._rows)::bigint".to_string(),
+        };
+    }
+    match fun {
+        StatsQueryAggFunKind::Sum => format!("sum(_.{mes_key}__sum)"),
+        StatsQueryAggFunKind::Avg => format!("ROUND(sum(_.{mes_key}__sum)/NULLIF(sum(_._rows),0),2)"),
+        StatsQueryAggFunKind::Max => format!("max(_.{mes_key}__max)"),
+        StatsQueryAggFunKind::Min => format!("min(_.{mes_key}__min)"),
+        StatsQueryAggFunKind::Count => format!("sum(_.{mes_key}__count)::bigint"),
+    }
+}
+
+async fn create_rollup_tables(fact_conf_key: &str, rollup: &StatsConfFactRollupInfoResp, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    let fact_table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}"), ctx);
+    let key_table_name = key_table_name(fact_conf_key, ctx);
+    // the latest records are fetched by the keys
+    conn.execute_one(
+        &format!("CREATE INDEX IF NOT EXISTS {}_key_idx ON {fact_table_name} (key)", unqualified_table_name(&fact_table_name)),
+        vec![],
+    )
+    .await?;
+    conn.execute_one(
+        &format!(
+            "CREATE TABLE {key_table_name} AS SELECT {}, NULL::timestamp with time zone AS _del_ct FROM {fact_table_name} fact WITH NO DATA",
+            key_table_columns(rollup).iter().filter(|column| *column != "_del_ct").map(|column| format!("fact.{column}")).collect::<Vec<_>>().join(", ")
+        ),
+        vec![],
+    )
+    .await?;
+    conn.execute_one(&format!("CREATE UNIQUE INDEX ON {key_table_name} (key)"), vec![]).await?;
+    conn.execute_one(&format!("CREATE INDEX ON {key_table_name} (ct)"), vec![]).await?;
+    conn.execute_one(&format!("CREATE INDEX ON {key_table_name} (_del_ct)"), vec![]).await?;
+    conn.execute_one(&format!("CREATE INDEX ON {key_table_name} (own_paths)"), vec![]).await?;
+    conn.execute_one(
+        &format!("CREATE TABLE {} (key character varying PRIMARY KEY)", dirty_table_name(fact_conf_key, ctx)),
+        vec![],
+    )
+    .await?;
+    for unit in bucket_units(rollup) {
+        let table_name = rollup_table_name(fact_conf_key, unit, ctx);
+        conn.execute_one(
+            &format!("CREATE TABLE {table_name} AS {} WITH NO DATA", sql_group_select(rollup, unit, &key_table_name, "1 = 1")),
+            vec![],
+        )
+        .await?;
+        conn.execute_one(&format!("ALTER TABLE {table_name} ADD COLUMN _stale boolean NOT NULL DEFAULT FALSE"), vec![]).await?;
+        conn.execute_one(&format!("CREATE INDEX ON {table_name} (ct, own_paths)"), vec![]).await?;
+        conn.execute_one(&format!("CREATE INDEX ON {table_name} (_rows) WHERE _rows <= 0"), vec![]).await?;
+        conn.execute_one(&format!("CREATE INDEX ON {table_name} (_stale) WHERE _stale"), vec![]).await?;
+    }
+    Ok(())
+}
+
+async fn drop_rollup_tables(fact_conf_key: &str, rollup: &StatsConfFactRollupInfoResp, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    for unit in bucket_units(rollup) {
+        conn.execute_one(&format!("DROP TABLE IF EXISTS {}", rollup_table_name(fact_conf_key, unit, ctx)), vec![]).await?;
+    }
+    conn.execute_one(&format!("DROP TABLE IF EXISTS {}", key_table_name(fact_conf_key, ctx)), vec![]).await?;
+    conn.execute_one(&format!("DROP TABLE IF EXISTS {}", dirty_table_name(fact_conf_key, ctx)), vec![]).await?;
+    Ok(())
+}
+
+async fn rebuild_rollup(fact_conf_key: &str, rollup: &StatsConfFactRollupInfoResp, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<()> {
+    let key_table_name = key_table_name(fact_conf_key, ctx);
+    conn.execute_one(&format!("DELETE FROM {key_table_name}"), vec![]).await?;
+    conn.execute_one(
+        &format!(
+            "INSERT INTO {key_table_name} ({}) {}",
+            key_table_columns(rollup).join(", "),
+            sql_latest_records(fact_conf_key, rollup, "1 = 1", ctx)
+        ),
+        vec![],
+    )
+    .await?;
+    for unit in bucket_units(rollup) {
+        let table_name = rollup_table_name(fact_conf_key, unit, ctx);
+        conn.execute_one(&format!("DELETE FROM {table_name}"), vec![]).await?;
+        conn.execute_one(
+            &format!(
+                "INSERT INTO {table_name} ({}) {}",
+                rollup_table_columns(rollup).join(", "),
+                sql_group_select(rollup, unit, &key_table_name, "1 = 1")
+            ),
+            vec![],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn key_table_name(fact_conf_key: &str, ctx: &TardisContext) -> String {
+    package_table_name(&format!("stats_inst_fact_{fact_conf_key}_rollup_key"), ctx)
+}
+
+fn dirty_table_name(fact_conf_key: &str, ctx: &TardisContext) -> String {
+    package_table_name(&format!("stats_inst_fact_{fact_conf_key}_rollup_dirty"), ctx)
+}
+
+fn rollup_table_name(fact_conf_key: &str, unit: &str, ctx: &TardisContext) -> String {
+    package_table_name(&format!("stats_inst_fact_{fact_conf_key}_rollup_{unit}"), ctx)
+}
+
+fn unqualified_table_name(table_name: &str) -> &str {
+    table_name.split_once('.').map(|(_, table_name)| table_name).unwrap_or(table_name)
+}
+
+/// Columns of the key table, a column shared by a dimension and a measure is kept once
+fn key_table_columns(rollup: &StatsConfFactRollupInfoResp) -> Vec<String> {
+    let mut columns = vec!["key".to_string(), "own_paths".to_string(), "ct".to_string()];
+    for column in rollup.dim_keys.iter().chain(rollup.mes_keys.iter()) {
+        if !columns.contains(column) {
+            columns.push(column.clone());
+        }
+    }
+    columns.push("_del_ct".to_string());
+    columns
+}
+
+/// Columns of the rollup table except the stale flag, in the order of the group select
+fn rollup_table_columns(rollup: &StatsConfFactRollupInfoResp) -> Vec<String> {
+    let mut columns = vec!["ct".to_string(), "_ct_end".to_string(), "own_paths".to_string()];
+    columns.extend(rollup.dim_keys.iter().cloned());
+    columns.push("_rows".to_string());
+    for mes_key in &rollup.mes_keys {
+        columns.extend([format!("{mes_key}__sum"), format!("{mes_key}__count"), format!("{mes_key}__max"), format!("{mes_key}__min")]);
+    }
+    columns
+}
+
+fn bucket_units(rollup: &StatsConfFactRollupInfoResp) -> Vec<&'static str> {
+    BUCKET_UNITS.into_iter().filter(|unit| rollup.time_windows.iter().any(|time_window| bucket_unit(time_window) == *unit)).collect()
+}
+
+fn bucket_unit(time_window: &StatsQueryTimeWindowKind) -> &'static str {
+    match time_window {
+        StatsQueryTimeWindowKind::Date | StatsQueryTimeWindowKind::Day => "day",
+        StatsQueryTimeWindowKind::Hour => "hour",
+        StatsQueryTimeWindowKind::Week => "week",
+        StatsQueryTimeWindowKind::Month => "month",
+        StatsQueryTimeWindowKind::Year => "year",
+    }
+}
+
+/// Whether the buckets of the unit are within the labels of the time window
+fn unit_answers(unit: &str, time_window: &StatsQueryTimeWindowKind) -> bool {
+    match unit {
+        "hour" => true,
+        "day" => time_window != &StatsQueryTimeWindowKind::Hour,
+        "week" => time_window == &StatsQueryTimeWindowKind::Week || time_window == &StatsQueryTimeWindowKind::Year,
+        "month" => time_window == &StatsQueryTimeWindowKind::Month || time_window == &StatsQueryTimeWindowKind::Year,
+        _ => time_window == &StatsQueryTimeWindowKind::Year,
+    }
+}
+
+/// Start of the bucket containing the time, a week bucket is cut at the start of the year to keep it within one week label
+fn sql_bucket_start(unit: &str, column_name: &str) -> String {
+    let local = format!("timezone('{TIME_ZONE}', {column_name})");
+    if unit == "week" {
+        format!("timezone('{TIME_ZONE}', GREATEST(date_trunc('week', {local}), date_trunc('year', {local})))")
+    } else {
+        format!("timezone('{TIME_ZONE}', date_trunc('{unit}', {local}))")
+    }
+}
+
+fn sql_bucket_end(unit: &str, column_name: &str) -> String {
+    let local = format!("timezone('{TIME_ZONE}', {column_name})");
+    if unit == "week" {
+        format!("timezone('{TIME_ZONE}', LEAST(date_trunc('week', {local}) + interval '1 week', date_trunc('year', {local}) + interval '1 year'))")
+    } else {
+        format!("timezone('{TIME_ZONE}', date_trunc('{unit}', {local}) + interval '1 {unit}')")
+    }
+}
+
+/// The key is counted when it is not deleted after its latest record
+fn sql_live_key() -> &'static str {
+    "(fact._del_ct IS NULL OR fact._del_ct < fact.ct)"
+}
+
+fn sql_latest_records(fact_conf_key: &str, rollup: &StatsConfFactRollupInfoResp, sql_where: &str, ctx: &TardisContext) -> String {
+    let fact_table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}"), ctx);
+    let fact_del_table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}_del"), ctx);
+    let columns = key_table_columns(rollup).into_iter().filter(|column| column != "_del_ct").collect::<Vec<_>>();
+    format!(
+        r#"SELECT latest.*, (SELECT max(del.ct) FROM {fact_del_table_name} del WHERE del.key = latest.key) AS _del_ct
+FROM (
+    SELECT DISTINCT ON (fact.key) {}
+    FROM {fact_table_name} fact
+    WHERE {sql_where}
+    ORDER BY fact.key, fact.ct DESC
+) latest"#,
+        columns.iter().map(|column| format!("fact.{column}")).collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// Aggregate the live keys of the key table into the groups of the rollup table, the measures are aggregated as the query does
+fn sql_group_select(rollup: &StatsConfFactRollupInfoResp, unit: &str, key_table_name: &str, sql_where: &str) -> String {
+    let mut selects = vec![
+        format!("{} AS ct", sql_bucket_start(unit, "fact.ct")),
+        format!("{} AS _ct_end", sql_bucket_end(unit, "fact.ct")),
+        "fact.own_paths AS own_paths".to_string(),
+    ];
+    selects.extend(rollup.dim_keys.iter().map(|dim_key| format!("fact.{dim_key} AS {dim_key}")));
+    let group_len = selects.len();
+    selects.push("count(*) AS _rows".to_string());
+    for mes_key in &rollup.mes_keys {
+        selects.push(format!("sum(COALESCE(fact.{mes_key}::decimal,0)) AS {mes_key}__sum"));
+        selects.push(format!("count(fact.{mes_key}) AS {mes_key}__count"));
+        selects.push(format!("max(COALESCE(fact.{mes_key}::decimal,0)) AS {mes_key}__max"));
+        selects.push(format!("min(COALESCE(fact.{mes_key}::decimal,0)) AS {mes_key}__min"));
+    }
+    format!(
+        "SELECT {} FROM {key_table_name} fact WHERE {sql_where} AND {} GROUP BY {}",
+        selects.join(", "),
+        sql_live_key(),
+        (1..=group_len).map(|idx| idx.to_string()).collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// Combine the statements as the common table expressions of the main statement
+fn sql_with(ctes: &[String], main: &str) -> String {
+    if ctes.is_empty() {
+        main.to_string()
+    } else {
+        format!("WITH {}\n{main}", ctes.join(",\n"))
+    }
+}
+
+fn sql_group_match(rollup: &StatsConfFactRollupInfoResp, left: &str, right: &str) -> String {
+    let mut matches = vec![format!("{left}.ct = {right}.ct"), format!("{left}.own_paths = {right}.own_paths")];
+    matches.extend(rollup.dim_keys.iter().map(|dim_key| format!("{left}.{dim_key} IS NOT DISTINCT FROM {right}.{dim_key}")));
+    matches.join(" AND ")
+}
@@ -23,7 +23,7 @@ use crate::{
     stats_constants::SYNC_FACT_TASK_CODE,
 };
 
-use super::{stats_pg_conf_fact_col_serv, stats_pg_initializer, stats_pg_sync_serv};
+use super::{stats_pg_conf_fact_col_serv, stats_pg_conf_fact_rollup_serv, stats_pg_initializer, stats_pg_sync_serv};
 
 pub async fn online(fact_conf_key: &str, conn: &TardisRelDBlConnection, ctx: &TardisContext) -> TardisResult<bool> {
     common_pg::check_table_exit(&format!("stats_inst_fact_{fact_conf_key}"), conn, ctx).await
@@ -197,6 +197,7 @@ pub(crate) async fn delete(fact_conf_key: &str, funs: &TardisFunsInst, ctx: &Tar
         )
         .await?;
     }
+    stats_pg_conf_fact_rollup_serv::delete_by_fact_conf_key(fact_conf_key, &conn, ctx).await?;
     if online(fact_conf_key, &conn, ctx).await? {
         conn.execute_one(&format!("DROP TABLE {}{fact_conf_key}", package_table_name("stats_inst_fact_", ctx)), vec![]).await?;
         conn.execute_one(&format!("DROP TABLE {}{fact_conf_key}_del", package_table_name("stats_inst_fact_", ctx)), vec![]).await?;
@@ -111,6 +111,34 @@ pub async fn init_conf_fact_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRel
     .await
 }
 
+pub async fn init_conf_fact_rollup_table_and_conn(
+    bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>,
+    ctx: &TardisContext,
+    mgr: bool,
+) -> TardisResult<(TardisRelDBlConnection, String)> {
+    spi_initializer::common_pg::init_table_and_conn(
+        bs_inst,
+        ctx,
+        mgr,
+        None,
+        "stats_conf_fact_rollup",
+        r#"rel_conf_fact_key character varying NOT NULL,
+    dim_keys character varying[] NOT NULL,
+    mes_keys character varying[] NOT NULL,
+    time_windows character varying[] NOT NULL,
+    stale boolean NOT NULL DEFAULT FALSE,
+    stale_version bigint NOT NULL DEFAULT 0,
+    create_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    update_time timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    unique (rel_conf_fact_key)"#,
+        None,
+        vec![],
+        None,
+        Some("update_time"),
+    )
+    .await
+}
+
 pub async fn init_conf_fact_col_table_and_conn(bs_inst: TypedSpiBsInst<'_, TardisRelDBClient>, ctx: &TardisContext, mgr: bool) -> TardisResult<(TardisRelDBlConnection, String)> {
     spi_initializer::common_pg::init_table_and_conn(
         bs_inst,
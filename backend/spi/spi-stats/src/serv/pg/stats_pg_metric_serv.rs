@@ -18,7 +18,7 @@ use tardis::{
     TardisFunsInst,
 };
 
-use super::{stats_pg_conf_fact_detail_serv, stats_pg_record_serv};
+use super::{stats_pg_conf_fact_detail_serv, stats_pg_conf_fact_rollup_serv, stats_pg_record_serv};
 use crate::{
     dto::{
         stats_conf_dto::StatsConfFactInfoResp,
@@ -106,6 +106,11 @@ const FUNCTION_SUFFIX_FLAG: &str = "__";
 /// -- Length limit after grouping, optional
 ///   2
 /// ```
+///
+/// When the rollup of the fact can answer the query, the inner query reads the rollup table instead,
+/// the measures are aggregated from the pre-aggregated columns, and the query limit of the fact does not apply.
+///
+/// 当事实的汇总可以响应查询时，内层查询改为读取汇总表，度量基于预聚合的列进行聚合，且不受事实的查询限制。
 pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsInst, ctx: &TardisContext, inst: &SpiBsInst) -> TardisResult<StatsQueryMetricsResp> {
     let bs_inst = inst.inst::<TardisRelDBClient>();
     let (conn, _) = common_pg::init_conn(bs_inst).await?;
@@ -145,6 +150,7 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         }
         false
     });
+    let rollup_table_name = find_rollup_table(query_req, &conf_info, mes_distinct, &conn, ctx).await?;
 
     let mut params = if let Some(own_paths) = &query_req.own_paths {
         own_paths.iter().map(Value::from).collect_vec()
@@ -168,11 +174,18 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
 
     // Package outer select
     // (column name with fun, alias name, show_name, is dimension)
-    let (sql_part_groups, sql_part_outer_selects, sql_part_outer_select_infos) =
-        sql_part_outer_selects(sql_part_groups.clone(), sql_part_group_infos, ct_agg, measure_conf_info, query_req.select.clone(), funs)?;
+    let (sql_part_groups, sql_part_outer_selects, sql_part_outer_select_infos) = sql_part_outer_selects(
+        sql_part_groups.clone(),
+        sql_part_group_infos,
+        ct_agg,
+        measure_conf_info,
+        query_req.select.clone(),
+        rollup_table_name.is_some(),
+        funs,
+    )?;
 
     // Package having
-    let sql_part_havings = sql_part_havings(conf_info.clone(), query_req.having.clone(), &mut params, funs)?;
+    let sql_part_havings = sql_part_havings(conf_info.clone(), query_req.having.clone(), rollup_table_name.is_some(), &mut params, funs)?;
 
     // Package dimension order
     let sql_dimension_orders = sql_dimension_orders(dim_conf_info.clone(), query_req.dimension_order.clone(), funs)?;
@@ -191,10 +204,18 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     } else {
         "fact.own_paths LIKE $1".to_string()
     };
-    let final_sql = format!(
-        r#"SELECT {sql_part_outer_selects}{}
-    FROM (
-        SELECT
+    let sql_part_inner = if let Some(rollup_table_name) = &rollup_table_name {
+        format!(
+            r#"SELECT *
+             FROM {rollup_table_name} fact
+             WHERE
+                {filter_own_paths}
+                AND fact.ct >= {create_time_placeholder} AND fact.ct <= {end_time_placeholder}
+            {sql_part_wheres}"#
+        )
+    } else {
+        format!(
+            r#"SELECT
              {sql_part_inner_selects}{}
              FROM(
                 SELECT {}fact.*, 1 as _count
@@ -209,7 +230,40 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
              where 1 = 1
             {sql_part_wheres}
             {sql_dimension_orders}
-        LIMIT {conf_limit}
+        LIMIT {conf_limit}"#,
+            if ignore_group_agg {
+                "".to_string()
+            } else {
+                ",fact.key as _key, fact.own_paths as _own_paths, fact.ct as _ct".to_string()
+            },
+            if query_req.ignore_distinct.unwrap_or(false) {
+                ""
+            } else if mes_distinct {
+                if ct_agg {
+                    "DISTINCT ON (fact.key,date_part('day',fact.ct)) fact.key AS _key,"
+                } else {
+                    "DISTINCT ON (fact.key) fact.key AS _key,"
+                }
+            } else {
+                ""
+            },
+            if query_req.ignore_distinct.unwrap_or(false) {
+                ""
+            } else if mes_distinct {
+                if ct_agg {
+                    "_key,date_part('day',fact.ct),"
+                } else {
+                    "_key,"
+                }
+            } else {
+                ""
+            },
+        )
+    };
+    let final_sql = format!(
+        r#"SELECT {sql_part_outer_selects}{}
+    FROM (
+        {sql_part_inner}
     ) _
     {}
     {sql_part_havings}
@@ -220,33 +274,6 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
         } else {
             ",string_agg(_._key || ' - ' || _._own_paths || ' - ' || to_char(_._ct, 'YYYY-MM-DD HH24:MI:SS'), ',') as s_agg".to_string()
         },
-        if ignore_group_agg {
-            "".to_string()
-        } else {
-            ",fact.key as _key, fact.own_paths as _own_paths, fact.ct as _ct".to_string()
-        },
-        if query_req.ignore_distinct.unwrap_or(false) {
-            ""
-        } else if mes_distinct {
-            if ct_agg {
-                "DISTINCT ON (fact.key,date_part('day',fact.ct)) fact.key AS _key,"
-            } else {
-                "DISTINCT ON (fact.key) fact.key AS _key,"
-            }
-        } else {
-            ""
-        },
-        if query_req.ignore_distinct.unwrap_or(false) {
-            ""
-        } else if mes_distinct {
-            if ct_agg {
-                "_key,date_part('day',fact.ct),"
-            } else {
-                "_key,"
-            }
-        } else {
-            ""
-        },
         if sql_part_groups.is_empty() {
             "".to_string()
         } else {
@@ -282,6 +309,58 @@ pub async fn query_metrics(query_req: &StatsQueryMetricsReq, funs: &TardisFunsIn
     })
 }
 
+/// Find the rollup table able to answer the query, `None` means the query should use the fact records
+///
+/// The records should be deduplicated by the key without the group aggregation details,
+/// only the dimensions and measures of the rollup should be used, and the create time should only be grouped by a time window.
+///
+/// 查找能够响应查询的汇总表，`None` 表示查询应使用事实记录。
+/// 记录需按 key 去重且不返回分组聚合详情，仅能使用汇总的维度及度量，且创建时间仅能按时间窗口分组。
+async fn find_rollup_table(
+    query_req: &StatsQueryMetricsReq,
+    conf_info: &HashMap<String, StatsConfInfo>,
+    mes_distinct: bool,
+    conn: &TardisRelDBlConnection,
+    ctx: &TardisContext,
+) -> TardisResult<Option<String>> {
+    if !mes_distinct || query_req.ignore_distinct.unwrap_or(false) || query_req.group_agg.unwrap_or(false) {
+        return Ok(None);
+    }
+    let Some(rollup) = stats_pg_conf_fact_rollup_serv::find(&query_req.from, conn, ctx).await? else {
+        return Ok(None);
+    };
+    // The stale rollup is being rebuilt, the fact records are used meanwhile
+    if rollup.stale {
+        return Ok(None);
+    }
+    // The columns of external ids are stored in the ext field, which is not in the rollup
+    let is_fact_col = |keys: &[String], code: &str, rel_external_id: &Option<String>| {
+        keys.iter().any(|key| key == code)
+            && !rel_external_id.as_ref().is_some_and(|i| !i.is_empty())
+            && conf_info.get(code).is_some_and(|conf| !conf.rel_external_id.as_ref().is_some_and(|i| !i.is_empty()))
+    };
+    let mut time_windows = vec![];
+    for group in &query_req.group {
+        if group.code == "ct" {
+            let Some(time_window) = &group.time_window else {
+                return Ok(None);
+            };
+            time_windows.push(time_window.clone());
+        } else if !is_fact_col(&rollup.dim_keys, &group.code, &group.rel_external_id) {
+            return Ok(None);
+        }
+    }
+    let is_mes_col = |code: &str, rel_external_id: &Option<String>| code == "_count" || is_fact_col(&rollup.mes_keys, code, rel_external_id);
+    if !query_req.select.iter().all(|select| is_mes_col(&select.code, &select.rel_external_id))
+        || !query_req.having.iter().flatten().all(|having| is_mes_col(&having.code, &having.rel_external_id))
+        || !query_req.metrics_order.iter().flatten().all(|order| is_mes_col(&order.code, &order.rel_external_id))
+        || !query_req._where.iter().flatten().flatten().all(|and_where| is_fact_col(&rollup.dim_keys, &and_where.code, &and_where.rel_external_id))
+    {
+        return Ok(None);
+    }
+    stats_pg_conf_fact_rollup_serv::find_query_table(&query_req.from, &rollup, &time_windows, query_req.start_time, query_req.end_time, conn, ctx).await
+}
+
 async fn fetch_conf_info(
     from: String,
     rel_external_ids: Option<HashSet<String>>,
@@ -676,6 +755,7 @@ fn sql_part_outer_selects(
     ct_agg: bool,
     measure_conf_info: HashMap<String, StatsConfInfo>,
     select: Vec<StatsQueryMetricsSelectReq>,
+    from_rollup: bool,
     funs: &TardisFunsInst,
 ) -> TardisResult<(String, String, Vec<(String, String, String, bool)>)> {
     let mut sql_part_outer_select_infos = vec![];
@@ -724,6 +804,8 @@ fn sql_part_outer_selects(
                     format!("ORDER BY {}", order_dim)
                 }
             )
+        } else if from_rollup {
+            stats_pg_conf_fact_rollup_serv::agg_sql(&select.code, &select.fun)
         } else {
             col_data_type.to_pg_select(&format!("_.{}", select.code.clone()), &select.fun)
         };
@@ -739,6 +821,7 @@ fn sql_part_outer_selects(
 fn sql_part_havings(
     conf_info: HashMap<String, StatsConfInfo>,
     having: Option<Vec<StatsQueryMetricsHavingReq>>,
+    from_rollup: bool,
     params: &mut Vec<Value>,
     funs: &TardisFunsInst,
 ) -> TardisResult<String> {
@@ -767,7 +850,16 @@ fn sql_part_havings(
                 .to_pg_having(false, &format!("_.{}", having.code.clone()), &having.op, params.len() + 1, &having.value, Some(&having.fun))?
             {
                 value.iter().for_each(|v| params.push(v.clone()));
-                sql_part_havings.push(sql_part);
+                if from_rollup {
+                    // Replace the aggregate on the fact records with the one on the rollup table
+                    sql_part_havings.push(sql_part.replacen(
+                        &having.fun.to_sql(&format!("_.{}", having.code)),
+                        &stats_pg_conf_fact_rollup_serv::agg_sql(&having.code, &having.fun),
+                        1,
+                    ));
+                } else {
+                    sql_part_havings.push(sql_part);
+                }
             } else {
                 return Err(funs.err().not_found(
                     "metric",
@@ -1134,8 +1226,15 @@ pub async fn query_metrics_record_paginated(
 
     // Package outer select
     // (column name with fun, alias name, show_name, is dimension)
-    let (sql_part_groups, sql_part_outer_selects, _) =
-        sql_part_outer_selects(sql_part_groups.clone(), sql_part_group_infos, ct_agg, measure_conf_info, query_req.select.clone(), funs)?;
+    let (sql_part_groups, sql_part_outer_selects, _) = sql_part_outer_selects(
+        sql_part_groups.clone(),
+        sql_part_group_infos,
+        ct_agg,
+        measure_conf_info,
+        query_req.select.clone(),
+        false,
+        funs,
+    )?;
 
     let own_paths_placeholder = (1..=own_paths_count).map(|idx| format!("${}", idx)).collect::<Vec<String>>().join(", ");
     let create_time_placeholder = format!("${}", own_paths_count + 1);
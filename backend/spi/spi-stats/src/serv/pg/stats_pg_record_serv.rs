@@ -28,7 +28,7 @@ use crate::{
     stats_enumeration::StatsFactColKind,
 };
 
-use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_conf_fact_serv};
+use super::{stats_pg_conf_dim_serv, stats_pg_conf_fact_col_serv, stats_pg_conf_fact_rollup_serv, stats_pg_conf_fact_serv};
 
 pub(crate) async fn get_fact_record_latest(
     fact_conf_key: &str,
@@ -375,6 +375,7 @@ VALUES
         field_values.into_iter().cloned().collect::<Vec<Value>>(),
     )
     .await?;
+    stats_pg_conf_fact_rollup_serv::refresh_keys(fact_conf_key, &[fact_record_key.to_string()], &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -398,6 +399,7 @@ pub(crate) async fn fact_records_load(
     let mut has_fields_init = false;
     let mut fields = vec!["key".to_string(), "own_paths".to_string(), "ext".to_string(), "ct".to_string(), "idempotent_id".to_string()];
     let mut value_sets = vec![];
+    let mut loaded_keys = vec![];
 
     for add_req in add_req_set {
         let Some(req_data) = add_req.data.as_object() else {
@@ -494,6 +496,7 @@ pub(crate) async fn fact_records_load(
             // TODO check data type
         }
         value_sets.push(values);
+        loaded_keys.push(add_req.key.clone());
         has_fields_init = true;
     }
 
@@ -513,6 +516,7 @@ pub(crate) async fn fact_records_load(
         )
         .await?;
     }
+    stats_pg_conf_fact_rollup_serv::refresh_keys(fact_conf_key, &loaded_keys, &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -595,6 +599,13 @@ WHERE idempotent_id = $1"#,
         params,
     )
     .await?;
+    let modified_keys = conn
+        .query_all(&format!("SELECT DISTINCT key FROM {table_name} WHERE idempotent_id = $1"), vec![Value::from(idempotent_id)])
+        .await?
+        .into_iter()
+        .map(|item| item.try_get("", "key"))
+        .collect::<Result<Vec<String>, _>>()?;
+    stats_pg_conf_fact_rollup_serv::refresh_keys(fact_conf_key, &modified_keys, &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -619,6 +630,7 @@ VALUES
         vec![Value::from(fact_record_key)],
     )
     .await?;
+    stats_pg_conf_fact_rollup_serv::refresh_keys(fact_conf_key, &[fact_record_key.to_string()], &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -645,6 +657,7 @@ pub(crate) async fn fact_records_delete(fact_conf_key: &str, fact_record_delete_
         )
         .await?;
     }
+    stats_pg_conf_fact_rollup_serv::refresh_keys(fact_conf_key, fact_record_delete_keys, &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -667,7 +680,11 @@ pub(crate) async fn fact_records_delete_by_ownership(fact_conf_key: &str, own_pa
         vec![Value::from(own_paths)],
     )
     .await?;
+    let rollup_stale = stats_pg_conf_fact_rollup_serv::mark_stale(fact_conf_key, &conn, ctx).await?;
     conn.commit().await?;
+    if rollup_stale {
+        stats_pg_conf_fact_rollup_serv::spawn_rebuild(fact_conf_key, ctx);
+    }
     Ok(())
 }
 
@@ -690,7 +707,7 @@ pub(crate) async fn fact_records_delete_by_dim_key(
         return Ok(());
     }
     let table_name = package_table_name(&format!("stats_inst_fact_{fact_conf_key}_del"), ctx);
-    for delete_key in fact_record_delete_keys {
+    for delete_key in &fact_record_delete_keys {
         conn.execute_one(
             &format!(
                 r#"INSERT INTO {table_name}
@@ -703,6 +720,7 @@ pub(crate) async fn fact_records_delete_by_dim_key(
         )
         .await?;
     }
+    stats_pg_conf_fact_rollup_serv::refresh_keys(fact_conf_key, &fact_record_delete_keys, &conn, ctx).await?;
     conn.commit().await?;
     Ok(())
 }
@@ -767,7 +785,11 @@ pub(crate) async fn fact_records_clean(fact_conf_key: &str, before_ct: Option<Da
     } else {
         conn.execute_one(&format!("DELETE FROM {table_name}"), vec![]).await?;
     }
+    let rollup_stale = stats_pg_conf_fact_rollup_serv::mark_stale(fact_conf_key, &conn, ctx).await?;
     conn.commit().await?;
+    if rollup_stale {
+        stats_pg_conf_fact_rollup_serv::spawn_rebuild(fact_conf_key, ctx);
+    }
     Ok(())
 }
 
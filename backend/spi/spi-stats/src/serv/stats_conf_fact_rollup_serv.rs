@@ -0,0 +1,23 @@
+use crate::dto::stats_conf_dto::{StatsConfFactRollupInfoResp, StatsConfFactRollupSaveReq};
+use crate::stats_initializer;
+use bios_basic::spi::spi_constants;
+use bios_basic::spi::spi_funs::SpiBsInstExtractor;
+use bios_basic::spi_dispatch_service;
+use tardis::basic::result::TardisResult;
+
+use super::pg;
+
+spi_dispatch_service! {
+    @mgr: true,
+    @init: stats_initializer::init_fun,
+    @dispatch: {
+        #[cfg(feature = "spi-pg")]
+        spi_constants::SPI_PG_KIND_CODE => pg::stats_pg_conf_fact_rollup_serv,
+    },
+    @method: {
+        save(fact_conf_key: &str, save_req: &StatsConfFactRollupSaveReq) -> TardisResult<()>;
+        get(fact_conf_key: &str) -> TardisResult<Option<StatsConfFactRollupInfoResp>>;
+        delete(fact_conf_key: &str) -> TardisResult<()>;
+        rebuild(fact_conf_key: &str) -> TardisResult<()>;
+    }
+}
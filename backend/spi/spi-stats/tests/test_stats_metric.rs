@@ -49,6 +49,7 @@ pub async fn test(client: &mut TestHttpClient) -> TardisResult<()> {
     test_metric_query_check(client).await?;
     test_metric_query(client).await?;
     test_metric_record_detail_query(client).await?;
+    test_metric_rollup_query(client).await?;
     Ok(())
 }
 
@@ -628,3 +629,67 @@ pub async fn test_metric_record_detail_query(client: &mut TestHttpClient) -> Tar
 
     Ok(())
 }
+
+pub async fn test_metric_rollup_query(client: &mut TestHttpClient) -> TardisResult<()> {
+    // the start time is the start of the day in Asia/Shanghai
+    let queries = vec![
+        json!({
+            "from":"req",
+            "select":[{"code":"act_hours","fun":"sum"},{"code":"_count","fun":"count"}],
+            "group":[{"code":"source"}],
+            "start_time":"2022-12-31T16:00:00.000Z",
+            "end_time": Utc::now().to_rfc3339()
+        }),
+        json!({
+            "from":"req",
+            "select":[{"code":"act_hours","fun":"avg"},{"code":"act_hours","fun":"max"}],
+            "group":[{"code":"ct","time_window":"day"},{"code":"status"}],
+            "where":[[{"code":"source", "op":"=", "value":"hangzhou"}]],
+            "start_time":"2022-12-31T16:00:00.000Z",
+            "end_time": Utc::now().to_rfc3339()
+        }),
+    ];
+    let mut expected_groups = vec![];
+    for query in &queries {
+        let resp: StatsQueryMetricsResp = client.put("/ci/metric", query).await;
+        expected_groups.push(resp.group);
+    }
+
+    // dimension not exist error
+    assert_eq!(
+        client.put_resp::<Value, Void>("/ci/conf/fact/req/rollup", &json!({"dim_keys":["xxx"],"time_windows":["day"]})).await.code,
+        "400-spi-stats-fact-rollup-dim-not-legal"
+    );
+    let _: Void = client.put("/ci/conf/fact/req/rollup", &json!({"dim_keys":["source","status"],"time_windows":["day","month"]})).await;
+    let mut rollup: Value = client.get("/ci/conf/fact/req/rollup").await;
+    assert_eq!(rollup["dim_keys"], json!(["source", "status"]));
+    assert!(rollup["mes_keys"].as_array().unwrap().contains(&json!("act_hours")));
+    // the rollup is rebuilt in the background, after the other nodes have seen the new config
+    for _ in 0..250 {
+        if rollup["stale"] == json!(false) {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+        rollup = client.get("/ci/conf/fact/req/rollup").await;
+    }
+    assert_eq!(rollup["stale"], json!(false));
+
+    // the results are the same as the ones on the fact records
+    for (query, expected_group) in queries.iter().zip(expected_groups.iter()) {
+        let resp: StatsQueryMetricsResp = client.put("/ci/metric", query).await;
+        assert_eq!(&resp.group, expected_group);
+    }
+
+    // the rollup is updated by the record writes
+    assert_eq!(client.delete_resp("/ci/record/fact/req/r011").await.code, "200");
+    sleep(Duration::from_millis(100)).await;
+    let mut query = queries[0].clone();
+    query["end_time"] = json!(Utc::now().to_rfc3339());
+    let resp_rollup: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    client.delete("/ci/conf/fact/req/rollup").await;
+    let resp: StatsQueryMetricsResp = client.put("/ci/metric", &query).await;
+    assert_eq!(resp_rollup.group, resp.group);
+    assert!(client.get_resp::<Option<Value>>("/ci/conf/fact/req/rollup").await.data.flatten().is_none());
+
+    Ok(())
+}
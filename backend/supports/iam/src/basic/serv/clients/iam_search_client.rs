@@ -437,6 +437,7 @@ impl IamSearchClient {
                     roles: Some(account_roles),
                     groups: Some(account_resp_dept_id),
                 }),
+                embedding: None,
             },
             Some(account_resp.name.clone()),
             Some(account_resp.disabled),
@@ -97,6 +97,26 @@ pub struct KvTagItemFindResp {
     pub service: Option<String>,
 }
 
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug)]
+pub struct KvItemChangeResp {
+    pub seq: i64,
+    pub key: String,
+    pub revision: i64,
+    pub value: Value,
+    pub disable: bool,
+    pub owner: String,
+    pub own_paths: String,
+    pub scope_level: i16,
+    pub op: String,
+    pub ts: DateTime<Utc>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Clone, Debug)]
+pub struct KvItemWatchResp {
+    pub seq: i64,
+    pub changes: Vec<KvItemChangeResp>,
+}
+
 impl SpiKvClient {
     /// Initialize the KV backend service: create if not exists and bind to app/tenant.
     /// Reads all configuration from `InvokeModuleConfig.bs_init`.
@@ -256,6 +276,33 @@ impl SpiKvClient {
         BaseSpiClient::package_resp(resp)
     }
 
+    /// Watch the changes of the items by key prefix (long polling).
+    /// Pass the returned `seq` as `since_seq` in the next call, `None` returns the latest `seq` immediately.
+    ///
+    /// 通过key前缀监听Item的变更（长轮询）
+    pub async fn watch_items(
+        key_prefix: &str,
+        since_seq: Option<i64>,
+        timeout_sec: Option<u64>,
+        funs: &TardisFunsInst,
+        ctx: &TardisContext,
+    ) -> TardisResult<Option<KvItemWatchResp>> {
+        let kv_url = BaseSpiClient::module_url(InvokeModuleKind::Kv, funs).await?;
+        let headers = BaseSpiClient::headers(None, funs, ctx).await?;
+        let mut url = format!(
+            "{kv_url}/ci/item/watch?key_prefix={}",
+            tardis::url::form_urlencoded::byte_serialize(key_prefix.as_bytes()).collect::<String>()
+        );
+        if let Some(since_seq) = since_seq {
+            url = format!("{url}&since_seq={since_seq}");
+        }
+        if let Some(timeout_sec) = timeout_sec {
+            url = format!("{url}&timeout_sec={timeout_sec}");
+        }
+        let resp = funs.web_client().get::<TardisResp<KvItemWatchResp>>(&url, headers.clone()).await?;
+        BaseSpiClient::package_resp(resp)
+    }
+
     pub async fn delete_item(key: &str, funs: &TardisFunsInst, ctx: &TardisContext) -> TardisResult<()> {
         let kv_url = BaseSpiClient::module_url(InvokeModuleKind::Kv, funs).await?;
         let headers = BaseSpiClient::headers(None, funs, ctx).await?;
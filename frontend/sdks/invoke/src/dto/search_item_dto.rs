@@ -28,6 +28,8 @@ pub struct SearchItemAddReq {
     pub ext: Option<Value>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
     pub kv_disable: Option<bool>,
+    // Embedding vector of the item, generated by the embedding provider when it is empty
+    pub embedding: Option<Vec<f32>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -51,6 +53,8 @@ pub struct SearchItemModifyReq {
     pub ext_override: Option<bool>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
     pub kv_disable: Option<bool>,
+    // Embedding vector of the item, regenerated by the embedding provider when it is empty and the title or content changes
+    pub embedding: Option<Vec<f32>>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -77,6 +81,45 @@ pub struct SearchItemSearchReq {
     // When the record set is very large, it will seriously affect the performance, it is not recommended to use.
     pub sort: Option<Vec<SearchItemSearchSortReq>>,
     pub page: SearchItemSearchPageReq,
+    // Vector search, sorted by the similarity when `sort` is empty
+    pub vector: Option<SearchItemVectorQueryReq>,
+    // Highlight the matched keywords of `query.q` in the title and content
+    pub highlight: Option<SearchItemHighlightReq>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchItemVectorQueryReq {
+    // Search mode, default is hybrid
+    pub mode: Option<SearchItemVectorQueryModeKind>,
+    // Query vector, generated from `query.q` by the embedding provider when it is empty
+    pub embedding: Option<Vec<f32>>,
+    // Weight of the keyword rank in the hybrid mode, between 0 and 1, default is 0.5
+    // The vector similarity takes the rest
+    pub keyword_weight: Option<f32>,
+    // Minimum cosine similarity of the matched items
+    pub min_similarity: Option<f32>,
+}
+
+#[derive(poem_openapi::Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchItemVectorQueryModeKind {
+    // Rank by the vector similarity only, `query.q` is only used to generate the query vector
+    #[oai(rename = "vector")]
+    Vector,
+    // Items matching the keywords or the vector, ranked by the blend of the keyword rank and the vector similarity
+    #[oai(rename = "hybrid")]
+    Hybrid,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchItemHighlightReq {
+    // Tag before the matched keywords, default is `<em>`
+    pub pre_tag: Option<String>,
+    // Tag after the matched keywords, default is `</em>`
+    pub post_tag: Option<String>,
+    // Max number of the content fragments, default is 3
+    pub max_fragments: Option<u16>,
+    // Max size of each content fragment, counted in words on pg and characters on ES, default is 35 / 100
+    pub fragment_size: Option<u16>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default, Clone)]
@@ -234,6 +277,17 @@ pub struct SearchItemSearchResp {
     pub ext: Value,
     pub rank_title: f32,
     pub rank_content: f32,
+    // Vector similarity, only available in vector search
+    pub rank_vector: f32,
+    // Highlighted fragments, only available when `highlight` is requested with `query.q`
+    pub highlight: Option<SearchItemHighlightResp>,
+}
+
+#[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Default)]
+pub struct SearchItemHighlightResp {
+    pub title: Option<String>,
+    // Fragments joined by ` ... `
+    pub content: Option<String>,
 }
 
 #[derive(poem_openapi::Object, Serialize, Deserialize, Debug, Clone)]
@@ -255,4 +309,6 @@ pub struct SearchSaveItemReq {
     pub update_time: Option<DateTime<Utc>>,
     pub ext: Option<Value>,
     pub visit_keys: Option<SearchItemVisitKeysReq>,
+    // Embedding vector of the item, same as the add and modify requests
+    pub embedding: Option<Vec<f32>>,
 }